@@ -100,6 +100,13 @@ pub enum PythonPrintMode {
     Latex,
     Mathematica,
     Sympy,
+    Pretty,
+    C,
+    Fortran,
+    NumPy,
+    MathML,
+    ContentMathML,
+    Form,
 }
 
 impl From<PrintMode> for PythonPrintMode {
@@ -109,6 +116,13 @@ impl From<PrintMode> for PythonPrintMode {
             PrintMode::Latex => PythonPrintMode::Latex,
             PrintMode::Mathematica => PythonPrintMode::Mathematica,
             PrintMode::Sympy => PythonPrintMode::Sympy,
+            PrintMode::Pretty => PythonPrintMode::Pretty,
+            PrintMode::C => PythonPrintMode::C,
+            PrintMode::Fortran => PythonPrintMode::Fortran,
+            PrintMode::NumPy => PythonPrintMode::NumPy,
+            PrintMode::MathML => PythonPrintMode::MathML,
+            PrintMode::ContentMathML => PythonPrintMode::ContentMathML,
+            PrintMode::Form => PythonPrintMode::Form,
         }
     }
 }
@@ -120,6 +134,13 @@ impl From<PythonPrintMode> for PrintMode {
             PythonPrintMode::Latex => PrintMode::Latex,
             PythonPrintMode::Mathematica => PrintMode::Mathematica,
             PythonPrintMode::Sympy => PrintMode::Sympy,
+            PythonPrintMode::Pretty => PrintMode::Pretty,
+            PythonPrintMode::C => PrintMode::C,
+            PythonPrintMode::Fortran => PrintMode::Fortran,
+            PythonPrintMode::NumPy => PrintMode::NumPy,
+            PythonPrintMode::MathML => PrintMode::MathML,
+            PythonPrintMode::ContentMathML => PrintMode::ContentMathML,
+            PythonPrintMode::Form => PrintMode::Form,
         }
     }
 }
@@ -156,6 +177,9 @@ impl<'py> IntoPyDict<'py> for PrintOptions {
         dict.set_item("num_exp_as_superscript", self.num_exp_as_superscript)?;
         dict.set_item("precision", self.precision)?;
         dict.set_item("pretty_matrix", self.pretty_matrix)?;
+        dict.set_item("pretty_ascii", self.pretty_ascii)?;
+        dict.set_item("pretty_big_operators", self.pretty_big_operators)?;
+        dict.set_item("max_line_length", self.max_line_length)?;
         dict.set_item("hide_namespace", self.hide_namespace)?;
         dict.set_item("hide_all_namespaces", self.hide_all_namespaces)?;
         dict.set_item("color_namespace", self.color_namespace)?;
@@ -286,7 +310,9 @@ fn get_license_key(email: String) -> PyResult<()> {
         .map_err(exceptions::PyConnectionError::new_err)
 }
 
-#[pyfunction(name = "S", signature = (*names,is_symmetric=None,is_antisymmetric=None,is_cyclesymmetric=None,is_linear=None,custom_normalization=None,custom_print=None))]
+#[pyfunction(name = "S", signature = (*names,is_symmetric=None,is_antisymmetric=None,is_cyclesymmetric=None,is_linear=None,
+    is_noncommutative=None,is_grassmann=None,is_idempotent=None,is_involution=None,is_associative=None,
+    is_homogeneous=None,linear_args=None,custom_normalization=None,custom_print=None))]
 /// Shorthand notation for :func:`Expression.symbol`.
 fn symbol_shorthand(
     names: &Bound<'_, PyTuple>,
@@ -294,6 +320,13 @@ fn symbol_shorthand(
     is_antisymmetric: Option<bool>,
     is_cyclesymmetric: Option<bool>,
     is_linear: Option<bool>,
+    is_noncommutative: Option<bool>,
+    is_grassmann: Option<bool>,
+    is_idempotent: Option<bool>,
+    is_involution: Option<bool>,
+    is_associative: Option<bool>,
+    is_homogeneous: Option<bool>,
+    linear_args: Option<Vec<usize>>,
     custom_normalization: Option<PythonTransformer>,
     custom_print: Option<PyObject>,
     py: Python<'_>,
@@ -306,6 +339,13 @@ fn symbol_shorthand(
         is_antisymmetric,
         is_cyclesymmetric,
         is_linear,
+        is_noncommutative,
+        is_grassmann,
+        is_idempotent,
+        is_involution,
+        is_associative,
+        is_homogeneous,
+        linear_args,
         custom_normalization,
         custom_print,
     )
@@ -1875,6 +1915,9 @@ impl PythonTransformer {
                 mode: mode.into(),
                 precision,
                 pretty_matrix: false,
+                pretty_ascii: false,
+                pretty_big_operators: false,
+                max_line_length: None,
                 hide_all_namespaces: !show_namespaces,
                 color_namespace: true,
                 hide_namespace: Some("python"),
@@ -2755,7 +2798,12 @@ impl PythonExpression {
     /// The symbol can signal that it is symmetric if it is used as a function
     /// using `is_symmetric=True`, antisymmetric using `is_antisymmetric=True`,
     /// cyclesymmetric using `is_cyclesymmetric=True` and
-    /// multilinear using `is_linear=True`. If no attributes
+    /// multilinear using `is_linear=True`. A symbol keeps its relative order in products
+    /// using `is_noncommutative=True` and anticommutes with other Grassmann-odd symbols using
+    /// `is_grassmann=True`. A function can be made linear in some of its arguments only with
+    /// `linear_args`, and idempotent, an involution, associative or homogeneous with
+    /// `is_idempotent=True`, `is_involution=True`, `is_associative=True` and
+    /// `is_homogeneous=True`. If no attributes
     /// are specified, the attributes are inherited from the symbol if it was already defined,
     /// otherwise all attributes are set to `false`.  A transformer that is executed
     /// after normalization can be defined with `custom_normalization`.
@@ -2792,7 +2840,9 @@ impl PythonExpression {
     /// Define a custom normalization function:
     /// >>> e = S('real_log', custom_normalization=Transformer().replace(E("x_(exp(x1_))"), E("x1_")))
     /// >>> E("real_log(exp(x)) + real_log(5)")
-    #[pyo3(signature = (*names,is_symmetric=None,is_antisymmetric=None,is_cyclesymmetric=None,is_linear=None,custom_normalization=None, custom_print=None))]
+    #[pyo3(signature = (*names,is_symmetric=None,is_antisymmetric=None,is_cyclesymmetric=None,is_linear=None,
+        is_noncommutative=None,is_grassmann=None,is_idempotent=None,is_involution=None,is_associative=None,
+        is_homogeneous=None,linear_args=None,custom_normalization=None, custom_print=None))]
     #[classmethod]
     pub fn symbol(
         _cls: &Bound<'_, PyType>,
@@ -2802,6 +2852,13 @@ impl PythonExpression {
         is_antisymmetric: Option<bool>,
         is_cyclesymmetric: Option<bool>,
         is_linear: Option<bool>,
+        is_noncommutative: Option<bool>,
+        is_grassmann: Option<bool>,
+        is_idempotent: Option<bool>,
+        is_involution: Option<bool>,
+        is_associative: Option<bool>,
+        is_homogeneous: Option<bool>,
+        linear_args: Option<Vec<usize>>,
         custom_normalization: Option<PythonTransformer>,
         custom_print: Option<PyObject>,
     ) -> PyResult<PyObject> {
@@ -2843,6 +2900,13 @@ impl PythonExpression {
             && is_antisymmetric.is_none()
             && is_cyclesymmetric.is_none()
             && is_linear.is_none()
+            && is_noncommutative.is_none()
+            && is_grassmann.is_none()
+            && is_idempotent.is_none()
+            && is_involution.is_none()
+            && is_associative.is_none()
+            && is_homogeneous.is_none()
+            && linear_args.is_none()
             && custom_normalization.is_none()
             && custom_print.is_none()
         {
@@ -2898,11 +2962,38 @@ impl PythonExpression {
             opts.push(FunctionAttribute::Linear);
         }
 
+        if let Some(true) = is_noncommutative {
+            opts.push(FunctionAttribute::Noncommutative);
+        }
+
+        if let Some(true) = is_grassmann {
+            opts.push(FunctionAttribute::Grassmann);
+        }
+
+        if let Some(true) = is_idempotent {
+            opts.push(FunctionAttribute::Idempotent);
+        }
+
+        if let Some(true) = is_involution {
+            opts.push(FunctionAttribute::Involution);
+        }
+
+        if let Some(true) = is_associative {
+            opts.push(FunctionAttribute::Associative);
+        }
+
+        if let Some(true) = is_homogeneous {
+            opts.push(FunctionAttribute::Homogeneous);
+        }
+
         if names.len() == 1 {
             let name = names.get_item(0).unwrap().extract::<PyBackedStr>()?;
             let name = namespace.attach_namespace(name_check(&name)?);
 
             let mut symbol = Symbol::new(name).with_attributes(opts);
+            if let Some(args) = &linear_args {
+                symbol = symbol.with_linear_arguments(args);
+            }
 
             if let Some(f) = custom_normalization {
                 if let Pattern::Transformer(t) = f.expr {
@@ -2963,6 +3054,9 @@ impl PythonExpression {
                 let name = a.extract::<PyBackedStr>()?;
                 let name = namespace.attach_namespace(name_check(&name)?);
                 let mut symbol = Symbol::new(name).with_attributes(opts.clone());
+                if let Some(args) = &linear_args {
+                    symbol = symbol.with_linear_arguments(args);
+                }
 
                 if let Some(f) = &custom_normalization {
                     if let Pattern::Transformer(t) = &f.expr {
@@ -3227,7 +3321,8 @@ impl PythonExpression {
             precision = None,
             show_namespaces = false,
             max_terms = Some(100),
-            custom_print_mode = None)
+            custom_print_mode = None,
+            max_line_length = None)
         )]
     pub fn format(
         &self,
@@ -3247,6 +3342,7 @@ impl PythonExpression {
         show_namespaces: bool,
         max_terms: Option<usize>,
         custom_print_mode: Option<usize>,
+        max_line_length: Option<usize>,
     ) -> PyResult<String> {
         Ok(format!(
             "{}",
@@ -3267,6 +3363,9 @@ impl PythonExpression {
                     mode: mode.into(),
                     precision,
                     pretty_matrix: false,
+                    pretty_ascii: false,
+                    pretty_big_operators: false,
+                    max_line_length,
                     hide_all_namespaces: !show_namespaces,
                     color_namespace: true,
                     hide_namespace: Some("python"),
@@ -6095,6 +6194,9 @@ impl PythonSeries {
                     mode: mode.into(),
                     precision,
                     pretty_matrix: false,
+                    pretty_ascii: false,
+                    pretty_big_operators: false,
+                    max_line_length: None,
                     hide_all_namespaces: !show_namespaces,
                     color_namespace: true,
                     hide_namespace: Some("python"),
@@ -6654,6 +6756,9 @@ impl PythonPolynomial {
                 mode: mode.into(),
                 precision,
                 pretty_matrix: false,
+                pretty_ascii: false,
+                pretty_big_operators: false,
+                max_line_length: None,
                 hide_all_namespaces: !show_namespaces,
                 color_namespace: true,
                 hide_namespace: Some("python"),
@@ -7561,6 +7666,9 @@ impl PythonFiniteFieldPolynomial {
                 mode: mode.into(),
                 precision,
                 pretty_matrix: false,
+                pretty_ascii: false,
+                pretty_big_operators: false,
+                max_line_length: None,
                 hide_all_namespaces: !show_namespaces,
                 color_namespace: true,
                 hide_namespace: Some("python"),
@@ -8327,6 +8435,9 @@ impl PythonPrimeTwoPolynomial {
                 mode: mode.into(),
                 precision,
                 pretty_matrix: false,
+                pretty_ascii: false,
+                pretty_big_operators: false,
+                max_line_length: None,
                 hide_all_namespaces: !show_namespaces,
                 color_namespace: true,
                 hide_namespace: Some("python"),
@@ -8968,6 +9079,9 @@ impl PythonGaloisFieldPrimeTwoPolynomial {
                 mode: mode.into(),
                 precision,
                 pretty_matrix: false,
+                pretty_ascii: false,
+                pretty_big_operators: false,
+                max_line_length: None,
                 hide_all_namespaces: !show_namespaces,
                 color_namespace: true,
                 hide_namespace: Some("python"),
@@ -9680,6 +9794,9 @@ impl PythonGaloisFieldPolynomial {
                 mode: mode.into(),
                 precision,
                 pretty_matrix: false,
+                pretty_ascii: false,
+                pretty_big_operators: false,
+                max_line_length: None,
                 hide_all_namespaces: !show_namespaces,
                 color_namespace: true,
                 hide_namespace: Some("python"),
@@ -10326,6 +10443,9 @@ impl PythonNumberFieldPolynomial {
                 mode: mode.into(),
                 precision,
                 pretty_matrix: false,
+                pretty_ascii: false,
+                pretty_big_operators: false,
+                max_line_length: None,
                 hide_all_namespaces: !show_namespaces,
                 color_namespace: true,
                 hide_namespace: Some("python"),
@@ -11587,6 +11707,7 @@ impl PythonExpressionEvaluator {
     /// - `('pow', ('out', 0), ('param', 0), -1)` which means `out[0] = param[0]^-1`.
     /// - `('powf', ('out', 0), ('param', 0), ('param', 1))` which means `out[0] = param[0]^param[1]`.
     /// - `('fun', ('temp', 1), cos, ('param', 0))` which means `temp[1] = cos(param[0])`.
+    /// - `('funmulti', ('temp', 1), polygamma, [('param', 0), ('param', 1)])` which means `temp[1] = polygamma(param[0], param[1])`.
     ///
     /// Examples
     /// --------
@@ -11681,6 +11802,23 @@ impl PythonExpressionEvaluator {
                         ],
                     )?);
                 }
+                Instruction::FunMulti(o, f, s) => {
+                    v.push(PyTuple::new(
+                        py,
+                        [
+                            "funmulti".into_pyobject(py)?.as_any(),
+                            slot_to_object(o).into_pyobject(py)?.as_any(),
+                            PythonExpression::from(Atom::new_var(f.get_symbol()))
+                                .into_pyobject(py)?
+                                .as_any(),
+                            s.iter()
+                                .map(slot_to_object)
+                                .collect::<Vec<_>>()
+                                .into_pyobject(py)?
+                                .as_any(),
+                        ],
+                    )?);
+                }
             }
         }
         Ok((
@@ -12338,6 +12476,9 @@ impl PythonMatrix {
                 mode: mode.into(),
                 precision,
                 pretty_matrix,
+                pretty_ascii: false,
+                pretty_big_operators: false,
+                max_line_length: None,
                 hide_all_namespaces: !show_namespaces,
                 color_namespace: true,
                 hide_namespace: Some("python"),
@@ -0,0 +1,374 @@
+//! Versioned binary archives of a working session.
+//!
+//! An [Archive] stores named expressions, polynomials and evaluators together
+//! with the symbol table, including the symbol attributes and namespaces.
+//! The archive can be loaded into another process, even one whose state
+//! already assigned different ids to the symbols:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use symbolica::{
+//!     archive::{Archive, Compression},
+//!     atom::AtomCore,
+//!     domains::integer::Z,
+//!     parse,
+//! };
+//!
+//! let mut archive = Archive::new();
+//! archive.insert_expression("e", parse!("x^2 + f(y)").unwrap());
+//! archive.insert_polynomial("p", &parse!("x + 1").unwrap().to_polynomial::<_, u8>(&Z, None));
+//!
+//! let mut data = vec![];
+//! archive.export(&mut data, Compression::Brotli(6)).unwrap();
+//!
+//! let archive = Archive::import(Cursor::new(&data), None).unwrap();
+//! assert_eq!(archive.expression("e"), Some(&parse!("x^2 + f(y)").unwrap()));
+//! let p = archive.polynomial::<_, u8>("p", &Z).unwrap();
+//! assert_eq!(p.to_expression(), parse!("x + 1").unwrap());
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    io::{Read, Write},
+    sync::Arc,
+};
+
+use brotli::{CompressorWriter, Decompressor};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use smartstring::alias::String as SmartString;
+
+use crate::{
+    atom::{Atom, AtomView},
+    coefficient::{Coefficient, ConvertToRing},
+    domains::{Ring, rational::Rational},
+    evaluate::ExpressionEvaluator,
+    poly::{Exponent, LexOrder, MonomialOrder, Variable, polynomial::MultivariatePolynomial},
+    state::{State, StateMap},
+};
+
+const ARCHIVE_MAGIC: u32 = 0x53594d41;
+const ARCHIVE_FORMAT_VERSION: u16 = 1;
+
+/// A function that yields a new name for a symbol with conflicting attributes.
+type ConflictFn = Option<Box<dyn Fn(&str) -> SmartString>>;
+
+/// The compression of the contents of an [Archive].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Store the contents uncompressed.
+    None,
+    /// Compress the contents with brotli at the given quality, from 0 to 11.
+    Brotli(u32),
+}
+
+/// A polynomial in an archive, with coefficients stored as numbers.
+#[derive(Debug, Clone)]
+struct ArchivedPolynomial {
+    variables: Arc<Vec<Variable>>,
+    coefficients: Vec<Atom>,
+    exponents: Vec<i32>,
+}
+
+/// A self-describing collection of named expressions, polynomials and evaluators
+/// that can be exported to a binary stream together with the state.
+///
+/// The binary format starts with a magic number, a format version and the compression
+/// of the remainder of the stream. Archives are read with [Archive::import], which
+/// merges the symbol table on file with the current state.
+#[derive(Default, Clone)]
+pub struct Archive {
+    expressions: BTreeMap<String, Atom>,
+    polynomials: BTreeMap<String, ArchivedPolynomial>,
+    evaluators: BTreeMap<String, ExpressionEvaluator<Rational>>,
+}
+
+impl Archive {
+    /// Create an empty archive.
+    pub fn new() -> Archive {
+        Archive::default()
+    }
+
+    /// Add an expression with the name `name`, replacing any previous expression with that name.
+    pub fn insert_expression(&mut self, name: &str, expr: Atom) {
+        self.expressions.insert(name.into(), expr);
+    }
+
+    /// Get the expression with the name `name`.
+    pub fn expression(&self, name: &str) -> Option<&Atom> {
+        self.expressions.get(name)
+    }
+
+    /// Iterate over all named expressions.
+    pub fn expressions(&self) -> impl Iterator<Item = (&str, &Atom)> {
+        self.expressions.iter().map(|(n, e)| (n.as_str(), e))
+    }
+
+    /// Add a polynomial and its variables with the name `name`, replacing any previous
+    /// polynomial with that name.
+    pub fn insert_polynomial<R: Ring, E: Exponent, O: MonomialOrder>(
+        &mut self,
+        name: &str,
+        poly: &MultivariatePolynomial<R, E, O>,
+    ) where
+        R::Element: Into<Coefficient>,
+    {
+        let mut coefficients = Vec::with_capacity(poly.nterms());
+        let mut exponents = Vec::with_capacity(poly.nterms() * poly.nvars());
+        for t in poly {
+            coefficients.push(Atom::new_num(t.coefficient.clone()));
+            exponents.extend(t.exponents.iter().map(|e| e.to_i32()));
+        }
+
+        self.polynomials.insert(
+            name.into(),
+            ArchivedPolynomial {
+                variables: poly.variables.clone(),
+                coefficients,
+                exponents,
+            },
+        );
+    }
+
+    /// Get the polynomial with the name `name`, with its coefficients converted to `ring`.
+    /// Returns `None` if there is no such polynomial or if an exponent does not fit in `E`.
+    pub fn polynomial<R: ConvertToRing, E: Exponent>(
+        &self,
+        name: &str,
+        ring: &R,
+    ) -> Option<MultivariatePolynomial<R, E, LexOrder>> {
+        let p = self.polynomials.get(name)?;
+
+        let mut poly =
+            MultivariatePolynomial::new(ring, Some(p.coefficients.len()), p.variables.clone());
+        let n_vars = p.variables.len();
+        let mut exponents = vec![E::zero(); n_vars];
+        for (i, c) in p.coefficients.iter().enumerate() {
+            let AtomView::Num(n) = c.as_view() else {
+                unreachable!("Polynomial coefficient is not a number")
+            };
+
+            for (ee, e) in exponents
+                .iter_mut()
+                .zip(&p.exponents[i * n_vars..(i + 1) * n_vars])
+            {
+                *ee = E::try_from(*e).ok()?;
+            }
+
+            poly.append_monomial(
+                ring.element_from_coefficient_view(n.get_coeff_view()),
+                &exponents,
+            );
+        }
+
+        Some(poly)
+    }
+
+    /// Get the names of all polynomials.
+    pub fn polynomial_names(&self) -> impl Iterator<Item = &str> {
+        self.polynomials.keys().map(|n| n.as_str())
+    }
+
+    /// Add an evaluator with the name `name`, replacing any previous evaluator with that name.
+    ///
+    /// The evaluator can be converted to another number type after loading with
+    /// [ExpressionEvaluator::map_coeff].
+    pub fn insert_evaluator(&mut self, name: &str, evaluator: ExpressionEvaluator<Rational>) {
+        self.evaluators.insert(name.into(), evaluator);
+    }
+
+    /// Get the evaluator with the name `name`.
+    pub fn evaluator(&self, name: &str) -> Option<&ExpressionEvaluator<Rational>> {
+        self.evaluators.get(name)
+    }
+
+    /// Iterate over all named evaluators.
+    pub fn evaluators(&self) -> impl Iterator<Item = (&str, &ExpressionEvaluator<Rational>)> {
+        self.evaluators.iter().map(|(n, e)| (n.as_str(), e))
+    }
+
+    /// Export the archive and the current state to a binary stream.
+    /// The archive can be loaded with [Archive::import].
+    pub fn export<W: Write>(
+        &self,
+        mut dest: W,
+        compression: Compression,
+    ) -> Result<(), std::io::Error> {
+        dest.write_u32::<LittleEndian>(ARCHIVE_MAGIC)?;
+        dest.write_u16::<LittleEndian>(ARCHIVE_FORMAT_VERSION)?;
+
+        match compression {
+            Compression::None => {
+                dest.write_u8(0)?;
+                self.write_contents(&mut dest)
+            }
+            Compression::Brotli(quality) => {
+                dest.write_u8(1)?;
+                let mut writer = CompressorWriter::new(dest, 4096, quality, 22);
+                self.write_contents(&mut writer)?;
+                writer.into_inner().flush()
+            }
+        }
+    }
+
+    fn write_contents<W: Write>(&self, dest: &mut W) -> Result<(), std::io::Error> {
+        State::export(dest)?;
+
+        dest.write_u64::<LittleEndian>(self.expressions.len() as u64)?;
+        for (name, e) in &self.expressions {
+            write_name(dest, name)?;
+            e.as_view().write(&mut *dest)?;
+        }
+
+        dest.write_u64::<LittleEndian>(self.polynomials.len() as u64)?;
+        for (name, p) in &self.polynomials {
+            write_name(dest, name)?;
+
+            dest.write_u64::<LittleEndian>(p.variables.len() as u64)?;
+            for v in p.variables.iter() {
+                State::write_variable(dest, v)?;
+            }
+
+            dest.write_u64::<LittleEndian>(p.coefficients.len() as u64)?;
+            for c in &p.coefficients {
+                c.as_view().write(&mut *dest)?;
+            }
+            for e in &p.exponents {
+                dest.write_i32::<LittleEndian>(*e)?;
+            }
+        }
+
+        dest.write_u64::<LittleEndian>(self.evaluators.len() as u64)?;
+        for (name, e) in &self.evaluators {
+            write_name(dest, name)?;
+            e.write(dest)?;
+        }
+
+        Ok(())
+    }
+
+    /// Import an archive and its state from a binary stream. The state will be merged
+    /// with the current one, and all symbols in the archive are mapped to their ids in the
+    /// current state. If a symbol has conflicting attributes, the conflict
+    /// can be resolved using the renaming function `conflict_fn`.
+    ///
+    /// Archives can be exported using [Archive::export].
+    pub fn import<R: Read>(
+        mut source: R,
+        conflict_fn: ConflictFn,
+    ) -> Result<Archive, std::io::Error> {
+        let magic = source.read_u32::<LittleEndian>()?;
+        if magic != ARCHIVE_MAGIC {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Invalid magic number: the file is not a Symbolica archive",
+            ));
+        }
+
+        let version = source.read_u16::<LittleEndian>()?;
+        if version != ARCHIVE_FORMAT_VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "Unsupported archive format version {}, expected {}",
+                    version, ARCHIVE_FORMAT_VERSION
+                ),
+            ));
+        }
+
+        match source.read_u8()? {
+            0 => Self::read_contents(&mut source, conflict_fn),
+            1 => Self::read_contents(&mut Decompressor::new(source, 4096), conflict_fn),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Unknown archive compression",
+            )),
+        }
+    }
+
+    fn read_contents<R: Read>(
+        source: &mut R,
+        conflict_fn: ConflictFn,
+    ) -> Result<Archive, std::io::Error> {
+        let state_map = State::import(source, conflict_fn)?;
+
+        let mut archive = Archive::new();
+
+        let mut a = Atom::new();
+        let n_expressions = source.read_u64::<LittleEndian>()?;
+        for _ in 0..n_expressions {
+            let name = State::read_str(source)?;
+            a.read(&mut *source)?;
+            archive
+                .expressions
+                .insert(name, a.as_view().rename(&state_map));
+        }
+
+        let n_polynomials = source.read_u64::<LittleEndian>()?;
+        for _ in 0..n_polynomials {
+            let name = State::read_str(source)?;
+            archive
+                .polynomials
+                .insert(name, read_polynomial(source, &state_map)?);
+        }
+
+        let n_evaluators = source.read_u64::<LittleEndian>()?;
+        for _ in 0..n_evaluators {
+            let name = State::read_str(source)?;
+            archive
+                .evaluators
+                .insert(name, ExpressionEvaluator::read(source, &state_map)?);
+        }
+
+        Ok(archive)
+    }
+}
+
+fn write_name<W: Write>(dest: &mut W, name: &str) -> Result<(), std::io::Error> {
+    dest.write_u32::<LittleEndian>(name.len() as u32)?;
+    dest.write_all(name.as_bytes())
+}
+
+fn read_polynomial<R: Read>(
+    source: &mut R,
+    state_map: &StateMap,
+) -> Result<ArchivedPolynomial, std::io::Error> {
+    // the lengths come from the file, so the vectors are not preallocated
+    let n_vars = source.read_u64::<LittleEndian>()?;
+    let mut variables = vec![];
+    for _ in 0..n_vars {
+        variables.push(State::read_variable(source, state_map)?);
+    }
+
+    let n_terms = source.read_u64::<LittleEndian>()?;
+    let mut coefficients = vec![];
+    let mut a = Atom::new();
+    for _ in 0..n_terms {
+        a.read(&mut *source)?;
+        let c = a.as_view().rename(state_map);
+        if !matches!(c, Atom::Num(_)) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Polynomial coefficient is not a number",
+            ));
+        }
+        coefficients.push(c);
+    }
+
+    let mut exponents = vec![];
+    for _ in 0..coefficients.len() * variables.len() {
+        let e = source.read_i32::<LittleEndian>()?;
+        if e < 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Polynomial exponent is negative",
+            ));
+        }
+        exponents.push(e);
+    }
+
+    Ok(ArchivedPolynomial {
+        variables: Arc::new(variables),
+        coefficients,
+        exponents,
+    })
+}
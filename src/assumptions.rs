@@ -0,0 +1,603 @@
+//! Derive properties of the value of an expression, such as its sign,
+//! from the [Assumption]s on its symbols.
+
+use crate::{
+    atom::{Assumption, Atom, AtomView, Symbol},
+    coefficient::CoefficientView,
+    domains::rational::Rational,
+    state::State,
+};
+
+/// A lower or upper bound on a real value.
+#[derive(Clone, Debug, PartialEq)]
+struct Bound {
+    value: Rational,
+    /// The bound itself is excluded.
+    strict: bool,
+}
+
+impl Bound {
+    fn new(value: Rational, strict: bool) -> Bound {
+        Bound { value, strict }
+    }
+
+    /// The sum of two lower or two upper bounds.
+    fn add(&self, other: &Bound) -> Bound {
+        Bound::new(&self.value + &other.value, self.strict || other.strict)
+    }
+
+    /// The bound of `c * x`, where `self` is a bound of `x`.
+    fn scale(&self, c: &Rational) -> Bound {
+        Bound::new(c * &self.value, self.strict)
+    }
+
+    /// Returns `true` iff the value is certainly larger than `c`.
+    fn above(&self, c: &Rational) -> bool {
+        self.value > *c || self.strict && self.value == *c
+    }
+
+    /// Returns `true` iff the value is certainly smaller than `c`.
+    fn below(&self, c: &Rational) -> bool {
+        self.value < *c || self.strict && self.value == *c
+    }
+}
+
+/// Properties of the value of an expression that follow from the
+/// assumptions on its symbols. A property that is `false` is unknown.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Properties {
+    pub(crate) real: bool,
+    pub(crate) integer: bool,
+    /// `Some(true)` if the value is an even integer and `Some(false)` if it is an odd integer.
+    pub(crate) even: Option<bool>,
+    nonzero: bool,
+    /// The lower bound of a real value.
+    lower: Option<Bound>,
+    /// The upper bound of a real value.
+    upper: Option<Bound>,
+}
+
+impl Properties {
+    fn real() -> Properties {
+        Properties {
+            real: true,
+            ..Default::default()
+        }
+    }
+
+    fn constant(r: Rational, exact: bool) -> Properties {
+        let integer = exact && r.is_integer();
+        Properties {
+            real: true,
+            integer,
+            even: integer.then(|| (&r * &Rational::from_unchecked(1, 2)).is_integer()),
+            nonzero: !r.is_zero(),
+            lower: Some(Bound::new(r.clone(), false)),
+            upper: Some(Bound::new(r, false)),
+        }
+    }
+
+    /// A real value in the interval between `min` and `max`.
+    fn interval(min: Option<Bound>, max: Option<Bound>) -> Properties {
+        Properties {
+            real: true,
+            lower: min,
+            upper: max,
+            ..Default::default()
+        }
+        .finish()
+    }
+
+    /// Derive the properties that follow from the others.
+    fn finish(mut self) -> Properties {
+        if !self.real {
+            self.lower = None;
+            self.upper = None;
+        }
+        self.nonzero |= self.is_positive() || self.is_negative() || self.even == Some(false);
+        self
+    }
+
+    fn as_constant(&self) -> Option<&Rational> {
+        match (&self.lower, &self.upper) {
+            (Some(l), Some(u)) if !l.strict && !u.strict && l.value == u.value => Some(&l.value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn is_positive(&self) -> bool {
+        self.lower
+            .as_ref()
+            .is_some_and(|l| l.above(&Rational::zero()))
+    }
+
+    pub(crate) fn is_negative(&self) -> bool {
+        self.upper
+            .as_ref()
+            .is_some_and(|u| u.below(&Rational::zero()))
+    }
+
+    pub(crate) fn is_nonnegative(&self) -> bool {
+        self.lower
+            .as_ref()
+            .is_some_and(|l| l.value >= Rational::zero())
+    }
+
+    pub(crate) fn is_nonpositive(&self) -> bool {
+        self.upper
+            .as_ref()
+            .is_some_and(|u| u.value <= Rational::zero())
+    }
+
+    /// Returns `true` iff the value certainly satisfies the assumption `a`.
+    pub(crate) fn satisfies(&self, a: &Assumption) -> bool {
+        match a {
+            Assumption::Real => self.real,
+            Assumption::Complex => true,
+            Assumption::Positive => self.is_positive(),
+            Assumption::Nonnegative => self.is_nonnegative(),
+            Assumption::Nonzero => self.nonzero,
+            Assumption::Integer => self.integer,
+            Assumption::Even => self.even == Some(true),
+            Assumption::Odd => self.even == Some(false),
+            Assumption::Range(min, max) => {
+                self.lower.as_ref().is_some_and(|l| l.value >= *min)
+                    && self.upper.as_ref().is_some_and(|u| u.value <= *max)
+            }
+        }
+    }
+
+    /// Restrict the value further by the assumption `a`.
+    fn assume(&mut self, a: &Assumption) {
+        let zero = Rational::zero();
+        match a {
+            Assumption::Real => self.real = true,
+            Assumption::Complex => {}
+            Assumption::Positive => {
+                self.real = true;
+                self.raise_lower(Bound::new(zero, true));
+            }
+            Assumption::Nonnegative => {
+                self.real = true;
+                self.raise_lower(Bound::new(zero, false));
+            }
+            Assumption::Nonzero => self.nonzero = true,
+            Assumption::Integer => {
+                self.real = true;
+                self.integer = true;
+            }
+            Assumption::Even | Assumption::Odd => {
+                self.real = true;
+                self.integer = true;
+                self.even = Some(*a == Assumption::Even);
+            }
+            Assumption::Range(min, max) => {
+                self.real = true;
+                self.raise_lower(Bound::new(min.clone(), false));
+                self.lower_upper(Bound::new(max.clone(), false));
+            }
+        }
+    }
+
+    fn raise_lower(&mut self, b: Bound) {
+        if self
+            .lower
+            .as_ref()
+            .is_none_or(|l| b.value > l.value || b.value == l.value && b.strict)
+        {
+            self.lower = Some(b);
+        }
+    }
+
+    fn lower_upper(&mut self, b: Bound) {
+        if self
+            .upper
+            .as_ref()
+            .is_none_or(|u| b.value < u.value || b.value == u.value && b.strict)
+        {
+            self.upper = Some(b);
+        }
+    }
+
+    fn add(&self, other: &Properties) -> Properties {
+        Properties {
+            real: self.real && other.real,
+            integer: self.integer && other.integer,
+            even: self.even.zip(other.even).map(|(a, b)| a == b),
+            nonzero: false,
+            lower: self
+                .lower
+                .as_ref()
+                .zip(other.lower.as_ref())
+                .map(|(a, b)| a.add(b)),
+            upper: self
+                .upper
+                .as_ref()
+                .zip(other.upper.as_ref())
+                .map(|(a, b)| a.add(b)),
+        }
+        .finish()
+    }
+
+    fn mul(&self, other: &Properties) -> Properties {
+        let mut r = Properties {
+            real: self.real && other.real,
+            integer: self.integer && other.integer,
+            even: match (self.even, other.even) {
+                (Some(false), Some(false)) => Some(false),
+                (Some(true), _) | (_, Some(true)) if self.integer && other.integer => Some(true),
+                _ => None,
+            },
+            nonzero: self.nonzero && other.nonzero,
+            lower: None,
+            upper: None,
+        };
+
+        if !r.real {
+            return r.finish();
+        }
+
+        if let Some(c) = self.as_constant() {
+            r.scale_bounds(other, c);
+        } else if let Some(c) = other.as_constant() {
+            r.scale_bounds(self, c);
+        } else if self.is_nonnegative() && other.is_nonnegative() {
+            let (l1, l2) = (self.lower.as_ref().unwrap(), other.lower.as_ref().unwrap());
+            let zero = Rational::zero();
+            let strict = l1.strict && l2.above(&zero)
+                || l2.strict && l1.above(&zero)
+                || l1.strict && l2.strict;
+            r.lower = Some(Bound::new(&l1.value * &l2.value, strict));
+            r.upper = self
+                .upper
+                .as_ref()
+                .zip(other.upper.as_ref())
+                .map(|(u1, u2)| {
+                    let strict = (u1.strict || u2.strict) && u1.value > zero && u2.value > zero;
+                    Bound::new(&u1.value * &u2.value, strict)
+                });
+        } else {
+            // determine the sign
+            let positive = |p: &Properties| (p.is_positive(), p.is_nonnegative());
+            let negative = |p: &Properties| (p.is_negative(), p.is_nonpositive());
+            let zero = Rational::zero();
+            let sign = |(s1, w1): (bool, bool), (s2, w2): (bool, bool)| {
+                if s1 && s2 {
+                    Some(Bound::new(zero.clone(), true))
+                } else if w1 && w2 {
+                    Some(Bound::new(zero.clone(), false))
+                } else {
+                    None
+                }
+            };
+
+            r.lower = sign(negative(self), negative(other));
+            r.upper =
+                sign(positive(self), negative(other)).or(sign(negative(self), positive(other)));
+        }
+
+        r.finish()
+    }
+
+    /// Set the bounds of `c * x`, where `x` has the properties `p`.
+    fn scale_bounds(&mut self, p: &Properties, c: &Rational) {
+        if c.is_negative() {
+            self.lower = p.upper.as_ref().map(|b| b.scale(c));
+            self.upper = p.lower.as_ref().map(|b| b.scale(c));
+        } else {
+            self.lower = p.lower.as_ref().map(|b| b.scale(c));
+            self.upper = p.upper.as_ref().map(|b| b.scale(c));
+        }
+    }
+
+    fn pow(&self, exp: &Properties) -> Properties {
+        let zero = Rational::zero();
+
+        if let Some(n) = exp.as_constant().filter(|n| n.is_integer()) {
+            let mut r = Properties {
+                real: self.real,
+                integer: self.integer && !n.is_negative(),
+                even: self.even.filter(|_| !n.is_negative() && !n.is_zero()),
+                nonzero: self.nonzero,
+                lower: None,
+                upper: None,
+            };
+
+            if !self.real {
+                return r.finish();
+            }
+
+            if exp.even == Some(true) {
+                r.lower = Some(Bound::new(zero, self.nonzero));
+            } else if self.is_nonnegative() {
+                r.lower = Some(Bound::new(zero, self.is_positive()));
+            } else if self.is_nonpositive() {
+                r.upper = Some(Bound::new(zero, self.is_negative()));
+            }
+
+            // monotonic in the base for a nonnegative base and positive exponent
+            if self.is_nonnegative() && !n.is_negative() {
+                if let Some(n) = n.numerator().to_i64().filter(|n| *n <= 64) {
+                    let n = n as u64;
+                    r.lower = self
+                        .lower
+                        .as_ref()
+                        .map(|l| Bound::new(l.value.pow(n), l.strict));
+                    r.upper = self
+                        .upper
+                        .as_ref()
+                        .map(|u| Bound::new(u.value.pow(n), u.strict));
+                }
+            }
+
+            return r.finish();
+        }
+
+        if let Some(c) = self.as_constant() {
+            if *c == Rational::from(-1) && exp.integer {
+                // (-1)^n
+                return Properties {
+                    integer: true,
+                    even: Some(false),
+                    ..Properties::interval(
+                        Some(Bound::new(c.clone(), false)),
+                        Some(Bound::new(Rational::one(), false)),
+                    )
+                }
+                .finish();
+            }
+        }
+
+        if self.is_positive() && exp.real {
+            Properties::interval(Some(Bound::new(zero, true)), None)
+        } else if self.is_nonnegative() && exp.is_positive() {
+            Properties::interval(Some(Bound::new(zero, false)), None)
+        } else {
+            Properties {
+                nonzero: self.nonzero,
+                ..Default::default()
+            }
+        }
+    }
+
+    fn symbol(s: Symbol) -> Properties {
+        match s {
+            Atom::PI => Properties::interval(
+                Some(Bound::new(3.into(), true)),
+                Some(Bound::new(4.into(), true)),
+            ),
+            Atom::E => Properties::interval(
+                Some(Bound::new(2.into(), true)),
+                Some(Bound::new(3.into(), true)),
+            ),
+            Atom::I => Properties {
+                nonzero: true,
+                ..Default::default()
+            },
+            _ => {
+                let mut p = Properties::default();
+                for a in State::get_assumptions(s) {
+                    p.assume(a);
+                }
+                p.finish()
+            }
+        }
+    }
+
+    fn function(s: Symbol, x: &Properties) -> Properties {
+        let zero = Rational::zero();
+        let one = Rational::one();
+        let unit = || {
+            Properties::interval(
+                Some(Bound::new(one.neg(), false)),
+                Some(Bound::new(one.clone(), false)),
+            )
+        };
+        // the sign of an odd, increasing function of a real argument
+        let same_sign = |mut p: Properties| {
+            if x.is_positive() {
+                p.raise_lower(Bound::new(zero.clone(), true));
+            } else if x.is_nonnegative() {
+                p.raise_lower(Bound::new(zero.clone(), false));
+            }
+            if x.is_negative() {
+                p.lower_upper(Bound::new(zero.clone(), true));
+            } else if x.is_nonpositive() {
+                p.lower_upper(Bound::new(zero.clone(), false));
+            }
+            p.finish()
+        };
+
+        match s {
+            Atom::EXP if x.real => Properties::interval(Some(Bound::new(zero, true)), None),
+            Atom::EXP => Properties {
+                nonzero: true,
+                ..Default::default()
+            },
+            Atom::LOG if x.is_positive() => {
+                let l = x.lower.as_ref().unwrap();
+                Properties::interval(
+                    (l.value >= one).then(|| Bound::new(zero.clone(), l.above(&one))),
+                    x.upper
+                        .as_ref()
+                        .filter(|u| u.value <= one)
+                        .map(|u| Bound::new(zero.clone(), u.below(&one))),
+                )
+            }
+            Atom::SQRT if x.is_nonnegative() => {
+                Properties::interval(Some(Bound::new(zero, x.is_positive())), None)
+            }
+            Atom::ABS if x.real => Properties {
+                integer: x.integer,
+                even: x.even,
+                ..Properties::interval(Some(Bound::new(zero, x.nonzero)), None)
+            }
+            .finish(),
+            Atom::ABS => Properties::interval(Some(Bound::new(zero, x.nonzero)), None),
+            Atom::SIN | Atom::COS if x.real => unit(),
+            Atom::SIGN if x.real => same_sign(Properties {
+                integer: true,
+                ..unit()
+            }),
+            Atom::TANH if x.real => same_sign(Properties::interval(
+                Some(Bound::new(one.neg(), true)),
+                Some(Bound::new(one, true)),
+            )),
+            Atom::SINH | Atom::ARCSINH | Atom::ARCTAN if x.real => same_sign(Properties::real()),
+            Atom::COSH if x.real => Properties::interval(Some(Bound::new(one, false)), None),
+            Atom::HEAVISIDE if x.real => {
+                Properties::interval(Some(Bound::new(zero, false)), Some(Bound::new(one, false)))
+            }
+            Atom::GAMMA if x.is_positive() => {
+                Properties::interval(Some(Bound::new(zero, true)), None)
+            }
+            Atom::FACTORIAL | Atom::DOUBLE_FACTORIAL if x.integer && x.is_nonnegative() => {
+                Properties {
+                    integer: true,
+                    ..Properties::interval(Some(Bound::new(one, false)), None)
+                }
+                .finish()
+            }
+            _ => Properties::default(),
+        }
+    }
+}
+
+impl AtomView<'_> {
+    /// Check if the expression contains a symbol with assumptions. The rewrites of the
+    /// normalization that use assumptions are only tried on such expressions.
+    pub(crate) fn has_assumptions(&self) -> bool {
+        match self {
+            AtomView::Num(_) => false,
+            AtomView::Var(v) => !State::get_assumptions(v.get_symbol()).is_empty(),
+            AtomView::Fun(f) => f.iter().any(|a| a.has_assumptions()),
+            AtomView::Pow(p) => {
+                let (b, e) = p.get_base_exp();
+                b.has_assumptions() || e.has_assumptions()
+            }
+            AtomView::Mul(m) => m.iter().any(|a| a.has_assumptions()),
+            AtomView::Add(a) => a.iter().any(|a| a.has_assumptions()),
+        }
+    }
+
+    /// Derive the properties of the value of the expression
+    /// from the assumptions on its symbols.
+    pub(crate) fn properties(&self) -> Properties {
+        match self {
+            AtomView::Num(n) => match n.get_coeff_view() {
+                CoefficientView::Natural(n, d) => {
+                    Properties::constant(Rational::from_unchecked(n, d), true)
+                }
+                CoefficientView::Large(r) => Properties::constant(r.to_rat(), true),
+                CoefficientView::Float(f) => {
+                    let f = f.to_float();
+                    if f.is_finite() {
+                        Properties::constant(f.to_rational(), false)
+                    } else {
+                        Properties::default()
+                    }
+                }
+                CoefficientView::FiniteField(..) | CoefficientView::RationalPolynomial(_) => {
+                    Properties::default()
+                }
+            },
+            AtomView::Var(v) => Properties::symbol(v.get_symbol()),
+            AtomView::Fun(f) => {
+                if f.get_nargs() == 1 {
+                    let x = f.iter().next().unwrap().properties();
+                    Properties::function(f.get_symbol(), &x)
+                } else {
+                    Properties::default()
+                }
+            }
+            AtomView::Pow(p) => {
+                let (b, e) = p.get_base_exp();
+                b.properties().pow(&e.properties())
+            }
+            AtomView::Mul(m) => {
+                let mut it = m.iter();
+                let first = it.next().map(|x| x.properties()).unwrap_or_default();
+                it.fold(first, |acc, x| acc.mul(&x.properties()))
+            }
+            AtomView::Add(a) => {
+                let mut it = a.iter();
+                let first = it.next().map(|x| x.properties()).unwrap_or_default();
+                it.fold(first, |acc, x| acc.add(&x.properties()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        atom::{Assumption, AtomCore, Symbol},
+        parse, wrap_symbol,
+    };
+
+    fn assume(name: &str, assumptions: &[Assumption]) {
+        Symbol::new(wrap_symbol!(name))
+            .with_assumptions(assumptions)
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    fn sign() {
+        assume("xsg", &[Assumption::Positive]);
+        assume("ysg", &[Assumption::Real]);
+        assume("zsg", &[Assumption::Range((-2).into(), (-1).into())]);
+
+        for (e, positive, real) in [
+            ("xsg", true, true),
+            ("ysg", false, true),
+            ("ysg^2+xsg", true, true),
+            ("-zsg*xsg", true, true),
+            ("zsg+1", false, true),
+            ("zsg+3", true, true),
+            ("exp(ysg)", true, true),
+            ("log(xsg)", false, true),
+            ("log(xsg+1)", true, true),
+            ("abs(ysg)+1", true, true),
+            ("2+sin(ysg)", true, true),
+            ("𝜋-3", true, true),
+            ("xsg^(1/2)*ysg^2", false, true),
+            ("xsg^ysg", true, true),
+            ("wsg", false, false),
+            ("𝑖*xsg", false, false),
+        ] {
+            let a = parse!(e).unwrap();
+            assert_eq!(a.is_positive(), positive, "{}", e);
+            assert_eq!(a.is_real(), real, "{}", e);
+        }
+    }
+
+    #[test]
+    fn parity() {
+        assume("npar", &[Assumption::Integer]);
+        assume("mpar", &[Assumption::Odd]);
+
+        let p = parse!("2*npar+mpar").unwrap().as_view().properties();
+        assert_eq!(p.even, Some(false));
+        assert!(p.nonzero);
+
+        let p = parse!("npar*(mpar+1)").unwrap().as_view().properties();
+        assert_eq!(p.even, Some(true));
+        assert!(p.integer);
+    }
+
+    #[test]
+    fn normal_form() {
+        assume("xnf", &[Assumption::Positive]);
+
+        // the rewrites only use the assumptions on the symbols in the expression
+        for e in ["log(exp(2))", "abs(exp(2))", "(2^(1/2))^ynf", "(-1)^ynf"] {
+            assert_eq!(parse!(e).unwrap().to_string(), e);
+        }
+
+        assert_eq!(
+            parse!("log(exp(xnf)) + (xnf^(1/2))^ynf").unwrap(),
+            parse!("xnf + xnf^(ynf/2)").unwrap()
+        );
+    }
+}
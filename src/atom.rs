@@ -48,7 +48,8 @@ use smartstring::{LazyCompact, SmartString};
 
 use crate::{
     coefficient::Coefficient,
-    parser::Token,
+    domains::rational::Rational,
+    parser::{ParseMode, Syntax, Token},
     printer::{AtomPrinter, PrintFunction, PrintOptions},
     state::{RecycledAtom, State, Workspace},
     transformer::StatsOptions,
@@ -280,6 +281,97 @@ pub enum FunctionAttribute {
     Cyclesymmetric,
     /// The function is linear.
     Linear,
+    /// The symbol is noncommutative: its relative order in products is preserved.
+    Noncommutative,
+    /// The symbol is Grassmann-odd: it anticommutes with other Grassmann-odd symbols
+    /// and squares to zero.
+    Grassmann,
+    /// The function is idempotent: `f(f(x)) = f(x)`.
+    Idempotent,
+    /// The function is an involution: `f(f(x)) = x`.
+    Involution,
+    /// The function is associative: `f(a, f(b, c)) = f(a, b, c)`.
+    Associative,
+    /// Numerical factors are pulled out of every argument: `f(2*x, 3*y) = 6*f(x, y)`.
+    Homogeneous,
+}
+
+impl FunctionAttribute {
+    /// Returns `true` iff the attribute is stored in the symbol itself, instead
+    /// of in the symbol data of the state.
+    pub(crate) fn is_packed(&self) -> bool {
+        matches!(
+            self,
+            FunctionAttribute::Symmetric
+                | FunctionAttribute::Antisymmetric
+                | FunctionAttribute::Cyclesymmetric
+                | FunctionAttribute::Linear
+                | FunctionAttribute::Noncommutative
+                | FunctionAttribute::Grassmann
+        )
+    }
+}
+
+/// Assumptions on the value of a symbol, which are used to simplify expressions
+/// during normalization and can be queried with [AtomCore::is_positive] and [AtomCore::is_real].
+///
+/// # Examples
+///
+/// ```
+/// use symbolica::{atom::{Assumption, Symbol}, parse, wrap_symbol};
+///
+/// Symbol::new(wrap_symbol!("x")).with_assumptions(&[Assumption::Positive]).build().unwrap();
+/// assert_eq!(parse!("sqrt(x^2)").unwrap(), parse!("x").unwrap());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Assumption {
+    /// The symbol is real.
+    Real,
+    /// The symbol is complex. This is the default and does not enable any simplification.
+    Complex,
+    /// The symbol is real and positive.
+    Positive,
+    /// The symbol is real and nonnegative.
+    Nonnegative,
+    /// The symbol is not zero.
+    Nonzero,
+    /// The symbol is an integer.
+    Integer,
+    /// The symbol is an even integer.
+    Even,
+    /// The symbol is an odd integer.
+    Odd,
+    /// The symbol is real and lies in the closed interval `[min, max]`.
+    Range(Rational, Rational),
+}
+
+impl Assumption {
+    /// Check that the assumptions do not contradict each other.
+    fn check_consistency(assumptions: &[Assumption]) -> Result<(), String> {
+        let has = |a: &Assumption| assumptions.contains(a);
+
+        if has(&Assumption::Even) && has(&Assumption::Odd) {
+            return Err("A symbol cannot be both even and odd".into());
+        }
+
+        if has(&Assumption::Complex)
+            && assumptions
+                .iter()
+                .any(|a| !matches!(a, Assumption::Complex | Assumption::Nonzero))
+        {
+            return Err("A complex symbol cannot have assumptions that imply it is real".into());
+        }
+
+        for a in assumptions {
+            if let Assumption::Range(min, max) = a {
+                if min > max {
+                    return Err(format!("Empty range [{}, {}]", min, max));
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// A symbol, for example the name of a variable or the name of a function,
@@ -311,6 +403,8 @@ pub struct Symbol {
     is_antisymmetric: bool,
     is_cyclesymmetric: bool,
     is_linear: bool,
+    is_noncommutative: bool,
+    is_grassmann: bool,
 }
 
 impl std::fmt::Debug for Symbol {
@@ -333,6 +427,8 @@ impl std::fmt::Display for Symbol {
 pub struct SymbolBuilder {
     symbol: NamespacedSymbol,
     attributes: Option<Cow<'static, [FunctionAttribute]>>,
+    linear_arguments: Option<Vec<usize>>,
+    assumptions: Option<Vec<Assumption>>,
     normalization_function: Option<NormalizationFunction>,
     print_function: Option<PrintFunction>,
 }
@@ -344,6 +440,8 @@ impl SymbolBuilder {
         SymbolBuilder {
             symbol,
             attributes: None,
+            linear_arguments: None,
+            assumptions: None,
             normalization_function: None,
             print_function: None,
         }
@@ -372,6 +470,59 @@ impl SymbolBuilder {
         self
     }
 
+    /// Make the function linear in the arguments at the positions `args` only, for example
+    /// `f(x + y, z + w) = f(x, z + w) + f(y, z + w)` for `args = [0]`. Numerical factors
+    /// of these arguments are pulled out of the function.
+    ///
+    /// This function will return an error when an existing symbol is redefined
+    /// with different linear arguments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolica::{atom::Symbol, parse, wrap_symbol};
+    ///
+    /// Symbol::new(wrap_symbol!("f")).with_linear_arguments(&[0]).build().unwrap();
+    /// assert_eq!(
+    ///     parse!("f(2*x + y, z + w)").unwrap(),
+    ///     parse!("2*f(x, z + w) + f(y, z + w)").unwrap()
+    /// );
+    /// ```
+    pub fn with_linear_arguments(mut self, args: &[usize]) -> Self {
+        let mut args = args.to_vec();
+        args.sort_unstable();
+        args.dedup();
+        self.linear_arguments = Some(args);
+        self
+    }
+
+    /// Set assumptions on the value of the symbol, that are used to simplify expressions.
+    ///
+    /// This function will return an error when an existing symbol is redefined
+    /// with different assumptions, or when the assumptions contradict each other.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolica::{atom::{Assumption, AtomCore, Symbol}, parse, wrap_symbol};
+    ///
+    /// let n = Symbol::new(wrap_symbol!("n"))
+    ///     .with_assumptions(&[Assumption::Integer])
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(parse!("(-1)^(2*n)").unwrap(), parse!("1").unwrap());
+    /// assert!(parse!("n^2 + 1").unwrap().is_positive());
+    /// ```
+    pub fn with_assumptions(mut self, assumptions: &[Assumption]) -> Self {
+        self.assumptions = Some(assumptions.to_vec());
+        self
+    }
+
+    /// Set a function that is called after the arguments of the function are normalized
+    /// and the built-in attributes are applied. Any previously set normalization function
+    /// is replaced. Use [add_normalization_function](Self::add_normalization_function)
+    /// to compose several functions.
+    ///
     /// ```
     /// use symbolica::{atom::{AtomView, Symbol}, wrap_symbol};
     ///
@@ -397,6 +548,66 @@ impl SymbolBuilder {
         self
     }
 
+    /// Add a normalization function that is applied after the previously set ones.
+    /// The added function only receives the output of the earlier ones if it is still
+    /// a call of the same function.
+    ///
+    /// ```
+    /// use symbolica::{atom::{Atom, AtomView, Symbol}, parse, wrap_symbol};
+    ///
+    /// Symbol::new(wrap_symbol!("f"))
+    ///     .with_normalization_function(|view, out| {
+    ///         // f(0, x) = 0
+    ///         if let AtomView::Fun(f) = view {
+    ///             if f.get_nargs() == 2 && f.iter().next().unwrap().is_zero() {
+    ///                 *out = Atom::new();
+    ///                 return true;
+    ///             }
+    ///         }
+    ///         false
+    ///     })
+    ///     .add_normalization_function(|view, out| {
+    ///         // f(x) = x
+    ///         if let AtomView::Fun(f) = view {
+    ///             if f.get_nargs() == 1 {
+    ///                 *out = f.iter().next().unwrap().to_owned();
+    ///                 return true;
+    ///             }
+    ///         }
+    ///         false
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(parse!("f(0, y)").unwrap(), Atom::new());
+    /// assert_eq!(parse!("f(y)").unwrap(), parse!("y").unwrap());
+    /// ```
+    pub fn add_normalization_function(
+        mut self,
+        normalization_function: impl Fn(AtomView, &mut Atom) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.normalization_function = Some(match self.normalization_function.take() {
+            Some(prev) => Box::new(move |view, out: &mut Atom| {
+                if !prev(view, out) {
+                    return normalization_function(view, out);
+                }
+
+                if let (AtomView::Fun(f), AtomView::Fun(g)) = (view, out.as_view()) {
+                    if f.get_symbol() == g.get_symbol() {
+                        let mut next = Atom::new();
+                        if normalization_function(out.as_view(), &mut next) {
+                            *out = next;
+                        }
+                    }
+                }
+
+                true
+            }),
+            None => Box::new(normalization_function),
+        });
+        self
+    }
+
     /// ```
     /// use symbolica::{atom::Symbol, wrap_symbol};
     ///
@@ -421,7 +632,13 @@ impl SymbolBuilder {
     /// This function will return an error when an existing symbol is redefined
     /// with different attributes.
     pub fn build(self) -> Result<Symbol, SmartString<LazyCompact>> {
+        if let Some(assumptions) = &self.assumptions {
+            Assumption::check_consistency(assumptions)?;
+        }
+
         if self.attributes.is_none()
+            && self.linear_arguments.is_none()
+            && self.assumptions.is_none()
             && self.normalization_function.is_none()
             && self.print_function.is_none()
         {
@@ -430,6 +647,8 @@ impl SymbolBuilder {
             State::get_state_mut().get_symbol_with_attributes(
                 self.symbol,
                 self.attributes.as_ref().map(|x| x.as_ref()).unwrap_or(&[]),
+                self.linear_arguments.as_deref().unwrap_or(&[]),
+                self.assumptions.as_deref().unwrap_or(&[]),
                 self.normalization_function,
                 self.print_function,
             )
@@ -578,6 +797,51 @@ impl Symbol {
         self.is_linear
     }
 
+    /// Check if the symbol is noncommutative.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolica::symbol;
+    ///
+    /// let a = symbol!("a"; Noncommutative).unwrap();
+    /// assert!(a.is_noncommutative());
+    /// ```
+    pub fn is_noncommutative(&self) -> bool {
+        self.is_noncommutative
+    }
+
+    /// Check if the symbol is Grassmann-odd.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolica::symbol;
+    ///
+    /// let theta = symbol!("theta"; Grassmann).unwrap();
+    /// assert!(theta.is_grassmann());
+    /// ```
+    pub fn is_grassmann(&self) -> bool {
+        self.is_grassmann
+    }
+
+    /// Get the assumptions on the value of the symbol.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolica::{atom::{Assumption, Symbol}, wrap_symbol};
+    ///
+    /// let x = Symbol::new(wrap_symbol!("x"))
+    ///     .with_assumptions(&[Assumption::Real])
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(x.get_assumptions(), &[Assumption::Real]);
+    /// ```
+    pub fn get_assumptions(&self) -> &'static [Assumption] {
+        State::get_assumptions(*self)
+    }
+
     /// Returns `true` iff this identifier is defined by Symbolica.
     pub fn is_builtin(id: Symbol) -> bool {
         State::is_builtin(id)
@@ -593,6 +857,8 @@ impl Symbol {
             is_antisymmetric: false,
             is_cyclesymmetric: false,
             is_linear: false,
+            is_noncommutative: false,
+            is_grassmann: false,
         }
     }
 
@@ -613,9 +879,23 @@ impl Symbol {
             is_antisymmetric,
             is_cyclesymmetric,
             is_linear,
+            is_noncommutative: false,
+            is_grassmann: false,
         }
     }
 
+    /// Expert use: set whether the symbol is noncommutative.
+    pub(crate) const fn with_noncommutative(mut self, is_noncommutative: bool) -> Self {
+        self.is_noncommutative = is_noncommutative;
+        self
+    }
+
+    /// Expert use: set whether the symbol is Grassmann-odd.
+    pub(crate) const fn with_grassmann(mut self, is_grassmann: bool) -> Self {
+        self.is_grassmann = is_grassmann;
+        self
+    }
+
     pub fn format<W: std::fmt::Write>(
         &self,
         opts: &PrintOptions,
@@ -631,6 +911,24 @@ impl Symbol {
             }
         }
 
+        if opts.mode.is_sympy() {
+            let sympy_name = match *self {
+                Atom::EQUAL => Some("Eq"),
+                Atom::UNEQUAL => Some("Ne"),
+                Atom::LESS => Some("Lt"),
+                Atom::LESS_EQUAL => Some("Le"),
+                Atom::GREATER => Some("Gt"),
+                Atom::GREATER_EQUAL => Some("Ge"),
+                Atom::FACTORIAL => Some("factorial"),
+                Atom::DOUBLE_FACTORIAL => Some("factorial2"),
+                _ => None,
+            };
+
+            if let Some(name) = sympy_name {
+                return f.write_str(name);
+            }
+        }
+
         if opts.mode.is_latex() {
             match *self {
                 Atom::E => f.write_char('e'),
@@ -640,6 +938,15 @@ impl Symbol {
                 Atom::SIN => f.write_str("\\sin"),
                 Atom::EXP => f.write_str("\\exp"),
                 Atom::LOG => f.write_str("\\log"),
+                Atom::TAN => f.write_str("\\tan"),
+                Atom::ARCSIN => f.write_str("\\arcsin"),
+                Atom::ARCCOS => f.write_str("\\arccos"),
+                Atom::ARCTAN => f.write_str("\\arctan"),
+                Atom::SINH => f.write_str("\\sinh"),
+                Atom::COSH => f.write_str("\\cosh"),
+                Atom::TANH => f.write_str("\\tanh"),
+                Atom::GAMMA => f.write_str("\\Gamma"),
+                Atom::ZETA => f.write_str("\\zeta"),
                 _ => {
                     f.write_str(name)?;
                     if !opts.hide_all_namespaces {
@@ -649,9 +956,33 @@ impl Symbol {
                     }
                 }
             }
+        } else if opts.mode.is_form() && State::is_builtin(*self) {
+            match *self {
+                Atom::E => f.write_str("ee_"),
+                Atom::PI => f.write_str("pi_"),
+                Atom::I => f.write_str("i_"),
+                Atom::SQRT => f.write_str("sqrt_"),
+                Atom::EXP => f.write_str("eexp_"),
+                Atom::LOG => f.write_str("ln_"),
+                Atom::SIN => f.write_str("sin_"),
+                Atom::COS => f.write_str("cos_"),
+                Atom::TAN => f.write_str("tan_"),
+                Atom::ARCSIN => f.write_str("asin_"),
+                Atom::ARCCOS => f.write_str("acos_"),
+                Atom::ARCTAN => f.write_str("atan_"),
+                Atom::SINH => f.write_str("sinh_"),
+                Atom::COSH => f.write_str("cosh_"),
+                Atom::TANH => f.write_str("tanh_"),
+                Atom::ARCSINH => f.write_str("asinh_"),
+                Atom::ARCCOSH => f.write_str("acosh_"),
+                Atom::ARCTANH => f.write_str("atanh_"),
+                Atom::ABS => f.write_str("abs_"),
+                Atom::FACTORIAL => f.write_str("fac_"),
+                _ => f.write_str(name),
+            }
         } else {
             if !opts.hide_all_namespaces
-                && !State::is_builtin(*self)
+                && !State::is_reserved(*self)
                 && opts.hide_namespace != Some(namespace)
             {
                 if opts.color_namespace {
@@ -992,6 +1323,52 @@ impl AtomView<'_> {
         }
     }
 
+    /// Check if the expression is noncommutative, i.e., if it contains a noncommutative
+    /// symbol whose relative order in a product matters. Arguments of commutative
+    /// functions are not considered.
+    pub fn is_noncommutative(&self) -> bool {
+        match self {
+            AtomView::Num(_) => false,
+            AtomView::Var(v) => v.is_noncommutative(),
+            AtomView::Fun(f) => f.is_noncommutative(),
+            AtomView::Pow(p) => p.get_base().is_noncommutative(),
+            AtomView::Mul(m) => m.iter().any(|x| x.is_noncommutative()),
+            AtomView::Add(a) => a.iter().any(|x| x.is_noncommutative()),
+        }
+    }
+
+    /// Get the Grassmann parity of the expression: `Some(false)` if it is even,
+    /// `Some(true)` if it is odd and `None` if it is a sum of even and odd terms.
+    /// Arguments of functions are not considered.
+    pub fn grassmann_parity(&self) -> Option<bool> {
+        match self {
+            AtomView::Num(_) => Some(false),
+            AtomView::Var(v) => Some(v.is_grassmann()),
+            AtomView::Fun(f) => Some(f.is_grassmann()),
+            AtomView::Pow(p) => match p.get_base().grassmann_parity() {
+                Some(false) => Some(false),
+                _ => None,
+            },
+            AtomView::Mul(m) => {
+                let mut odd = false;
+                for x in m.iter() {
+                    odd ^= x.grassmann_parity()?;
+                }
+                Some(odd)
+            }
+            AtomView::Add(a) => {
+                let mut it = a.iter();
+                let parity = it.next()?.grassmann_parity()?;
+                for x in it {
+                    if x.grassmann_parity()? != parity {
+                        return None;
+                    }
+                }
+                Some(parity)
+            }
+        }
+    }
+
     /// Subtract two atoms and return the buffer that contains the unnormalized result.
     fn sub_no_norm(&self, workspace: &Workspace, rhs: AtomView<'_>) -> RecycledAtom {
         let mut e = workspace.new_atom();
@@ -1193,6 +1570,85 @@ impl Atom {
     pub const I: Symbol = State::I;
     /// The mathematical constant `π`.
     pub const PI: Symbol = State::PI;
+    // The names of the symbols below are not reserved. They are in the `symbolica` namespace
+    // and are parsed from `symbolica::tan(x)`, whereas `tan(x)` refers to a user symbol.
+
+    /// The tangent function.
+    pub const TAN: Symbol = State::TAN;
+    /// The inverse sine function.
+    pub const ARCSIN: Symbol = State::ARCSIN;
+    /// The inverse cosine function.
+    pub const ARCCOS: Symbol = State::ARCCOS;
+    /// The inverse tangent function. With two arguments `arctan(y, x)`, it is the
+    /// two-argument inverse tangent that takes the quadrant of `(x, y)` into account.
+    pub const ARCTAN: Symbol = State::ARCTAN;
+    /// The hyperbolic sine function.
+    pub const SINH: Symbol = State::SINH;
+    /// The hyperbolic cosine function.
+    pub const COSH: Symbol = State::COSH;
+    /// The hyperbolic tangent function.
+    pub const TANH: Symbol = State::TANH;
+    /// The inverse hyperbolic sine function.
+    pub const ARCSINH: Symbol = State::ARCSINH;
+    /// The inverse hyperbolic cosine function.
+    pub const ARCCOSH: Symbol = State::ARCCOSH;
+    /// The inverse hyperbolic tangent function.
+    pub const ARCTANH: Symbol = State::ARCTANH;
+    /// The absolute value.
+    pub const ABS: Symbol = State::ABS;
+    /// The sign function, which is `0` at `0`.
+    pub const SIGN: Symbol = State::SIGN;
+    /// The Heaviside step function `heaviside(x)`, which is `1/2` at `0`.
+    pub const HEAVISIDE: Symbol = State::HEAVISIDE;
+    /// The gamma function `Γ(x)`.
+    pub const GAMMA: Symbol = State::GAMMA;
+    /// The polygamma function `polygamma(n, x)`, the `n`th derivative of the digamma function.
+    pub const POLYGAMMA: Symbol = State::POLYGAMMA;
+    /// The beta function `beta(a, b) = Γ(a)Γ(b)/Γ(a+b)`.
+    pub const BETA: Symbol = State::BETA;
+    /// The error function.
+    pub const ERF: Symbol = State::ERF;
+    /// The Riemann zeta function.
+    pub const ZETA: Symbol = State::ZETA;
+    /// The polylogarithm `li(n, x)` of order `n`.
+    pub const POLYLOG: Symbol = State::POLYLOG;
+    /// The Bessel function of the first kind `bessel_j(ν, x)`.
+    pub const BESSEL_J: Symbol = State::BESSEL_J;
+    /// The Bessel function of the second kind `bessel_y(ν, x)`.
+    pub const BESSEL_Y: Symbol = State::BESSEL_Y;
+    /// The modified Bessel function of the first kind `bessel_i(ν, x)`.
+    pub const BESSEL_I: Symbol = State::BESSEL_I;
+    /// The modified Bessel function of the second kind `bessel_k(ν, x)`.
+    pub const BESSEL_K: Symbol = State::BESSEL_K;
+    /// The multiple (Goncharov) polylogarithm `mpl(a_1, ..., a_n, x)`, written as
+    /// `G(a_1, ..., a_n; x)` in the literature.
+    pub const MPL: Symbol = State::MPL;
+    /// The nested harmonic sum `hsum(m_1, ..., m_k, n)`, written as
+    /// `S_{m_1, ..., m_k}(n)` in the literature. Negative indices denote alternating sums.
+    pub const HSUM: Symbol = State::HSUM;
+    // The symbols below are produced by the parser for `{...}`, relations and `!`. They are
+    // builtins, so that the parser, the normalization and the printers can recognize them
+    // by a fixed id, and so that printed output parses back to the same symbols.
+
+    /// The list `list(a_1, ..., a_n)`, written as `{a_1, ..., a_n}`. The tuple `(a_1, ..., a_n)`
+    /// is parsed as a list as well.
+    pub const LIST: Symbol = State::LIST;
+    /// The relation `equal(a, b)`, written as `a == b`.
+    pub const EQUAL: Symbol = State::EQUAL;
+    /// The relation `unequal(a, b)`, written as `a != b`.
+    pub const UNEQUAL: Symbol = State::UNEQUAL;
+    /// The relation `less(a, b)`, written as `a < b`.
+    pub const LESS: Symbol = State::LESS;
+    /// The relation `less_equal(a, b)`, written as `a <= b`.
+    pub const LESS_EQUAL: Symbol = State::LESS_EQUAL;
+    /// The relation `greater(a, b)`, written as `a > b`.
+    pub const GREATER: Symbol = State::GREATER;
+    /// The relation `greater_equal(a, b)`, written as `a >= b`.
+    pub const GREATER_EQUAL: Symbol = State::GREATER_EQUAL;
+    /// The factorial `factorial(n) = Γ(n+1)`, written as `n!`.
+    pub const FACTORIAL: Symbol = State::FACTORIAL;
+    /// The double factorial `factorial2(n) = n(n-2)(n-4)...`, written as `n!!`.
+    pub const DOUBLE_FACTORIAL: Symbol = State::DOUBLE_FACTORIAL;
 
     /// Exponentiate the atom.
     pub fn exp(&self) -> Atom {
@@ -1330,6 +1786,39 @@ impl Atom {
         Workspace::get_local().with(|ws| Token::parse(input.data)?.to_atom(&input, ws))
     }
 
+    /// Parse an atom from a namespaced string written in the syntax `mode`,
+    /// mapping the builtin functions and constants of that syntax to Symbolica's.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use symbolica::{parse, wrap_input};
+    /// use symbolica::atom::Atom;
+    /// use symbolica::parser::ParseMode;
+    /// let a = Atom::parse_with_mode(wrap_input!("Sin[x]^2 + Pi"), ParseMode::Mathematica);
+    /// assert_eq!(a.unwrap(), parse!("sin(x)^2 + 𝜋").unwrap());
+    /// ```
+    pub fn parse_with_mode(input: DefaultNamespace, mode: ParseMode) -> Result<Atom, String> {
+        Workspace::get_local()
+            .with(|ws| Token::parse_with_mode(input.data, mode)?.to_atom(&input, ws))
+    }
+
+    /// Parse an atom from a namespaced string, using the custom operators and
+    /// subscript setting of `syntax` instead of those of the global syntax.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use symbolica::{parse, wrap_input};
+    /// use symbolica::atom::Atom;
+    /// use symbolica::parser::Syntax;
+    /// let syntax = Syntax::new().with_subscripts(true);
+    /// let a = Atom::parse_with_syntax(wrap_input!("p_mu"), &syntax);
+    /// assert_eq!(a.unwrap(), parse!("p(mu)").unwrap());
+    /// ```
+    pub fn parse_with_syntax(input: DefaultNamespace, syntax: &Syntax) -> Result<Atom, String> {
+        Workspace::get_local()
+            .with(|ws| Token::parse_with_syntax(input.data, syntax)?.to_atom(&input, ws))
+    }
+
     #[inline]
     pub fn new_var(id: Symbol) -> Atom {
         Var::new(id).into()
@@ -1659,7 +2148,13 @@ macro_rules! function {
 /// You can specify attributes for the symbol, using `;` as a separator
 /// between symbol names and attributes. The options
 /// are [Symmetric](FunctionAttribute::Symmetric), [Antisymmetric](FunctionAttribute::Antisymmetric),
-/// [Cyclesymmetric](FunctionAttribute::Cyclesymmetric), and [Linear](FunctionAttribute::Linear).
+/// [Cyclesymmetric](FunctionAttribute::Cyclesymmetric), [Linear](FunctionAttribute::Linear),
+/// [Noncommutative](FunctionAttribute::Noncommutative), [Grassmann](FunctionAttribute::Grassmann),
+/// [Idempotent](FunctionAttribute::Idempotent), [Involution](FunctionAttribute::Involution),
+/// [Associative](FunctionAttribute::Associative),
+/// and [Homogeneous](FunctionAttribute::Homogeneous).
+/// A function that is linear in some of its arguments only can be defined with
+/// [SymbolBuilder::with_linear_arguments].
 /// ```
 /// use symbolica::symbol;
 /// let x = symbol!("x"; Symmetric, Linear);
@@ -1914,7 +2409,7 @@ mod test {
         let x = parse!("v1+f1(v2)").unwrap();
         assert_eq!(
             format!("{:?}", x),
-            "AddView { data: [5, 17, 2, 13, 2, 1, 12, 3, 5, 0, 0, 0, 1, 42, 2, 1, 13] }"
+            "AddView { data: [5, 17, 2, 13, 2, 1, 46, 3, 5, 0, 0, 0, 1, 76, 2, 1, 47] }"
         );
         assert_eq!(
             x.get_all_symbols(true),
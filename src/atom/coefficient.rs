@@ -578,6 +578,102 @@ impl PackedRationalNumberReader for [u8] {
     }
 }
 
+/// Read a little-endian natural number of `size` bytes from untrusted data.
+fn checked_natural(source: &[u8], size: u8) -> Option<(u64, &[u8])> {
+    let (bytes, rest) = source.split_at_checked(size as usize)?;
+    let mut buf = [0; 8];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    Some((u64::from_le_bytes(buf), rest))
+}
+
+/// Read a packed fraction from untrusted data, returning `None` if the data is malformed.
+/// The sign is ignored.
+pub(crate) fn checked_frac_u64(source: &[u8]) -> Option<(u64, u64, &[u8])> {
+    let (&disc, source) = source.split_first()?;
+
+    let num_size = match disc & NUM_MASK {
+        x @ (U8_NUM | U16_NUM | U32_NUM | U64_NUM) => get_size_of_natural(x),
+        _ => return None,
+    };
+    let (num, source) = checked_natural(source, num_size)?;
+
+    match (disc & DEN_MASK) >> 4 {
+        0 => Some((num, 1, source)),
+        x @ (U8_NUM | U16_NUM | U32_NUM | U64_NUM) => {
+            let (den, source) = checked_natural(source, get_size_of_natural(x))?;
+            Some((num, den, source))
+        }
+        _ => None,
+    }
+}
+
+/// Check that untrusted data starts with a well-formed coefficient and return the remaining data.
+/// The finite fields and variable lists that the coefficient refers to must exist in the state.
+pub(crate) fn check_coefficient(source: &[u8]) -> Option<&[u8]> {
+    let (&disc, rest) = source.split_first()?;
+
+    if disc == RAT_POLY {
+        let (len, rest) = checked_natural(rest, 4)?;
+        let (data, rest) = rest.split_at_checked(len as usize)?;
+        check_rational_polynomial(data)?;
+        Some(rest)
+    } else if disc == FLOAT {
+        let (len, rest) = checked_natural(rest, 8)?;
+        let (data, rest) = rest.split_at_checked(usize::try_from(len).ok()?)?;
+        let (prec, digits) = checked_natural(data, 4)?;
+        if prec < rug::float::prec_min() as u64 || prec > rug::float::prec_max() as u64 {
+            return None;
+        }
+        let f = rug::Float::parse_radix(digits, 16).ok()?;
+        rug::Float::with_val(prec as u32, f)
+            .is_finite()
+            .then_some(rest)
+    } else if (disc & NUM_MASK) == ARB_NUM {
+        let (num_len, den_len, rest) = checked_frac_u64(rest)?;
+        let (num, rest) = rest.split_at_checked(usize::try_from(num_len).ok()?)?;
+        let (den, rest) = rest.split_at_checked(usize::try_from(den_len).ok()?)?;
+        (!num.is_empty() && den.iter().any(|d| *d != 0)).then_some(rest)
+    } else if (disc & NUM_MASK) == FIN_NUM {
+        let (_, index, rest) = checked_frac_u64(rest)?;
+        State::has_finite_field(FiniteFieldIndex(usize::try_from(index).ok()?)).then_some(rest)
+    } else {
+        let (num, den, rest) = checked_frac_u64(source)?;
+        (num <= i64::MAX as u64 && den != 0 && den <= i64::MAX as u64).then_some(rest)
+    }
+}
+
+/// Check that untrusted data is a well-formed serialized rational polynomial.
+fn check_rational_polynomial(source: &[u8]) -> Option<()> {
+    let (index, num_nterms, source) = checked_frac_u64(source)?;
+    let (den_nterms, _, mut source) = checked_frac_u64(source)?;
+
+    let index = VariableListIndex(usize::try_from(index).ok()?);
+    if den_nterms == 0 || !State::has_variable_list(index) {
+        return None;
+    }
+
+    let nterms = usize::try_from(num_nterms.checked_add(den_nterms)?).ok()?;
+    let exp_len = nterms
+        .checked_mul(State::get_variable_list(index).len())?
+        .checked_mul(2)?;
+    source = source.get(exp_len..)?;
+
+    for _ in 0..nterms {
+        let (&tag, rest) = source.split_first()?;
+        source = match tag {
+            1 => rest.get(8..)?,
+            2 => rest.get(16..)?,
+            4 | 5 => {
+                let (len, _, rest) = checked_frac_u64(rest)?;
+                rest.get(usize::try_from(len).ok()?..)?
+            }
+            _ => return None,
+        };
+    }
+
+    source.is_empty().then_some(())
+}
+
 impl PackedRationalNumberWriter for (i64, u64) {
     #[inline(always)]
     fn write_packed(&self, dest: &mut Vec<u8>) {
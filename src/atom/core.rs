@@ -476,6 +476,67 @@ pub trait AtomCore {
             .series(x, expansion_point.as_atom_view(), depth, depth_is_absolute)
     }
 
+    /// Expand products of multiple polylogarithms `mpl` with the same argument using the shuffle product
+    /// and products of harmonic sums `hsum` with the same upper bound and integer indices using the
+    /// quasi-shuffle product.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use symbolica::{atom::AtomCore, parse};
+    /// let expr = parse!("symbolica::hsum(1, n)^2").unwrap();
+    /// let r = parse!("2*symbolica::hsum(1, 1, n) - symbolica::hsum(2, n)").unwrap();
+    /// assert_eq!(expr.expand_polylog_products(), r);
+    /// ```
+    fn expand_polylog_products(&self) -> Atom {
+        self.as_atom_view().expand_polylog_products()
+    }
+
+    /// Write all multiple polylogarithms `mpl` and harmonic sums `hsum` with integer indices as
+    /// polynomials in the functions whose indices form Lyndon words, with respect to the canonical
+    /// ordering of the indices. The Lyndon words form a basis of the shuffle and quasi-shuffle algebras.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use symbolica::{atom::AtomCore, parse};
+    /// let expr = parse!("symbolica::mpl(1, 0, x)").unwrap();
+    /// let r = parse!("symbolica::mpl(0, x) * symbolica::mpl(1, x) - symbolica::mpl(0, 1, x)")
+    ///     .unwrap();
+    /// assert_eq!(expr.to_lyndon_basis(), r);
+    /// ```
+    fn to_lyndon_basis(&self) -> Atom {
+        self.as_atom_view().to_lyndon_basis()
+    }
+
+    /// Convert multiple polylogarithms `mpl(a_1,...,a_n,x)` to classical multiple polylogarithms
+    /// `li(m_1,...,m_k,x_1,...,x_k)`, extracting trailing zeros as powers of `log(x)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use symbolica::{atom::AtomCore, parse};
+    /// let expr = parse!("symbolica::mpl(0, a, x)").unwrap();
+    /// assert_eq!(expr.mpl_to_li(), parse!("-symbolica::li(2, x/a)").unwrap());
+    /// ```
+    fn mpl_to_li(&self) -> Atom {
+        self.as_atom_view().mpl_to_li()
+    }
+
+    /// Convert classical multiple polylogarithms `li(m_1,...,m_k,x_1,...,x_k)` with positive integer orders
+    /// to multiple polylogarithms `mpl(a_1,...,a_n,1)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use symbolica::{atom::AtomCore, parse};
+    /// let expr = parse!("symbolica::li(3, x)").unwrap();
+    /// assert_eq!(expr.li_to_mpl(), parse!("-symbolica::mpl(0, 0, 1/x, 1)").unwrap());
+    /// ```
+    fn li_to_mpl(&self) -> Atom {
+        self.as_atom_view().li_to_mpl()
+    }
+
     /// Find the root of a function in `x` numerically over the reals using Newton's method.
     ///
     /// # Example
@@ -863,6 +924,9 @@ pub trait AtomCore {
     /// added to the variable map. Similarly, non-polynomial parts are automatically
     /// defined as a new independent variable in the polynomial.
     ///
+    /// A product of Grassmann-odd factors, which cannot be multiplied as polynomial
+    /// variables, is converted to a single opaque variable.
+    ///
     /// # Example
     ///
     /// ```
@@ -898,6 +962,10 @@ pub trait AtomCore {
     ///
     /// This routine does not perform expansions.
     ///
+    /// # Panics
+    ///
+    /// Panics if a variable in `var_map` is Grassmann-odd.
+    ///
     /// # Example
     ///
     /// ```
@@ -923,6 +991,9 @@ pub trait AtomCore {
     /// added to the variable map. Similarly, non-rational polynomial parts are automatically
     /// defined as a new independent variable in the rational polynomial.
     ///
+    /// A product of Grassmann-odd factors, which cannot be multiplied as polynomial
+    /// variables, is converted to a single opaque variable.
+    ///
     /// # Example
     ///
     /// ```
@@ -956,6 +1027,9 @@ pub trait AtomCore {
     /// added to the variable map. Similarly, non-rational polynomial parts are automatically
     /// defined as a new independent variable in the rational polynomial.
     ///
+    /// A product of Grassmann-odd factors, which cannot be multiplied as polynomial
+    /// variables, is converted to a single opaque variable.
+    ///
     /// # Example
     ///
     /// ```
@@ -1167,6 +1241,36 @@ pub trait AtomCore {
         self.as_atom_view().get_all_indeterminates(enter_functions)
     }
 
+    /// Returns `true` iff the expression is known to be positive, using
+    /// the [Assumption](crate::atom::Assumption)s on its symbols.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use symbolica::{atom::{Assumption, AtomCore, Symbol}, parse, wrap_symbol};
+    /// Symbol::new(wrap_symbol!("x")).with_assumptions(&[Assumption::Real]).build().unwrap();
+    /// assert!(parse!("x^2 + 1").unwrap().is_positive());
+    /// assert!(!parse!("x + 1").unwrap().is_positive());
+    /// ```
+    fn is_positive(&self) -> bool {
+        self.as_atom_view().properties().is_positive()
+    }
+
+    /// Returns `true` iff the expression is known to be real, using
+    /// the [Assumption](crate::atom::Assumption)s on its symbols.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use symbolica::{atom::{Assumption, AtomCore, Symbol}, parse, wrap_symbol};
+    /// Symbol::new(wrap_symbol!("x")).with_assumptions(&[Assumption::Positive]).build().unwrap();
+    /// assert!(parse!("log(x) + sin(x)").unwrap().is_real());
+    /// assert!(!parse!("log(-x)").unwrap().is_real());
+    /// ```
+    fn is_real(&self) -> bool {
+        self.as_atom_view().properties().real
+    }
+
     /// Returns true iff `self` contains the symbol `s`.
     ///
     /// # Example
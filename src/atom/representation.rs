@@ -17,7 +17,9 @@ use crate::{
 
 use super::{
     Atom, AtomView, SliceType, Symbol,
-    coefficient::{PackedRationalNumberReader, PackedRationalNumberWriter},
+    coefficient::{
+        PackedRationalNumberReader, PackedRationalNumberWriter, check_coefficient, checked_frac_u64,
+    },
 };
 
 const NUM_ID: u8 = 1;
@@ -37,6 +39,9 @@ const FUN_LINEAR_FLAG: u8 = 0b01000000;
 const VAR_ANTISYMMETRIC_FLAG: u8 = 0b10000000;
 const VAR_CYCLESYMMETRIC_FLAG: u8 = 0b10100000; // coded as symmetric | antisymmetric
 const FUN_ANTISYMMETRIC_FLAG: u64 = 1 << 32; // stored in the function id
+const SYMBOL_NONCOMMUTATIVE_FLAG: u64 = 1 << 33; // stored in the variable or function id
+const SYMBOL_GRASSMANN_FLAG: u64 = 1 << 34; // stored in the variable or function id
+const SYMBOL_ID_MASK: u64 = u32::MAX as u64;
 const MUL_HAS_COEFF_FLAG: u8 = 0b01000000;
 
 const ZERO_DATA: [u8; 3] = [NUM_ID, 1, 0];
@@ -98,8 +103,16 @@ impl InlineVar {
 
         data[0] = flags;
 
-        let size = 1 + (symbol.id as u64, 1).get_packed_size() as u8;
-        (symbol.id as u64, 1).write_packed_fixed(&mut data[1..]);
+        let mut id = symbol.id as u64;
+        if symbol.is_noncommutative {
+            id |= SYMBOL_NONCOMMUTATIVE_FLAG;
+        }
+        if symbol.is_grassmann {
+            id |= SYMBOL_GRASSMANN_FLAG;
+        }
+
+        let size = 1 + (id, 1).get_packed_size() as u8;
+        (id, 1).write_packed_fixed(&mut data[1..]);
         InlineVar { data, size }
     }
 
@@ -244,6 +257,58 @@ impl<C: crate::state::HasStateMap> bincode::Decode<C> for Atom {
     }
 }
 
+/// Check that untrusted data starts with a well-formed atom and return the remaining data.
+/// All symbols, finite fields and variable lists that the atom refers to must exist in the state.
+fn check_atom(data: &[u8]) -> Option<&[u8]> {
+    let (&flags, rest) = data.split_first()?;
+
+    let check_args = |mut args: &[u8], n_args: u64| {
+        let mut last = None;
+        for _ in 0..n_args {
+            last = args.first().map(|t| t & TYPE_MASK);
+            args = check_atom(args)?;
+        }
+        args.is_empty().then_some(last)
+    };
+
+    match flags & TYPE_MASK {
+        NUM_ID => check_coefficient(rest),
+        VAR_ID => {
+            let (id, _, rest) = checked_frac_u64(rest)?;
+            State::has_symbol_id((id & SYMBOL_ID_MASK) as u32).then_some(rest)
+        }
+        FUN_ID | MUL_ID => {
+            let (size, rest) = rest.split_at_checked(4)?;
+            let size = u32::from_le_bytes(size.try_into().unwrap()) as usize;
+            let (body, rest) = rest.split_at_checked(size)?;
+
+            if flags & TYPE_MASK == FUN_ID {
+                let (id, n_args, args) = checked_frac_u64(body)?;
+                if !State::has_symbol_id((id & SYMBOL_ID_MASK) as u32) {
+                    return None;
+                }
+                check_args(args, n_args)?;
+            } else {
+                let (n_args, _, args) = checked_frac_u64(body)?;
+                let last = check_args(args, n_args)?;
+                if flags & MUL_HAS_COEFF_FLAG != 0 && last != Some(NUM_ID) {
+                    return None;
+                }
+            }
+
+            Some(rest)
+        }
+        ADD_ID => {
+            let (n_args, size, rest) = checked_frac_u64(rest)?;
+            let (args, rest) = rest.split_at_checked(usize::try_from(size).ok()?)?;
+            check_args(args, n_args)?;
+            Some(rest)
+        }
+        POW_ID => check_atom(check_atom(rest)?),
+        _ => None,
+    }
+}
+
 impl Atom {
     /// Read from a binary stream. The format is the byte-length first
     /// followed by the data.
@@ -259,19 +324,37 @@ impl Atom {
 
         let n_size = u64::from_le_bytes(size_buf);
 
-        dest.extend(size_buf);
-        dest.resize(n_size as usize, 0);
-        source.read_exact(&mut dest)?;
+        // grow the buffer as data arrives, as the size may be corrupt
+        dest.clear();
+        (&mut source).take(n_size).read_to_end(&mut dest)?;
+        if dest.len() as u64 != n_size {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "Atom data is truncated",
+            ));
+        }
+
+        if check_atom(&dest).is_none_or(|rest| !rest.is_empty()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Malformed atom data",
+            ));
+        }
 
         unsafe {
-            match dest[0] & TYPE_MASK {
-                NUM_ID => *self = Atom::Num(Num::from_raw(dest)),
-                VAR_ID => *self = Atom::Var(Var::from_raw(dest)),
-                FUN_ID => *self = Atom::Fun(Fun::from_raw(dest)),
-                MUL_ID => *self = Atom::Mul(Mul::from_raw(dest)),
-                ADD_ID => *self = Atom::Add(Add::from_raw(dest)),
-                POW_ID => *self = Atom::Pow(Pow::from_raw(dest)),
-                _ => unreachable!("Unknown type {}", dest[0]),
+            match dest.first().map(|t| t & TYPE_MASK) {
+                Some(NUM_ID) => *self = Atom::Num(Num::from_raw(dest)),
+                Some(VAR_ID) => *self = Atom::Var(Var::from_raw(dest)),
+                Some(FUN_ID) => *self = Atom::Fun(Fun::from_raw(dest)),
+                Some(MUL_ID) => *self = Atom::Mul(Mul::from_raw(dest)),
+                Some(ADD_ID) => *self = Atom::Add(Add::from_raw(dest)),
+                Some(POW_ID) => *self = Atom::Pow(Pow::from_raw(dest)),
+                _ => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "Unknown atom type",
+                    ));
+                }
             }
         }
 
@@ -497,7 +580,15 @@ impl Var {
 
         self.data.put_u8(flags);
 
-        (symbol.id as u64, 1).write_packed(&mut self.data);
+        let mut id = symbol.id as u64;
+        if symbol.is_noncommutative {
+            id |= SYMBOL_NONCOMMUTATIVE_FLAG;
+        }
+        if symbol.is_grassmann {
+            id |= SYMBOL_GRASSMANN_FLAG;
+        }
+
+        (id, 1).write_packed(&mut self.data);
     }
 
     #[inline]
@@ -578,12 +669,19 @@ impl Fun {
 
         let buf_pos = self.data.len();
 
-        let id = if symbol.is_antisymmetric || symbol.is_cyclesymmetric {
+        let mut id = if symbol.is_antisymmetric || symbol.is_cyclesymmetric {
             symbol.id as u64 | FUN_ANTISYMMETRIC_FLAG
         } else {
             symbol.id as u64
         };
 
+        if symbol.is_noncommutative {
+            id |= SYMBOL_NONCOMMUTATIVE_FLAG;
+        }
+        if symbol.is_grassmann {
+            id |= SYMBOL_GRASSMANN_FLAG;
+        }
+
         (id, 0).write_packed(&mut self.data);
 
         let new_buf_pos = self.data.len();
@@ -1087,15 +1185,30 @@ impl<'a> VarView<'a> {
     #[inline(always)]
     pub fn get_symbol(&self) -> Symbol {
         let is_cyclesymmetric = self.data[0] & VAR_CYCLESYMMETRIC_FLAG == VAR_CYCLESYMMETRIC_FLAG;
+        let id = self.data[1..].get_frac_u64().0;
 
         Symbol::raw_fn(
-            self.data[1..].get_frac_u64().0 as u32,
+            (id & SYMBOL_ID_MASK) as u32,
             self.get_wildcard_level(),
             !is_cyclesymmetric && self.data[0] & FUN_SYMMETRIC_FLAG == FUN_SYMMETRIC_FLAG,
             !is_cyclesymmetric && self.data[0] & VAR_ANTISYMMETRIC_FLAG == VAR_ANTISYMMETRIC_FLAG,
             is_cyclesymmetric,
             self.data[0] & FUN_LINEAR_FLAG == FUN_LINEAR_FLAG,
         )
+        .with_noncommutative(id & SYMBOL_NONCOMMUTATIVE_FLAG == SYMBOL_NONCOMMUTATIVE_FLAG)
+        .with_grassmann(id & SYMBOL_GRASSMANN_FLAG == SYMBOL_GRASSMANN_FLAG)
+    }
+
+    /// Check if the variable is noncommutative.
+    #[inline(always)]
+    pub fn is_noncommutative(&self) -> bool {
+        self.data[1..].get_frac_u64().0 & SYMBOL_NONCOMMUTATIVE_FLAG == SYMBOL_NONCOMMUTATIVE_FLAG
+    }
+
+    /// Check if the variable is Grassmann-odd.
+    #[inline(always)]
+    pub fn is_grassmann(&self) -> bool {
+        self.data[1..].get_frac_u64().0 & SYMBOL_GRASSMANN_FLAG == SYMBOL_GRASSMANN_FLAG
     }
 
     #[inline(always)]
@@ -1186,13 +1299,29 @@ impl<'a> FunView<'a> {
             && id & FUN_ANTISYMMETRIC_FLAG == FUN_ANTISYMMETRIC_FLAG;
 
         Symbol::raw_fn(
-            id as u32,
+            (id & SYMBOL_ID_MASK) as u32,
             self.get_wildcard_level(),
             !is_cyclesymmetric && self.data[0] & FUN_SYMMETRIC_FLAG == FUN_SYMMETRIC_FLAG,
             !is_cyclesymmetric && id & FUN_ANTISYMMETRIC_FLAG == FUN_ANTISYMMETRIC_FLAG,
             is_cyclesymmetric,
             self.is_linear(),
         )
+        .with_noncommutative(id & SYMBOL_NONCOMMUTATIVE_FLAG == SYMBOL_NONCOMMUTATIVE_FLAG)
+        .with_grassmann(id & SYMBOL_GRASSMANN_FLAG == SYMBOL_GRASSMANN_FLAG)
+    }
+
+    /// Check if the function is noncommutative.
+    #[inline(always)]
+    pub fn is_noncommutative(&self) -> bool {
+        let id = self.data[1 + 4..].get_frac_u64().0;
+        id & SYMBOL_NONCOMMUTATIVE_FLAG == SYMBOL_NONCOMMUTATIVE_FLAG
+    }
+
+    /// Check if the function is Grassmann-odd.
+    #[inline(always)]
+    pub fn is_grassmann(&self) -> bool {
+        let id = self.data[1 + 4..].get_frac_u64().0;
+        id & SYMBOL_GRASSMANN_FLAG == SYMBOL_GRASSMANN_FLAG
     }
 
     #[inline(always)]
@@ -248,6 +248,144 @@ fn unique_permutations_impl<T: Clone>(
     }
 }
 
+/// Compute the shuffle product of the words `a` and `b`, i.e., all interleavings of `a` and `b`
+/// that preserve the order of the letters of each word. Returns the distinct words with their
+/// multiplicity, sorted by word.
+///
+/// # Example
+///
+/// ```
+/// # use symbolica::combinatorics::shuffle;
+/// let s = shuffle(&['a', 'b'], &['a']);
+/// assert_eq!(s, vec![(2.into(), vec!['a', 'a', 'b']), (1.into(), vec!['a', 'b', 'a'])]);
+/// ```
+pub fn shuffle<T: Clone + Hash + Ord>(a: &[T], b: &[T]) -> Vec<(Integer, Vec<T>)> {
+    // every unique permutation of the mask is a unique way to interleave the words
+    let mut mask = vec![false; a.len()];
+    mask.resize(a.len() + b.len(), true);
+
+    let mut words: HashMap<Vec<T>, Integer> = HashMap::default();
+    for m in unique_permutations(&mask).1 {
+        let (mut ia, mut ib) = (a.iter(), b.iter());
+        let w = m
+            .iter()
+            .map(|from_b| {
+                if *from_b {
+                    ib.next().unwrap().clone()
+                } else {
+                    ia.next().unwrap().clone()
+                }
+            })
+            .collect();
+        *words.entry(w).or_insert(Integer::zero()) += 1;
+    }
+
+    let mut res: Vec<_> = words.into_iter().map(|(w, c)| (c, w)).collect();
+    res.sort_by(|a, b| a.1.cmp(&b.1));
+    res
+}
+
+/// Compute the quasi-shuffle (stuffle) product of the words `a` and `b`. Besides the
+/// interleavings of the shuffle product, a letter of `a` and a letter of `b` may be contracted
+/// into a single letter using `contract`, which returns the contracted letter and its coefficient.
+/// Returns the distinct words with their multiplicity, sorted by word.
+///
+/// # Example
+///
+/// Harmonic sums `Z_{m_1,...}(n)` with strictly nested summations satisfy the quasi-shuffle
+/// relation with the contraction `(1, m_1 + m_2)`:
+/// ```
+/// # use symbolica::combinatorics::quasi_shuffle;
+/// let s = quasi_shuffle(&[1], &[2], &|a, b| (1.into(), a + b));
+/// assert_eq!(s, vec![(1.into(), vec![1, 2]), (1.into(), vec![2, 1]), (1.into(), vec![3])]);
+/// ```
+pub fn quasi_shuffle<T: Clone + Hash + Ord, F: Fn(&T, &T) -> (Integer, T)>(
+    a: &[T],
+    b: &[T],
+    contract: &F,
+) -> Vec<(Integer, Vec<T>)> {
+    fn rec<T: Clone + Hash + Ord, F: Fn(&T, &T) -> (Integer, T)>(
+        a: &[T],
+        b: &[T],
+        contract: &F,
+        coeff: &Integer,
+        accum: &mut Vec<T>,
+        words: &mut HashMap<Vec<T>, Integer>,
+    ) {
+        if a.is_empty() || b.is_empty() {
+            let mut w = accum.clone();
+            w.extend_from_slice(a);
+            w.extend_from_slice(b);
+            *words.entry(w).or_insert(Integer::zero()) += coeff;
+            return;
+        }
+
+        accum.push(a[0].clone());
+        rec(&a[1..], b, contract, coeff, accum, words);
+        accum.pop();
+
+        accum.push(b[0].clone());
+        rec(a, &b[1..], contract, coeff, accum, words);
+        accum.pop();
+
+        let (c, l) = contract(&a[0], &b[0]);
+        if !c.is_zero() {
+            accum.push(l);
+            rec(&a[1..], &b[1..], contract, &(c * coeff), accum, words);
+            accum.pop();
+        }
+    }
+
+    let mut words = HashMap::default();
+    rec(a, b, contract, &Integer::one(), &mut vec![], &mut words);
+
+    let mut res: Vec<_> = words
+        .into_iter()
+        .filter(|(_, c)| !c.is_zero())
+        .map(|(w, c)| (c, w))
+        .collect();
+    res.sort_by(|a, b| a.1.cmp(&b.1));
+    res
+}
+
+/// Returns `true` if `word` is a Lyndon word, i.e., if it is non-empty and strictly smaller than all of its
+/// proper rotations.
+pub fn is_lyndon_word<T: Ord>(word: &[T]) -> bool {
+    !word.is_empty() && lyndon_factorization(word).len() == 1
+}
+
+/// Compute the Chen-Fox-Lyndon factorization of `word` into a lexicographically non-increasing
+/// sequence of Lyndon words using Duval's algorithm.
+///
+/// # Example
+///
+/// ```
+/// # use symbolica::combinatorics::lyndon_factorization;
+/// let f = lyndon_factorization(&[1, 2, 1, 2, 1]);
+/// assert_eq!(f, vec![&[1, 2][..], &[1, 2], &[1]]);
+/// ```
+pub fn lyndon_factorization<T: Ord>(word: &[T]) -> Vec<&[T]> {
+    let mut factors = vec![];
+    let mut i = 0;
+    while i < word.len() {
+        let (mut j, mut k) = (i + 1, i);
+        while j < word.len() && word[k] <= word[j] {
+            if word[k] < word[j] {
+                k = i;
+            } else {
+                k += 1;
+            }
+            j += 1;
+        }
+
+        while i <= k {
+            factors.push(&word[i..i + j - k]);
+            i += j - k;
+        }
+    }
+    factors
+}
+
 /// Partition the unordered list `elements` into named bins of unordered lists with a given length,
 /// returning all partitions and their multiplicity.
 ///
@@ -447,7 +585,10 @@ pub fn partitions<T: Ord + Hash + Copy, B: Ord + Hash + Copy>(
 
 #[cfg(test)]
 mod test {
-    use super::{CombinationIterator, partitions};
+    use super::{
+        CombinationIterator, is_lyndon_word, lyndon_factorization, partitions, quasi_shuffle,
+        shuffle,
+    };
 
     #[test]
     fn combinations() {
@@ -496,4 +637,39 @@ mod test {
 
         assert_eq!(p, res);
     }
+
+    #[test]
+    fn shuffle_product() {
+        let s = shuffle(&[1, 2], &[3, 4]);
+        assert_eq!(s.len(), 6);
+        assert!(s.iter().all(|(c, _)| c.is_one()));
+        assert_eq!(s[0].1, vec![1, 2, 3, 4]);
+
+        let s = shuffle(&[1, 1], &[1]);
+        assert_eq!(s, vec![(3.into(), vec![1, 1, 1])]);
+    }
+
+    #[test]
+    fn stuffle_product() {
+        // S-sums: S_a S_b = S_{a,b} + S_{b,a} - S_{a+b}
+        let s = quasi_shuffle(&[1, 1], &[2], &|a, b| ((-1).into(), a + b));
+        let res = vec![
+            (1.into(), vec![1, 1, 2]),
+            (1.into(), vec![1, 2, 1]),
+            ((-1).into(), vec![1, 3]),
+            (1.into(), vec![2, 1, 1]),
+            ((-1).into(), vec![3, 1]),
+        ];
+        assert_eq!(s, res);
+    }
+
+    #[test]
+    fn lyndon() {
+        assert!(is_lyndon_word(&[0, 0, 1, 0, 1]));
+        assert!(!is_lyndon_word(&[0, 1, 0]));
+        assert!(!is_lyndon_word::<u8>(&[]));
+
+        let f = lyndon_factorization(&[3, 1, 2, 1, 1, 2]);
+        assert_eq!(f, vec![&[3][..], &[1, 2], &[1, 1, 2]]);
+    }
 }
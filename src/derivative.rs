@@ -3,13 +3,16 @@ use std::{
     sync::Arc,
 };
 
+use smallvec::SmallVec;
+
 use crate::{
-    atom::{Atom, AtomView, FunctionBuilder, Symbol},
+    atom::{Atom, AtomCore, AtomView, FunctionBuilder, Symbol, representation::FunView},
     coefficient::{Coefficient, CoefficientView},
     combinatorics::CombinationWithReplacementIterator,
     domains::{atom::AtomField, integer::Integer, rational::Rational},
+    function,
     poly::{Variable, series::Series},
-    state::Workspace,
+    state::{State, Workspace},
 };
 
 impl AtomView<'_> {
@@ -141,6 +144,16 @@ impl AtomView<'_> {
                 let mut n = workspace.new_atom();
                 let mut mul = workspace.new_atom();
                 for (index, arg_der) in args_der {
+                    if !is_der {
+                        if let Some(p) = Self::builtin_partial_derivative(f, index) {
+                            let m = mul.to_mul();
+                            m.extend(p.as_view());
+                            m.extend(arg_der.as_view());
+                            a.extend(mul.as_view());
+                            continue;
+                        }
+                    }
+
                     let p = fn_der.to_fun(Atom::DERIVATIVE);
 
                     if is_der {
@@ -258,34 +271,50 @@ impl AtomView<'_> {
                 true
             }
             AtomView::Mul(args) => {
+                // the left derivative in a Grassmann-odd variable picks up a sign for
+                // every odd factor it passes, which requires factors with a definite parity
+                let grassmann = x.is_grassmann();
+                if grassmann && args.iter().any(|a| a.grassmann_parity().is_none()) {
+                    let mut expanded = workspace.new_atom();
+                    if self.expand_with_ws_into(workspace, None, &mut expanded) {
+                        return expanded
+                            .as_view()
+                            .derivative_with_ws_into(x, workspace, out);
+                    }
+                }
+
                 let mut add_h = workspace.new_atom();
                 let add = add_h.to_add();
                 let mut mul_h = workspace.new_atom();
                 let mut non_zero = false;
-                for arg in args.iter() {
+                let mut odd_prefix = false;
+                for (i, arg) in args.iter().enumerate() {
                     let mut arg_der = workspace.new_atom();
                     if arg.derivative_with_ws_into(x, workspace, &mut arg_der) {
-                        if let Atom::Mul(mm) = arg_der.deref_mut() {
-                            for other_arg in args.iter() {
-                                if other_arg != arg {
-                                    mm.extend(other_arg);
-                                }
+                        // keep the derivative at the position of the factor, as factors may not commute
+                        let mm = mul_h.to_mul();
+                        for (j, other_arg) in args.iter().enumerate() {
+                            if i == j {
+                                mm.extend(arg_der.as_view());
+                            } else {
+                                mm.extend(other_arg);
                             }
+                        }
 
-                            add.extend(arg_der.as_view());
-                        } else {
-                            let mm = mul_h.to_mul();
-                            mm.extend(arg_der.as_view());
-                            for other_arg in args.iter() {
-                                if other_arg != arg {
-                                    mm.extend(other_arg);
-                                }
-                            }
-                            add.extend(mul_h.as_view());
+                        if odd_prefix {
+                            let mut sign = workspace.new_atom();
+                            sign.to_num((-1).into());
+                            mm.extend(sign.as_view());
                         }
 
+                        add.extend(mul_h.as_view());
+
                         non_zero = true;
                     }
+
+                    if grassmann && arg.grassmann_parity() == Some(true) {
+                        odd_prefix = !odd_prefix;
+                    }
                 }
 
                 if non_zero {
@@ -319,6 +348,80 @@ impl AtomView<'_> {
         }
     }
 
+    /// Compute the partial derivative of the builtin function `f` with respect
+    /// to its argument at position `index`. Returns `None` if `f` is not a builtin
+    /// function or if the derivative has no closed form, such as for derivatives in the order
+    /// of special functions.
+    fn builtin_partial_derivative(f: FunView, index: usize) -> Option<Atom> {
+        let id = f.get_symbol();
+        if !State::is_builtin(id) || id.get_id() < Atom::TAN.get_id() {
+            return None;
+        }
+
+        let args: SmallVec<[Atom; 2]> = f.iter().map(|a| a.to_owned()).collect();
+        let one = || Atom::new_num(1);
+        let pi = || Atom::new_var(Atom::PI);
+
+        let r = match (id, args.as_slice(), index) {
+            (Atom::TAN, [x], _) => function!(Atom::COS, x).npow(-2),
+            (Atom::ARCSIN, [x], _) => (one() - x.npow(2)).npow((-1, 2)),
+            (Atom::ARCCOS, [x], _) => -(one() - x.npow(2)).npow((-1, 2)),
+            (Atom::ARCTAN, [x], _) => (one() + x.npow(2)).npow(-1),
+            (Atom::ARCTAN, [y, x], 0) => x / (x.npow(2) + y.npow(2)),
+            (Atom::ARCTAN, [y, x], 1) => -y / (x.npow(2) + y.npow(2)),
+            (Atom::SINH, [x], _) => function!(Atom::COSH, x),
+            (Atom::COSH, [x], _) => function!(Atom::SINH, x),
+            (Atom::TANH, [x], _) => function!(Atom::COSH, x).npow(-2),
+            (Atom::ARCSINH, [x], _) => (one() + x.npow(2)).npow((-1, 2)),
+            (Atom::ARCCOSH, [x], _) => (x.npow(2) - one()).npow((-1, 2)),
+            (Atom::ARCTANH, [x], _) => (one() - x.npow(2)).npow(-1),
+            (Atom::ABS, [x], _) => function!(Atom::SIGN, x),
+            (Atom::GAMMA, [x], _) => function!(Atom::GAMMA, x) * function!(Atom::POLYGAMMA, 0, x),
+            (Atom::FACTORIAL, [x], _) => {
+                function!(Atom::FACTORIAL, x) * function!(Atom::POLYGAMMA, 0, x + one())
+            }
+            (Atom::POLYGAMMA, [n, x], 1) => function!(Atom::POLYGAMMA, n + one(), x),
+            (Atom::BETA, [a, b], _) => {
+                let d = if index == 0 { a } else { b };
+                function!(Atom::BETA, a, b)
+                    * (function!(Atom::POLYGAMMA, 0, d) - function!(Atom::POLYGAMMA, 0, a + b))
+            }
+            (Atom::ERF, [x], _) => pi().npow((-1, 2)) * function!(Atom::EXP, -x.npow(2)) * 2,
+            (Atom::POLYLOG, [n, x], 1) => function!(Atom::POLYLOG, n - one(), x) / x,
+            (Atom::MPL, [a, rest @ .., x], _) if index == args.len() - 1 => {
+                // d/dx mpl(a_1, a_2, ..., x) = mpl(a_2, ..., x)/(x - a_1)
+                FunctionBuilder::new(Atom::MPL)
+                    .add_args(rest)
+                    .add_arg(x)
+                    .finish()
+                    / (x - a)
+            }
+            (Atom::BESSEL_J, [nu, x], 1) => {
+                (function!(Atom::BESSEL_J, nu - one(), x)
+                    - function!(Atom::BESSEL_J, nu + one(), x))
+                    / 2
+            }
+            (Atom::BESSEL_Y, [nu, x], 1) => {
+                (function!(Atom::BESSEL_Y, nu - one(), x)
+                    - function!(Atom::BESSEL_Y, nu + one(), x))
+                    / 2
+            }
+            (Atom::BESSEL_I, [nu, x], 1) => {
+                (function!(Atom::BESSEL_I, nu - one(), x)
+                    + function!(Atom::BESSEL_I, nu + one(), x))
+                    / 2
+            }
+            (Atom::BESSEL_K, [nu, x], 1) => {
+                (function!(Atom::BESSEL_K, nu - one(), x)
+                    + function!(Atom::BESSEL_K, nu + one(), x))
+                    / -2
+            }
+            _ => return None,
+        };
+
+        Some(r)
+    }
+
     /// Series expand in `x` around `expansion_point` to depth `depth`.
     pub fn series(
         &self,
@@ -369,6 +472,194 @@ impl AtomView<'_> {
         }
     }
 
+    /// Series expand the builtin special function `f` in `x` by Taylor expanding it around
+    /// the constant term of the argument that depends on `x`. Returns `None` if `f` is not a
+    /// builtin special function or if more than one argument depends on `x`.
+    fn builtin_series(
+        f: FunView,
+        x: Symbol,
+        expansion_point: AtomView,
+        info: &Series<AtomField>,
+        args_series: &mut [Series<AtomField>],
+    ) -> Option<Result<Series<AtomField>, &'static str>> {
+        let id = f.get_symbol();
+        if !State::is_builtin(id) || id.get_id() < Atom::TAN.get_id() {
+            return None;
+        }
+
+        let args: SmallVec<[Atom; 2]> = f.iter().map(|a| a.to_owned()).collect();
+
+        if let (Atom::BETA, [a, b]) = (id, args.as_slice()) {
+            let g = function!(Atom::GAMMA, a) * function!(Atom::GAMMA, b)
+                / function!(Atom::GAMMA, a + b);
+            return Some(g.as_view().series_impl(x, expansion_point, info));
+        }
+
+        let mut dependent = args
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| a.contains_symbol(x))
+            .map(|(i, _)| i);
+        let index = dependent.next()?;
+        if dependent.next().is_some() {
+            return None;
+        }
+
+        match id {
+            Atom::HSUM => return None,
+            Atom::POLYLOG if args.len() != 2 => return None,
+            Atom::MPL if index != args.len() - 1 => return None,
+            _ => {}
+        }
+
+        let arg = &mut args_series[index];
+        if arg.get_trailing_exponent().is_negative() {
+            return Some(Err("Cannot series expand special function with poles"));
+        }
+
+        let c = if arg.get_trailing_exponent().is_zero() {
+            let c = arg.get_trailing_coefficient();
+            *arg = &*arg - &arg.constant(c.clone());
+            c
+        } else {
+            Atom::new_num(0)
+        };
+
+        let c_num = if let AtomView::Num(n) = c.as_view() {
+            match n.get_coeff_view() {
+                CoefficientView::Natural(n, d) => Some(Rational::from_unchecked(n, d)),
+                CoefficientView::Large(r) => Some(r.to_rat()),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        if let Some(c_num) = &c_num {
+            let is_order_integer =
+                matches!(args[0].as_view(), AtomView::Num(n) if n.get_coeff_view().is_integer());
+
+            let singular = match id {
+                Atom::ZETA | Atom::POLYLOG => c_num.is_one(),
+                Atom::BESSEL_Y | Atom::BESSEL_K => c_num.is_zero(),
+                Atom::BESSEL_J | Atom::BESSEL_I => c_num.is_zero() && !is_order_integer,
+                Atom::ABS | Atom::SIGN | Atom::HEAVISIDE => c_num.is_zero(),
+                Atom::MPL => args[..index].iter().any(|a| match a.as_view() {
+                    AtomView::Num(n) => match n.get_coeff_view() {
+                        CoefficientView::Natural(n, d) => Rational::from_unchecked(n, d) == *c_num,
+                        CoefficientView::Large(r) => r.to_rat() == *c_num,
+                        _ => false,
+                    },
+                    _ => false,
+                }),
+                Atom::ARCSIN | Atom::ARCCOS | Atom::ARCCOSH | Atom::ARCTANH => c_num.abs().is_one(),
+                _ => false,
+            };
+
+            if singular {
+                return Some(Err(
+                    "Cannot series expand special function at a singular point",
+                ));
+            }
+
+            // use the recurrence relation to move away from the poles
+            if (id == Atom::GAMMA || id == Atom::POLYGAMMA)
+                && c_num.is_integer()
+                && (c_num.is_zero() || c_num.is_negative())
+            {
+                let m = c_num.numerator_ref().to_i64()?.unsigned_abs() as i64 + 1;
+                let z = &args[index];
+
+                let g = if id == Atom::GAMMA {
+                    // gamma(z) = gamma(z+m)/(z(z+1)...(z+m-1))
+                    let mut g = function!(Atom::GAMMA, z + m);
+                    for i in 0..m {
+                        g /= z + i;
+                    }
+                    g
+                } else {
+                    // polygamma(n, z) = polygamma(n, z+m) - (-1)^n n! sum_i (z+i)^(-n-1)
+                    let n = match args[0].as_view() {
+                        AtomView::Num(n) => match n.get_coeff_view() {
+                            CoefficientView::Natural(n, 1) if n >= 0 => n,
+                            _ => {
+                                return Some(Err(
+                                    "Cannot series expand polygamma of non-integer order at a pole",
+                                ));
+                            }
+                        },
+                        _ => {
+                            return Some(Err(
+                                "Cannot series expand polygamma of non-integer order at a pole",
+                            ));
+                        }
+                    };
+
+                    let mut g = function!(Atom::POLYGAMMA, n, z + m);
+                    let f = Integer::factorial(n as u32);
+                    let f = if n % 2 == 0 { -f } else { f };
+                    for i in 0..m {
+                        g += (z + i).npow(-n - 1) * f.clone();
+                    }
+                    g
+                };
+
+                return Some(g.as_view().series_impl(x, expansion_point, info));
+            }
+        }
+
+        // TODO: depth is an overestimate
+        let order = info.absolute_order();
+        let depth = order.numerator().to_i64().unwrap() as u32
+            * order.denominator().to_i64().unwrap() as u32;
+
+        if id == Atom::POLYLOG && index == 1 && c.is_zero() {
+            // li(n, x) = sum_k x^k/k^n
+            let mut result = info.zero();
+            let mut term = info.one();
+            for k in 1..=depth {
+                term = &term * &args_series[index];
+                result = &result
+                    + &term
+                        .clone()
+                        .div_coeff(&Atom::new_num(k as i64).pow(&args[0]));
+            }
+            return Some(Ok(result));
+        }
+
+        // use x as a dummy variable, as the other arguments do not depend on it
+        let z = Atom::new_var(x);
+        let mut g = FunctionBuilder::new(id);
+        for (i, a) in args.iter().enumerate() {
+            g = if i == index {
+                g.add_arg(&z)
+            } else {
+                g.add_arg(a)
+            };
+        }
+        let mut g = g.finish();
+
+        let pattern = z.to_pattern();
+        let rhs = c.to_pattern();
+        let mut result = info.constant(g.replace(&pattern).with(&rhs));
+        let mut term = info.one();
+        for i in 1..=depth {
+            g = g.derivative(x);
+            if g.is_zero() {
+                break;
+            }
+
+            term = &term * &args_series[index];
+            result = &result
+                + &term
+                    .clone()
+                    .mul_coeff(&g.replace(&pattern).with(&rhs))
+                    .div_coeff(&Atom::new_num(Integer::factorial(i)));
+        }
+
+        Some(Ok(result))
+    }
+
     /// Series expand in `x` around `expansion_point` to depth `depth`.
     pub(crate) fn series_impl(
         &self,
@@ -400,6 +691,12 @@ impl AtomView<'_> {
                     return Ok(info.constant(f.to_owned().into()));
                 }
 
+                if let Some(r) =
+                    Self::builtin_series(*f, x, expansion_point, info, &mut args_series)
+                {
+                    return r;
+                }
+
                 match f.get_symbol() {
                     Atom::COS => args_series[0].cos(),
                     Atom::SIN => args_series[0].sin(),
@@ -1007,4 +1304,66 @@ mod test {
         let mul = &r * &t2;
         assert_eq!(mul.absolute_order(), (11, 1).into());
     }
+
+    #[test]
+    fn derivative_grassmann() {
+        let t1 = symbol!("gt1"; Grassmann).unwrap();
+        symbol!("gt2"; Grassmann).unwrap();
+
+        let r = parse!("gt2*gt1*v1 + gt1").unwrap().derivative(t1);
+        assert_eq!(r, parse!("1-gt2*v1").unwrap());
+
+        let r = parse!("(gt2+v1)*gt1").unwrap().derivative(t1);
+        assert_eq!(r, parse!("v1-gt2").unwrap());
+    }
+
+    #[test]
+    fn derivative_special_functions() {
+        let v1 = symbol!("v1");
+        let inputs = [
+            "tan(v1) + arctan(v1^2) + arctan(y, v1)",
+            "gamma(2*v1) + erf(v1)",
+            "li(3, v1) + bessel_j(n, v1)",
+            "zeta(v1) + polygamma(v1, 1/2)",
+        ];
+        let r = inputs.map(|input| parse!(input).unwrap().derivative(v1));
+
+        let res = [
+            "cos(v1)^-2+2*v1*(1+v1^4)^-1-y*(v1^2+y^2)^-1",
+            "2*gamma(2*v1)*polygamma(0,2*v1)+2*𝜋^(-1/2)*exp(-v1^2)",
+            "v1^-1*li(2,v1)+1/2*(bessel_j(n-1,v1)-bessel_j(n+1,v1))",
+            "der(1,zeta(v1))+der(1,0,polygamma(v1,1/2))",
+        ];
+        let res = res.map(|input| parse!(input).unwrap());
+
+        assert_eq!(r, res);
+    }
+
+    #[test]
+    fn series_special_functions() {
+        let v1 = symbol!("v1");
+
+        let t = parse!("gamma(v1)")
+            .unwrap()
+            .series(v1, Atom::new_num(0).as_view(), 1.into(), true)
+            .unwrap()
+            .to_atom();
+        let res = parse!("v1^-1+polygamma(0,1)+1/2*v1*(polygamma(0,1)^2+1/6*𝜋^2)").unwrap();
+        assert_eq!(t.expand(), res.expand());
+
+        let t = parse!("li(2, v1) + arcsin(2*v1)")
+            .unwrap()
+            .series(v1, Atom::new_num(0).as_view(), 3.into(), true)
+            .unwrap()
+            .to_atom();
+        let res = parse!("3*v1+1/4*v1^2+13/9*v1^3").unwrap();
+        assert_eq!(t, res);
+
+        assert!(
+            parse!("zeta(v1)")
+                .unwrap()
+                .series(v1, Atom::new_num(1).as_view(), 2.into(), true)
+                .is_err()
+        );
+    }
 }
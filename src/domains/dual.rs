@@ -995,6 +995,84 @@ macro_rules! create_hyperdual_from_components {
                 // TODO: improve
                 (self.log() * e).exp()
             }
+
+            #[inline]
+            fn gamma(&self) -> Self {
+                // Γ(a + p) = Γ(a) exp(sum_k ψ^(k-1)(a) p^k / k!)
+                let a = &self.values[0];
+
+                let mut p = self.clone();
+                p.values[0] = a.zero();
+
+                let mut log = self.zero();
+                let mut sp = p.clone();
+                let mut scale = 1;
+                for i in 1..Self::MAX_POW + 1 {
+                    scale *= i;
+                    let b = a.polygamma(&a.from_usize(i - 1)) * &a.from_usize(scale).inv();
+                    log += sp.clone() * &b;
+                    sp *= &p;
+                }
+
+                log.exp() * &a.gamma()
+            }
+
+            #[inline]
+            fn polygamma(&self, n: &Self) -> Self {
+                let a = &self.values[0];
+                let n = &n.values[0];
+
+                let mut p = self.clone();
+                p.values[0] = a.zero();
+
+                let mut e = self.one();
+                e.values[0] = a.polygamma(n);
+                let mut sp = p.clone();
+                let mut scale = 1;
+                for i in 1..Self::MAX_POW + 1 {
+                    scale *= i;
+                    let b =
+                        a.polygamma(&(n.clone() + &a.from_usize(i))) * &a.from_usize(scale).inv();
+                    e += sp.clone() * &b;
+                    sp *= &p;
+                }
+
+                e
+            }
+
+            #[inline]
+            fn erf(&self) -> Self {
+                // the derivatives are 2/sqrt(π) (-1)^(k-1) H_(k-1)(a) exp(-a^2),
+                // with H_k the physicists' Hermite polynomials
+                let a = &self.values[0];
+
+                let mut p = self.clone();
+                p.values[0] = a.zero();
+
+                let two = a.from_usize(2);
+                let gauss = (-a.clone() * a).exp() * &two / &a.pi().sqrt();
+                let (mut h_prev, mut h) = (a.zero(), a.one());
+
+                let mut e = self.one();
+                e.values[0] = a.erf();
+                let mut sp = p.clone();
+                let mut scale = 1;
+                for i in 1..Self::MAX_POW + 1 {
+                    scale *= i;
+                    let mut b = gauss.clone() * &h * &a.from_usize(scale).inv();
+                    if i % 2 == 0 {
+                        b = b.neg();
+                    }
+                    e += sp.clone() * &b;
+                    sp *= &p;
+
+                    let h_next =
+                        two.clone() * a * &h - two.clone() * &a.from_usize(i - 1) * &h_prev;
+                    h_prev = std::mem::replace(&mut h, h_next);
+                }
+
+                e
+            }
         }
     };
 }
@@ -12,6 +12,8 @@ use wide::{f64x2, f64x4};
 
 use crate::domains::integer::Integer;
 
+mod special;
+
 use super::{EuclideanDomain, Field, InternalOrdering, Ring, SelfRing, rational::Rational};
 use rug::{
     Assign, Float as MultiPrecisionFloat,
@@ -401,6 +403,45 @@ pub trait Real: NumericalFloatLike {
     fn acosh(&self) -> Self;
     fn atanh(&self) -> Self;
     fn powf(&self, e: &Self) -> Self;
+    // The special functions below yield NaN by default, which is used by types
+    // that cannot represent them, such as complex numbers with a non-zero imaginary part.
+
+    /// The gamma function `Γ(x)`.
+    fn gamma(&self) -> Self {
+        self.zero() / self.zero()
+    }
+    /// The polygamma function `ψ⁽ⁿ⁾(x)` of integer order `n`, where `n = 0` yields the digamma function.
+    fn polygamma(&self, _n: &Self) -> Self {
+        self.zero() / self.zero()
+    }
+    /// The error function.
+    fn erf(&self) -> Self {
+        self.zero() / self.zero()
+    }
+    /// The Riemann zeta function.
+    fn zeta(&self) -> Self {
+        self.zero() / self.zero()
+    }
+    /// The polylogarithm `Li_n(x)` of integer order `n`. For real `x > 1`, the real part is returned.
+    fn polylog(&self, _n: &Self) -> Self {
+        self.zero() / self.zero()
+    }
+    /// The Bessel function of the first kind `J_ν(x)`.
+    fn bessel_j(&self, _nu: &Self) -> Self {
+        self.zero() / self.zero()
+    }
+    /// The Bessel function of the second kind `Y_ν(x)`.
+    fn bessel_y(&self, _nu: &Self) -> Self {
+        self.zero() / self.zero()
+    }
+    /// The modified Bessel function of the first kind `I_ν(x)`.
+    fn bessel_i(&self, _nu: &Self) -> Self {
+        self.zero() / self.zero()
+    }
+    /// The modified Bessel function of the second kind `K_ν(x)`.
+    fn bessel_k(&self, _nu: &Self) -> Self {
+        self.zero() / self.zero()
+    }
 }
 
 impl NumericalFloatLike for f64 {
@@ -644,6 +685,51 @@ impl Real for f64 {
     fn powf(&self, e: &f64) -> Self {
         (*self).powf(*e)
     }
+
+    #[inline]
+    fn gamma(&self) -> Self {
+        libm::tgamma(*self)
+    }
+
+    #[inline]
+    fn polygamma(&self, n: &Self) -> Self {
+        special::polygamma_f64(*n, *self)
+    }
+
+    #[inline]
+    fn erf(&self) -> Self {
+        libm::erf(*self)
+    }
+
+    #[inline]
+    fn zeta(&self) -> Self {
+        special::zeta_f64(*self)
+    }
+
+    #[inline]
+    fn polylog(&self, n: &Self) -> Self {
+        special::polylog_f64(*n, *self)
+    }
+
+    #[inline]
+    fn bessel_j(&self, nu: &Self) -> Self {
+        special::bessel_f64(special::BesselKind::J, *nu, *self)
+    }
+
+    #[inline]
+    fn bessel_y(&self, nu: &Self) -> Self {
+        special::bessel_f64(special::BesselKind::Y, *nu, *self)
+    }
+
+    #[inline]
+    fn bessel_i(&self, nu: &Self) -> Self {
+        special::bessel_f64(special::BesselKind::I, *nu, *self)
+    }
+
+    #[inline]
+    fn bessel_k(&self, nu: &Self) -> Self {
+        special::bessel_f64(special::BesselKind::K, *nu, *self)
+    }
 }
 
 impl From<&Rational> for f64 {
@@ -1044,6 +1130,51 @@ impl Real for F64 {
     fn powf(&self, e: &Self) -> Self {
         self.0.powf(e.0).into()
     }
+
+    #[inline]
+    fn gamma(&self) -> Self {
+        Real::gamma(&self.0).into()
+    }
+
+    #[inline]
+    fn polygamma(&self, n: &Self) -> Self {
+        self.0.polygamma(&n.0).into()
+    }
+
+    #[inline]
+    fn erf(&self) -> Self {
+        Real::erf(&self.0).into()
+    }
+
+    #[inline]
+    fn zeta(&self) -> Self {
+        self.0.zeta().into()
+    }
+
+    #[inline]
+    fn polylog(&self, n: &Self) -> Self {
+        self.0.polylog(&n.0).into()
+    }
+
+    #[inline]
+    fn bessel_j(&self, nu: &Self) -> Self {
+        self.0.bessel_j(&nu.0).into()
+    }
+
+    #[inline]
+    fn bessel_y(&self, nu: &Self) -> Self {
+        self.0.bessel_y(&nu.0).into()
+    }
+
+    #[inline]
+    fn bessel_i(&self, nu: &Self) -> Self {
+        self.0.bessel_i(&nu.0).into()
+    }
+
+    #[inline]
+    fn bessel_k(&self, nu: &Self) -> Self {
+        self.0.bessel_k(&nu.0).into()
+    }
 }
 
 impl From<f64> for F64 {
@@ -2019,6 +2150,55 @@ impl Real for Float {
 
         c.pow(&e.0).into()
     }
+
+    #[inline]
+    fn gamma(&self) -> Self {
+        self.0.clone().gamma().into()
+    }
+
+    #[inline]
+    fn polygamma(&self, n: &Self) -> Self {
+        special::polygamma(&n.0, &self.0).into()
+    }
+
+    #[inline]
+    fn erf(&self) -> Self {
+        self.0.clone().erf().into()
+    }
+
+    #[inline]
+    fn zeta(&self) -> Self {
+        self.0.clone().zeta().into()
+    }
+
+    #[inline]
+    fn polylog(&self, n: &Self) -> Self {
+        if n.0 == 2 {
+            self.0.clone().li2().into()
+        } else {
+            special::polylog(&n.0, &self.0).into()
+        }
+    }
+
+    #[inline]
+    fn bessel_j(&self, nu: &Self) -> Self {
+        special::bessel(special::BesselKind::J, &nu.0, &self.0).into()
+    }
+
+    #[inline]
+    fn bessel_y(&self, nu: &Self) -> Self {
+        special::bessel(special::BesselKind::Y, &nu.0, &self.0).into()
+    }
+
+    #[inline]
+    fn bessel_i(&self, nu: &Self) -> Self {
+        special::bessel(special::BesselKind::I, &nu.0, &self.0).into()
+    }
+
+    #[inline]
+    fn bessel_k(&self, nu: &Self) -> Self {
+        special::bessel(special::BesselKind::K, &nu.0, &self.0).into()
+    }
 }
 
 impl Rational {
@@ -2759,6 +2939,104 @@ impl<T: Real + RealNumberLike> Real for ErrorPropagatingFloat<T> {
         }
         .truncate()
     }
+
+    fn gamma(&self) -> Self {
+        let t = self.value.gamma();
+        let d = t.to_f64() * self.value.polygamma(&self.value.zero()).to_f64();
+        ErrorPropagatingFloat {
+            abs_err: self.abs_err * d.abs(),
+            value: t,
+        }
+        .truncate()
+    }
+
+    fn polygamma(&self, n: &Self) -> Self {
+        let d = self.value.polygamma(&(n.value.clone() + self.value.one()));
+        ErrorPropagatingFloat {
+            abs_err: self.abs_err * d.to_f64().abs(),
+            value: self.value.polygamma(&n.value),
+        }
+        .truncate()
+    }
+
+    fn erf(&self) -> Self {
+        let v = self.value.to_f64();
+        let d = 2. / std::f64::consts::PI.sqrt() * (-v * v).exp();
+        ErrorPropagatingFloat {
+            abs_err: self.abs_err * d,
+            value: self.value.erf(),
+        }
+        .truncate()
+    }
+
+    fn zeta(&self) -> Self {
+        // estimate the derivative with a central difference
+        let v = self.value.to_f64();
+        let h = 1e-6 * v.abs().max(1.);
+        let d = ((v + h).zeta() - (v - h).zeta()) / (2. * h);
+        ErrorPropagatingFloat {
+            abs_err: self.abs_err * d.abs(),
+            value: self.value.zeta(),
+        }
+        .truncate()
+    }
+
+    fn polylog(&self, n: &Self) -> Self {
+        let d = self.value.polylog(&(n.value.clone() - self.value.one())) / &self.value;
+        ErrorPropagatingFloat {
+            abs_err: self.abs_err * d.to_f64().abs(),
+            value: self.value.polylog(&n.value),
+        }
+        .truncate()
+    }
+
+    fn bessel_j(&self, nu: &Self) -> Self {
+        let one = self.value.one();
+        let d = (self.value.bessel_j(&(nu.value.clone() - &one))
+            - self.value.bessel_j(&(nu.value.clone() + &one)))
+            / self.value.from_usize(2);
+        ErrorPropagatingFloat {
+            abs_err: self.abs_err * d.to_f64().abs(),
+            value: self.value.bessel_j(&nu.value),
+        }
+        .truncate()
+    }
+
+    fn bessel_y(&self, nu: &Self) -> Self {
+        let one = self.value.one();
+        let d = (self.value.bessel_y(&(nu.value.clone() - &one))
+            - self.value.bessel_y(&(nu.value.clone() + &one)))
+            / self.value.from_usize(2);
+        ErrorPropagatingFloat {
+            abs_err: self.abs_err * d.to_f64().abs(),
+            value: self.value.bessel_y(&nu.value),
+        }
+        .truncate()
+    }
+
+    fn bessel_i(&self, nu: &Self) -> Self {
+        let one = self.value.one();
+        let d = (self.value.bessel_i(&(nu.value.clone() - &one))
+            + self.value.bessel_i(&(nu.value.clone() + &one)))
+            / self.value.from_usize(2);
+        ErrorPropagatingFloat {
+            abs_err: self.abs_err * d.to_f64().abs(),
+            value: self.value.bessel_i(&nu.value),
+        }
+        .truncate()
+    }
+
+    fn bessel_k(&self, nu: &Self) -> Self {
+        let one = self.value.one();
+        let d = (self.value.bessel_k(&(nu.value.clone() - &one))
+            + self.value.bessel_k(&(nu.value.clone() + &one)))
+            / self.value.from_usize(2);
+        ErrorPropagatingFloat {
+            abs_err: self.abs_err * d.to_f64().abs(),
+            value: self.value.bessel_k(&nu.value),
+        }
+        .truncate()
+    }
 }
 
 macro_rules! simd_impl {
@@ -2941,6 +3219,48 @@ macro_rules! simd_impl {
             fn powf(&self, e: &Self) -> Self {
                 (*self).$p(*e)
             }
+
+            fn gamma(&self) -> Self {
+                self.to_array().map(|x| Real::gamma(&x)).into()
+            }
+
+            fn polygamma(&self, n: &Self) -> Self {
+                let (x, n) = (self.to_array(), n.to_array());
+                std::array::from_fn(|i| x[i].polygamma(&n[i])).into()
+            }
+
+            fn erf(&self) -> Self {
+                self.to_array().map(|x| Real::erf(&x)).into()
+            }
+
+            fn zeta(&self) -> Self {
+                self.to_array().map(|x| x.zeta()).into()
+            }
+
+            fn polylog(&self, n: &Self) -> Self {
+                let (x, n) = (self.to_array(), n.to_array());
+                std::array::from_fn(|i| x[i].polylog(&n[i])).into()
+            }
+
+            fn bessel_j(&self, nu: &Self) -> Self {
+                let (x, nu) = (self.to_array(), nu.to_array());
+                std::array::from_fn(|i| x[i].bessel_j(&nu[i])).into()
+            }
+
+            fn bessel_y(&self, nu: &Self) -> Self {
+                let (x, nu) = (self.to_array(), nu.to_array());
+                std::array::from_fn(|i| x[i].bessel_y(&nu[i])).into()
+            }
+
+            fn bessel_i(&self, nu: &Self) -> Self {
+                let (x, nu) = (self.to_array(), nu.to_array());
+                std::array::from_fn(|i| x[i].bessel_i(&nu[i])).into()
+            }
+
+            fn bessel_k(&self, nu: &Self) -> Self {
+                let (x, nu) = (self.to_array(), nu.to_array());
+                std::array::from_fn(|i| x[i].bessel_k(&nu[i])).into()
+            }
         }
 
         impl From<&Rational> for $t {
@@ -3938,6 +4258,64 @@ impl<T: Real> Real for Complex<T> {
             (e * self.log()).exp()
         }
     }
+
+    #[inline]
+    fn gamma(&self) -> Self {
+        self.real_special_function(|re| re.gamma())
+    }
+
+    #[inline]
+    fn polygamma(&self, n: &Self) -> Self {
+        self.real_special_function(|re| re.polygamma(&n.re))
+    }
+
+    #[inline]
+    fn erf(&self) -> Self {
+        self.real_special_function(|re| re.erf())
+    }
+
+    #[inline]
+    fn zeta(&self) -> Self {
+        self.real_special_function(|re| re.zeta())
+    }
+
+    #[inline]
+    fn polylog(&self, n: &Self) -> Self {
+        self.real_special_function(|re| re.polylog(&n.re))
+    }
+
+    #[inline]
+    fn bessel_j(&self, nu: &Self) -> Self {
+        self.real_special_function(|re| re.bessel_j(&nu.re))
+    }
+
+    #[inline]
+    fn bessel_y(&self, nu: &Self) -> Self {
+        self.real_special_function(|re| re.bessel_y(&nu.re))
+    }
+
+    #[inline]
+    fn bessel_i(&self, nu: &Self) -> Self {
+        self.real_special_function(|re| re.bessel_i(&nu.re))
+    }
+
+    #[inline]
+    fn bessel_k(&self, nu: &Self) -> Self {
+        self.real_special_function(|re| re.bessel_k(&nu.re))
+    }
+}
+
+impl<T: Real> Complex<T> {
+    /// Evaluate a special function that is only supported for real arguments.
+    /// For complex arguments, NaN is returned.
+    fn real_special_function(&self, f: impl FnOnce(&T) -> T) -> Self {
+        if self.im != self.im.zero() {
+            let nan = self.im.zero() / &self.im.zero();
+            return Complex::new(nan.clone(), nan);
+        }
+
+        Complex::new(f(&self.re), self.im.zero())
+    }
 }
 
 impl<T: NumericalFloatLike> From<T> for Complex<T> {
@@ -4157,6 +4535,100 @@ mod test {
         assert_eq!(c.get_precision(), 57);
     }
 
+    #[test]
+    fn special_functions() {
+        fn check(r: Float, expected: &str) {
+            let e = Float::parse(expected, Some(200)).unwrap();
+            let rel = ((r - &e) / e).norm();
+            assert!(
+                rel.to_f64() < 1e-28,
+                "relative error {} too large for {expected}",
+                rel.to_f64()
+            );
+        }
+
+        let f = |x: f64| Float::with_val(200, x);
+
+        check(f(0.5).polylog(&f(3.)), "0.537213193608040200940623225595");
+        check(f(0.9).polylog(&f(3.)), "1.04965895018643990171190384709");
+        check(f(-0.8).polylog(&f(3.)), "-0.734371305634442942653635945281");
+        check(f(-3.).polylog(&f(3.)), "-2.34879055458407655780587066981");
+        check(f(3.).polylog(&f(3.)), "3.74212259424073163537852955032");
+        check(f(0.3).polylog(&f(-2.)), "1.13702623906705528769634625608");
+        check(f(0.99).polylog(&f(4.)), "1.07032414616522914123097007317");
+
+        check(f(1.).polygamma(&f(2.)), "-2.40411380631918857079947632302");
+        check(f(0.5).polygamma(&f(1.)), "4.93480220054467930941724549994");
+        check(f(-1.5).polygamma(&f(3.)), "194.594276219187622421625517874");
+        check(
+            f(10.3).polygamma(&f(1.)),
+            "0.101952596170991902628229490584",
+        );
+
+        check(
+            f(1.3).bessel_j(&f(2.5)),
+            "0.0906895213536789456099124351253",
+        );
+        check(f(1.3).bessel_y(&f(2.5)), "-1.70115494265060971567570740244");
+        check(f(1.3).bessel_i(&f(2.5)), "0.115464750431413528871108784017");
+        check(f(1.3).bessel_k(&f(2.5)), "1.52269140073989553828554095441");
+        check(f(1.3).bessel_k(&f(2.)), "0.851397639579968718409827457852");
+        check(f(1.3).bessel_i(&f(3.)), "0.0508145046384680817016904898575");
+        check(
+            f(80.).bessel_j(&f(0.5)),
+            "-0.0886610358117654584751161239658",
+        );
+        check(
+            f(100.).bessel_k(&f(1.)),
+            "4.67985373563690928656254424202e-45",
+        );
+        check(
+            f(100.).bessel_i(&f(1.5)),
+            "1.06167954671688737463973942698e42",
+        );
+        check(f(2.).bessel_y(&f(-3.)), "1.12778377684042778608158395773");
+        check(f(1.3).bessel_j(&f(-2.5)), "1.70115494265060971567570740244");
+
+        assert!((Real::gamma(&5f64) - 24.).abs() < 1e-13);
+        assert!((Real::erf(&0.5f64) - 0.5204998778130465).abs() < 1e-15);
+        assert!((2f64.zeta() - std::f64::consts::PI.powi(2) / 6.).abs() < 1e-15);
+
+        // the double-precision implementations agree with the arbitrary-precision ones
+        type Case = (fn(&f64, &f64) -> f64, fn(&Float, &Float) -> Float, f64, f64);
+        let cases: [Case; 16] = [
+            (|x, n| x.polylog(n), |x, n| x.polylog(n), 0.3, 3.),
+            (|x, n| x.polylog(n), |x, n| x.polylog(n), 0.9, 3.),
+            (|x, n| x.polylog(n), |x, n| x.polylog(n), -0.8, 4.),
+            (|x, n| x.polylog(n), |x, n| x.polylog(n), -3., 3.),
+            (|x, n| x.polylog(n), |x, n| x.polylog(n), 3., 2.),
+            (|x, n| x.polylog(n), |x, n| x.polylog(n), 0.3, -2.),
+            (|x, n| x.polygamma(n), |x, n| x.polygamma(n), 0.7, 0.),
+            (|x, n| x.polygamma(n), |x, n| x.polygamma(n), -1.5, 0.),
+            (|x, n| x.polygamma(n), |x, n| x.polygamma(n), 1., 2.),
+            (|x, n| x.polygamma(n), |x, n| x.polygamma(n), -1.5, 3.),
+            (|x, _| x.zeta(), |x, _| x.zeta(), 0.7, 0.),
+            (|x, _| x.zeta(), |x, _| x.zeta(), -3.5, 0.),
+            (|x, _| x.zeta(), |x, _| x.zeta(), 5.5, 0.),
+            (|x, n| x.bessel_j(n), |x, n| x.bessel_j(n), 1.3, 3.),
+            (|x, n| x.bessel_y(n), |x, n| x.bessel_y(n), 1.3, 3.),
+            (|x, _| Real::gamma(x), |x, _| Real::gamma(x), -2.5, 0.),
+        ];
+        for (f_double, f_multi, x, n) in cases {
+            let r = f_double(&x, &n);
+            let e = f_multi(&f(x), &f(n)).to_f64();
+            assert!((r - e).abs() < 1e-14 * e.abs(), "{r} != {e} for ({x}, {n})");
+        }
+
+        // SIMD floats are evaluated per lane
+        let r = f64x2::from([1.3, 2.])
+            .bessel_k(&f64x2::from([2.5, 1.]))
+            .to_array();
+        assert_eq!(r, [1.3.bessel_k(&2.5), 2f64.bessel_k(&1.)]);
+
+        // unsupported arguments yield NaN instead of panicking
+        assert!(Complex::new(2f64, 1.).zeta().re.is_nan());
+    }
+
     #[cfg(feature = "bincode")]
     #[test]
     fn bincode_export() {
@@ -0,0 +1,804 @@
+//! Evaluation of special functions that are not provided by MPFR or libm.
+//!
+//! The arbitrary-precision functions take MPFR floats and return a result with the precision
+//! of the main argument. Intermediate computations are performed with guard bits to compensate
+//! for cancellations. The functions with an `_f64` suffix work in double precision.
+
+use rug::{
+    Float, Integer,
+    float::{Constant, Special},
+    ops::Pow,
+};
+
+/// The number of extra bits used in intermediate computations.
+const GUARD_BITS: u32 = 32;
+
+fn nan(prec: u32) -> Float {
+    Float::with_val(prec, Special::Nan)
+}
+
+fn infinity(prec: u32) -> Float {
+    Float::with_val(prec, Special::Infinity)
+}
+
+/// Get the value of `n` if it is an integer that fits in an `i64`.
+fn to_integer(n: &Float) -> Option<i64> {
+    if n.is_integer() && n.to_f64().abs() < i64::MAX as f64 {
+        Some(n.to_f64() as i64)
+    } else {
+        None
+    }
+}
+
+/// Returns `true` if `term` does not contribute to `sum` at the working precision of `sum`.
+fn is_negligible(term: &Float, sum: &Float) -> bool {
+    match (term.get_exp(), sum.get_exp()) {
+        (None, _) => term.is_zero(),
+        (Some(t), Some(s)) => t < s - sum.prec() as i32,
+        (Some(_), None) => false,
+    }
+}
+
+/// The number of guard bits needed to compensate a cancellation in a series
+/// whose terms grow as `e^x`.
+fn cancellation_bits(x: &Float) -> u32 {
+    (x.to_f64().abs() * std::f64::consts::LOG2_E).ceil() as u32
+}
+
+/// Compute `B_{2k}/(2k)!` using `B_{2k} = (-1)^{k+1} 2 (2k)! ζ(2k) / (2π)^{2k}`.
+fn bernoulli_over_factorial(k: u32, two_pi_pow: &Float) -> Float {
+    let prec = two_pi_pow.prec();
+    let z = Float::with_val(prec, Float::zeta_u(2 * k));
+    let r = z * 2u32 / two_pi_pow;
+    if k % 2 == 0 { -r } else { r }
+}
+
+/// The Hurwitz zeta function `ζ(s, a)` for integer `s >= 2`, computed using the
+/// Euler-Maclaurin summation formula.
+fn hurwitz_zeta(s: u32, a: &Float) -> Float {
+    let wp = a.prec();
+    if *a <= 0 && a.is_integer() {
+        return infinity(wp);
+    }
+
+    // shift the argument until the asymptotic expansion converges fast enough
+    let shift = (wp as f64 * 0.3).ceil() + s as f64;
+    let mut a = a.clone();
+    let mut res = Float::with_val(wp, 0);
+    while a < shift {
+        res += Float::with_val(wp, a.clone().pow(s)).recip();
+        a += 1;
+    }
+
+    res += Float::with_val(wp, a.clone().pow(1 - s as i32)) / (s - 1);
+    res += Float::with_val(wp, a.clone().pow(-(s as i32))) / 2u32;
+
+    let a2 = Float::with_val(wp, a.square_ref());
+    let two_pi_sq = Float::with_val(wp, Float::with_val(wp, Constant::Pi) * 2u32).square();
+
+    let mut pow = Float::with_val(wp, a.clone().pow(-(s as i32) - 1));
+    let mut poch = Float::with_val(wp, s);
+    let mut two_pi_pow = two_pi_sq.clone();
+    for k in 1..=wp {
+        let term = bernoulli_over_factorial(k, &two_pi_pow) * &poch * &pow;
+        res += &term;
+
+        if is_negligible(&term, &res) {
+            break;
+        }
+
+        poch *= (s + 2 * k - 1) * (s + 2 * k);
+        pow /= &a2;
+        two_pi_pow *= &two_pi_sq;
+    }
+
+    res
+}
+
+/// The polygamma function `ψ⁽ⁿ⁾(x)` of integer order `n >= 0`.
+pub(super) fn polygamma(n: &Float, x: &Float) -> Float {
+    let prec = x.prec();
+    let Some(n) = to_integer(n) else {
+        return nan(prec);
+    };
+
+    if n < 0 {
+        return nan(prec);
+    }
+
+    if n == 0 {
+        return x.clone().digamma();
+    }
+
+    let wp = prec + GUARD_BITS;
+    let z = hurwitz_zeta(n as u32 + 1, &Float::with_val(wp, x));
+    let r = z * Float::with_val(wp, Float::factorial(n as u32));
+    Float::with_val(prec, if n % 2 == 0 { -r } else { r })
+}
+
+/// The polylogarithm `Li_n(x)` of integer order `n`. For `x > 1`, the real part is returned.
+pub(super) fn polylog(n: &Float, x: &Float) -> Float {
+    let prec = x.prec();
+    let Some(n) = to_integer(n) else {
+        return nan(prec);
+    };
+
+    Float::with_val(prec, polylog_int(n, &Float::with_val(prec + GUARD_BITS, x)))
+}
+
+fn polylog_int(n: i64, x: &Float) -> Float {
+    let wp = x.prec();
+
+    if x.is_nan() {
+        return x.clone();
+    }
+
+    if n <= 0 {
+        // Li_{-m}(x) = sum_{k=0}^{m} k! S(m+1, k+1) (x/(1-x))^(k+1)
+        let m = (-n) as usize;
+        let mut stirling = vec![Integer::from(1)];
+        for r in 1..=m + 1 {
+            let mut next = vec![Integer::new(); r + 1];
+            for (k, s) in stirling.iter().enumerate() {
+                next[k] += Integer::from(k) * s;
+                next[k + 1] += s;
+            }
+            stirling = next;
+        }
+
+        let y = Float::with_val(wp, x / Float::with_val(wp, 1 - x));
+        let mut y_pow = y.clone();
+        let mut res = Float::with_val(wp, 0);
+        for k in 0..=m {
+            let c = Integer::from(Integer::factorial(k as u32)) * &stirling[k + 1];
+            res += Float::with_val(wp, &y_pow * c);
+            y_pow *= &y;
+        }
+        return res;
+    }
+
+    if x.is_zero() {
+        return Float::with_val(wp, 0);
+    }
+
+    let n_u = n as u32;
+
+    if n == 1 {
+        // the real part of -log(1-x)
+        let r = Float::with_val(wp, 1 - x).abs();
+        return -r.ln();
+    }
+
+    if *x == 1 {
+        return Float::with_val(wp, Float::zeta_u(n_u));
+    }
+
+    if *x == -1 {
+        let z = Float::with_val(wp, Float::zeta_u(n_u));
+        let f: Float = 1 - Float::with_val(wp, Float::i_exp(1, 1 - n as i32));
+        return -(z * f);
+    }
+
+    if x.clone().abs() <= 0.5 {
+        // direct summation
+        let mut res = Float::with_val(wp, 0);
+        let mut x_pow = x.clone();
+        for k in 1u32.. {
+            let term = Float::with_val(wp, &x_pow / Float::with_val(wp, k).pow(n_u));
+            res += &term;
+            if is_negligible(&term, &res) {
+                break;
+            }
+            x_pow *= x;
+        }
+        return res;
+    }
+
+    if *x > 0.5 && *x < 1 {
+        // expansion in mu = log(x)
+        let mu = x.clone().ln();
+        let mut res = Float::with_val(wp, 0);
+        let mut mu_pow = Float::with_val(wp, 1);
+        let mut small_count = 0;
+        for k in 0i64.. {
+            let term = if k == n - 1 {
+                let mut harmonic = Float::with_val(wp, 0);
+                for j in 1..n {
+                    harmonic += Float::with_val(wp, j).recip();
+                }
+                let log = Float::with_val(wp, -&mu).ln();
+                Float::with_val(wp, &mu_pow * (harmonic - log))
+            } else {
+                Float::with_val(wp, n - k).zeta() * &mu_pow
+            };
+
+            res += &term;
+
+            if k > n && is_negligible(&term, &res) {
+                small_count += 1;
+                if small_count == 2 {
+                    break;
+                }
+            } else {
+                small_count = 0;
+            }
+
+            mu_pow *= &mu;
+            mu_pow /= k + 1;
+        }
+        return res;
+    }
+
+    if *x > -1 && *x < -0.5 {
+        // Li_n(x) = 2^(1-n) Li_n(x^2) - Li_n(-x)
+        let sq = polylog_int(n, &Float::with_val(wp, x.square_ref()));
+        let neg = polylog_int(n, &Float::with_val(wp, -x));
+        return sq * Float::with_val(wp, Float::i_exp(1, 1 - n as i32)) - neg;
+    }
+
+    // inversion formula for |x| > 1
+    let inv = polylog_int(n, &Float::with_val(wp, x.clone().recip()));
+    let log = Float::with_val(wp, x.clone().abs().ln());
+
+    let mut res =
+        Float::with_val(wp, log.clone().pow(n_u)) / Float::with_val(wp, Float::factorial(n_u));
+    res = -res;
+    if n % 2 == 0 {
+        res -= inv;
+    } else {
+        res += inv;
+    }
+
+    for k in 1..=n_u / 2 {
+        let mut c = Float::with_val(wp, Float::zeta_u(2 * k));
+        if *x < 0 {
+            // Li_{2k}(-1) = -(1 - 2^(1-2k)) zeta(2k)
+            c *= 1 - Float::with_val(wp, Float::i_exp(1, 1 - 2 * k as i32));
+            c = -c;
+        }
+
+        let l = Float::with_val(wp, log.clone().pow(n_u - 2 * k));
+        res += c * l * 2u32 / Float::with_val(wp, Float::factorial(n_u - 2 * k));
+    }
+
+    res
+}
+
+/// The kind of Bessel function.
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum BesselKind {
+    J,
+    Y,
+    I,
+    K,
+}
+
+/// Evaluate the Bessel function of the given kind and order `nu` at `x`.
+pub(super) fn bessel(kind: BesselKind, nu: &Float, x: &Float) -> Float {
+    let prec = x.prec();
+
+    if nu.is_nan() || x.is_nan() {
+        return nan(prec);
+    }
+
+    let order = to_integer(nu);
+
+    // use the reflection formulas for negative integer orders
+    if let Some(n) = order {
+        if n < 0 {
+            let r = bessel(kind, &Float::with_val(nu.prec(), -nu), x);
+            return if n % 2 != 0 && matches!(kind, BesselKind::J | BesselKind::Y) {
+                -r
+            } else {
+                r
+            };
+        }
+    }
+
+    if *x < 0 {
+        // only integer orders of the functions of the first kind are real for negative arguments
+        return match (kind, order) {
+            (BesselKind::J | BesselKind::I, Some(n)) => {
+                let r = bessel(kind, nu, &Float::with_val(prec, -x));
+                if n % 2 != 0 { -r } else { r }
+            }
+            _ => nan(prec),
+        };
+    }
+
+    if x.is_zero() {
+        return match kind {
+            BesselKind::J | BesselKind::I => {
+                if order == Some(0) {
+                    Float::with_val(prec, 1)
+                } else if *nu > 0 {
+                    Float::with_val(prec, 0)
+                } else {
+                    nan(prec)
+                }
+            }
+            BesselKind::Y => -infinity(prec),
+            BesselKind::K => infinity(prec),
+        };
+    }
+
+    if let Some(n) = order {
+        match kind {
+            BesselKind::J if n <= i32::MAX as i64 => return x.clone().jn(n as i32),
+            BesselKind::Y if n <= i32::MAX as i64 => return x.clone().yn(n as i32),
+            _ => {}
+        }
+    }
+
+    let wp = prec + GUARD_BITS;
+    let nu_f = nu.to_f64();
+    if x.to_f64() > wp as f64 * 0.5 + nu_f * nu_f {
+        return Float::with_val(
+            prec,
+            bessel_asymptotic(kind, &Float::with_val(wp, nu), &Float::with_val(wp, x)),
+        );
+    }
+
+    let res = match kind {
+        BesselKind::J => {
+            let wp = wp + cancellation_bits(x);
+            bessel_series(&Float::with_val(wp, nu), &Float::with_val(wp, x), true)
+        }
+        BesselKind::I => bessel_series(&Float::with_val(wp, nu), &Float::with_val(wp, x), false),
+        BesselKind::Y => {
+            // Y_nu = (J_nu cos(nu pi) - J_{-nu}) / sin(nu pi) for non-integer nu
+            let wp = wp + cancellation_bits(x);
+            let nu = Float::with_val(wp, nu);
+            let x = Float::with_val(wp, x);
+            let j = bessel_series(&nu, &x, true);
+            let jm = bessel_series(&Float::with_val(wp, -&nu), &x, true);
+            let nu_pi = nu * Float::with_val(wp, Constant::Pi);
+            (j * nu_pi.clone().cos() - jm) / nu_pi.sin()
+        }
+        BesselKind::K => {
+            let wp = wp + 2 * cancellation_bits(x);
+            let nu = Float::with_val(wp, nu);
+            let x = Float::with_val(wp, x);
+            if let Some(n) = order {
+                bessel_k_integer(n as u32, &x)
+            } else {
+                // K_nu = pi/2 (I_{-nu} - I_nu) / sin(nu pi) for non-integer nu
+                let i = bessel_series(&nu, &x, false);
+                let im = bessel_series(&Float::with_val(wp, -&nu), &x, false);
+                let pi = Float::with_val(wp, Constant::Pi);
+                let s = Float::with_val(wp, &nu * &pi).sin();
+                pi * (im - i) / s / 2u32
+            }
+        }
+    };
+
+    Float::with_val(prec, res)
+}
+
+/// Compute `sum_k (±1)^k (x/2)^(2k+nu) / (k! Γ(k+nu+1))`, which is `J_nu(x)` for
+/// alternating signs and `I_nu(x)` otherwise.
+fn bessel_series(nu: &Float, x: &Float, alternating: bool) -> Float {
+    let wp = x.prec();
+    let h = Float::with_val(wp, x / 2u32);
+    let mut h2 = Float::with_val(wp, h.square_ref());
+    if alternating {
+        h2 = -h2;
+    }
+
+    let nu_p1 = Float::with_val(wp, nu + 1u32);
+    let mut term = Float::with_val(wp, h.clone().pow(nu)) / nu_p1.gamma();
+    let mut res = term.clone();
+
+    let peak = h2.to_f64().abs();
+    for k in 1u32.. {
+        term *= &h2;
+        term /= Float::with_val(wp, nu + k) * k;
+        res += &term;
+
+        if k as f64 > peak && is_negligible(&term, &res) {
+            break;
+        }
+    }
+
+    res
+}
+
+/// The modified Bessel function of the second kind of integer order `n`.
+fn bessel_k_integer(n: u32, x: &Float) -> Float {
+    let wp = x.prec();
+    let h = Float::with_val(wp, x / 2u32);
+    let h2 = Float::with_val(wp, h.square_ref());
+
+    // finite sum: 1/2 (x/2)^(-n) sum_{k=0}^{n-1} (n-k-1)!/k! (-x^2/4)^k
+    let mut finite = Float::with_val(wp, 0);
+    let mut pow = Float::with_val(wp, 1);
+    for k in 0..n {
+        let c = Float::with_val(wp, Float::factorial(n - k - 1))
+            / Float::with_val(wp, Float::factorial(k));
+        finite += c * &pow;
+        pow *= &h2;
+        pow = -pow;
+    }
+    finite /= Float::with_val(wp, h.clone().pow(n));
+    finite /= 2u32;
+
+    // logarithmic term: (-1)^(n+1) log(x/2) I_n(x)
+    let i = bessel_series(&Float::with_val(wp, n), x, false);
+    let mut log_term = Float::with_val(wp, h.ln_ref()) * i;
+    if n % 2 == 0 {
+        log_term = -log_term;
+    }
+
+    // infinite sum: (-1)^n 1/2 (x/2)^n sum_k (psi(k+1) + psi(n+k+1)) (x^2/4)^k / (k! (n+k)!)
+    let euler = Float::with_val(wp, Constant::Euler);
+    let mut harmonic_k = Float::with_val(wp, 0);
+    let mut harmonic_nk = Float::with_val(wp, 0);
+    for j in 1..=n {
+        harmonic_nk += Float::with_val(wp, j).recip();
+    }
+
+    let mut coeff = Float::with_val(wp, Float::factorial(n)).recip();
+    let mut infinite = Float::with_val(wp, 0);
+    let peak = h2.to_f64();
+    for k in 0u32.. {
+        let psi =
+            Float::with_val(wp, &harmonic_k + &harmonic_nk) - Float::with_val(wp, &euler * 2u32);
+        let term = Float::with_val(wp, &coeff * psi);
+        infinite += &term;
+
+        if k as f64 > peak && is_negligible(&term, &infinite) {
+            break;
+        }
+
+        harmonic_k += Float::with_val(wp, k + 1).recip();
+        harmonic_nk += Float::with_val(wp, n + k + 1).recip();
+        coeff *= &h2;
+        coeff /= (k + 1) * (n + k + 1);
+    }
+    infinite *= Float::with_val(wp, h.clone().pow(n));
+    infinite /= 2u32;
+    if n % 2 == 1 {
+        infinite = -infinite;
+    }
+
+    finite + log_term + infinite
+}
+
+/// The Hankel asymptotic expansions of the Bessel functions for large `x`.
+fn bessel_asymptotic(kind: BesselKind, nu: &Float, x: &Float) -> Float {
+    let wp = x.prec();
+    let mu = Float::with_val(wp, nu.square_ref()) * 4u32;
+
+    // a_k(nu) / x^k, with a_k(nu) = prod_{j=1}^{k} (4 nu^2 - (2j-1)^2) / (k! 8^k)
+    let mut terms = vec![Float::with_val(wp, 1)];
+    let mut last_abs = Float::with_val(wp, 1);
+    for k in 1u32.. {
+        let prev = terms.last().unwrap();
+        let t = Float::with_val(
+            wp,
+            prev * Float::with_val(wp, &mu - (2 * k - 1) * (2 * k - 1)),
+        ) / Float::with_val(wp, x * (8 * k));
+        let t_abs = t.clone().abs();
+        if t_abs > last_abs || is_negligible(&t, &terms[0]) {
+            break;
+        }
+        last_abs = t_abs;
+        terms.push(t);
+    }
+
+    let pi = Float::with_val(wp, Constant::Pi);
+    match kind {
+        BesselKind::J | BesselKind::Y => {
+            let mut p = Float::with_val(wp, 0);
+            let mut q = Float::with_val(wp, 0);
+            for (k, t) in terms.iter().enumerate() {
+                let sign_neg = (k / 2) % 2 == 1;
+                let target = if k % 2 == 0 { &mut p } else { &mut q };
+                if sign_neg {
+                    *target -= t;
+                } else {
+                    *target += t;
+                }
+            }
+
+            let omega = Float::with_val(wp, x - Float::with_val(wp, nu * &pi) / 2u32)
+                - Float::with_val(wp, &pi / 4u32);
+            let (s, c) = (omega.clone().sin(), omega.cos());
+            let prefactor = Float::with_val(wp, Float::with_val(wp, &pi * x).recip() * 2u32).sqrt();
+            if kind == BesselKind::J {
+                prefactor * (p * c - q * s)
+            } else {
+                prefactor * (p * s + q * c)
+            }
+        }
+        BesselKind::I => {
+            let mut sum = Float::with_val(wp, 0);
+            for (k, t) in terms.iter().enumerate() {
+                if k % 2 == 1 {
+                    sum -= t;
+                } else {
+                    sum += t;
+                }
+            }
+
+            let prefactor = Float::with_val(wp, x.exp_ref())
+                / Float::with_val(wp, Float::with_val(wp, &pi * x) * 2u32).sqrt();
+            prefactor * sum
+        }
+        BesselKind::K => {
+            let mut sum = Float::with_val(wp, 0);
+            for t in &terms {
+                sum += t;
+            }
+
+            let prefactor = Float::with_val(wp, Float::with_val(wp, &pi / x) / 2u32).sqrt()
+                * Float::with_val(wp, (-x.clone()).exp());
+            prefactor * sum
+        }
+    }
+}
+
+/// The coefficients `B_{2k}/(2k)!` of the Euler-Maclaurin summation formula.
+const BERNOULLI_OVER_FACTORIAL: [f64; 10] = [
+    0.083_333_333_333_333_33,
+    -0.001_388_888_888_888_889,
+    3.306_878_306_878_307e-5,
+    -8.267_195_767_195_768e-7,
+    2.087_675_698_786_81e-8,
+    -5.284_190_138_687_493e-10,
+    1.338_253_653_068_467_9e-11,
+    -3.389_680_296_322_582_7e-13,
+    8.586_062_056_277_845e-15,
+    -2.174_868_698_558_062e-16,
+];
+
+/// The Hurwitz zeta function `ζ(s, a)` for `s != 1`, computed using the Euler-Maclaurin
+/// summation formula.
+fn hurwitz_zeta_f64(s: f64, a: f64) -> f64 {
+    if a <= 0. && a.fract() == 0. {
+        return f64::INFINITY;
+    }
+
+    // shift the argument until the asymptotic expansion converges fast enough
+    let mut a = a;
+    let mut res = 0.;
+    while a < 10. + s.abs() {
+        res += a.powf(-s);
+        a += 1.;
+    }
+
+    res += a.powf(1. - s) / (s - 1.) + a.powf(-s) / 2.;
+
+    let mut pow = a.powf(-s - 1.);
+    let mut poch = s;
+    for (k, c) in BERNOULLI_OVER_FACTORIAL.iter().enumerate() {
+        let term = c * poch * pow;
+        res += term;
+
+        if term.abs() <= res.abs() * f64::EPSILON {
+            break;
+        }
+
+        let k = (k + 1) as f64;
+        poch *= (s + 2. * k - 1.) * (s + 2. * k);
+        pow /= a * a;
+    }
+
+    res
+}
+
+/// The Riemann zeta function `ζ(s)`.
+pub(super) fn zeta_f64(s: f64) -> f64 {
+    if s == 1. {
+        return f64::INFINITY;
+    }
+
+    if s == 0. {
+        return -0.5;
+    }
+
+    if s < 0.5 {
+        if s.fract() == 0. && s % 2. == 0. {
+            return 0.;
+        }
+
+        // ζ(s) = 2^s π^(s-1) sin(πs/2) Γ(1-s) ζ(1-s)
+        let pi = std::f64::consts::PI;
+        return 2f64.powf(s)
+            * pi.powf(s - 1.)
+            * (pi * s / 2.).sin()
+            * libm::tgamma(1. - s)
+            * zeta_f64(1. - s);
+    }
+
+    hurwitz_zeta_f64(s, 1.)
+}
+
+/// The digamma function `ψ(x)`.
+fn digamma_f64(x: f64) -> f64 {
+    if x <= 0. && x.fract() == 0. {
+        return f64::NAN;
+    }
+
+    if x < 0. {
+        // ψ(x) = ψ(1-x) - π cot(πx)
+        let pi = std::f64::consts::PI;
+        return digamma_f64(1. - x) - pi / (pi * x).tan();
+    }
+
+    let mut x = x;
+    let mut res = 0.;
+    while x < 10. {
+        res -= 1. / x;
+        x += 1.;
+    }
+
+    // ψ(x) = log(x) - 1/(2x) - sum_k B_{2k}/(2k x^(2k))
+    let x2 = 1. / (x * x);
+    let tail = x2
+        * (1. / 12.
+            - x2 * (1. / 120.
+                - x2 * (1. / 252. - x2 * (1. / 240. - x2 * (1. / 132. - x2 * 691. / 32760.)))));
+    res + x.ln() - 0.5 / x - tail
+}
+
+/// The polygamma function `ψ⁽ⁿ⁾(x)` of integer order `n >= 0`.
+pub(super) fn polygamma_f64(n: f64, x: f64) -> f64 {
+    if n < 0. || n.fract() != 0. {
+        return f64::NAN;
+    }
+
+    if n == 0. {
+        return digamma_f64(x);
+    }
+
+    // ψ⁽ⁿ⁾(x) = (-1)^(n+1) n! ζ(n+1, x)
+    let r = hurwitz_zeta_f64(n + 1., x) * libm::tgamma(n + 1.);
+    if n % 2. == 0. { -r } else { r }
+}
+
+/// The polylogarithm `Li_n(x)` of integer order `n`. For `x > 1`, the real part is returned.
+pub(super) fn polylog_f64(n: f64, x: f64) -> f64 {
+    if n.fract() != 0. || n.abs() >= i64::MAX as f64 {
+        return f64::NAN;
+    }
+
+    polylog_int_f64(n as i64, x)
+}
+
+fn polylog_int_f64(n: i64, x: f64) -> f64 {
+    if x.is_nan() {
+        return x;
+    }
+
+    if n <= 0 {
+        // Li_{-m}(x) = sum_{k=0}^{m} k! S(m+1, k+1) (x/(1-x))^(k+1)
+        let m = (-n) as usize;
+        let mut stirling = vec![1.];
+        for r in 1..=m + 1 {
+            let mut next = vec![0.; r + 1];
+            for (k, s) in stirling.iter().enumerate() {
+                next[k] += k as f64 * s;
+                next[k + 1] += s;
+            }
+            stirling = next;
+        }
+
+        let y = x / (1. - x);
+        let mut y_pow = y;
+        let mut factorial = 1.;
+        let mut res = 0.;
+        for (k, s) in stirling[1..].iter().enumerate() {
+            res += factorial * s * y_pow;
+            y_pow *= y;
+            factorial *= (k + 1) as f64;
+        }
+        return res;
+    }
+
+    if x == 0. {
+        return 0.;
+    }
+
+    if n == 1 {
+        // the real part of -log(1-x)
+        return -(1. - x).abs().ln();
+    }
+
+    let n_f = n as f64;
+    if x == 1. {
+        return zeta_f64(n_f);
+    }
+
+    if x == -1. {
+        return -(1. - 2f64.powf(1. - n_f)) * zeta_f64(n_f);
+    }
+
+    if x.abs() <= 0.5 {
+        // direct summation
+        let mut res = 0.;
+        let mut x_pow = x;
+        for k in 1u32.. {
+            let term = x_pow / (k as f64).powf(n_f);
+            res += term;
+            if term.abs() <= res.abs() * f64::EPSILON {
+                break;
+            }
+            x_pow *= x;
+        }
+        return res;
+    }
+
+    if x > 0.5 && x < 1. {
+        // expansion in mu = log(x)
+        let mu = x.ln();
+        let mut res = 0.;
+        let mut mu_pow = 1.;
+        let mut small_count = 0;
+        for k in 0i64.. {
+            let term = if k == n - 1 {
+                let harmonic: f64 = (1..n).map(|j| 1. / j as f64).sum();
+                mu_pow * (harmonic - (-mu).ln())
+            } else {
+                zeta_f64((n - k) as f64) * mu_pow
+            };
+
+            res += term;
+
+            if k > n && term.abs() <= res.abs() * f64::EPSILON {
+                small_count += 1;
+                if small_count == 2 {
+                    break;
+                }
+            } else {
+                small_count = 0;
+            }
+
+            mu_pow *= mu / (k + 1) as f64;
+        }
+        return res;
+    }
+
+    if x > -1. && x < -0.5 {
+        // Li_n(x) = 2^(1-n) Li_n(x^2) - Li_n(-x)
+        return polylog_int_f64(n, x * x) * 2f64.powf(1. - n_f) - polylog_int_f64(n, -x);
+    }
+
+    // inversion formula for |x| > 1
+    let inv = polylog_int_f64(n, 1. / x);
+    let log = x.abs().ln();
+
+    let mut res = -log.powf(n_f) / libm::tgamma(n_f + 1.);
+    if n % 2 == 0 {
+        res -= inv;
+    } else {
+        res += inv;
+    }
+
+    for k in 1..=n / 2 {
+        let mut c = zeta_f64((2 * k) as f64);
+        if x < 0. {
+            // Li_{2k}(-1) = -(1 - 2^(1-2k)) zeta(2k)
+            c *= -(1. - 2f64.powf(1. - (2 * k) as f64));
+        }
+
+        res += c * log.powf((n - 2 * k) as f64) * 2. / libm::tgamma((n - 2 * k + 1) as f64);
+    }
+
+    res
+}
+
+/// Evaluate the Bessel function of the given kind and order `nu` at `x`.
+pub(super) fn bessel_f64(kind: BesselKind, nu: f64, x: f64) -> f64 {
+    if nu.fract() == 0. && nu.abs() <= i32::MAX as f64 {
+        match kind {
+            BesselKind::J => return libm::jn(nu as i32, x),
+            BesselKind::Y => return libm::yn(nu as i32, x),
+            _ => {}
+        }
+    }
+
+    // the power series of the remaining functions suffer from cancellations
+    // that require more than double precision
+    bessel(kind, &Float::with_val(53, nu), &Float::with_val(53, x)).to_f64()
+}
@@ -571,6 +571,19 @@ impl Integer {
         }
     }
 
+    /// Compute the double factorial `n!! = n(n-2)(n-4)...`.
+    pub fn double_factorial(n: u32) -> Integer {
+        if n <= 33 {
+            let mut f: i64 = 1;
+            for x in (2..=n as i64).rev().step_by(2) {
+                f *= x;
+            }
+            Integer::Natural(f)
+        } else {
+            Integer::Large(rug::Integer::factorial_2(n).complete())
+        }
+    }
+
     /// Compute the binomial coefficient `(n k) = n!/(k!(n-k)!)`.
     ///
     /// The implementation does not to overflow.
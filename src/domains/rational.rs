@@ -8,7 +8,10 @@ use std::{
 
 use crate::{
     poly::{polynomial::PolynomialRing, Exponent},
-    printer::{PrintOptions, PrintState},
+    printer::{
+        pretty::{self, Block},
+        PrintOptions, PrintState,
+    },
 };
 
 use super::{
@@ -389,6 +392,12 @@ impl<R: EuclideanDomain + FractionNormalization> Ring for FractionField<R> {
     ) -> Result<bool, Error> {
         let has_denom = !self.ring.is_one(&element.denominator);
 
+        if opts.mode.is_pretty() && has_denom {
+            let num = pretty::ring_element(&self.ring, &element.numerator, opts);
+            let den = pretty::ring_element(&self.ring, &element.denominator, opts);
+            return pretty::write(f, &pretty::signed_fraction(num, den, opts));
+        }
+
         let write_par = has_denom && state.in_exp;
         if write_par {
             if state.in_sum {
@@ -448,6 +457,12 @@ where
     ) -> Result<bool, Error> {
         let has_denom = !self.denominator.is_one();
 
+        if opts.mode.is_pretty() && has_denom {
+            let num = Block::from_text(&self.numerator.format_string(opts, PrintState::new()));
+            let den = Block::from_text(&self.denominator.format_string(opts, PrintState::new()));
+            return pretty::write(f, &pretty::signed_fraction(num, den, opts));
+        }
+
         let write_par = has_denom && state.in_exp;
         if write_par {
             if state.in_sum {
@@ -745,6 +760,27 @@ impl Rational {
         Q.gcd(self, other)
     }
 
+    /// Compute the Bernoulli number `B_n`, using the convention `B_1 = -1/2`.
+    pub fn bernoulli(n: u32) -> Rational {
+        if n == 1 {
+            return (-1, 2).into();
+        }
+        if n % 2 == 1 {
+            return Rational::zero();
+        }
+
+        // Akiyama-Tanigawa algorithm
+        let mut a = Vec::with_capacity(n as usize + 1);
+        for m in 0..=n as usize {
+            a.push(Rational::from_unchecked(1, m as i64 + 1));
+            for j in (1..=m).rev() {
+                let d = Q.sub(&a[j - 1], &a[j]);
+                a[j - 1] = Q.mul(&d, &Rational::from(j as i64));
+            }
+        }
+        a.swap_remove(0)
+    }
+
     pub fn to_f64(&self) -> f64 {
         rug::Rational::from((
             self.numerator.clone().to_multi_prec(),
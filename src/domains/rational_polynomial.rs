@@ -20,7 +20,7 @@ use crate::{
         polynomial::{MultivariatePolynomial, PolynomialRing},
         univariate::UnivariatePolynomial,
     },
-    printer::{PrintOptions, PrintState},
+    printer::{PrintOptions, PrintState, pretty},
 };
 
 use super::{
@@ -234,6 +234,16 @@ impl<R: Ring, E: PositiveExponent> SelfRing for RationalPolynomial<R, E> {
             return Ok(false);
         }
 
+        if opts.mode.is_pretty() {
+            let num = self.numerator.to_pretty_block(opts, state.level == 0);
+            if self.denominator.is_one() {
+                return pretty::write(f, &num);
+            }
+
+            let den = self.denominator.to_pretty_block(opts, false);
+            return pretty::write(f, &pretty::signed_fraction(num, den, opts));
+        }
+
         if self.denominator.is_one() {
             self.numerator.format(opts, state, f)
         } else {
@@ -1426,6 +1436,25 @@ mod test {
 
     use super::RationalPolynomialField;
 
+    #[test]
+    fn grassmann_variables() {
+        symbol!("rpg1"; Grassmann).unwrap();
+        symbol!("rpg2"; Grassmann).unwrap();
+
+        // products of anticommuting symbols become a single variable
+        let e = parse!("v1*rpg2*rpg1 + (rpg1 + v2)*(rpg2 + v1) + rpg1").unwrap();
+        let p = e.to_polynomial::<_, u8>(&Q, None);
+        assert_eq!(p.nvars(), 4);
+        assert_eq!(p.to_expression(), e);
+
+        let r = e.to_rational_polynomial::<_, _, u8>(&Q, &Z, None);
+        assert_eq!(r.to_expression(), e);
+
+        let f = e.to_factorized_rational_polynomial::<_, _, u8>(&Q, &Z, None);
+        assert!(f.denominators.is_empty());
+        assert_eq!(f.numerator.to_expression(), e);
+    }
+
     #[test]
     fn field() {
         let field = RationalPolynomialField::<_, u8>::new(Z);
@@ -4,6 +4,7 @@
 
 use std::{
     hash::{Hash, Hasher},
+    io::{Read, Write},
     os::raw::c_ulong,
     sync::{
         Arc, Mutex,
@@ -12,9 +13,11 @@ use std::{
 };
 
 use ahash::{AHasher, HashMap};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use rand::{Rng, rng};
 
 use self_cell::self_cell;
+use smallvec::SmallVec;
 
 use crate::{
     LicenseManager,
@@ -29,7 +32,7 @@ use crate::{
         rational::Rational,
     },
     id::ConditionResult,
-    state::State,
+    state::{State, StateMap},
 };
 
 type EvalFnType<A, T> = Box<
@@ -278,6 +281,139 @@ impl BuiltinSymbol {
     pub fn get_symbol(&self) -> Symbol {
         self.0
     }
+
+    /// Returns `true` if `symbol` is a builtin function that can be evaluated
+    /// with `nargs` arguments.
+    pub fn is_evaluable(symbol: Symbol, nargs: usize) -> bool {
+        match symbol {
+            Atom::ARCTAN => nargs == 1 || nargs == 2,
+            Atom::POLYGAMMA
+            | Atom::BETA
+            | Atom::POLYLOG
+            | Atom::BESSEL_J
+            | Atom::BESSEL_Y
+            | Atom::BESSEL_I
+            | Atom::BESSEL_K => nargs == 2,
+            Atom::EXP
+            | Atom::LOG
+            | Atom::SIN
+            | Atom::COS
+            | Atom::SQRT
+            | Atom::TAN
+            | Atom::ARCSIN
+            | Atom::ARCCOS
+            | Atom::SINH
+            | Atom::COSH
+            | Atom::TANH
+            | Atom::ARCSINH
+            | Atom::ARCCOSH
+            | Atom::ARCTANH
+            | Atom::ABS
+            | Atom::SIGN
+            | Atom::HEAVISIDE
+            | Atom::GAMMA
+            | Atom::FACTORIAL
+            | Atom::ERF
+            | Atom::ZETA => nargs == 1,
+            _ => false,
+        }
+    }
+
+    /// Get the name of the C++ function that evaluates the builtin function
+    /// with `nargs` arguments. Only functions that are defined for both `double`
+    /// and `std::complex<double>` are supported.
+    fn cpp_name(&self, nargs: usize) -> Option<&'static str> {
+        Some(match (self.0, nargs) {
+            (Atom::EXP, 1) => "exp",
+            (Atom::LOG, 1) => "log",
+            (Atom::SIN, 1) => "sin",
+            (Atom::COS, 1) => "cos",
+            (Atom::SQRT, 1) => "sqrt",
+            (Atom::TAN, 1) => "tan",
+            (Atom::ARCSIN, 1) => "asin",
+            (Atom::ARCCOS, 1) => "acos",
+            (Atom::ARCTAN, 1) => "atan",
+            (Atom::SINH, 1) => "sinh",
+            (Atom::COSH, 1) => "cosh",
+            (Atom::TANH, 1) => "tanh",
+            (Atom::ARCSINH, 1) => "asinh",
+            (Atom::ARCCOSH, 1) => "acosh",
+            (Atom::ARCTANH, 1) => "atanh",
+            (Atom::ABS, 1) => "abs",
+            _ => return None,
+        })
+    }
+
+    /// Return an error if the builtin function with `nargs` arguments cannot be exported to C++.
+    fn check_cpp_export(&self, nargs: usize) -> Result<(), String> {
+        if self.cpp_name(nargs).is_none() {
+            Err(format!(
+                "The builtin function {} with {} arguments cannot be exported to C++",
+                self.0, nargs
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Get the C++ code that evaluates the builtin function for the arguments `args`.
+    /// The caller must make sure that the function can be exported with [Self::check_cpp_export].
+    fn cpp_function(&self, args: &[String]) -> String {
+        let name = self.cpp_name(args.len()).unwrap();
+        format!("{}({})", name, args.join(", "))
+    }
+
+    /// Evaluate the builtin function for the arguments `args`.
+    fn evaluate<T: Real>(&self, args: &[&T]) -> T {
+        match (self.0, args) {
+            (Atom::EXP, [x]) => x.exp(),
+            (Atom::LOG, [x]) => x.log(),
+            (Atom::SIN, [x]) => x.sin(),
+            (Atom::COS, [x]) => x.cos(),
+            (Atom::SQRT, [x]) => x.sqrt(),
+            (Atom::TAN, [x]) => x.tan(),
+            (Atom::ARCSIN, [x]) => x.asin(),
+            (Atom::ARCCOS, [x]) => x.acos(),
+            (Atom::ARCTAN, [x]) => x.atan2(&x.one()),
+            (Atom::ARCTAN, [y, x]) => y.atan2(x),
+            (Atom::SINH, [x]) => x.sinh(),
+            (Atom::COSH, [x]) => x.cosh(),
+            (Atom::TANH, [x]) => x.tanh(),
+            (Atom::ARCSINH, [x]) => x.asinh(),
+            (Atom::ARCCOSH, [x]) => x.acosh(),
+            (Atom::ARCTANH, [x]) => x.atanh(),
+            (Atom::ABS, [x]) => x.norm(),
+            (Atom::SIGN, [x]) => {
+                let n = x.norm();
+                if n == x.zero() { n } else { (*x).clone() / n }
+            }
+            (Atom::HEAVISIDE, [x]) => {
+                let n = x.norm();
+                let one = x.one();
+                if n == x.zero() {
+                    one.clone() / (one.clone() + one)
+                } else {
+                    ((*x).clone() / n + &one) / (one.clone() + one)
+                }
+            }
+            (Atom::GAMMA, [x]) => x.gamma(),
+            (Atom::FACTORIAL, [x]) => ((*x).clone() + x.one()).gamma(),
+            (Atom::POLYGAMMA, [n, x]) => x.polygamma(n),
+            (Atom::BETA, [a, b]) => a.gamma() * b.gamma() / ((*a).clone() + *b).gamma(),
+            (Atom::ERF, [x]) => x.erf(),
+            (Atom::ZETA, [x]) => x.zeta(),
+            (Atom::POLYLOG, [n, x]) => x.polylog(n),
+            (Atom::BESSEL_J, [nu, x]) => x.bessel_j(nu),
+            (Atom::BESSEL_Y, [nu, x]) => x.bessel_y(nu),
+            (Atom::BESSEL_I, [nu, x]) => x.bessel_i(nu),
+            (Atom::BESSEL_K, [nu, x]) => x.bessel_k(nu),
+            _ => unreachable!(
+                "Cannot evaluate builtin function {} with {} arguments",
+                self.0,
+                args.len()
+            ),
+        }
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -294,6 +430,8 @@ pub enum Expression<T> {
     ReadArg(usize), // read nth function argument
     BuiltinFun(BuiltinSymbol, Box<Expression<T>>),
     SubExpression(usize),
+    /// A builtin function with more than one argument, such as `polygamma(n, x)`.
+    BuiltinFunMulti(BuiltinSymbol, Vec<Expression<T>>),
 }
 
 type ExpressionHash = u64;
@@ -313,6 +451,7 @@ pub enum HashedExpression<T> {
     ReadArg(ExpressionHash, usize), // read nth function argument
     BuiltinFun(ExpressionHash, BuiltinSymbol, Box<HashedExpression<T>>),
     SubExpression(ExpressionHash, usize),
+    BuiltinFunMulti(ExpressionHash, BuiltinSymbol, Vec<HashedExpression<T>>),
 }
 
 impl<T> HashedExpression<T> {
@@ -328,6 +467,7 @@ impl<T> HashedExpression<T> {
             HashedExpression::ReadArg(h, _) => *h,
             HashedExpression::BuiltinFun(h, _, _) => *h,
             HashedExpression::SubExpression(h, _) => *h,
+            HashedExpression::BuiltinFunMulti(h, _, _) => *h,
         }
     }
 }
@@ -355,6 +495,9 @@ impl<T: Clone> HashedExpression<T> {
                 Expression::BuiltinFun(*s, Box::new(a.to_expression()))
             }
             HashedExpression::SubExpression(_, s) => Expression::SubExpression(*s),
+            HashedExpression::BuiltinFunMulti(_, s, a) => {
+                Expression::BuiltinFunMulti(*s, a.iter().map(|x| x.to_expression()).collect())
+            }
         }
     }
 }
@@ -384,6 +527,10 @@ impl<T: Ord> Ord for HashedExpression<T> {
             (HashedExpression::SubExpression(_, s1), HashedExpression::SubExpression(_, s2)) => {
                 s1.cmp(s2)
             }
+            (
+                HashedExpression::BuiltinFunMulti(_, a, b),
+                HashedExpression::BuiltinFunMulti(_, c, d),
+            ) => a.cmp(c).then_with(|| b.cmp(d)),
             (HashedExpression::Const(_, _), _) => std::cmp::Ordering::Less,
             (_, HashedExpression::Const(_, _)) => std::cmp::Ordering::Greater,
             (HashedExpression::Parameter(_, _), _) => std::cmp::Ordering::Less,
@@ -402,6 +549,8 @@ impl<T: Ord> Ord for HashedExpression<T> {
             (_, HashedExpression::ReadArg(_, _)) => std::cmp::Ordering::Greater,
             (HashedExpression::BuiltinFun(_, _, _), _) => std::cmp::Ordering::Less,
             (_, HashedExpression::BuiltinFun(_, _, _)) => std::cmp::Ordering::Greater,
+            (HashedExpression::SubExpression(_, _), _) => std::cmp::Ordering::Less,
+            (_, HashedExpression::SubExpression(_, _)) => std::cmp::Ordering::Greater,
         }
     }
 }
@@ -456,6 +605,7 @@ impl<T: Eq + Hash + Clone + Ord> HashedExpression<T> {
             }
             HashedExpression::BuiltinFun(_, _, _) => {}
             HashedExpression::SubExpression(_, _) => {}
+            HashedExpression::BuiltinFunMulti(_, _, _) => {}
         }
 
         false
@@ -496,6 +646,7 @@ impl<T: Eq + Hash + Clone + Ord> HashedExpression<T> {
             }
             HashedExpression::BuiltinFun(_, _, _) => {}
             HashedExpression::SubExpression(_, _) => {}
+            HashedExpression::BuiltinFunMulti(_, _, _) => {}
         }
     }
 
@@ -568,6 +719,16 @@ impl<T: Eq + Hash + Clone + Ord> HashedExpression<T> {
                 b.count_operations_with_subexpression(sub_expr)
             } // not clear how to count this, third arg?
             HashedExpression::SubExpression(_, _) => (0, 0),
+            HashedExpression::BuiltinFunMulti(_, _, args) => {
+                let mut add = 0;
+                let mut mul = 0;
+                for arg in args {
+                    let (a, m) = arg.count_operations_with_subexpression(sub_expr);
+                    add += a;
+                    mul += m;
+                }
+                (add, mul)
+            }
         }
     }
 }
@@ -676,6 +837,19 @@ impl<T: std::hash::Hash + Clone> Expression<T> {
                 let h = hasher.finish();
                 (h, HashedExpression::SubExpression(h, *i))
             }
+            Expression::BuiltinFunMulti(s, a) => {
+                let mut hasher = AHasher::default();
+                hasher.write_u8(10);
+                s.hash(&mut hasher);
+                let mut new_a = vec![];
+                for x in a {
+                    let (ha, va) = x.to_hashed_expression();
+                    new_a.push(va);
+                    hasher.write_u64(ha);
+                }
+                let h = hasher.finish();
+                (h, HashedExpression::BuiltinFunMulti(h, *s, new_a))
+            }
         }
     }
 }
@@ -733,14 +907,14 @@ impl<T: Real> ExpressionEvaluator<T> {
                 Instr::Powf(r, b, e) => {
                     self.stack[*r] = self.stack[*b].powf(&self.stack[*e]);
                 }
-                Instr::BuiltinFun(r, s, arg) => match s.0 {
-                    Atom::EXP => self.stack[*r] = self.stack[*arg].exp(),
-                    Atom::LOG => self.stack[*r] = self.stack[*arg].log(),
-                    Atom::SIN => self.stack[*r] = self.stack[*arg].sin(),
-                    Atom::COS => self.stack[*r] = self.stack[*arg].cos(),
-                    Atom::SQRT => self.stack[*r] = self.stack[*arg].sqrt(),
-                    _ => unreachable!(),
-                },
+                Instr::BuiltinFun(r, s, args) => {
+                    let res = {
+                        let args: SmallVec<[&T; 2]> =
+                            args.iter().map(|a| &self.stack[*a]).collect();
+                        s.evaluate(&args)
+                    };
+                    self.stack[*r] = res;
+                }
             }
         }
 
@@ -966,10 +1140,16 @@ impl<T: Default> ExpressionEvaluator<T> {
 
                         *p = new_pos;
                     }
-                    Instr::Pow(p, b, _) | Instr::BuiltinFun(p, _, b) => {
+                    Instr::Pow(p, b, _) => {
                         *b = rename!(*b);
                         *p = new_pos;
                     }
+                    Instr::BuiltinFun(p, _, a) => {
+                        for x in &mut *a {
+                            *x = rename!(*x);
+                        }
+                        *p = new_pos;
+                    }
                     Instr::Powf(p, a, b) => {
                         *a = rename!(*a);
                         *b = rename!(*b);
@@ -1029,7 +1209,7 @@ impl<T: Default + Clone + Eq + Hash> ExpressionEvaluator<T> {
         if delta > 0 {
             for i in &mut self.instructions {
                 match i {
-                    Instr::Add(r, a) | Instr::Mul(r, a) => {
+                    Instr::Add(r, a) | Instr::Mul(r, a) | Instr::BuiltinFun(r, _, a) => {
                         *r += delta;
                         for aa in a {
                             if *aa >= self.reserved_indices {
@@ -1037,7 +1217,7 @@ impl<T: Default + Clone + Eq + Hash> ExpressionEvaluator<T> {
                             }
                         }
                     }
-                    Instr::Pow(r, b, _) | Instr::BuiltinFun(r, _, b) => {
+                    Instr::Pow(r, b, _) => {
                         *r += delta;
                         if *b >= self.reserved_indices {
                             *b += delta;
@@ -1063,7 +1243,7 @@ impl<T: Default + Clone + Eq + Hash> ExpressionEvaluator<T> {
         delta = old_len + new_reserved_indices - other.reserved_indices;
         for i in &mut other.instructions {
             match i {
-                Instr::Add(r, a) | Instr::Mul(r, a) => {
+                Instr::Add(r, a) | Instr::Mul(r, a) | Instr::BuiltinFun(r, _, a) => {
                     *r += delta;
                     for aa in a {
                         if *aa >= other.reserved_indices {
@@ -1073,7 +1253,7 @@ impl<T: Default + Clone + Eq + Hash> ExpressionEvaluator<T> {
                         }
                     }
                 }
-                Instr::Pow(r, b, _) | Instr::BuiltinFun(r, _, b) => {
+                Instr::Pow(r, b, _) => {
                     *r += delta;
                     if *b >= other.reserved_indices {
                         *b += delta;
@@ -1113,7 +1293,7 @@ impl<T: Default + Clone + Eq + Hash> ExpressionEvaluator<T> {
         let mut unfold = HashMap::default();
         for (index, i) in &mut self.instructions.iter_mut().enumerate() {
             match i {
-                Instr::Add(r, a) | Instr::Mul(r, a) => {
+                Instr::Add(r, a) | Instr::Mul(r, a) | Instr::BuiltinFun(r, _, a) => {
                     for aa in a {
                         if *aa >= self.reserved_indices {
                             *aa = unfold[aa];
@@ -1123,7 +1303,7 @@ impl<T: Default + Clone + Eq + Hash> ExpressionEvaluator<T> {
                     unfold.insert(*r, index + self.reserved_indices);
                     *r = index + self.reserved_indices;
                 }
-                Instr::Pow(r, b, _) | Instr::BuiltinFun(r, _, b) => {
+                Instr::Pow(r, b, _) => {
                     if *b >= self.reserved_indices {
                         *b = unfold[b];
                     }
@@ -1165,18 +1345,169 @@ impl<T: Default + Clone + Eq + Hash> ExpressionEvaluator<T> {
     }
 }
 
+impl ExpressionEvaluator<Rational> {
+    /// Write the evaluator to a binary stream. To import the evaluator in a new session,
+    /// also export the [`State`].
+    pub(crate) fn write<W: Write>(&self, dest: &mut W) -> Result<(), std::io::Error> {
+        let write_slots = |dest: &mut W, slots: &[usize]| -> Result<(), std::io::Error> {
+            dest.write_u64::<LittleEndian>(slots.len() as u64)?;
+            for s in slots {
+                dest.write_u64::<LittleEndian>(*s as u64)?;
+            }
+            Ok(())
+        };
+
+        dest.write_u64::<LittleEndian>(self.param_count as u64)?;
+        dest.write_u64::<LittleEndian>(self.reserved_indices as u64)?;
+
+        dest.write_u64::<LittleEndian>(self.stack.len() as u64)?;
+        for c in &self.stack {
+            Atom::new_num(c.clone()).as_view().write(&mut *dest)?;
+        }
+
+        dest.write_u64::<LittleEndian>(self.instructions.len() as u64)?;
+        for i in &self.instructions {
+            match i {
+                Instr::Add(o, a) => {
+                    dest.write_u8(0)?;
+                    dest.write_u64::<LittleEndian>(*o as u64)?;
+                    write_slots(dest, a)?;
+                }
+                Instr::Mul(o, a) => {
+                    dest.write_u8(1)?;
+                    dest.write_u64::<LittleEndian>(*o as u64)?;
+                    write_slots(dest, a)?;
+                }
+                Instr::Pow(o, b, e) => {
+                    dest.write_u8(2)?;
+                    dest.write_u64::<LittleEndian>(*o as u64)?;
+                    dest.write_u64::<LittleEndian>(*b as u64)?;
+                    dest.write_i64::<LittleEndian>(*e)?;
+                }
+                Instr::Powf(o, b, e) => {
+                    dest.write_u8(3)?;
+                    dest.write_u64::<LittleEndian>(*o as u64)?;
+                    dest.write_u64::<LittleEndian>(*b as u64)?;
+                    dest.write_u64::<LittleEndian>(*e as u64)?;
+                }
+                Instr::BuiltinFun(o, s, a) => {
+                    dest.write_u8(4)?;
+                    dest.write_u64::<LittleEndian>(*o as u64)?;
+                    dest.write_u32::<LittleEndian>(s.0.get_id())?;
+                    write_slots(dest, a)?;
+                }
+            }
+        }
+
+        write_slots(dest, &self.result_indices)
+    }
+
+    /// Read an evaluator written by [ExpressionEvaluator::write] from a binary stream,
+    /// renaming the symbols using the provided state map.
+    pub(crate) fn read<R: Read>(
+        source: &mut R,
+        state_map: &StateMap,
+    ) -> Result<Self, std::io::Error> {
+        fn read_slot<R: Read>(source: &mut R) -> Result<usize, std::io::Error> {
+            Ok(source.read_u64::<LittleEndian>()? as usize)
+        }
+
+        fn read_slots<R: Read>(source: &mut R) -> Result<Vec<usize>, std::io::Error> {
+            let n = source.read_u64::<LittleEndian>()?;
+            (0..n).map(|_| read_slot(source)).collect()
+        }
+
+        let param_count = read_slot(source)?;
+        let reserved_indices = read_slot(source)?;
+
+        // the lengths come from the file, so the vectors are not preallocated
+        let n_stack = source.read_u64::<LittleEndian>()?;
+        let mut stack = vec![];
+        let mut a = Atom::new();
+        for _ in 0..n_stack {
+            a.read(&mut *source)?;
+            stack.push(
+                Rational::try_from(a.as_view())
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+            );
+        }
+
+        let n_instructions = source.read_u64::<LittleEndian>()?;
+        let mut instructions = vec![];
+        for _ in 0..n_instructions {
+            let instr = match source.read_u8()? {
+                0 => Instr::Add(read_slot(source)?, read_slots(source)?),
+                1 => Instr::Mul(read_slot(source)?, read_slots(source)?),
+                2 => Instr::Pow(
+                    read_slot(source)?,
+                    read_slot(source)?,
+                    source.read_i64::<LittleEndian>()?,
+                ),
+                3 => Instr::Powf(read_slot(source)?, read_slot(source)?, read_slot(source)?),
+                4 => {
+                    let o = read_slot(source)?;
+                    let s = state_map.get_symbol(source.read_u32::<LittleEndian>()?)?;
+                    let args = read_slots(source)?;
+                    if !BuiltinSymbol::is_evaluable(s, args.len()) {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!("{} is not an evaluable builtin function", s),
+                        ));
+                    }
+                    Instr::BuiltinFun(o, BuiltinSymbol(s), args)
+                }
+                _ => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "Invalid instruction type",
+                    ));
+                }
+            };
+            instructions.push(instr);
+        }
+
+        let result_indices = read_slots(source)?;
+
+        let in_bounds = |i: &usize| *i < stack.len();
+        let valid = param_count <= reserved_indices
+            && reserved_indices <= stack.len()
+            && result_indices.iter().all(in_bounds)
+            && instructions.iter().all(|instr| match instr {
+                Instr::Add(o, a) | Instr::Mul(o, a) | Instr::BuiltinFun(o, _, a) => {
+                    in_bounds(o) && a.iter().all(in_bounds)
+                }
+                Instr::Pow(o, b, _) => in_bounds(o) && in_bounds(b),
+                Instr::Powf(o, b, e) => in_bounds(o) && in_bounds(b) && in_bounds(e),
+            });
+        if !valid {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Evaluator slot index out of bounds",
+            ));
+        }
+
+        Ok(ExpressionEvaluator {
+            stack,
+            param_count,
+            reserved_indices,
+            instructions,
+            result_indices,
+        })
+    }
+}
+
 impl<T> ExpressionEvaluator<T> {
     pub fn optimize_stack(&mut self) {
         let mut last_use: Vec<usize> = vec![0; self.stack.len()];
 
         for (i, x) in self.instructions.iter().enumerate() {
             match x {
-                Instr::Add(_, a) | Instr::Mul(_, a) => {
+                Instr::Add(_, a) | Instr::Mul(_, a) | Instr::BuiltinFun(_, _, a) => {
                     for v in a {
                         last_use[*v] = i;
                     }
                 }
-                Instr::Pow(_, b, _) | Instr::BuiltinFun(_, _, b) => {
+                Instr::Pow(_, b, _) => {
                     last_use[*b] = i;
                 }
                 Instr::Powf(_, a, b) => {
@@ -1227,13 +1558,13 @@ impl<T> ExpressionEvaluator<T> {
             max_reg = max_reg.max(new_reg);
 
             match x {
-                Instr::Add(r, a) | Instr::Mul(r, a) => {
+                Instr::Add(r, a) | Instr::Mul(r, a) | Instr::BuiltinFun(r, _, a) => {
                     *r = new_reg;
                     for v in a {
                         *v = rename_map[*v];
                     }
                 }
-                Instr::Pow(r, b, _) | Instr::BuiltinFun(r, _, b) => {
+                Instr::Pow(r, b, _) => {
                     *r = new_reg;
                     *b = rename_map[*b];
                 }
@@ -1275,7 +1606,8 @@ impl<T: std::fmt::Display> ExpressionEvaluator<T> {
             InlineASM::X64 => self.export_asm_str(function_name, include_header, inline_asm),
             InlineASM::AArch64 => self.export_asm_str(function_name, include_header, inline_asm),
             InlineASM::None => self.export_cpp_str(function_name, include_header),
-        };
+        }
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Unsupported, e))?;
 
         std::fs::write(&filename, cpp)?;
         Ok(ExportedCode {
@@ -1284,7 +1616,17 @@ impl<T: std::fmt::Display> ExpressionEvaluator<T> {
         })
     }
 
-    pub fn export_cpp_str(&self, function_name: &str, include_header: bool) -> String {
+    /// Create a C++ code representation of the evaluation tree.
+    ///
+    /// Returns an error if the evaluator contains a builtin function without a C++ equivalent,
+    /// such as `zeta`.
+    pub fn export_cpp_str(
+        &self,
+        function_name: &str,
+        include_header: bool,
+    ) -> Result<String, String> {
+        self.check_cpp_export()?;
+
         let mut res = String::new();
         if include_header {
             res += "#include <iostream>\n#include <complex>\n#include <cmath>\n\n";
@@ -1334,7 +1676,16 @@ impl<T: std::fmt::Display> ExpressionEvaluator<T> {
             function_name
         );
 
-        res
+        Ok(res)
+    }
+
+    fn check_cpp_export(&self) -> Result<(), String> {
+        for i in &self.instructions {
+            if let Instr::BuiltinFun(_, s, a) = i {
+                s.check_cpp_export(a.len())?;
+            }
+        }
+        Ok(())
     }
 
     fn export_cpp_impl(instr: &[Instr], out: &mut String) {
@@ -1367,39 +1718,26 @@ impl<T: std::fmt::Display> ExpressionEvaluator<T> {
                     let exp = format!("Z{}", e);
                     *out += format!("\tZ{} = pow({}, {});\n", o, base, exp).as_str();
                 }
-                Instr::BuiltinFun(o, s, a) => match s.0 {
-                    Atom::EXP => {
-                        let arg = format!("Z{}", a);
-                        *out += format!("\tZ{} = exp({});\n", o, arg).as_str();
-                    }
-                    Atom::LOG => {
-                        let arg = format!("Z{}", a);
-                        *out += format!("\tZ{} = log({});\n", o, arg).as_str();
-                    }
-                    Atom::SIN => {
-                        let arg = format!("Z{}", a);
-                        *out += format!("\tZ{} = sin({});\n", o, arg).as_str();
-                    }
-                    Atom::COS => {
-                        let arg = format!("Z{}", a);
-                        *out += format!("\tZ{} = cos({});\n", o, arg).as_str();
-                    }
-                    Atom::SQRT => {
-                        let arg = format!("Z{}", a);
-                        *out += format!("\tZ{} = sqrt({});\n", o, arg).as_str();
-                    }
-                    _ => unreachable!(),
-                },
+                Instr::BuiltinFun(o, s, a) => {
+                    let args = a.iter().map(|x| format!("Z{}", x)).collect::<Vec<_>>();
+                    *out += format!("\tZ{} = {};\n", o, s.cpp_function(&args)).as_str();
+                }
             }
         }
     }
 
+    /// Create a C++ code representation of the evaluation tree with inline assembly.
+    ///
+    /// Returns an error if the evaluator contains a builtin function without a C++ equivalent,
+    /// such as `zeta`.
     pub fn export_asm_str(
         &self,
         function_name: &str,
         include_header: bool,
         asm_flavour: InlineASM,
-    ) -> String {
+    ) -> Result<String, String> {
+        self.check_cpp_export()?;
+
         let mut res = String::new();
         if include_header {
             res += "#include <iostream>\n#include <complex>\n#include <cmath>\n\n";
@@ -1455,7 +1793,7 @@ impl<T: std::fmt::Display> ExpressionEvaluator<T> {
 
         res += "\treturn;\n}\n";
 
-        res
+        Ok(res)
     }
 
     fn export_asm_double_impl(
@@ -1582,7 +1920,7 @@ impl<T: std::fmt::Display> ExpressionEvaluator<T> {
 
         for (i, ins) in instr.iter().enumerate() {
             match ins {
-                Instr::Add(r, a) | Instr::Mul(r, a) => {
+                Instr::Add(r, a) | Instr::Mul(r, a) | Instr::BuiltinFun(r, _, a) => {
                     for x in a {
                         if x >= &self.reserved_indices {
                             reg_last_use[stack_to_reg[x]] = i;
@@ -1606,12 +1944,6 @@ impl<T: std::fmt::Display> ExpressionEvaluator<T> {
                     }
                     stack_to_reg.insert(r, i);
                 }
-                Instr::BuiltinFun(r, _, b) => {
-                    if b >= &self.reserved_indices {
-                        reg_last_use[stack_to_reg[b]] = i;
-                    }
-                    stack_to_reg.insert(r, i);
-                }
             }
         }
 
@@ -1633,7 +1965,7 @@ impl<T: std::fmt::Display> ExpressionEvaluator<T> {
             Mul(MemOrReg, u16, Vec<MemOrReg>),
             Pow(MemOrReg, u16, MemOrReg, i64),
             Powf(usize, usize, usize),
-            BuiltinFun(usize, BuiltinSymbol, usize),
+            BuiltinFun(usize, BuiltinSymbol, Vec<usize>),
         }
 
         let mut new_instr: Vec<RegInstr> = instr
@@ -1653,7 +1985,7 @@ impl<T: std::fmt::Display> ExpressionEvaluator<T> {
                     RegInstr::Pow(MemOrReg::Mem(*r), u16::MAX, MemOrReg::Mem(*b), *e)
                 }
                 Instr::Powf(r, b, e) => RegInstr::Powf(*r, *b, *e),
-                Instr::BuiltinFun(r, s, a) => RegInstr::BuiltinFun(*r, *s, *a),
+                Instr::BuiltinFun(r, s, a) => RegInstr::BuiltinFun(*r, *s, a.clone()),
             })
             .collect();
 
@@ -1736,7 +2068,7 @@ impl<T: std::fmt::Display> ExpressionEvaluator<T> {
                             }
                         }
                         RegInstr::BuiltinFun(_, _, a) => {
-                            if *a == old_reg {
+                            if a.contains(&old_reg) {
                                 panic!("use outside of ASM block");
                             }
                         }
@@ -2264,26 +2596,8 @@ impl<T: std::fmt::Display> ExpressionEvaluator<T> {
                 RegInstr::BuiltinFun(o, s, a) => {
                     end_asm_block!(in_asm_block);
 
-                    let arg = get_input!(*a);
-
-                    match s.0 {
-                        Atom::EXP => {
-                            *out += format!("\tZ[{}] = exp({});\n", o, arg).as_str();
-                        }
-                        Atom::LOG => {
-                            *out += format!("\tZ[{}] = log({});\n", o, arg).as_str();
-                        }
-                        Atom::SIN => {
-                            *out += format!("\tZ[{}] = sin({});\n", o, arg).as_str();
-                        }
-                        Atom::COS => {
-                            *out += format!("\tZ[{}] = cos({});\n", o, arg).as_str();
-                        }
-                        Atom::SQRT => {
-                            *out += format!("\tZ[{}] = sqrt({});\n", o, arg).as_str();
-                        }
-                        _ => unreachable!(),
-                    }
+                    let args = a.iter().map(|x| get_input!(*x)).collect::<Vec<_>>();
+                    *out += format!("\tZ[{}] = {};\n", o, s.cpp_function(&args)).as_str();
                 }
             }
         }
@@ -2688,26 +3002,8 @@ impl<T: std::fmt::Display> ExpressionEvaluator<T> {
                 Instr::BuiltinFun(o, s, a) => {
                     end_asm_block!(in_asm_block);
 
-                    let arg = get_input!(*a);
-
-                    match s.0 {
-                        Atom::EXP => {
-                            *out += format!("\tZ[{}] = exp({});\n", o, arg).as_str();
-                        }
-                        Atom::LOG => {
-                            *out += format!("\tZ[{}] = log({});\n", o, arg).as_str();
-                        }
-                        Atom::SIN => {
-                            *out += format!("\tZ[{}] = sin({});\n", o, arg).as_str();
-                        }
-                        Atom::COS => {
-                            *out += format!("\tZ[{}] = cos({});\n", o, arg).as_str();
-                        }
-                        Atom::SQRT => {
-                            *out += format!("\tZ[{}] = sqrt({});\n", o, arg).as_str();
-                        }
-                        _ => unreachable!(),
-                    }
+                    let args = a.iter().map(|x| get_input!(*x)).collect::<Vec<_>>();
+                    *out += format!("\tZ[{}] = {};\n", o, s.cpp_function(&args)).as_str();
                 }
             }
         }
@@ -2828,6 +3124,9 @@ pub enum Instruction {
     /// `Fun(o, s, a)` means `o = s(a)`, where `s` is assumed to
     /// be a built-in function such as `sin`.
     Fun(Slot, BuiltinSymbol, Slot),
+    /// `FunMulti(o, s, [a0,...,a_n])` means `o = s(a0,...,a_n)`, where `s` is assumed to
+    /// be a built-in function with more than one argument, such as `polygamma`.
+    FunMulti(Slot, BuiltinSymbol, Vec<Slot>),
 }
 
 impl std::fmt::Display for Instruction {
@@ -2864,6 +3163,18 @@ impl std::fmt::Display for Instruction {
             Instruction::Fun(o, s, a) => {
                 write!(f, "{} = {}({})", o, s.0, a)
             }
+            Instruction::FunMulti(o, s, a) => {
+                write!(
+                    f,
+                    "{} = {}({})",
+                    o,
+                    s.0,
+                    a.iter()
+                        .map(|x| x.to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                )
+            }
         }
     }
 }
@@ -2916,7 +3227,15 @@ impl<T: Clone> ExpressionEvaluator<T> {
                     ));
                 }
                 Instr::BuiltinFun(o, s, a) => {
-                    instr.push(Instruction::Fun(get_slot!(*o), *s, get_slot!(*a)));
+                    if let [a] = a.as_slice() {
+                        instr.push(Instruction::Fun(get_slot!(*o), *s, get_slot!(*a)));
+                    } else {
+                        instr.push(Instruction::FunMulti(
+                            get_slot!(*o),
+                            *s,
+                            a.iter().map(|x| get_slot!(*x)).collect(),
+                        ));
+                    }
                 }
             }
         }
@@ -2933,7 +3252,7 @@ enum Instr {
     Mul(usize, Vec<usize>),
     Pow(usize, usize, i64),
     Powf(usize, usize, usize),
-    BuiltinFun(usize, BuiltinSymbol, usize),
+    BuiltinFun(usize, BuiltinSymbol, Vec<usize>),
 }
 
 impl<T: Clone + PartialEq> SplitExpression<T> {
@@ -2971,6 +3290,9 @@ impl<T: Clone + PartialEq> Expression<T> {
             }
             Expression::ReadArg(s) => Expression::ReadArg(*s),
             Expression::BuiltinFun(s, a) => Expression::BuiltinFun(*s, Box::new(a.map_coeff(f))),
+            Expression::BuiltinFunMulti(s, a) => {
+                Expression::BuiltinFunMulti(*s, a.iter().map(|x| x.map_coeff(f)).collect())
+            }
             Expression::SubExpression(i) => Expression::SubExpression(*i),
         }
     }
@@ -3007,6 +3329,11 @@ impl<T: Clone + PartialEq> Expression<T> {
             Expression::BuiltinFun(_, a) => {
                 a.strip_constants(stack, param_len);
             }
+            Expression::BuiltinFunMulti(_, a) => {
+                for arg in a {
+                    arg.strip_constants(stack, param_len);
+                }
+            }
             Expression::SubExpression(_) => {}
         }
     }
@@ -3196,6 +3523,18 @@ impl<T: Clone + Default + PartialEq> EvalTree<T> {
             Expression::BuiltinFun(s, v) => {
                 let arg = self.linearize_impl(v, subexpressions, stack, instr, sub_expr_pos, args);
                 stack.push(T::default());
+                let c = Instr::BuiltinFun(stack.len() - 1, *s, vec![arg]);
+                instr.push(c);
+                stack.len() - 1
+            }
+            Expression::BuiltinFunMulti(s, v) => {
+                let arg = v
+                    .iter()
+                    .map(|x| {
+                        self.linearize_impl(x, subexpressions, stack, instr, sub_expr_pos, args)
+                    })
+                    .collect();
+                stack.push(T::default());
                 let c = Instr::BuiltinFun(stack.len() - 1, *s, arg);
                 instr.push(c);
                 stack.len() - 1
@@ -3367,6 +3706,12 @@ impl Expression<Rational> {
                 b.apply_horner_scheme(scheme);
                 return;
             }
+            Expression::BuiltinFunMulti(_, b) => {
+                for arg in b {
+                    arg.apply_horner_scheme(scheme);
+                }
+                return;
+            }
             _ => {
                 return;
             }
@@ -3585,6 +3930,11 @@ impl Expression<Rational> {
             Expression::BuiltinFun(_, a) => {
                 a.occurrence_order_horner_scheme();
             }
+            Expression::BuiltinFunMulti(_, a) => {
+                for arg in a {
+                    arg.occurrence_order_horner_scheme();
+                }
+            }
             Expression::SubExpression(_) => {}
         }
     }
@@ -3777,6 +4127,11 @@ impl Expression<Rational> {
             Expression::BuiltinFun(_, a) => {
                 a.find_all_variables(vars);
             }
+            Expression::BuiltinFunMulti(_, a) => {
+                for arg in a {
+                    arg.find_all_variables(vars);
+                }
+            }
             Expression::SubExpression(_) => {}
         }
     }
@@ -3907,6 +4262,11 @@ impl<T: Clone + Default + std::fmt::Debug + Eq + std::hash::Hash + Ord> Expressi
             Expression::BuiltinFun(_, a) => {
                 a.rename_subexpression(subexp);
             }
+            Expression::BuiltinFunMulti(_, a) => {
+                for arg in a {
+                    arg.rename_subexpression(subexp);
+                }
+            }
             Expression::SubExpression(i) => {
                 *self = Expression::SubExpression(*subexp.get(i).unwrap());
             }
@@ -3936,6 +4296,11 @@ impl<T: Clone + Default + std::fmt::Debug + Eq + std::hash::Hash + Ord> Expressi
             Expression::BuiltinFun(_, a) => {
                 a.get_dependent_subexpressions(dep);
             }
+            Expression::BuiltinFunMulti(_, a) => {
+                for arg in a {
+                    arg.get_dependent_subexpressions(dep);
+                }
+            }
             Expression::SubExpression(i) => {
                 dep.push(*i);
             }
@@ -4010,6 +4375,16 @@ impl<T: Clone + Default + std::fmt::Debug + Eq + std::hash::Hash + Ord> Expressi
             }
             Expression::ReadArg(_) => (0, 0),
             Expression::BuiltinFun(_, b) => b.count_operations(), // not clear how to count this, third arg?
+            Expression::BuiltinFunMulti(_, b) => {
+                let mut add = 0;
+                let mut mul = 0;
+                for arg in b {
+                    let (a, m) = arg.count_operations();
+                    add += a;
+                    mul += m;
+                }
+                (add, mul)
+            }
             Expression::SubExpression(_) => (0, 0),
         }
     }
@@ -4078,6 +4453,16 @@ impl<T: Clone + Default + std::fmt::Debug + Eq + std::hash::Hash + Ord> Expressi
             }
             Expression::ReadArg(_) => (0, 0),
             Expression::BuiltinFun(_, b) => b.count_operations_with_subexpression(sub_expr), // not clear how to count this, third arg?
+            Expression::BuiltinFunMulti(_, b) => {
+                let mut add = 0;
+                let mut mul = 0;
+                for arg in b {
+                    let (a, m) = arg.count_operations_with_subexpression(sub_expr);
+                    add += a;
+                    mul += m;
+                }
+                (add, mul)
+            }
             Expression::SubExpression(_) => (0, 0),
         }
     }
@@ -4143,14 +4528,15 @@ impl<T: Real> EvalTree<T> {
             Expression::ReadArg(i) => args[*i].clone(),
             Expression::BuiltinFun(s, a) => {
                 let arg = self.evaluate_impl(a, subexpressions, params, args);
-                match s.0 {
-                    Atom::EXP => arg.exp(),
-                    Atom::LOG => arg.log(),
-                    Atom::SIN => arg.sin(),
-                    Atom::COS => arg.cos(),
-                    Atom::SQRT => arg.sqrt(),
-                    _ => unreachable!(),
-                }
+                s.evaluate(&[&arg])
+            }
+            Expression::BuiltinFunMulti(s, a) => {
+                let arg: SmallVec<[T; 2]> = a
+                    .iter()
+                    .map(|x| self.evaluate_impl(x, subexpressions, params, args))
+                    .collect();
+                let arg: SmallVec<[&T; 2]> = arg.iter().collect();
+                s.evaluate(&arg)
             }
             Expression::SubExpression(s) => {
                 // TODO: cache
@@ -4445,7 +4831,14 @@ impl Default for InlineASM {
 impl<T: NumericalFloatLike> EvalTree<T> {
     /// Export the evaluation tree to C++ code. For much improved performance,
     /// optimize the tree instead.
-    pub fn export_cpp_str(&self, function_name: &str, include_header: bool) -> String {
+    ///
+    /// Returns an error if the tree contains a builtin function without a C++ equivalent,
+    /// such as `zeta`.
+    pub fn export_cpp_str(
+        &self,
+        function_name: &str,
+        include_header: bool,
+    ) -> Result<String, String> {
         let mut res = if include_header {
             "#include <iostream>\n#include <cmath>\n#include <complex>\n\n".to_string()
         } else {
@@ -4466,14 +4859,14 @@ impl<T: NumericalFloatLike> EvalTree<T> {
             );
 
             for (i, s) in body.subexpressions.iter().enumerate() {
-                res += &format!("\tT Z{}_ = {};\n", i, self.export_cpp_impl(s, arg_names));
+                res += &format!("\tT Z{}_ = {};\n", i, self.export_cpp_impl(s, arg_names)?);
             }
 
             if body.tree.len() > 1 {
                 panic!("Tensor functions not supported yet");
             }
 
-            let ret = self.export_cpp_impl(&body.tree[0], arg_names);
+            let ret = self.export_cpp_impl(&body.tree[0], arg_names)?;
             res += &format!("\treturn {};\n}}\n", ret);
         }
 
@@ -4483,11 +4876,11 @@ impl<T: NumericalFloatLike> EvalTree<T> {
         );
 
         for (i, s) in self.expressions.subexpressions.iter().enumerate() {
-            res += &format!("\tT Z{}_ = {};\n", i, self.export_cpp_impl(s, &[]));
+            res += &format!("\tT Z{}_ = {};\n", i, self.export_cpp_impl(s, &[])?);
         }
 
         for (i, e) in self.expressions.tree.iter().enumerate() {
-            res += &format!("\tout[{}] = {};\n", i, self.export_cpp_impl(e, &[]));
+            res += &format!("\tout[{}] = {};\n", i, self.export_cpp_impl(e, &[])?);
         }
 
         res += "\treturn;\n}\n";
@@ -4501,11 +4894,11 @@ impl<T: NumericalFloatLike> EvalTree<T> {
             function_name
         );
 
-        res
+        Ok(res)
     }
 
-    fn export_cpp_impl(&self, expr: &Expression<T>, args: &[Symbol]) -> String {
-        match expr {
+    fn export_cpp_impl(&self, expr: &Expression<T>, args: &[Symbol]) -> Result<String, String> {
+        Ok(match expr {
             Expression::Const(c) => {
                 format!("T({})", c)
             }
@@ -4517,34 +4910,34 @@ impl<T: NumericalFloatLike> EvalTree<T> {
 
                 for a in e_args {
                     r.push_str(", ");
-                    r += &self.export_cpp_impl(a, args);
+                    r += &self.export_cpp_impl(a, args)?;
                 }
                 r.push(')');
                 r
             }
             Expression::Add(a) => {
                 let mut r = "(".to_string();
-                r += &self.export_cpp_impl(&a[0], args);
+                r += &self.export_cpp_impl(&a[0], args)?;
                 for arg in &a[1..] {
                     r.push_str(" + ");
-                    r += &self.export_cpp_impl(arg, args);
+                    r += &self.export_cpp_impl(arg, args)?;
                 }
                 r.push(')');
                 r
             }
             Expression::Mul(m) => {
                 let mut r = "(".to_string();
-                r += &self.export_cpp_impl(&m[0], args);
+                r += &self.export_cpp_impl(&m[0], args)?;
                 for arg in &m[1..] {
                     r.push_str(" * ");
-                    r += &self.export_cpp_impl(arg, args);
+                    r += &self.export_cpp_impl(arg, args)?;
                 }
                 r.push(')');
                 r
             }
             Expression::Pow(p) => {
                 let mut r = "pow(".to_string();
-                r += &self.export_cpp_impl(&p.0, args);
+                r += &self.export_cpp_impl(&p.0, args)?;
                 r.push_str(", ");
                 r.push_str(&p.1.to_string());
                 r.push(')');
@@ -4552,50 +4945,29 @@ impl<T: NumericalFloatLike> EvalTree<T> {
             }
             Expression::Powf(p) => {
                 let mut r = "powf(".to_string();
-                r += &self.export_cpp_impl(&p.0, args);
+                r += &self.export_cpp_impl(&p.0, args)?;
                 r.push_str(", ");
-                r += &self.export_cpp_impl(&p.1, args);
+                r += &self.export_cpp_impl(&p.1, args)?;
                 r.push(')');
                 r
             }
             Expression::ReadArg(s) => args[*s].to_string(),
-            Expression::BuiltinFun(s, a) => match s.0 {
-                Atom::EXP => {
-                    let mut r = "exp(".to_string();
-                    r += &self.export_cpp_impl(a, args);
-                    r.push(')');
-                    r
-                }
-                Atom::LOG => {
-                    let mut r = "log(".to_string();
-                    r += &self.export_cpp_impl(a, args);
-                    r.push(')');
-                    r
-                }
-                Atom::SIN => {
-                    let mut r = "sin(".to_string();
-                    r += &self.export_cpp_impl(a, args);
-                    r.push(')');
-                    r
-                }
-                Atom::COS => {
-                    let mut r = "cos(".to_string();
-                    r += &self.export_cpp_impl(a, args);
-                    r.push(')');
-                    r
-                }
-                Atom::SQRT => {
-                    let mut r = "sqrt(".to_string();
-                    r += &self.export_cpp_impl(a, args);
-                    r.push(')');
-                    r
-                }
-                _ => unreachable!(),
-            },
+            Expression::BuiltinFun(s, a) => {
+                s.check_cpp_export(1)?;
+                s.cpp_function(&[self.export_cpp_impl(a, args)?])
+            }
             Expression::SubExpression(id) => {
                 format!("Z{}_", id)
             }
-        }
+            Expression::BuiltinFunMulti(s, a) => {
+                s.check_cpp_export(a.len())?;
+                let a = a
+                    .iter()
+                    .map(|x| self.export_cpp_impl(x, args))
+                    .collect::<Result<Vec<_>, _>>()?;
+                s.cpp_function(&a)
+            }
+        })
     }
 }
 
@@ -4675,15 +5047,20 @@ impl<'a> AtomView<'a> {
             }
             AtomView::Fun(f) => {
                 let name = f.get_symbol();
-                if [Atom::EXP, Atom::LOG, Atom::SIN, Atom::COS, Atom::SQRT].contains(&name) {
-                    assert!(f.get_nargs() == 1);
-                    let arg = f.iter().next().unwrap();
-                    let arg_eval = arg.to_eval_tree_impl(fn_map, params, args, funcs)?;
-
-                    return Ok(Expression::BuiltinFun(
-                        BuiltinSymbol(f.get_symbol()),
-                        Box::new(arg_eval),
-                    ));
+                if BuiltinSymbol::is_evaluable(name, f.get_nargs()) {
+                    let mut arg_eval: Vec<_> = f
+                        .iter()
+                        .map(|arg| arg.to_eval_tree_impl(fn_map, params, args, funcs))
+                        .collect::<Result<_, _>>()?;
+
+                    return Ok(if arg_eval.len() == 1 {
+                        Expression::BuiltinFun(
+                            BuiltinSymbol(name),
+                            Box::new(arg_eval.pop().unwrap()),
+                        )
+                    } else {
+                        Expression::BuiltinFunMulti(BuiltinSymbol(name), arg_eval)
+                    });
                 }
 
                 let Some(fun) = fn_map.get(*self) else {
@@ -4836,19 +5213,19 @@ impl<'a> AtomView<'a> {
             },
             AtomView::Fun(f) => {
                 let name = f.get_symbol();
-                if [Atom::EXP, Atom::LOG, Atom::SIN, Atom::COS, Atom::SQRT].contains(&name) {
-                    assert!(f.get_nargs() == 1);
-                    let arg = f.iter().next().unwrap();
-                    let arg_eval = arg.evaluate_impl(coeff_map, const_map, function_map, cache)?;
-
-                    return Ok(match f.get_symbol() {
-                        Atom::EXP => arg_eval.exp(),
-                        Atom::LOG => arg_eval.log(),
-                        Atom::SIN => arg_eval.sin(),
-                        Atom::COS => arg_eval.cos(),
-                        Atom::SQRT => arg_eval.sqrt(),
-                        _ => unreachable!(),
-                    });
+                if BuiltinSymbol::is_evaluable(name, f.get_nargs()) {
+                    let mut arg_eval: SmallVec<[T; 2]> = SmallVec::new();
+                    for arg in f {
+                        arg_eval.push(arg.evaluate_impl(
+                            coeff_map,
+                            const_map,
+                            function_map,
+                            cache,
+                        )?);
+                    }
+
+                    let arg_eval: SmallVec<[&T; 2]> = arg_eval.iter().collect();
+                    return Ok(BuiltinSymbol(name).evaluate(&arg_eval));
                 }
 
                 if let Some(eval) = cache.get(self) {
@@ -5064,8 +5441,11 @@ mod test {
 
     use crate::{
         atom::{Atom, AtomCore},
-        domains::{float::Float, rational::Rational},
-        evaluate::{EvaluationFn, FunctionMap, OptimizationSettings},
+        domains::{
+            float::{Float, Real},
+            rational::Rational,
+        },
+        evaluate::{EvaluationFn, FunctionMap, InlineASM, OptimizationSettings},
         id::ConditionResult,
         parse, symbol,
     };
@@ -5183,6 +5563,56 @@ mod test {
         assert!((r - 1622709.2254269677).abs() / 1622709.2254269677 < 1e-10);
     }
 
+    #[test]
+    fn special_functions() {
+        let e = parse!(
+            "gamma(v1) + li(3, v1/2) + arctan(v1, 2) + bessel_k(2, v1) + abs(-v1) + polygamma(1, v1)"
+        )
+        .unwrap();
+
+        let x = 1.3f64;
+        let refr = Real::gamma(&x)
+            + (x / 2.).polylog(&3.)
+            + Real::atan2(&x, &2.)
+            + x.bessel_k(&2.)
+            + x
+            + x.polygamma(&1.);
+
+        let mut const_map = HashMap::default();
+        const_map.insert(Atom::new_var(symbol!("v1")), x);
+        let r = e
+            .evaluate(|x| x.into(), &const_map, &HashMap::default())
+            .unwrap();
+        assert!((r - refr).abs() < 1e-14 * refr);
+
+        let params = vec![parse!("v1").unwrap()];
+        let mut evaluator = e
+            .evaluator(
+                &FunctionMap::new(),
+                &params,
+                OptimizationSettings::default(),
+            )
+            .unwrap()
+            .map_coeff(&|x| x.into());
+        let r = evaluator.evaluate_single(&[x]);
+        assert!((r - refr).abs() < 1e-14 * refr);
+
+        // special functions have no C++ equivalent
+        assert!(
+            evaluator
+                .export_cpp("special_functions.cpp", "f", true, InlineASM::None)
+                .is_err()
+        );
+        assert!(evaluator.export_cpp_str("f", true).is_err());
+        assert!(evaluator.export_asm_str("f", true, InlineASM::X64).is_err());
+
+        let tree = e
+            .to_evaluation_tree(&FunctionMap::new(), &params)
+            .unwrap()
+            .map_coeff::<f64, _>(&|x| x.into());
+        assert!(tree.export_cpp_str("f", true).is_err());
+    }
+
     #[test]
     fn zero_test() {
         let e = parse!("(sin(v1)^2-sin(v1))(sin(v1)^2+sin(v1))^2 - (1/4 sin(2v1)^2-1/2 sin(2v1)cos(v1)-2 cos(v1)^2+1/2 sin(2v1)cos(v1)^3+3 cos(v1)^4-cos(v1)^6)").unwrap();
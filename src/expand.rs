@@ -200,7 +200,52 @@ impl AtomView<'_> {
                     return changed;
                 };
 
-                if let AtomView::Add(a) = new_base.as_view() {
+                if matches!(new_base.as_view(), AtomView::Add(_) | AtomView::Mul(_))
+                    && new_base.as_view().is_noncommutative()
+                    || matches!(new_base.as_view(), AtomView::Add(_))
+                        && new_base.as_view().grassmann_parity() != Some(false)
+                {
+                    // the order of the factors matters, so expand the power as a repeated product
+                    let mut mul_h = workspace.new_atom();
+                    let mul = mul_h.to_mul();
+
+                    if let (AtomView::Mul(m), true) = (new_base.as_view(), negative) {
+                        // (A*B)^-n = (B^-1*A^-1)^n
+                        let mut exp_h = workspace.new_atom();
+                        exp_h.to_num((-1i64).into());
+
+                        let args: SmallVec<[AtomView; 10]> = m.iter().collect();
+                        let mut pow_h = workspace.new_atom();
+                        for _ in 0..num {
+                            for arg in args.iter().rev() {
+                                pow_h.to_pow(*arg, exp_h.as_view());
+                                mul.extend(pow_h.as_view());
+                            }
+                        }
+                    } else {
+                        for _ in 0..num {
+                            mul.extend(new_base.as_view());
+                        }
+                    }
+
+                    let mut expanded = workspace.new_atom();
+                    mul_h
+                        .as_view()
+                        .expand_no_norm(workspace, var, &mut expanded);
+
+                    if negative && matches!(new_base.as_view(), AtomView::Add(_)) {
+                        let mut num_h = workspace.new_atom();
+                        num_h.to_num((-1i64).into());
+
+                        let mut pow_h = workspace.new_atom();
+                        pow_h.to_pow(expanded.as_view(), num_h.as_view());
+                        pow_h.as_view().normalize(workspace, out);
+                    } else {
+                        expanded.as_view().normalize(workspace, out);
+                    }
+
+                    true
+                } else if let AtomView::Add(a) = new_base.as_view() {
                     // expand (a+b+c+..)^n
                     let mut args: SmallVec<[AtomView; 10]> = SmallVec::with_capacity(a.get_nargs());
                     for arg in a {
@@ -567,4 +612,36 @@ mod test {
         let res = parse!("1+2*v1+v1^2+(v2+1)^100").unwrap();
         assert_eq!(exp, res);
     }
+
+    #[test]
+    fn expand_noncommutative() {
+        symbol!("nc1"; Noncommutative).unwrap();
+        symbol!("nc2"; Noncommutative).unwrap();
+
+        let exp = parse!("(nc1+nc2)^2").unwrap().expand();
+        let res = parse!("nc1^2+nc1*nc2+nc2*nc1+nc2^2").unwrap();
+        assert_eq!(exp, res);
+
+        let exp = parse!("(v1*nc1*nc2)^2").unwrap().expand();
+        let res = parse!("v1^2*nc1*nc2*nc1*nc2").unwrap();
+        assert_eq!(exp, res);
+
+        let exp = parse!("(nc1*nc2)^-1").unwrap().expand();
+        let res = parse!("nc2^-1*nc1^-1").unwrap();
+        assert_eq!(exp, res);
+    }
+
+    #[test]
+    fn expand_grassmann() {
+        symbol!("gt1"; Grassmann).unwrap();
+        symbol!("gt2"; Grassmann).unwrap();
+
+        let exp = parse!("(gt1+v1)^2").unwrap().expand();
+        let res = parse!("v1^2+2*v1*gt1").unwrap();
+        assert_eq!(exp, res);
+
+        let exp = parse!("(gt1+v1)*(gt2+v2)*gt1").unwrap().expand();
+        let res = parse!("-v1*gt1*gt2+v1*v2*gt1").unwrap();
+        assert_eq!(exp, res);
+    }
 }
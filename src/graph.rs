@@ -19,6 +19,12 @@
 //! assert_eq!(g.node(0).edges, [0, 1, 2]);
 //! ```
 
+pub mod feynman;
+pub mod momentum;
+pub mod planar;
+mod polynomial;
+mod subgraph;
+
 use ahash::{HashMap, HashSet};
 use dyn_clone::DynClone;
 use std::{
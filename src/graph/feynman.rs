@@ -0,0 +1,730 @@
+//! Generation of Feynman diagrams from a model of particles and vertex rules.
+//!
+//! Particles that differ from their antiparticle are represented by directed edges
+//! that follow the flow of the particle, self-conjugate particles by undirected edges.
+//! Every generated diagram carries its symmetry factor, its fermion sign and
+//! a symbolic representation as an [Atom].
+//!
+//! # Examples
+//!
+//! Generate the one-loop photon self-energy in QED:
+//! ```
+//! use symbolica::{
+//!     graph::{
+//!         feynman::{DiagramFilter, Model, Particle},
+//!         GenerationSettings,
+//!     },
+//!     parse, symbol,
+//! };
+//!
+//! let (e, ebar, a) = symbol!("e", "ebar", "a");
+//! let mut model = Model::new();
+//! model.add_particle(Particle::new(e, ebar, true)).unwrap();
+//! model.add_particle(Particle::neutral(a, false)).unwrap();
+//! model.add_vertex(&[ebar, e, a], parse!("ie").unwrap()).unwrap();
+//!
+//! let diagrams = model
+//!     .generate(
+//!         &[a],
+//!         &[a],
+//!         &GenerationSettings::new().max_loops(1),
+//!         &DiagramFilter::new(),
+//!     )
+//!     .unwrap();
+//!
+//! assert_eq!(diagrams.len(), 1);
+//! assert_eq!(diagrams[0].sign, -1);
+//! println!("{}", diagrams[0].expression);
+//! ```
+
+use ahash::HashMap;
+
+use crate::{
+    atom::{Atom, FunctionBuilder, Symbol},
+    domains::integer::Integer,
+    symbol,
+};
+
+use super::{GenerationSettings, Graph};
+
+/// A particle in a [Model].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Particle {
+    /// The name of the particle.
+    pub name: Symbol,
+    /// The name of the antiparticle. It is equal to `name`
+    /// for self-conjugate particles.
+    pub antiparticle: Symbol,
+    /// If `true`, the particle is a fermion.
+    pub fermion: bool,
+}
+
+impl Particle {
+    /// Create a new particle with a distinct antiparticle.
+    pub fn new(name: Symbol, antiparticle: Symbol, fermion: bool) -> Particle {
+        Particle {
+            name,
+            antiparticle,
+            fermion,
+        }
+    }
+
+    /// Create a new self-conjugate particle.
+    pub fn neutral(name: Symbol, fermion: bool) -> Particle {
+        Particle {
+            name,
+            antiparticle: name,
+            fermion,
+        }
+    }
+
+    /// Returns `true` if the particle is its own antiparticle.
+    pub fn is_self_conjugate(&self) -> bool {
+        self.name == self.antiparticle
+    }
+}
+
+/// Filters that remove classes of diagrams from the output of [Model::generate].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DiagramFilter {
+    one_particle_irreducible: bool,
+    no_tadpoles: bool,
+    no_self_energies: bool,
+    no_external_self_energies: bool,
+}
+
+impl DiagramFilter {
+    /// Create a filter that accepts all diagrams.
+    pub const fn new() -> Self {
+        Self {
+            one_particle_irreducible: false,
+            no_tadpoles: false,
+            no_self_energies: false,
+            no_external_self_energies: false,
+        }
+    }
+
+    /// Only keep diagrams that remain connected after cutting any internal propagator.
+    pub fn one_particle_irreducible(mut self, one_particle_irreducible: bool) -> Self {
+        self.one_particle_irreducible = one_particle_irreducible;
+        self
+    }
+
+    /// Remove diagrams that contain a subdiagram without external legs that is
+    /// attached to the rest of the diagram by a single propagator.
+    pub fn no_tadpoles(mut self, no_tadpoles: bool) -> Self {
+        self.no_tadpoles = no_tadpoles;
+        self
+    }
+
+    /// Remove diagrams with a self-energy insertion on any propagator or external leg.
+    /// A two-point insertion is a self-energy if it contains a loop, so tadpoles that
+    /// are attached to a propagator are removed as well.
+    pub fn no_self_energies(mut self, no_self_energies: bool) -> Self {
+        self.no_self_energies = no_self_energies;
+        self
+    }
+
+    /// Remove diagrams with a self-energy insertion on an external leg.
+    pub fn no_external_self_energies(mut self, no_external_self_energies: bool) -> Self {
+        self.no_external_self_energies = no_external_self_energies;
+        self
+    }
+}
+
+/// An error that occurred during the generation of Feynman diagrams by [Model::generate].
+#[derive(Clone, Debug)]
+pub enum GenerationError {
+    /// An external particle is not part of the model.
+    UnknownParticle(Symbol),
+    /// The generation was aborted. The diagrams that were generated up to that point
+    /// are included.
+    Aborted(Vec<FeynmanDiagram>),
+}
+
+impl std::fmt::Display for GenerationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenerationError::UnknownParticle(p) => write!(f, "Unknown particle {}", p),
+            GenerationError::Aborted(d) => {
+                write!(f, "Generation aborted after {} diagrams", d.len())
+            }
+        }
+    }
+}
+
+/// A Feynman diagram generated by [Model::generate].
+#[derive(Clone, Debug)]
+pub struct FeynmanDiagram {
+    /// The canonical graph of the diagram. External nodes have data `i + 1`,
+    /// where `i` is the index of the external leg, and internal nodes have data `0`.
+    /// The edge data is the name of the particle whose flow follows the
+    /// direction of the edge.
+    pub graph: Graph<usize, Symbol>,
+    /// The symmetry factor of the diagram.
+    pub symmetry_factor: Integer,
+    /// The fermion sign of the diagram.
+    pub sign: i64,
+    /// The diagram as an expression, which is a product of the sign divided
+    /// by the symmetry factor, the vertices `vx(v, coupling)`, the propagators
+    /// `prop(particle, edge, from, to)` and the external legs `ext(particle, leg, v)`.
+    pub expression: Atom,
+}
+
+/// A model of particles and vertex rules from which Feynman diagrams are generated.
+#[derive(Clone)]
+pub struct Model {
+    particles: Vec<Particle>,
+    lookup: HashMap<Symbol, (usize, bool)>,
+    vertices: HashMap<Vec<(Option<bool>, Symbol)>, Atom>,
+    /// The function used for propagators in the diagram expressions.
+    pub propagator: Symbol,
+    /// The function used for external legs in the diagram expressions.
+    pub external: Symbol,
+    /// The function used for vertices in the diagram expressions.
+    pub vertex: Symbol,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Model {
+    /// Create an empty model that uses the functions `prop`, `ext` and `vx`
+    /// in the diagram expressions.
+    pub fn new() -> Model {
+        Model {
+            particles: vec![],
+            lookup: HashMap::default(),
+            vertices: HashMap::default(),
+            propagator: symbol!("prop"),
+            external: symbol!("ext"),
+            vertex: symbol!("vx"),
+        }
+    }
+
+    /// Get the particles of the model.
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Add a particle to the model.
+    pub fn add_particle(&mut self, particle: Particle) -> Result<(), String> {
+        if self.lookup.contains_key(&particle.name)
+            || self.lookup.contains_key(&particle.antiparticle)
+        {
+            return Err(format!("Particle {} is already defined", particle.name));
+        }
+
+        if particle.fermion && particle.is_self_conjugate() {
+            return Err(format!(
+                "Majorana fermion {} is not supported",
+                particle.name
+            ));
+        }
+
+        let index = self.particles.len();
+        self.lookup.insert(particle.name, (index, false));
+        if !particle.is_self_conjugate() {
+            self.lookup.insert(particle.antiparticle, (index, true));
+        }
+        self.particles.push(particle);
+        Ok(())
+    }
+
+    /// Get the edge signature of an incoming particle, as seen from the vertex.
+    fn incoming_signature(&self, particle: Symbol) -> Option<(Option<bool>, Symbol)> {
+        let (index, anti) = *self.lookup.get(&particle)?;
+        let p = &self.particles[index];
+        if p.is_self_conjugate() {
+            Some((None, p.name))
+        } else {
+            Some((Some(anti), p.name))
+        }
+    }
+
+    /// Add a vertex rule with the given coupling, where all `particles` are
+    /// taken to be incoming. Vertex rules with the same particle content
+    /// have their couplings added.
+    pub fn add_vertex(&mut self, particles: &[Symbol], coupling: Atom) -> Result<(), String> {
+        let mut signature = Vec::with_capacity(particles.len());
+        for p in particles {
+            let Some(s) = self.incoming_signature(*p) else {
+                return Err(format!("Unknown particle {}", p));
+            };
+            signature.push(s);
+        }
+
+        let (mut fermions_in, mut fermions_out) = (0, 0);
+        for &(dir, p) in &signature {
+            if self.particles[self.lookup[&p].0].fermion {
+                if dir == Some(true) {
+                    fermions_out += 1;
+                } else {
+                    fermions_in += 1;
+                }
+            }
+        }
+
+        if fermions_in + fermions_out > 0 && (fermions_in != 1 || fermions_out != 1) {
+            return Err(
+                "Vertices must have either zero or one incoming and one outgoing fermion"
+                    .to_owned(),
+            );
+        }
+
+        signature.sort();
+        match self.vertices.get_mut(&signature) {
+            Some(c) => *c = &*c + &coupling,
+            None => {
+                self.vertices.insert(signature, coupling);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Generate all Feynman diagrams with the `incoming` and `outgoing` particles.
+    /// The external legs are numbered in the order of the incoming particles followed
+    /// by the outgoing particles. Diagrams that are rejected by `filter` are removed.
+    ///
+    /// An error is returned if an external particle is not part of the model. If the graph
+    /// generation was aborted, all diagrams that were generated up to that point are
+    /// returned in [GenerationError::Aborted].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `settings` do not limit the number of vertices or loops.
+    pub fn generate(
+        &self,
+        incoming: &[Symbol],
+        outgoing: &[Symbol],
+        settings: &GenerationSettings<usize, Symbol>,
+        filter: &DiagramFilter,
+    ) -> Result<Vec<FeynmanDiagram>, GenerationError> {
+        let mut external_particles = vec![];
+        let mut external_edges = vec![];
+        for (i, p) in incoming.iter().chain(outgoing).enumerate() {
+            let Some(&(index, anti)) = self.lookup.get(p) else {
+                return Err(GenerationError::UnknownParticle(*p));
+            };
+            let particle = &self.particles[index];
+
+            // edges of external nodes point into the diagram for incoming particles
+            let dir = if particle.is_self_conjugate() {
+                None
+            } else {
+                Some(anti == (i >= incoming.len()))
+            };
+
+            external_particles.push(*p);
+            external_edges.push((i + 1, (dir, particle.name)));
+        }
+
+        let vertex_signatures: Vec<_> = self.vertices.keys().cloned().collect();
+
+        let mut settings = settings.clone();
+        if filter.one_particle_irreducible {
+            settings.max_bridges = Some(0);
+        }
+
+        let (graphs, aborted) =
+            match Graph::generate(&external_edges, &vertex_signatures, &settings) {
+                Ok(g) => (g, false),
+                Err(g) => (g, true),
+            };
+
+        let mut graphs: Vec<_> = graphs.into_iter().collect();
+        graphs.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let diagrams = graphs
+            .into_iter()
+            .filter(|(g, _)| self.filter(g, filter))
+            .map(|(g, size)| self.to_diagram(g, size, &external_particles))
+            .collect();
+
+        if aborted {
+            Err(GenerationError::Aborted(diagrams))
+        } else {
+            Ok(diagrams)
+        }
+    }
+
+    /// Test if the graph passes the filter.
+    fn filter(&self, g: &Graph<usize, Symbol>, filter: &DiagramFilter) -> bool {
+        if !filter.no_tadpoles && !filter.no_self_energies && !filter.no_external_self_energies {
+            return true;
+        }
+
+        let bridges: Vec<_> = (0..g.edges().len())
+            .filter(|&e| {
+                let (a, b) = g.edge(e).vertices;
+                a != b && {
+                    let c = components(g, &[e]);
+                    c[a] != c[b]
+                }
+            })
+            .collect();
+
+        let count_externals = |c: &[usize], label: usize| {
+            g.nodes()
+                .iter()
+                .zip(c)
+                .filter(|(n, l)| n.data > 0 && **l == label)
+                .count()
+        };
+
+        if filter.no_tadpoles {
+            for &b in &bridges {
+                let c = components(g, &[b]);
+                let (v1, v2) = g.edge(b).vertices;
+                if count_externals(&c, c[v1]) == 0 || count_externals(&c, c[v2]) == 0 {
+                    return false;
+                }
+            }
+        }
+
+        if filter.no_self_energies || filter.no_external_self_energies {
+            for (i, &b1) in bridges.iter().enumerate() {
+                for &b2 in &bridges[i + 1..] {
+                    let c = components(g, &[b1, b2]);
+                    let (e1, e2) = (g.edge(b1).vertices, g.edge(b2).vertices);
+
+                    // find the component between the two bridges
+                    let Some((middle, outer1)) = [(c[e1.0], c[e1.1]), (c[e1.1], c[e1.0])]
+                        .into_iter()
+                        .find(|(m, _)| *m == c[e2.0] || *m == c[e2.1])
+                    else {
+                        continue;
+                    };
+                    let outer2 = if c[e2.0] == middle { c[e2.1] } else { c[e2.0] };
+
+                    if count_externals(&c, middle) > 0 {
+                        continue;
+                    }
+
+                    // a self-energy insertion contains at least one loop
+                    let num_vertices = c.iter().filter(|l| **l == middle).count();
+                    let num_edges = g
+                        .edges()
+                        .iter()
+                        .enumerate()
+                        .filter(|(e, x)| *e != b1 && *e != b2 && c[x.vertices.0] == middle)
+                        .count();
+                    if num_edges < num_vertices {
+                        continue;
+                    }
+
+                    if filter.no_self_energies
+                        || count_externals(&c, outer1) == 1
+                        || count_externals(&c, outer2) == 1
+                    {
+                        return false;
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Construct the diagram of a generated graph with automorphism group size `size`.
+    fn to_diagram(
+        &self,
+        g: Graph<usize, Symbol>,
+        size: Integer,
+        external_particles: &[Symbol],
+    ) -> FeynmanDiagram {
+        // the automorphism group size counts a factor 2 for every self-loop,
+        // which is not a symmetry for directed self-loops
+        let mut symmetry_factor = size;
+        for e in g.edges() {
+            if e.directed && e.is_self_loop() {
+                symmetry_factor /= &Integer::from(2);
+            }
+        }
+
+        let sign = self.fermion_sign(&g);
+
+        let mut expression = Atom::new_num(sign) / Atom::new_num(symmetry_factor.clone());
+        for (v, n) in g.nodes().iter().enumerate() {
+            if n.data > 0 {
+                continue;
+            }
+
+            let mut signature = vec![];
+            for e in &n.edges {
+                let edge = g.edge(*e);
+                if edge.is_self_loop() {
+                    let dirs = if edge.directed {
+                        [Some(false), Some(true)]
+                    } else {
+                        [None, None]
+                    };
+                    signature.extend(dirs.map(|d| (d, edge.data)));
+                } else if edge.directed {
+                    signature.push((Some(edge.vertices.0 == v), edge.data));
+                } else {
+                    signature.push((None, edge.data));
+                }
+            }
+            signature.sort();
+
+            expression *= FunctionBuilder::new(self.vertex)
+                .add_arg(Atom::new_num(v as i64))
+                .add_arg(&self.vertices[&signature])
+                .finish();
+        }
+
+        for (i, e) in g.edges().iter().enumerate() {
+            let (a, b) = e.vertices;
+            let f = if g.node(a).data > 0 || g.node(b).data > 0 {
+                let (ext, v) = if g.node(a).data > 0 { (a, b) } else { (b, a) };
+                let leg = g.node(ext).data - 1;
+                FunctionBuilder::new(self.external)
+                    .add_arg(external_particles[leg])
+                    .add_arg(Atom::new_num(leg as i64))
+                    .add_arg(Atom::new_num(v as i64))
+                    .finish()
+            } else {
+                FunctionBuilder::new(self.propagator)
+                    .add_arg(e.data)
+                    .add_arg(Atom::new_num(i as i64))
+                    .add_arg(Atom::new_num(a as i64))
+                    .add_arg(Atom::new_num(b as i64))
+                    .finish()
+            };
+            expression *= f;
+        }
+
+        FeynmanDiagram {
+            graph: g,
+            symmetry_factor,
+            sign,
+            expression,
+        }
+    }
+
+    /// Compute the fermion sign of a diagram: a factor `-1` for every closed fermion loop
+    /// times the parity of the sequence of external legs `(end, start)` of every open fermion line.
+    fn fermion_sign(&self, g: &Graph<usize, Symbol>) -> i64 {
+        let is_fermion = |e: usize| {
+            let edge = g.edge(e);
+            edge.directed && self.particles[self.lookup[&edge.data].0].fermion
+        };
+
+        // the unique fermion edge that leaves an internal vertex
+        let outgoing = |v: usize| {
+            g.node(v)
+                .edges
+                .iter()
+                .copied()
+                .find(|e| is_fermion(*e) && g.edge(*e).vertices.0 == v)
+                .unwrap()
+        };
+
+        let mut visited = vec![false; g.edges().len()];
+        let mut sequence = vec![];
+        let mut sign = 1;
+
+        // follow the open fermion lines
+        for (start, n) in g.nodes().iter().enumerate() {
+            if n.data == 0 || !is_fermion(n.edges[0]) || g.edge(n.edges[0]).vertices.0 != start {
+                continue;
+            }
+
+            let mut e = n.edges[0];
+            loop {
+                visited[e] = true;
+                let v = g.edge(e).vertices.1;
+                if g.node(v).data > 0 {
+                    sequence.push(g.node(v).data);
+                    sequence.push(n.data);
+                    break;
+                }
+                e = outgoing(v);
+            }
+        }
+
+        // the remaining fermion edges form closed loops
+        for start in 0..g.edges().len() {
+            if visited[start] || !is_fermion(start) {
+                continue;
+            }
+
+            sign = -sign;
+            let mut e = start;
+            while !visited[e] {
+                visited[e] = true;
+                e = outgoing(g.edge(e).vertices.1);
+            }
+        }
+
+        for i in 0..sequence.len() {
+            for j in i + 1..sequence.len() {
+                if sequence[i] > sequence[j] {
+                    sign = -sign;
+                }
+            }
+        }
+
+        sign
+    }
+}
+
+/// Label the connected components of the graph after removing the edges `removed`.
+fn components<N, E>(g: &Graph<N, E>, removed: &[usize]) -> Vec<usize> {
+    let mut labels = vec![usize::MAX; g.nodes().len()];
+    let mut stack = vec![];
+    for start in 0..g.nodes().len() {
+        if labels[start] != usize::MAX {
+            continue;
+        }
+
+        labels[start] = start;
+        stack.push(start);
+        while let Some(v) = stack.pop() {
+            for e in &g.node(v).edges {
+                if removed.contains(e) {
+                    continue;
+                }
+
+                let (a, b) = g.edge(*e).vertices;
+                let w = if a == v { b } else { a };
+                if labels[w] == usize::MAX {
+                    labels[w] = start;
+                    stack.push(w);
+                }
+            }
+        }
+    }
+
+    labels
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{domains::integer::Integer, graph::GenerationSettings, parse, symbol};
+
+    use super::{DiagramFilter, GenerationError, Model, Particle};
+
+    fn phi3() -> Model {
+        let phi = symbol!("phi");
+        let mut model = Model::new();
+        model.add_particle(Particle::neutral(phi, false)).unwrap();
+        model
+            .add_vertex(&[phi, phi, phi], parse!("g").unwrap())
+            .unwrap();
+        model
+    }
+
+    #[test]
+    fn phi3_self_energy() {
+        let phi = symbol!("phi");
+        let model = phi3();
+
+        let diagrams = model
+            .generate(
+                &[phi],
+                &[phi],
+                &GenerationSettings::new()
+                    .max_loops(1)
+                    .allow_self_loops(true),
+                &DiagramFilter::new(),
+            )
+            .unwrap();
+
+        let mut factors: Vec<_> = diagrams.iter().map(|d| d.symmetry_factor.clone()).collect();
+        factors.sort();
+        assert_eq!(factors, [Integer::from(2), Integer::from(2)]);
+
+        let diagrams = model
+            .generate(
+                &[phi],
+                &[phi],
+                &GenerationSettings::new()
+                    .max_loops(1)
+                    .allow_self_loops(true),
+                &DiagramFilter::new().no_tadpoles(true),
+            )
+            .unwrap();
+        assert_eq!(diagrams.len(), 1);
+        assert_eq!(
+            diagrams[0].expression,
+            parse!("1/2*prop(phi,0,0,1)*prop(phi,1,0,1)*ext(phi,0,1)*ext(phi,1,0)*vx(0,g)*vx(1,g)")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn phi3_vertex_filters() {
+        let phi = symbol!("phi");
+        let model = phi3();
+        let settings = GenerationSettings::new()
+            .max_loops(1)
+            .allow_self_loops(true);
+
+        let count = |filter: DiagramFilter| {
+            model
+                .generate(&[phi], &[phi, phi], &settings, &filter)
+                .unwrap()
+                .len()
+        };
+
+        // tree, triangle, three self-energies and three tadpoles on the external legs
+        assert_eq!(count(DiagramFilter::new()), 8);
+        assert_eq!(count(DiagramFilter::new().no_tadpoles(true)), 5);
+        // a tadpole on a propagator is a reducible self-energy insertion
+        assert_eq!(count(DiagramFilter::new().no_self_energies(true)), 2);
+        assert_eq!(
+            count(
+                DiagramFilter::new()
+                    .no_tadpoles(true)
+                    .no_external_self_energies(true)
+            ),
+            2
+        );
+        assert_eq!(
+            count(DiagramFilter::new().one_particle_irreducible(true)),
+            2
+        );
+    }
+
+    #[test]
+    fn bhabha_sign() {
+        let (e, ebar, a) = symbol!("e", "ebar", "a");
+        let mut model = Model::new();
+        model.add_particle(Particle::new(e, ebar, true)).unwrap();
+        model.add_particle(Particle::neutral(a, false)).unwrap();
+        model
+            .add_vertex(&[ebar, e, a], parse!("ie").unwrap())
+            .unwrap();
+
+        let diagrams = model
+            .generate(
+                &[e, ebar],
+                &[e, ebar],
+                &GenerationSettings::new().max_loops(0),
+                &DiagramFilter::new(),
+            )
+            .unwrap();
+
+        // the s- and t-channel diagrams have a relative minus sign
+        assert_eq!(diagrams.len(), 2);
+        assert_eq!(diagrams[0].sign * diagrams[1].sign, -1);
+        assert!(diagrams.iter().all(|d| d.symmetry_factor == 1));
+
+        let mu = symbol!("mu");
+        assert!(matches!(
+            model.generate(
+                &[e, mu],
+                &[e, mu],
+                &GenerationSettings::new().max_loops(0),
+                &DiagramFilter::new(),
+            ),
+            Err(GenerationError::UnknownParticle(p)) if p == mu
+        ));
+    }
+}
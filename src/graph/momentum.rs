@@ -0,0 +1,512 @@
+//! Cycle bases and loop-momentum routings of graphs.
+//!
+//! The momentum of an edge flows from `vertices.0` to `vertices.1`. For undirected
+//! edges, the vertices are sorted, so that the momentum flows from the node with the
+//! lowest index to the node with the highest index.
+//!
+//! # Examples
+//!
+//! Route the momentum through a one-loop bubble with two external legs:
+//! ```
+//! use symbolica::{
+//!     atom::Atom,
+//!     graph::{momentum::RoutingSettings, Graph},
+//!     parse,
+//! };
+//!
+//! let mut g = Graph::new();
+//! let n: Vec<_> = (0..4).map(|_| g.add_node(())).collect();
+//! g.add_edge(n[0], n[1], false, ()).unwrap();
+//! g.add_edge(n[1], n[2], false, ()).unwrap();
+//! g.add_edge(n[1], n[2], false, ()).unwrap();
+//! g.add_edge(n[2], n[3], false, ()).unwrap();
+//!
+//! let k = parse!("k").unwrap();
+//! let p = [
+//!     parse!("p").unwrap(),
+//!     Atom::new(),
+//!     Atom::new(),
+//!     parse!("-p").unwrap(),
+//! ];
+//! let r = g
+//!     .get_momentum_routing(&[k], &p, &RoutingSettings::new())
+//!     .unwrap();
+//!
+//! assert_eq!(r.loop_edges, [2]);
+//! assert_eq!(r.momenta[1], parse!("p-k").unwrap());
+//! assert_eq!(r.momenta[2], parse!("k").unwrap());
+//! ```
+
+use crate::atom::{Atom, AtomCore};
+
+use super::Graph;
+
+/// Settings for [Graph::get_momentum_routing].
+#[derive(Clone, Debug, Default)]
+pub struct RoutingSettings {
+    minimize_momenta: bool,
+    loop_edges: Option<Vec<usize>>,
+}
+
+impl RoutingSettings {
+    /// Create new settings, where the loop momenta are assigned to the edges
+    /// that are not in a depth-first spanning tree that starts at node 0.
+    pub const fn new() -> Self {
+        Self {
+            minimize_momenta: false,
+            loop_edges: None,
+        }
+    }
+
+    /// Choose the routing that minimizes the largest number of momenta on an edge
+    /// and then the total number of momenta on all edges. All spanning trees of
+    /// the graph are tried, unless the loop edges are set with [Self::loop_edges].
+    /// The external momenta of a tree edge are expressed using the nodes on the side
+    /// of the edge that has the fewest external momenta.
+    pub fn minimize_momenta(mut self, minimize_momenta: bool) -> Self {
+        self.minimize_momenta = minimize_momenta;
+        self
+    }
+
+    /// Set the edges that carry the loop momenta, in the order of the loop momenta.
+    /// The other edges must form a spanning tree.
+    pub fn loop_edges(mut self, loop_edges: Vec<usize>) -> Self {
+        self.loop_edges = Some(loop_edges);
+        self
+    }
+}
+
+/// A momentum routing of a graph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MomentumRouting {
+    /// The edges that carry the loop momenta, in the order of the loop momenta.
+    pub loop_edges: Vec<usize>,
+    /// For every edge, the coefficient of every loop momentum in the edge momentum.
+    pub loop_signatures: Vec<Vec<i64>>,
+    /// For every edge, the coefficient of the external momentum of every node in the
+    /// edge momentum. Nodes without external momentum have coefficient 0.
+    pub external_signatures: Vec<Vec<i64>>,
+    /// The momentum of every edge.
+    pub momenta: Vec<Atom>,
+}
+
+impl MomentumRouting {
+    /// Get the number of momenta in the routing of edge `edge`.
+    pub fn momentum_count(&self, edge: usize) -> usize {
+        self.loop_signatures[edge]
+            .iter()
+            .chain(&self.external_signatures[edge])
+            .filter(|s| **s != 0)
+            .count()
+    }
+
+    /// The largest number of momenta on an edge and the total number of momenta.
+    fn cost(&self) -> (usize, usize) {
+        (0..self.momenta.len())
+            .map(|e| self.momentum_count(e))
+            .fold((0, 0), |(m, t), c| (m.max(c), t + c))
+    }
+}
+
+/// The parent node, the edge to the parent and the depth of every node
+/// in a spanning forest. For a root, the edge is `None`.
+type TreeInfo = Vec<(usize, Option<usize>, usize)>;
+
+impl<N, E> Graph<N, E> {
+    /// Get the edges of a depth-first spanning forest of the graph.
+    fn get_spanning_forest_edges(&self) -> Vec<bool> {
+        let mut in_tree = vec![false; self.edges.len()];
+        let mut visited = vec![false; self.nodes.len()];
+
+        for start in 0..self.nodes.len() {
+            if visited[start] {
+                continue;
+            }
+
+            let tree = self.get_spanning_tree(start);
+            for (n, info) in tree.nodes.iter().enumerate() {
+                if info.position.is_none() {
+                    continue;
+                }
+
+                visited[n] = true;
+                if n == start {
+                    continue;
+                }
+
+                let e = self.nodes[n]
+                    .edges
+                    .iter()
+                    .find(|e| {
+                        let (a, b) = self.edges[**e].vertices;
+                        (a, b) == (n, info.parent) || (a, b) == (info.parent, n)
+                    })
+                    .unwrap();
+                in_tree[*e] = true;
+            }
+        }
+
+        in_tree
+    }
+
+    /// Get the parent, the parent edge and the depth of every node
+    /// in the forest that consists of the edges `in_tree`, together with
+    /// the nodes in breadth-first order.
+    fn get_tree_info(&self, in_tree: &[bool]) -> (TreeInfo, Vec<usize>) {
+        let mut info: TreeInfo = vec![(usize::MAX, None, 0); self.nodes.len()];
+        let mut order = Vec::with_capacity(self.nodes.len());
+
+        for start in 0..self.nodes.len() {
+            if info[start].0 != usize::MAX {
+                continue;
+            }
+
+            info[start].0 = start;
+            let first = order.len();
+            order.push(start);
+            let mut i = first;
+            while i < order.len() {
+                let n = order[i];
+                i += 1;
+
+                for e in &self.nodes[n].edges {
+                    if !in_tree[*e] {
+                        continue;
+                    }
+
+                    let (a, b) = self.edges[*e].vertices;
+                    let t = if a == n { b } else { a };
+                    if info[t].0 == usize::MAX {
+                        info[t] = (n, Some(*e), info[n].2 + 1);
+                        order.push(t);
+                    }
+                }
+            }
+        }
+
+        (info, order)
+    }
+
+    /// Get the path in the forest from `from` to `to`, as a list of edges
+    /// and whether they are traversed in their direction.
+    fn get_tree_path(&self, info: &TreeInfo, mut from: usize, mut to: usize) -> Vec<(usize, bool)> {
+        let mut start = vec![];
+        let mut end = vec![];
+
+        while from != to {
+            if info[from].2 >= info[to].2 {
+                let e = info[from].1.unwrap();
+                start.push((e, self.edges[e].vertices.0 == from));
+                from = info[from].0;
+            } else {
+                let e = info[to].1.unwrap();
+                end.push((e, self.edges[e].vertices.1 == to));
+                to = info[to].0;
+            }
+        }
+
+        start.extend(end.into_iter().rev());
+        start
+    }
+
+    /// Get the fundamental cycles of the forest `in_tree`.
+    fn get_fundamental_cycles(&self, in_tree: &[bool], info: &TreeInfo) -> Vec<Vec<(usize, bool)>> {
+        let mut cycles = vec![];
+        for (i, e) in self.edges.iter().enumerate() {
+            if in_tree[i] {
+                continue;
+            }
+
+            let mut cycle = vec![(i, true)];
+            cycle.extend(self.get_tree_path(info, e.vertices.1, e.vertices.0));
+            cycles.push(cycle);
+        }
+        cycles
+    }
+
+    /// Get a fundamental cycle basis of the graph, using a depth-first spanning forest.
+    /// Every edge that is not in the forest yields a cycle that starts with this edge,
+    /// followed by the path in the forest back to the start of the edge. Every cycle
+    /// is a list of edges and whether they are traversed in their direction.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolica::graph::Graph;
+    ///
+    /// let mut g = Graph::new();
+    /// let n: Vec<_> = (0..3).map(|_| g.add_node(())).collect();
+    /// g.add_edge(n[0], n[1], false, ()).unwrap();
+    /// g.add_edge(n[1], n[2], false, ()).unwrap();
+    /// g.add_edge(n[0], n[2], false, ()).unwrap();
+    ///
+    /// let cycles = g.get_cycle_basis();
+    /// assert_eq!(cycles.len(), 1);
+    /// assert_eq!(cycles[0].len(), 3);
+    /// ```
+    pub fn get_cycle_basis(&self) -> Vec<Vec<(usize, bool)>> {
+        let in_tree = self.get_spanning_forest_edges();
+        let (info, _) = self.get_tree_info(&in_tree);
+        self.get_fundamental_cycles(&in_tree, &info)
+    }
+
+    /// Get a routing of the `loop_momenta` and the `external_momenta` through the graph.
+    /// Every edge that is not in a spanning tree carries a loop momentum that flows back
+    /// through the tree, and the external momentum that flows into every node is
+    /// routed through the tree. The momentum is conserved at every vertex.
+    ///
+    /// The graph must be connected, the number of loop momenta must equal the
+    /// number of loops and the external momenta must sum to zero. Nodes without
+    /// external momentum should have momentum zero.
+    ///
+    /// See [RoutingSettings] for options to choose the loop edges and to minimize the
+    /// number of momenta per edge.
+    pub fn get_momentum_routing(
+        &self,
+        loop_momenta: &[Atom],
+        external_momenta: &[Atom],
+        settings: &RoutingSettings,
+    ) -> Result<MomentumRouting, &'static str> {
+        if !self.is_connected() || self.nodes.is_empty() {
+            return Err("The graph must be connected");
+        }
+        if loop_momenta.len() != self.num_loops() {
+            return Err("The number of loop momenta must equal the number of loops");
+        }
+        if external_momenta.len() != self.nodes.len() {
+            return Err("The number of external momenta must equal the number of nodes");
+        }
+
+        let mut total = Atom::new();
+        for p in external_momenta {
+            total += p;
+        }
+        if !total.expand().is_zero() {
+            return Err("The external momenta are not conserved");
+        }
+
+        if let Some(loop_edges) = &settings.loop_edges {
+            let mut in_tree = vec![true; self.edges.len()];
+            for e in loop_edges {
+                if *e >= self.edges.len() || !in_tree[*e] {
+                    return Err("The loop edges must be distinct edges of the graph");
+                }
+                in_tree[*e] = false;
+            }
+
+            if loop_edges.len() != self.num_loops()
+                || self
+                    .get_tree_info(&in_tree)
+                    .0
+                    .iter()
+                    .filter(|x| x.1.is_none())
+                    .count()
+                    != 1
+            {
+                return Err("The edges that are not loop edges must form a spanning tree");
+            }
+
+            return Ok(self.route_momenta(
+                &in_tree,
+                loop_edges,
+                loop_momenta,
+                external_momenta,
+                settings.minimize_momenta,
+            ));
+        }
+
+        if !settings.minimize_momenta {
+            let in_tree = self.get_spanning_forest_edges();
+            let loop_edges: Vec<_> = (0..self.edges.len()).filter(|e| !in_tree[*e]).collect();
+            return Ok(self.route_momenta(
+                &in_tree,
+                &loop_edges,
+                loop_momenta,
+                external_momenta,
+                false,
+            ));
+        }
+
+        let mut best: Option<MomentumRouting> = None;
+        for tree in self.get_spanning_forests(1) {
+            let mut in_tree = vec![false; self.edges.len()];
+            for e in tree {
+                in_tree[e] = true;
+            }
+            let loop_edges: Vec<_> = (0..self.edges.len()).filter(|e| !in_tree[*e]).collect();
+
+            let r = self.route_momenta(&in_tree, &loop_edges, loop_momenta, external_momenta, true);
+            if best.as_ref().map(|b| r.cost() < b.cost()).unwrap_or(true) {
+                best = Some(r);
+            }
+        }
+
+        Ok(best.unwrap())
+    }
+
+    /// Route the momenta through the spanning tree `in_tree`, where the
+    /// loop momenta are assigned to `loop_edges`.
+    fn route_momenta(
+        &self,
+        in_tree: &[bool],
+        loop_edges: &[usize],
+        loop_momenta: &[Atom],
+        external_momenta: &[Atom],
+        smallest_side: bool,
+    ) -> MomentumRouting {
+        let (info, order) = self.get_tree_info(in_tree);
+
+        let mut loop_signatures = vec![vec![0; loop_edges.len()]; self.edges.len()];
+        for (i, e) in loop_edges.iter().enumerate() {
+            let (a, b) = self.edges[*e].vertices;
+            loop_signatures[*e][i] = 1;
+            for (te, dir) in self.get_tree_path(&info, b, a) {
+                loop_signatures[te][i] = if dir { 1 } else { -1 };
+            }
+        }
+
+        // the external momentum that flows from a node to its parent is the
+        // sum of the momenta that flow into its subtree
+        let has_momentum: Vec<_> = external_momenta.iter().map(|p| !p.is_zero()).collect();
+        let mut subtree: Vec<Vec<i64>> = has_momentum
+            .iter()
+            .enumerate()
+            .map(|(i, h)| {
+                let mut s = vec![0; self.nodes.len()];
+                s[i] = *h as i64;
+                s
+            })
+            .collect();
+
+        let mut external_signatures = vec![vec![0; self.nodes.len()]; self.edges.len()];
+        for &n in order.iter().rev() {
+            let (parent, Some(e)) = (info[n].0, info[n].1) else {
+                continue;
+            };
+
+            let s = std::mem::take(&mut subtree[n]);
+            for (ps, c) in subtree[parent].iter_mut().zip(&s) {
+                *ps += c;
+            }
+
+            let sign = if self.edges[e].vertices.0 == n { 1 } else { -1 };
+            let inside = s.iter().filter(|x| **x != 0).count();
+            if smallest_side && 2 * inside > has_momentum.iter().filter(|x| **x).count() {
+                for (i, (c, h)) in s.iter().zip(&has_momentum).enumerate() {
+                    if *h && *c == 0 {
+                        external_signatures[e][i] = -sign;
+                    }
+                }
+            } else {
+                for (i, c) in s.iter().enumerate() {
+                    external_signatures[e][i] = sign * c;
+                }
+            }
+        }
+
+        let momenta = loop_signatures
+            .iter()
+            .zip(&external_signatures)
+            .map(|(ls, es)| {
+                let mut m = Atom::new();
+                for (s, k) in ls
+                    .iter()
+                    .zip(loop_momenta)
+                    .chain(es.iter().zip(external_momenta))
+                {
+                    if *s != 0 {
+                        m += &(k * *s);
+                    }
+                }
+                m
+            })
+            .collect();
+
+        MomentumRouting {
+            loop_edges: loop_edges.to_vec(),
+            loop_signatures,
+            external_signatures,
+            momenta,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        atom::{Atom, AtomCore},
+        graph::Graph,
+        parse,
+    };
+
+    use super::{MomentumRouting, RoutingSettings};
+
+    fn check_conservation(g: &Graph<(), ()>, r: &MomentumRouting, external: &[Atom]) {
+        for (n, p) in external.iter().enumerate() {
+            let mut sum = p.clone();
+            for (e, m) in g.edges().iter().zip(&r.momenta) {
+                if e.vertices.1 == n {
+                    sum += m;
+                }
+                if e.vertices.0 == n {
+                    sum -= m;
+                }
+            }
+            assert!(sum.expand().is_zero());
+        }
+    }
+
+    /// A two-loop double box with four external legs.
+    fn double_box() -> (Graph<(), ()>, Vec<Atom>) {
+        let mut g = Graph::new();
+        let n: Vec<_> = (0..10).map(|_| g.add_node(())).collect();
+        for (a, b) in [(0, 1), (1, 2), (3, 4), (4, 5), (0, 3), (1, 4), (2, 5)] {
+            g.add_edge(n[a], n[b], false, ()).unwrap();
+        }
+        for (a, b) in [(0, 6), (2, 7), (5, 8), (3, 9)] {
+            g.add_edge(n[a], n[b], false, ()).unwrap();
+        }
+
+        let mut p = vec![Atom::new(); 6];
+        p.push(parse!("p1").unwrap());
+        p.push(parse!("p2").unwrap());
+        p.push(parse!("p3").unwrap());
+        p.push(parse!("-p1-p2-p3").unwrap());
+        (g, p)
+    }
+
+    #[test]
+    fn routing() {
+        let (g, p) = double_box();
+        let k = [parse!("k1").unwrap(), parse!("k2").unwrap()];
+
+        assert_eq!(g.get_cycle_basis().len(), 2);
+
+        let r = g
+            .get_momentum_routing(&k, &p, &RoutingSettings::new())
+            .unwrap();
+        assert_eq!(r.loop_edges.len(), 2);
+        check_conservation(&g, &r, &p);
+
+        let m = g
+            .get_momentum_routing(&k, &p, &RoutingSettings::new().minimize_momenta(true))
+            .unwrap();
+        check_conservation(&g, &m, &p);
+        assert!(m.cost() <= r.cost());
+        assert_eq!(m.cost().0, 3);
+
+        let l = g
+            .get_momentum_routing(&k, &p, &RoutingSettings::new().loop_edges(vec![0, 3]))
+            .unwrap();
+        check_conservation(&g, &l, &p);
+        assert_eq!(l.momenta[0], parse!("k1").unwrap());
+        assert_eq!(l.momenta[3], parse!("k2").unwrap());
+
+        assert!(g
+            .get_momentum_routing(&k, &p, &RoutingSettings::new().loop_edges(vec![0, 4]))
+            .is_err());
+        assert!(g
+            .get_momentum_routing(&k[..1], &p, &RoutingSettings::new())
+            .is_err());
+    }
+}
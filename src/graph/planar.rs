@@ -0,0 +1,914 @@
+//! Planarity testing and combinatorial embeddings of graphs.
+//!
+//! An embedding is described by a rotation system: for every node, the clockwise
+//! cyclic order of the half-edges around it. The half-edges of edge `e` have
+//! the indices `2 * e` and `2 * e + 1`, which are incident to `vertices.0` and
+//! `vertices.1` of the edge respectively.
+//!
+//! # Examples
+//!
+//! ```
+//! use symbolica::graph::Graph;
+//!
+//! // the complete graph K4 is planar
+//! let mut g = Graph::new();
+//! for i in 0..4 {
+//!     g.add_node(i);
+//! }
+//! for i in 0..4 {
+//!     for j in i + 1..4 {
+//!         g.add_edge(i, j, false, ()).unwrap();
+//!     }
+//! }
+//!
+//! let embedding = g.get_planar_embedding().unwrap();
+//! assert_eq!(embedding.faces().len(), 4);
+//!
+//! let dual = g.get_dual_graph(&embedding);
+//! assert_eq!(dual.nodes().len(), 4);
+//!
+//! // adding a fifth node connected to all others yields K5, which is not planar
+//! g.add_node(4);
+//! for i in 0..4 {
+//!     g.add_edge(i, 4, false, ()).unwrap();
+//! }
+//! assert!(!g.is_planar());
+//! ```
+
+use std::{cmp::Ordering, collections::VecDeque};
+
+use ahash::HashMap;
+
+use super::{Empty, Graph};
+
+/// The node data and for every half-edge the node, the opposite half-edge,
+/// the direction and the edge data of a labeled embedded graph.
+type EmbeddingCode<'a, N, E> = (Vec<&'a N>, Vec<(usize, usize, Option<bool>, &'a E)>);
+
+/// A combinatorial embedding of a graph, given by the clockwise
+/// order of the half-edges around every node.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Embedding {
+    rotations: Vec<Vec<usize>>,
+    positions: Vec<(usize, usize)>,
+}
+
+impl Embedding {
+    /// Create an embedding of `graph` from the clockwise order of the half-edges
+    /// around every node.
+    pub fn new<N, E>(
+        graph: &Graph<N, E>,
+        rotations: Vec<Vec<usize>>,
+    ) -> Result<Self, &'static str> {
+        if rotations.len() != graph.nodes.len() {
+            return Err("The number of rotations must equal the number of nodes");
+        }
+
+        let mut positions = vec![(usize::MAX, usize::MAX); 2 * graph.edges.len()];
+        for (n, r) in rotations.iter().enumerate() {
+            for (i, h) in r.iter().enumerate() {
+                if *h >= positions.len() || positions[*h].0 != usize::MAX {
+                    return Err("Invalid or duplicate half-edge");
+                }
+
+                let e = &graph.edges[h / 2];
+                let node = if h % 2 == 0 {
+                    e.vertices.0
+                } else {
+                    e.vertices.1
+                };
+                if node != n {
+                    return Err("Half-edge is not incident to the node");
+                }
+
+                positions[*h] = (n, i);
+            }
+        }
+
+        if positions.iter().any(|p| p.0 == usize::MAX) {
+            return Err("Not all half-edges are part of the embedding");
+        }
+
+        Ok(Embedding {
+            rotations,
+            positions,
+        })
+    }
+
+    /// Get the clockwise order of the half-edges around `node`.
+    pub fn rotation(&self, node: usize) -> &[usize] {
+        &self.rotations[node]
+    }
+
+    /// Get the node that the half-edge `half_edge` is incident to.
+    pub fn node(&self, half_edge: usize) -> usize {
+        self.positions[half_edge].0
+    }
+
+    /// Get the half-edge that follows `half_edge` in clockwise order around its node.
+    pub fn next_half_edge(&self, half_edge: usize) -> usize {
+        let (n, i) = self.positions[half_edge];
+        let r = &self.rotations[n];
+        r[(i + 1) % r.len()]
+    }
+
+    /// Get the faces of the embedding, as lists of half-edges. Every half-edge `h` is
+    /// traversed away from its node and is followed by the half-edge that comes after
+    /// the opposite half-edge `h ^ 1` in clockwise order.
+    pub fn faces(&self) -> Vec<Vec<usize>> {
+        let mut visited = vec![false; self.positions.len()];
+        let mut faces = vec![];
+        for start in 0..self.positions.len() {
+            if visited[start] {
+                continue;
+            }
+
+            let mut face = vec![];
+            let mut h = start;
+            while !visited[h] {
+                visited[h] = true;
+                face.push(h);
+                h = self.next_half_edge(h ^ 1);
+            }
+            faces.push(face);
+        }
+
+        faces
+    }
+
+    /// Get the face index of every half-edge.
+    fn face_map(&self) -> (usize, Vec<usize>) {
+        let faces = self.faces();
+        let mut map = vec![0; self.positions.len()];
+        for (i, f) in faces.iter().enumerate() {
+            for h in f {
+                map[*h] = i;
+            }
+        }
+        (faces.len(), map)
+    }
+}
+
+/// An interval of return edges in the left-right planarity test.
+#[derive(Clone, Copy, Default)]
+struct Interval {
+    low: Option<usize>,
+    high: Option<usize>,
+}
+
+impl Interval {
+    fn is_empty(&self) -> bool {
+        self.low.is_none() && self.high.is_none()
+    }
+
+    fn conflicting(&self, edge: usize, lowpt: &[usize]) -> bool {
+        self.high.is_some_and(|h| lowpt[h] > lowpt[edge])
+    }
+}
+
+/// A pair of intervals of return edges that must be on different sides.
+#[derive(Clone, Copy, Default)]
+struct ConflictPair {
+    left: Interval,
+    right: Interval,
+}
+
+impl ConflictPair {
+    fn swap(&mut self) {
+        std::mem::swap(&mut self.left, &mut self.right);
+    }
+
+    fn lowest(&self, lowpt: &[usize]) -> usize {
+        match (self.left.low, self.right.low) {
+            (Some(l), Some(r)) => lowpt[l].min(lowpt[r]),
+            (Some(l), None) => lowpt[l],
+            (None, Some(r)) => lowpt[r],
+            (None, None) => unreachable!("Empty conflict pair"),
+        }
+    }
+}
+
+/// The state of the left-right planarity test of Brandes on a simple graph.
+struct LeftRightPlanarity {
+    edges: Vec<(usize, usize)>,
+    adjacency: Vec<Vec<usize>>,
+    height: Vec<Option<usize>>,
+    parent_edge: Vec<Option<usize>>,
+    roots: Vec<usize>,
+    oriented: Vec<bool>,
+    target: Vec<usize>,
+    source: Vec<usize>,
+    lowpt: Vec<usize>,
+    lowpt2: Vec<usize>,
+    nesting_depth: Vec<i64>,
+    ordered_adjacency: Vec<Vec<usize>>,
+    refs: Vec<Option<usize>>,
+    side: Vec<i64>,
+    stack: Vec<ConflictPair>,
+    stack_bottom: Vec<usize>,
+    lowpt_edge: Vec<usize>,
+    left_ref: Vec<usize>,
+    right_ref: Vec<usize>,
+    cw: Vec<usize>,
+    ccw: Vec<usize>,
+    first: Vec<Option<usize>>,
+}
+
+impl LeftRightPlanarity {
+    fn new(n_nodes: usize, edges: Vec<(usize, usize)>) -> Self {
+        let mut adjacency = vec![vec![]; n_nodes];
+        for (i, (a, b)) in edges.iter().enumerate() {
+            adjacency[*a].push(i);
+            adjacency[*b].push(i);
+        }
+
+        let m = edges.len();
+        LeftRightPlanarity {
+            edges,
+            adjacency,
+            height: vec![None; n_nodes],
+            parent_edge: vec![None; n_nodes],
+            roots: vec![],
+            oriented: vec![false; m],
+            target: vec![0; m],
+            source: vec![0; m],
+            lowpt: vec![0; m],
+            lowpt2: vec![0; m],
+            nesting_depth: vec![0; m],
+            ordered_adjacency: vec![vec![]; n_nodes],
+            refs: vec![None; m],
+            side: vec![1; m],
+            stack: vec![],
+            stack_bottom: vec![0; m],
+            lowpt_edge: vec![0; m],
+            left_ref: vec![0; n_nodes],
+            right_ref: vec![0; n_nodes],
+            cw: vec![0; 2 * m],
+            ccw: vec![0; 2 * m],
+            first: vec![None; n_nodes],
+        }
+    }
+
+    /// Get the half-edge of edge `edge` that is incident to `node`.
+    fn half_edge(&self, edge: usize, node: usize) -> usize {
+        2 * edge + (self.edges[edge].1 == node) as usize
+    }
+
+    /// Test for planarity and return the clockwise order of the half-edges
+    /// around every node if the graph is planar.
+    fn run(mut self) -> Option<Vec<Vec<usize>>> {
+        let n = self.adjacency.len();
+        if n > 2 && self.edges.len() > 3 * n - 6 {
+            return None;
+        }
+
+        for v in 0..n {
+            if self.height[v].is_none() {
+                self.height[v] = Some(0);
+                self.roots.push(v);
+                self.orient(v);
+            }
+        }
+
+        for v in 0..n {
+            let depth = &self.nesting_depth;
+            self.ordered_adjacency[v].sort_by_key(|e| depth[*e]);
+        }
+
+        for r in self.roots.clone() {
+            if !self.test(r) {
+                return None;
+            }
+        }
+
+        for e in 0..self.edges.len() {
+            self.nesting_depth[e] *= self.sign(e);
+        }
+
+        for v in 0..n {
+            let depth = &self.nesting_depth;
+            self.ordered_adjacency[v].sort_by_key(|e| depth[*e]);
+
+            let mut previous = None;
+            for i in 0..self.ordered_adjacency[v].len() {
+                let h = self.half_edge(self.ordered_adjacency[v][i], v);
+                self.add_half_edge_cw(v, h, previous);
+                previous = Some(h);
+            }
+        }
+
+        for r in self.roots.clone() {
+            self.embed(r);
+        }
+
+        let mut rotations = vec![vec![]; n];
+        for (v, r) in rotations.iter_mut().enumerate() {
+            if let Some(f) = self.first[v] {
+                let mut h = f;
+                loop {
+                    r.push(h);
+                    h = self.cw[h];
+                    if h == f {
+                        break;
+                    }
+                }
+            }
+        }
+
+        Some(rotations)
+    }
+
+    /// Orient the edges along a depth-first search and compute the lowpoints.
+    fn orient(&mut self, v: usize) {
+        let parent = self.parent_edge[v];
+        let hv = self.height[v].unwrap();
+        for i in 0..self.adjacency[v].len() {
+            let e = self.adjacency[v][i];
+            if self.oriented[e] {
+                continue;
+            }
+
+            let w = if self.edges[e].0 == v {
+                self.edges[e].1
+            } else {
+                self.edges[e].0
+            };
+            self.oriented[e] = true;
+            self.source[e] = v;
+            self.target[e] = w;
+            self.ordered_adjacency[v].push(e);
+            self.lowpt[e] = hv;
+            self.lowpt2[e] = hv;
+
+            if let Some(hw) = self.height[w] {
+                // back edge
+                self.lowpt[e] = hw;
+            } else {
+                // tree edge
+                self.parent_edge[w] = Some(e);
+                self.height[w] = Some(hv + 1);
+                self.orient(w);
+            }
+
+            self.nesting_depth[e] = 2 * self.lowpt[e] as i64;
+            if self.lowpt2[e] < hv {
+                // chordal edge
+                self.nesting_depth[e] += 1;
+            }
+
+            if let Some(p) = parent {
+                match self.lowpt[e].cmp(&self.lowpt[p]) {
+                    Ordering::Less => {
+                        self.lowpt2[p] = self.lowpt[p].min(self.lowpt2[e]);
+                        self.lowpt[p] = self.lowpt[e];
+                    }
+                    Ordering::Greater => {
+                        self.lowpt2[p] = self.lowpt2[p].min(self.lowpt[e]);
+                    }
+                    Ordering::Equal => {
+                        self.lowpt2[p] = self.lowpt2[p].min(self.lowpt2[e]);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Test the left-right constraints of the subtree rooted at `v`.
+    fn test(&mut self, v: usize) -> bool {
+        let parent = self.parent_edge[v];
+        let hv = self.height[v].unwrap();
+        for i in 0..self.ordered_adjacency[v].len() {
+            let e = self.ordered_adjacency[v][i];
+            let w = self.target[e];
+            self.stack_bottom[e] = self.stack.len();
+
+            if self.parent_edge[w] == Some(e) {
+                if !self.test(w) {
+                    return false;
+                }
+            } else {
+                self.lowpt_edge[e] = e;
+                self.stack.push(ConflictPair {
+                    left: Interval::default(),
+                    right: Interval {
+                        low: Some(e),
+                        high: Some(e),
+                    },
+                });
+            }
+
+            // integrate the new return edges
+            if self.lowpt[e] < hv {
+                let p = parent.unwrap();
+                if i == 0 {
+                    self.lowpt_edge[p] = self.lowpt_edge[e];
+                } else if !self.add_constraints(e, p) {
+                    return false;
+                }
+            }
+        }
+
+        if let Some(p) = parent {
+            self.remove_back_edges(p);
+        }
+
+        true
+    }
+
+    fn add_constraints(&mut self, ei: usize, e: usize) -> bool {
+        let mut p = ConflictPair::default();
+
+        // merge the return edges of `ei` into the right interval
+        loop {
+            let mut q = self.stack.pop().unwrap();
+            if !q.left.is_empty() {
+                q.swap();
+            }
+            if !q.left.is_empty() {
+                return false;
+            }
+
+            let q_low = q.right.low.unwrap();
+            if self.lowpt[q_low] > self.lowpt[e] {
+                if p.right.is_empty() {
+                    p.right = q.right;
+                } else {
+                    self.refs[p.right.low.unwrap()] = q.right.high;
+                }
+                p.right.low = q.right.low;
+            } else {
+                self.refs[q_low] = Some(self.lowpt_edge[e]);
+            }
+
+            if self.stack.len() == self.stack_bottom[ei] {
+                break;
+            }
+        }
+
+        // merge the conflicting return edges of the previous siblings into the left interval
+        while let Some(top) = self.stack.last() {
+            if !top.left.conflicting(ei, &self.lowpt) && !top.right.conflicting(ei, &self.lowpt) {
+                break;
+            }
+
+            let mut q = self.stack.pop().unwrap();
+            if q.right.conflicting(ei, &self.lowpt) {
+                q.swap();
+            }
+            if q.right.conflicting(ei, &self.lowpt) {
+                return false;
+            }
+
+            if let Some(l) = p.right.low {
+                self.refs[l] = q.right.high;
+            }
+            if q.right.low.is_some() {
+                p.right.low = q.right.low;
+            }
+
+            if p.left.is_empty() {
+                p.left = q.left;
+            } else if let Some(l) = p.left.low {
+                self.refs[l] = q.left.high;
+            }
+            p.left.low = q.left.low;
+        }
+
+        if !p.left.is_empty() || !p.right.is_empty() {
+            self.stack.push(p);
+        }
+
+        true
+    }
+
+    fn remove_back_edges(&mut self, e: usize) {
+        let u = self.source[e];
+        let hu = self.height[u].unwrap();
+
+        // drop the conflict pairs that only contain back edges to `u`
+        while let Some(top) = self.stack.last() {
+            if top.lowest(&self.lowpt) != hu {
+                break;
+            }
+
+            let p = self.stack.pop().unwrap();
+            if let Some(l) = p.left.low {
+                self.side[l] = -1;
+            }
+        }
+
+        if let Some(mut p) = self.stack.pop() {
+            // trim the left interval
+            while let Some(h) = p.left.high {
+                if self.target[h] != u {
+                    break;
+                }
+                p.left.high = self.refs[h];
+            }
+            if p.left.high.is_none() {
+                if let Some(l) = p.left.low {
+                    self.refs[l] = p.right.low;
+                    self.side[l] = -1;
+                    p.left.low = None;
+                }
+            }
+
+            // trim the right interval
+            while let Some(h) = p.right.high {
+                if self.target[h] != u {
+                    break;
+                }
+                p.right.high = self.refs[h];
+            }
+            if p.right.high.is_none() {
+                if let Some(l) = p.right.low {
+                    self.refs[l] = p.left.low;
+                    self.side[l] = -1;
+                    p.right.low = None;
+                }
+            }
+
+            self.stack.push(p);
+        }
+
+        // the side of `e` is the side of a highest return edge
+        if self.lowpt[e] < hu {
+            let top = self.stack.last().unwrap();
+            let (hl, hr) = (top.left.high, top.right.high);
+            self.refs[e] = match (hl, hr) {
+                (Some(l), Some(r)) if self.lowpt[l] > self.lowpt[r] => hl,
+                (Some(_), None) => hl,
+                _ => hr,
+            };
+        }
+    }
+
+    fn sign(&mut self, e: usize) -> i64 {
+        if let Some(r) = self.refs[e] {
+            self.side[e] *= self.sign(r);
+            self.refs[e] = None;
+        }
+        self.side[e]
+    }
+
+    /// Insert half-edge `h` at node `v` directly after `reference` in clockwise order.
+    fn add_half_edge_cw(&mut self, v: usize, h: usize, reference: Option<usize>) {
+        if let Some(r) = reference {
+            let next = self.cw[r];
+            self.cw[r] = h;
+            self.ccw[h] = r;
+            self.cw[h] = next;
+            self.ccw[next] = h;
+        } else {
+            self.cw[h] = h;
+            self.ccw[h] = h;
+            self.first[v] = Some(h);
+        }
+    }
+
+    /// Insert half-edge `h` at node `v` directly before `reference` in clockwise order.
+    fn add_half_edge_ccw(&mut self, v: usize, h: usize, reference: Option<usize>) {
+        if let Some(r) = reference {
+            self.add_half_edge_cw(v, h, Some(self.ccw[r]));
+            if self.first[v] == Some(r) {
+                self.first[v] = Some(h);
+            }
+        } else {
+            self.add_half_edge_cw(v, h, None);
+        }
+    }
+
+    fn embed(&mut self, v: usize) {
+        for i in 0..self.ordered_adjacency[v].len() {
+            let e = self.ordered_adjacency[v][i];
+            let w = self.target[e];
+            let h = self.half_edge(e, w);
+            if self.parent_edge[w] == Some(e) {
+                self.add_half_edge_ccw(w, h, self.first[w]);
+                self.left_ref[v] = self.half_edge(e, v);
+                self.right_ref[v] = self.half_edge(e, v);
+                self.embed(w);
+            } else if self.side[e] == 1 {
+                self.add_half_edge_cw(w, h, Some(self.right_ref[w]));
+            } else {
+                self.add_half_edge_ccw(w, h, Some(self.left_ref[w]));
+                self.left_ref[w] = h;
+            }
+        }
+    }
+}
+
+impl<N, E> Graph<N, E> {
+    /// Returns `true` if the graph is planar.
+    pub fn is_planar(&self) -> bool {
+        self.get_planar_embedding().is_some()
+    }
+
+    /// Test if the graph is planar in linear time using the left-right planarity test
+    /// and return a planar embedding if it is. Edge directions are ignored.
+    pub fn get_planar_embedding(&self) -> Option<Embedding> {
+        // reduce to a simple graph
+        let mut simple_edges = vec![];
+        let mut representatives: Vec<(usize, Vec<usize>)> = vec![];
+        let mut lookup = HashMap::default();
+        let mut self_loops = vec![vec![]; self.nodes.len()];
+        for (i, e) in self.edges.iter().enumerate() {
+            let (a, b) = e.vertices;
+            if a == b {
+                self_loops[a].push(i);
+                continue;
+            }
+
+            let key = (a.min(b), a.max(b));
+            if let Some(&j) = lookup.get(&key) {
+                let r: &mut (usize, Vec<usize>) = &mut representatives[j];
+                r.1.push(i);
+            } else {
+                lookup.insert(key, simple_edges.len());
+                simple_edges.push((a, b));
+                representatives.push((i, vec![]));
+            }
+        }
+
+        let simple_rotations =
+            LeftRightPlanarity::new(self.nodes.len(), simple_edges.clone()).run()?;
+
+        let half_edge = |e: usize, v: usize| 2 * e + (self.edges[e].vertices.0 != v) as usize;
+
+        let mut rotations = vec![vec![]; self.nodes.len()];
+        for (v, r) in simple_rotations.into_iter().enumerate() {
+            for h in r {
+                let (rep, parallel) = &representatives[h / 2];
+                if simple_edges[h / 2].0 == v {
+                    rotations[v].push(half_edge(*rep, v));
+                    rotations[v].extend(parallel.iter().map(|e| half_edge(*e, v)));
+                } else {
+                    // parallel edges are nested, so they appear in reverse order
+                    rotations[v].extend(parallel.iter().rev().map(|e| half_edge(*e, v)));
+                    rotations[v].push(half_edge(*rep, v));
+                }
+            }
+        }
+
+        for (v, loops) in self_loops.iter().enumerate() {
+            for e in loops {
+                rotations[v].extend([2 * e, 2 * e + 1]);
+            }
+        }
+
+        Some(Embedding::new(self, rotations).unwrap())
+    }
+}
+
+impl<N, E: Clone> Graph<N, E> {
+    /// Construct the dual graph of the graph with the embedding `embedding`. The nodes of the
+    /// dual graph are the faces of the embedding in the order of [Embedding::faces] and edge `i`
+    /// of the dual graph crosses edge `i` of the graph. The dual edges are undirected.
+    pub fn get_dual_graph(&self, embedding: &Embedding) -> Graph<Empty, E> {
+        let (n_faces, face) = embedding.face_map();
+
+        let mut g = Graph::new();
+        for _ in 0..n_faces {
+            g.add_node(Empty);
+        }
+        for (i, e) in self.edges.iter().enumerate() {
+            g.add_edge(face[2 * i], face[2 * i + 1], false, e.data.clone())
+                .unwrap();
+        }
+        g
+    }
+}
+
+impl<N: Clone + Ord, E: Clone + Ord> Graph<N, E> {
+    /// Compute a canonical form of the connected graph with the embedding `embedding`.
+    /// Two embedded graphs have the same canonical form if and only if there is an
+    /// isomorphism that preserves the node and edge data, the edge directions and the cyclic
+    /// order of the edges around every node. If `allow_reflection` is set, an
+    /// embedding and its mirror image have the same canonical form.
+    pub fn canonize_embedding(
+        &self,
+        embedding: &Embedding,
+        allow_reflection: bool,
+    ) -> Result<(Graph<N, E>, Embedding), &'static str> {
+        let n_half_edges = 2 * self.edges.len();
+        if n_half_edges == 0 {
+            if self.nodes.len() > 1 {
+                return Err("The graph must be connected");
+            }
+            return Ok((self.clone(), embedding.clone()));
+        }
+
+        let mut best: Option<EmbeddingCode<N, E>> = None;
+        for start in 0..n_half_edges {
+            for reflect in [false, true] {
+                if reflect && !allow_reflection {
+                    continue;
+                }
+
+                let labels = self.label_half_edges(embedding, start, reflect);
+                if labels.contains(&usize::MAX) {
+                    return Err("The graph must be connected");
+                }
+
+                let code = self.embedding_code(embedding, &labels);
+                if best.as_ref().is_none_or(|b| code < *b) {
+                    best = Some(code);
+                }
+            }
+        }
+
+        let (nodes, code) = best.unwrap();
+
+        // the edges are ordered by their lowest half-edge label
+        let mut g = Graph::new();
+        for n in nodes {
+            g.add_node(n.clone());
+        }
+
+        let mut new_half_edge = vec![0; n_half_edges];
+        for (l, (node, twin, dir, data)) in code.iter().enumerate() {
+            if *twin < l {
+                continue;
+            }
+
+            let other = code[*twin].0;
+            let e = match dir {
+                Some(false) => g.add_edge(other, *node, true, (*data).clone()),
+                Some(true) => g.add_edge(*node, other, true, (*data).clone()),
+                None => g.add_edge(*node, other, false, (*data).clone()),
+            }
+            .unwrap();
+
+            let v0 = g.edges[e].vertices.0;
+            let first_is_zero = if *node == other {
+                *dir != Some(false)
+            } else {
+                *node == v0
+            };
+            new_half_edge[l] = 2 * e + !first_is_zero as usize;
+            new_half_edge[*twin] = 2 * e + first_is_zero as usize;
+        }
+
+        let mut rotations = vec![vec![]; g.nodes.len()];
+        for l in 0..n_half_edges {
+            rotations[code[l].0].push(new_half_edge[l]);
+        }
+
+        let embedding = Embedding::new(&g, rotations)?;
+        Ok((g, embedding))
+    }
+
+    /// Label the half-edges by a breadth-first traversal that starts at `start` and
+    /// visits the half-edges around every node in clockwise order, or in counterclockwise
+    /// order if `reflect` is set.
+    fn label_half_edges(&self, embedding: &Embedding, start: usize, reflect: bool) -> Vec<usize> {
+        let n_half_edges = 2 * self.edges.len();
+        let mut labels = vec![usize::MAX; n_half_edges];
+        let mut node_visited = vec![false; self.nodes.len()];
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        node_visited[embedding.node(start)] = true;
+
+        let mut counter = 0;
+        while let Some(entry) = queue.pop_front() {
+            let (n, i) = embedding.positions[entry];
+            let r = &embedding.rotations[n];
+            for k in 0..r.len() {
+                let h = if reflect {
+                    r[(i + r.len() - k) % r.len()]
+                } else {
+                    r[(i + k) % r.len()]
+                };
+                labels[h] = counter;
+                counter += 1;
+
+                let twin = h ^ 1;
+                let w = embedding.node(twin);
+                if !node_visited[w] {
+                    node_visited[w] = true;
+                    queue.push_back(twin);
+                }
+            }
+        }
+
+        labels
+    }
+
+    /// Create the code of the embedded graph for the half-edge labeling `labels`,
+    /// consisting of the node data in the order of discovery and for every half-edge label
+    /// the node label, the label of the opposite half-edge, the direction and the edge data.
+    fn embedding_code(&self, embedding: &Embedding, labels: &[usize]) -> EmbeddingCode<'_, N, E> {
+        let mut node_labels = vec![usize::MAX; self.nodes.len()];
+        let mut nodes = vec![];
+        let mut code = vec![(0, 0, None, &self.edges[0].data); labels.len()];
+
+        let mut order: Vec<_> = (0..labels.len()).collect();
+        order.sort_by_key(|h| labels[*h]);
+        for h in order {
+            let n = embedding.node(h);
+            if node_labels[n] == usize::MAX {
+                node_labels[n] = nodes.len();
+                nodes.push(&self.nodes[n].data);
+            }
+
+            let e = &self.edges[h / 2];
+            let dir = if e.directed { Some(h % 2 == 0) } else { None };
+            code[labels[h]] = (node_labels[n], labels[h ^ 1], dir, &e.data);
+        }
+
+        (nodes, code)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::graph::Graph;
+
+    fn complete_bipartite(n: usize, m: usize) -> Graph<(), ()> {
+        let mut g = Graph::new();
+        for _ in 0..n + m {
+            g.add_node(());
+        }
+        for i in 0..n {
+            for j in 0..m {
+                g.add_edge(i, n + j, false, ()).unwrap();
+            }
+        }
+        g
+    }
+
+    #[test]
+    fn planarity() {
+        assert!(!complete_bipartite(3, 3).is_planar());
+        assert!(complete_bipartite(2, 5).is_planar());
+
+        // the Petersen graph
+        let mut g = Graph::new();
+        for _ in 0..10 {
+            g.add_node(());
+        }
+        for i in 0..5 {
+            g.add_edge(i, (i + 1) % 5, false, ()).unwrap();
+            g.add_edge(i, i + 5, false, ()).unwrap();
+            g.add_edge(i + 5, (i + 2) % 5 + 5, false, ()).unwrap();
+        }
+        assert!(!g.is_planar());
+
+        // the cube graph with a multi-edge and self-loops
+        let mut g = Graph::new();
+        for _ in 0..8 {
+            g.add_node(());
+        }
+        for i in 0..4 {
+            g.add_edge(i, (i + 1) % 4, false, ()).unwrap();
+            g.add_edge(i + 4, (i + 1) % 4 + 4, true, ()).unwrap();
+            g.add_edge(i, i + 4, false, ()).unwrap();
+        }
+        g.add_edge(1, 0, true, ()).unwrap();
+        g.add_edge(3, 3, false, ()).unwrap();
+        g.add_edge(3, 3, false, ()).unwrap();
+
+        let embedding = g.get_planar_embedding().unwrap();
+        let faces = embedding.faces();
+        assert_eq!(g.nodes().len() + faces.len(), g.edges().len() + 2);
+    }
+
+    #[test]
+    fn dual() {
+        // the dual of a theta graph is a triangle with doubled edges
+        let mut g = Graph::new();
+        let n: Vec<_> = (0..2).map(|_| g.add_node(())).collect();
+        for i in 0..3 {
+            g.add_edge(n[0], n[1], false, i).unwrap();
+        }
+
+        let embedding = g.get_planar_embedding().unwrap();
+        let dual = g.get_dual_graph(&embedding);
+        assert_eq!(dual.nodes().len(), 3);
+        assert!(dual.edges().iter().all(|e| !e.is_self_loop()));
+    }
+
+    #[test]
+    fn canonize_embedding() {
+        // a wheel with four spokes with two different labelings
+        let mut g1 = Graph::new();
+        let mut g2 = Graph::new();
+        for i in 0..5 {
+            g1.add_node(i == 0);
+            g2.add_node(i == 4);
+        }
+        for i in 0..4 {
+            g1.add_edge(0, i + 1, false, 0).unwrap();
+            g1.add_edge(i + 1, (i + 1) % 4 + 1, true, 1).unwrap();
+            g2.add_edge(4, 3 - i, false, 0).unwrap();
+            g2.add_edge((3 - i + 3) % 4, 3 - i, true, 1).unwrap();
+        }
+
+        let e1 = g1.get_planar_embedding().unwrap();
+        let e2 = g2.get_planar_embedding().unwrap();
+        let c1 = g1.canonize_embedding(&e1, true).unwrap();
+        let c2 = g2.canonize_embedding(&e2, true).unwrap();
+        assert_eq!(c1, c2);
+        assert_eq!(c1.1.faces().len(), 5);
+    }
+}
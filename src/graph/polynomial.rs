@@ -0,0 +1,400 @@
+//! Graph polynomials: the Kirchhoff polynomial, the Symanzik polynomials
+//! and the Tutte polynomial.
+
+use std::sync::Arc;
+
+use ahash::HashMap;
+
+use crate::{
+    atom::{Atom, AtomCore},
+    domains::{
+        atom::AtomField,
+        integer::{IntegerRing, Z},
+        rational_polynomial::{RationalPolynomial, RationalPolynomialField},
+        Ring,
+    },
+    poly::{polynomial::MultivariatePolynomial, Variable},
+    tensors::matrix::Matrix,
+};
+
+use super::{subgraph::find, Empty, Graph};
+
+impl<N, E> Graph<N, E> {
+    /// Create a polynomial in the edge variables that is the sum over `forests`
+    /// of the product of the variables of the edges that are (not) in the forest.
+    fn forest_polynomial(
+        &self,
+        forests: &[Vec<usize>],
+        edge_variables: &[Variable],
+        complement: bool,
+    ) -> Result<MultivariatePolynomial<IntegerRing>, &'static str> {
+        if edge_variables.len() != self.edges.len() {
+            return Err("The number of edge variables must equal the number of edges");
+        }
+
+        let mut poly =
+            MultivariatePolynomial::new(&Z, Some(forests.len()), Arc::new(edge_variables.to_vec()));
+        let mut exp = vec![0; self.edges.len()];
+        for f in forests {
+            exp.fill(if complement { 1 } else { 0 });
+            for e in f {
+                exp[*e] = if complement { 0 } else { 1 };
+            }
+            poly.append_monomial(Z.one(), &exp);
+        }
+
+        Ok(poly)
+    }
+
+    /// Compute the Kirchhoff polynomial of the graph, which is the sum over all
+    /// spanning trees of the product of the edge variables of the tree.
+    /// By the matrix-tree theorem, it is computed as the determinant of the reduced Laplacian
+    /// of the graph with edge weights `edge_variables`, which avoids enumerating the trees.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolica::{graph::Graph, parse, poly::Variable, symbol};
+    ///
+    /// let mut g = Graph::new();
+    /// let n0 = g.add_node(());
+    /// let n1 = g.add_node(());
+    /// g.add_edge(n0, n1, false, ()).unwrap();
+    /// g.add_edge(n0, n1, false, ()).unwrap();
+    ///
+    /// let vars: Vec<Variable> = vec![symbol!("x1").into(), symbol!("x2").into()];
+    /// let k = g.kirchhoff_polynomial(&vars).unwrap();
+    /// assert_eq!(k.to_expression(), parse!("x1 + x2").unwrap());
+    /// ```
+    pub fn kirchhoff_polynomial(
+        &self,
+        edge_variables: &[Variable],
+    ) -> Result<MultivariatePolynomial<IntegerRing>, &'static str> {
+        if edge_variables.len() != self.edges.len() {
+            return Err("The number of edge variables must equal the number of edges");
+        }
+
+        let one = MultivariatePolynomial::new(&Z, None, Arc::new(edge_variables.to_vec())).one();
+        if self.nodes.len() < 2 {
+            return Ok(one);
+        }
+
+        // the Laplacian with the row and column of the first node removed
+        let n = self.nodes.len() - 1;
+        let mut laplacian = vec![one.zero(); n * n];
+        for (e, v) in self.edges.iter().zip(edge_variables) {
+            if e.is_self_loop() {
+                continue;
+            }
+
+            let x = one.variable(v).unwrap();
+            let (a, b) = e.vertices;
+            if a > 0 {
+                laplacian[(a - 1) * n + a - 1] = &laplacian[(a - 1) * n + a - 1] + &x;
+            }
+            if b > 0 {
+                laplacian[(b - 1) * n + b - 1] = &laplacian[(b - 1) * n + b - 1] + &x;
+            }
+            if a > 0 && b > 0 {
+                laplacian[(a - 1) * n + b - 1] = &laplacian[(a - 1) * n + b - 1] - &x;
+                laplacian[(b - 1) * n + a - 1] = &laplacian[(b - 1) * n + a - 1] - &x;
+            }
+        }
+
+        let field = RationalPolynomialField::new(Z);
+        let laplacian = laplacian
+            .into_iter()
+            .map(RationalPolynomial::from)
+            .collect();
+        let det = Matrix::from_linear(laplacian, n as u32, n as u32, field)
+            .unwrap()
+            .det()
+            .unwrap();
+        Ok(det.numerator)
+    }
+
+    /// Compute the first Symanzik polynomial `U` of the graph, which is the sum over all
+    /// spanning trees of the product of the edge variables of the edges not in the tree.
+    pub fn first_symanzik_polynomial(
+        &self,
+        edge_variables: &[Variable],
+    ) -> Result<MultivariatePolynomial<IntegerRing>, &'static str> {
+        self.forest_polynomial(&self.get_spanning_forests(1), edge_variables, true)
+    }
+
+    /// Compute the second Symanzik polynomial
+    /// ```text
+    /// F = -sum_{T1,T2} P_{T1}^2 prod_{e not in T1,T2} x_e + U sum_e x_e m_e^2
+    /// ```
+    /// where the sum is over all spanning 2-forests and `P_{T1}` is the
+    /// total momentum that flows into the tree `T1`. The edge masses are given
+    /// by `masses` and the external momentum that flows into every node is given
+    /// by `momenta`. The momenta must be conserved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolica::{atom::Atom, graph::Graph, parse, poly::Variable, symbol};
+    ///
+    /// let mut g = Graph::new();
+    /// let n0 = g.add_node(());
+    /// let n1 = g.add_node(());
+    /// g.add_edge(n0, n1, false, ()).unwrap();
+    /// g.add_edge(n0, n1, false, ()).unwrap();
+    ///
+    /// let vars: Vec<Variable> = vec![symbol!("x1").into(), symbol!("x2").into()];
+    /// let masses = [parse!("m").unwrap(), Atom::new()];
+    /// let momenta = [parse!("p").unwrap(), parse!("-p").unwrap()];
+    /// let f = g.second_symanzik_polynomial(&vars, &masses, &momenta).unwrap();
+    /// assert_eq!(
+    ///     f.to_expression_with_coeff_map(|_, c, out| *out = c.clone()),
+    ///     parse!("(m^2-p^2)*x1*x2 + m^2*x1^2").unwrap()
+    /// );
+    /// ```
+    pub fn second_symanzik_polynomial(
+        &self,
+        edge_variables: &[Variable],
+        masses: &[Atom],
+        momenta: &[Atom],
+    ) -> Result<MultivariatePolynomial<AtomField>, &'static str> {
+        if masses.len() != self.edges.len() {
+            return Err("The number of masses must equal the number of edges");
+        }
+        if momenta.len() != self.nodes.len() {
+            return Err("The number of momenta must equal the number of nodes");
+        }
+
+        let field = AtomField::new();
+        let u = self.first_symanzik_polynomial(edge_variables)?;
+        let u = u.map_coeff(|c| Atom::new_num(c.clone()), field.clone());
+
+        let mut f = u.zero();
+        let mut parent = vec![0; self.nodes.len()];
+        let mut exp = vec![0; self.edges.len()];
+        for forest in self.get_spanning_forests(2) {
+            for (i, p) in parent.iter_mut().enumerate() {
+                *p = i;
+            }
+            for e in &forest {
+                let (a, b) = self.edges[*e].vertices;
+                let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+                parent[ra] = rb;
+            }
+
+            let root = find(&mut parent, 0);
+            let mut p = Atom::new();
+            for (i, m) in momenta.iter().enumerate() {
+                if find(&mut parent, i) == root {
+                    p += m;
+                }
+            }
+
+            let s = -p.npow(2).expand();
+            if s.is_zero() {
+                continue;
+            }
+
+            exp.fill(1);
+            for e in &forest {
+                exp[*e] = 0;
+            }
+            f.append_monomial(s, &exp);
+        }
+
+        let mut mass_term = u.zero();
+        for (i, m) in masses.iter().enumerate() {
+            if m.is_zero() {
+                continue;
+            }
+
+            exp.fill(0);
+            exp[i] = 1;
+            mass_term.append_monomial(m.npow(2), &exp);
+        }
+
+        Ok(f + u * &mass_term)
+    }
+
+    /// Compute the Tutte polynomial `T(x, y)` of the graph using deletion-contraction,
+    /// where the directions and the data of the edges and nodes are ignored.
+    /// Intermediate results are cached using the canonical forms of the graphs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolica::{graph::Graph, parse, symbol};
+    ///
+    /// let mut g = Graph::new();
+    /// let n: Vec<_> = (0..3).map(|_| g.add_node(())).collect();
+    /// g.add_edge(n[0], n[1], false, ()).unwrap();
+    /// g.add_edge(n[1], n[2], false, ()).unwrap();
+    /// g.add_edge(n[2], n[0], false, ()).unwrap();
+    ///
+    /// let t = g.tutte_polynomial(symbol!("x").into(), symbol!("y").into());
+    /// assert_eq!(t.to_expression(), parse!("x^2 + x + y").unwrap());
+    /// ```
+    pub fn tutte_polynomial(
+        &self,
+        x: Variable,
+        y: Variable,
+    ) -> MultivariatePolynomial<IntegerRing> {
+        let mut g = Graph::new();
+        for _ in &self.nodes {
+            g.add_node(Empty);
+        }
+        for e in &self.edges {
+            g.add_edge(e.vertices.0, e.vertices.1, false, Empty)
+                .unwrap();
+        }
+
+        let one = MultivariatePolynomial::new(&Z, None, Arc::new(vec![x.clone(), y.clone()])).one();
+        let x = one.variable(&x).unwrap();
+        let y = one.variable(&y).unwrap();
+
+        let mut cache = HashMap::default();
+        g.tutte_impl(&x, &y, &mut cache)
+    }
+}
+
+impl Graph<Empty, Empty> {
+    fn tutte_impl(
+        &self,
+        x: &MultivariatePolynomial<IntegerRing>,
+        y: &MultivariatePolynomial<IntegerRing>,
+        cache: &mut HashMap<Graph<Empty, Empty>, MultivariatePolynomial<IntegerRing>>,
+    ) -> MultivariatePolynomial<IntegerRing> {
+        // self-loops factor out
+        let loops = self.edges.iter().filter(|e| e.is_self_loop()).count();
+        if loops > 0 {
+            let mut g = Graph::new();
+            for _ in &self.nodes {
+                g.add_node(Empty);
+            }
+            for e in self.edges.iter().filter(|e| !e.is_self_loop()) {
+                g.add_edge(e.vertices.0, e.vertices.1, false, Empty)
+                    .unwrap();
+            }
+            return g.tutte_impl(x, y, cache) * &y.pow(loops);
+        }
+
+        if self.edges.is_empty() {
+            return x.one();
+        }
+
+        let key = self.canonize().graph;
+        if let Some(r) = cache.get(&key) {
+            return r.clone();
+        }
+
+        let e = self.edges.len() - 1;
+        let deleted = self.delete_edge(e);
+        let contracted = self.contract_edge(e);
+
+        let (a, b) = self.edges[e].vertices;
+        let mut parent: Vec<_> = (0..self.nodes.len()).collect();
+        for edge in &deleted.edges {
+            let (ra, rb) = (
+                find(&mut parent, edge.vertices.0),
+                find(&mut parent, edge.vertices.1),
+            );
+            parent[ra] = rb;
+        }
+
+        let r = if find(&mut parent, a) != find(&mut parent, b) {
+            // the edge is a bridge
+            contracted.tutte_impl(x, y, cache) * x
+        } else {
+            deleted.tutte_impl(x, y, cache) + contracted.tutte_impl(x, y, cache)
+        };
+
+        cache.insert(key, r.clone());
+        r
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{atom::Atom, graph::Graph, parse, poly::Variable, symbol};
+
+    fn sunrise() -> Graph<(), ()> {
+        let mut g = Graph::new();
+        let n0 = g.add_node(());
+        let n1 = g.add_node(());
+        for _ in 0..3 {
+            g.add_edge(n0, n1, false, ()).unwrap();
+        }
+        g
+    }
+
+    #[test]
+    fn symanzik() {
+        let g = sunrise();
+        let vars: Vec<Variable> = (1..=3).map(|i| symbol!(format!("x{}", i)).into()).collect();
+
+        let u = g.first_symanzik_polynomial(&vars).unwrap();
+        assert_eq!(u.to_expression(), parse!("x1*x2 + x1*x3 + x2*x3").unwrap());
+
+        let masses = vec![Atom::new(); 3];
+        let momenta = [parse!("p").unwrap(), parse!("-p").unwrap()];
+        let f = g
+            .second_symanzik_polynomial(&vars, &masses, &momenta)
+            .unwrap();
+        assert_eq!(
+            f.to_expression_with_coeff_map(|_, c, out| *out = c.clone()),
+            parse!("-p^2*x1*x2*x3").unwrap()
+        );
+
+        let k = g.kirchhoff_polynomial(&vars).unwrap();
+        assert_eq!(k.to_expression(), parse!("x1 + x2 + x3").unwrap());
+    }
+
+    #[test]
+    fn kirchhoff() {
+        // the complete graph K4 with a self-loop and a disconnected node
+        let mut g = Graph::new();
+        let n: Vec<_> = (0..5).map(|_| g.add_node(())).collect();
+        for i in 0..4 {
+            for j in i + 1..4 {
+                g.add_edge(n[i], n[j], false, ()).unwrap();
+            }
+        }
+        g.add_edge(n[1], n[1], false, ()).unwrap();
+
+        let vars: Vec<Variable> = (1..=7).map(|i| symbol!(format!("x{}", i)).into()).collect();
+        assert!(g.kirchhoff_polynomial(&vars).unwrap().is_zero());
+
+        g.add_edge(n[4], n[0], false, ()).unwrap();
+        let vars: Vec<Variable> = (1..=8).map(|i| symbol!(format!("x{}", i)).into()).collect();
+        let k = g.kirchhoff_polynomial(&vars).unwrap();
+        let trees = g
+            .forest_polynomial(&g.get_spanning_forests(1), &vars, false)
+            .unwrap();
+        assert_eq!(k.nterms(), 16);
+        assert_eq!(k, trees);
+    }
+
+    #[test]
+    fn tutte() {
+        // the complete graph K4
+        let mut g = Graph::new();
+        let n: Vec<_> = (0..4).map(|_| g.add_node(())).collect();
+        for i in 0..4 {
+            for j in i + 1..4 {
+                g.add_edge(n[i], n[j], false, ()).unwrap();
+            }
+        }
+
+        let t = g.tutte_polynomial(symbol!("x").into(), symbol!("y").into());
+        assert_eq!(
+            t.to_expression(),
+            parse!("x^3 + 3x^2 + 2x + 4x*y + 2y + 3y^2 + y^3").unwrap()
+        );
+
+        // T(1, 1) counts the spanning trees
+        assert_eq!(g.get_spanning_forests(1).len(), 16);
+
+        let g = sunrise();
+        let t = g.tutte_polynomial(symbol!("x").into(), symbol!("y").into());
+        assert_eq!(t.to_expression(), parse!("x + y + y^2").unwrap());
+    }
+}
@@ -0,0 +1,449 @@
+//! Graph minors, subgraphs and forests of subgraphs.
+//!
+//! Subgraphs are represented by a sorted list of edge indices; the nodes of a
+//! subgraph are the nodes that are incident to its edges.
+//!
+//! # Examples
+//!
+//! Find the divergent one-particle-irreducible subgraphs of the two-loop
+//! double-bubble graph in four-dimensional scalar theory and their forests:
+//! ```
+//! use symbolica::graph::Graph;
+//!
+//! let mut g = Graph::new();
+//! let n: Vec<_> = (0..3).map(|_| g.add_node(())).collect();
+//! g.add_edge(n[0], n[1], false, ()).unwrap();
+//! g.add_edge(n[0], n[1], false, ()).unwrap();
+//! g.add_edge(n[1], n[2], false, ()).unwrap();
+//! g.add_edge(n[1], n[2], false, ()).unwrap();
+//!
+//! let divergent = g.get_subgraphs(true, |s| {
+//!     g.superficial_degree_of_divergence(s, 4, |_| 2, |_| 0) >= 0
+//! });
+//! assert_eq!(divergent, [vec![0, 1], vec![0, 1, 2, 3], vec![2, 3]]);
+//!
+//! let forests = g.get_forests(&divergent, true);
+//! assert_eq!(forests.len(), 8);
+//! ```
+
+use std::hash::Hash;
+
+use ahash::HashMap;
+
+use crate::combinatorics::CombinationIterator;
+
+use super::{Edge, Graph, Node};
+
+/// Find the representative of `i` in the union-find structure `parent`.
+pub(super) fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+impl<N, E> Graph<N, E> {
+    /// Get all spanning forests with `k` trees, as lists of edge indices.
+    /// For `k = 1`, these are the spanning trees of the graph.
+    pub fn get_spanning_forests(&self, k: usize) -> Vec<Vec<usize>> {
+        if k == 0 || k > self.nodes.len() {
+            return vec![];
+        }
+
+        let n_edges = self.nodes.len() - k;
+        if n_edges == 0 {
+            return vec![vec![]];
+        }
+
+        let mut forests = vec![];
+        let mut parent = vec![0; self.nodes.len()];
+        let mut it = CombinationIterator::new(self.edges.len(), n_edges);
+        'next: while let Some(c) = it.next() {
+            for (i, p) in parent.iter_mut().enumerate() {
+                *p = i;
+            }
+
+            // the forest has `k` trees if and only if it has no cycles
+            for e in c {
+                let (a, b) = self.edges[*e].vertices;
+                let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+                if ra == rb {
+                    continue 'next;
+                }
+                parent[ra] = rb;
+            }
+
+            forests.push(c.to_vec());
+        }
+
+        forests
+    }
+
+    /// Get the sorted list of nodes that are incident to the edges of the subgraph.
+    pub fn get_subgraph_nodes(&self, edges: &[usize]) -> Vec<usize> {
+        let mut nodes: Vec<_> = edges
+            .iter()
+            .flat_map(|e| [self.edges[*e].vertices.0, self.edges[*e].vertices.1])
+            .collect();
+        nodes.sort_unstable();
+        nodes.dedup();
+        nodes
+    }
+
+    /// Get the number of connected components of the subgraph.
+    fn subgraph_components(&self, edges: &[usize], nodes: &[usize]) -> usize {
+        let mut parent: Vec<_> = (0..self.nodes.len()).collect();
+        let mut components = nodes.len();
+        for e in edges {
+            let (a, b) = self.edges[*e].vertices;
+            let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+            if ra != rb {
+                parent[ra] = rb;
+                components -= 1;
+            }
+        }
+        components
+    }
+
+    /// Get the number of loops of the subgraph, which is the number of edges minus
+    /// the number of nodes plus the number of connected components.
+    pub fn get_subgraph_loop_count(&self, edges: &[usize]) -> usize {
+        let nodes = self.get_subgraph_nodes(edges);
+        edges.len() + self.subgraph_components(edges, &nodes) - nodes.len()
+    }
+
+    /// Returns `true` if the subgraph is connected and remains connected
+    /// after removing any one of its edges.
+    pub fn is_one_particle_irreducible(&self, edges: &[usize]) -> bool {
+        let nodes = self.get_subgraph_nodes(edges);
+        if self.subgraph_components(edges, &nodes) != 1 {
+            return false;
+        }
+
+        let mut rest = Vec::with_capacity(edges.len());
+        for e in edges {
+            if self.edges[*e].is_self_loop() {
+                continue;
+            }
+
+            rest.clear();
+            rest.extend(edges.iter().filter(|x| *x != e));
+            if self.subgraph_components(&rest, &nodes) != 1 {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Compute the superficial degree of divergence of the subgraph in `dimension` dimensions,
+    /// `D L - sum_e w_e + sum_v w_v`, where `L` is the number of loops of the subgraph
+    /// and `w_e` and `w_v` are the momentum powers of the propagators and
+    /// the vertices of the subgraph respectively.
+    pub fn superficial_degree_of_divergence(
+        &self,
+        edges: &[usize],
+        dimension: i64,
+        edge_weight: impl Fn(&Edge<E>) -> i64,
+        node_weight: impl Fn(&Node<N>) -> i64,
+    ) -> i64 {
+        dimension * self.get_subgraph_loop_count(edges) as i64
+            - edges
+                .iter()
+                .map(|e| edge_weight(&self.edges[*e]))
+                .sum::<i64>()
+            + self
+                .get_subgraph_nodes(edges)
+                .iter()
+                .map(|n| node_weight(&self.nodes[*n]))
+                .sum::<i64>()
+    }
+
+    /// Enumerate all connected subgraphs that are accepted by `filter`.
+    /// If `one_particle_irreducible` is set, only one-particle-irreducible subgraphs
+    /// are considered. The subgraphs are returned in lexicographical order.
+    pub fn get_subgraphs(
+        &self,
+        one_particle_irreducible: bool,
+        filter: impl Fn(&[usize]) -> bool,
+    ) -> Vec<Vec<usize>> {
+        let mut out = vec![];
+        for seed in 0..self.edges.len() {
+            let mut candidates = vec![];
+            self.add_neighbouring_edges(seed, seed, &[seed], &[], &mut candidates);
+            self.connected_subgraphs_impl(seed, &mut vec![seed], candidates, &mut vec![], &mut out);
+        }
+
+        out.retain(|s| {
+            (!one_particle_irreducible || self.is_one_particle_irreducible(s)) && filter(s)
+        });
+        out.sort_unstable();
+        out
+    }
+
+    /// Add the edges with an index larger than `seed` that share a node with `edge` and that
+    /// are not in the subgraph, not excluded and not yet a candidate.
+    fn add_neighbouring_edges(
+        &self,
+        seed: usize,
+        edge: usize,
+        subgraph: &[usize],
+        excluded: &[usize],
+        candidates: &mut Vec<usize>,
+    ) {
+        let (a, b) = self.edges[edge].vertices;
+        for e in self.nodes[a].edges.iter().chain(&self.nodes[b].edges) {
+            if *e > seed
+                && !subgraph.contains(e)
+                && !excluded.contains(e)
+                && !candidates.contains(e)
+            {
+                candidates.push(*e);
+            }
+        }
+    }
+
+    /// Enumerate all connected subgraphs whose smallest edge is `seed` by deciding
+    /// for every candidate edge that is adjacent to the subgraph whether it is included
+    /// or excluded.
+    fn connected_subgraphs_impl(
+        &self,
+        seed: usize,
+        subgraph: &mut Vec<usize>,
+        mut candidates: Vec<usize>,
+        excluded: &mut Vec<usize>,
+        out: &mut Vec<Vec<usize>>,
+    ) {
+        let mut s = subgraph.clone();
+        s.sort_unstable();
+        out.push(s);
+
+        let n_excluded = excluded.len();
+        while let Some(e) = candidates.pop() {
+            let mut new_candidates = candidates.clone();
+            subgraph.push(e);
+            self.add_neighbouring_edges(seed, e, subgraph, excluded, &mut new_candidates);
+            self.connected_subgraphs_impl(seed, subgraph, new_candidates, excluded, out);
+            subgraph.pop();
+            excluded.push(e);
+        }
+        excluded.truncate(n_excluded);
+    }
+
+    /// Get all forests of the family of `subgraphs`, which are sets of subgraphs
+    /// in which every pair is either nested or disjoint. The forests are returned
+    /// as lists of indices into `subgraphs` and include the empty forest.
+    /// If `allow_shared_vertices` is `false`, disjoint subgraphs may not share any vertices.
+    pub fn get_forests(
+        &self,
+        subgraphs: &[Vec<usize>],
+        allow_shared_vertices: bool,
+    ) -> Vec<Vec<usize>> {
+        let n = subgraphs.len();
+        let nodes: Vec<_> = subgraphs
+            .iter()
+            .map(|s| self.get_subgraph_nodes(s))
+            .collect();
+
+        let is_subset = |a: &[usize], b: &[usize]| a.iter().all(|x| b.contains(x));
+        let mut compatible = vec![vec![false; n]; n];
+        for i in 0..n {
+            for j in 0..n {
+                let (a, b) = (&subgraphs[i], &subgraphs[j]);
+                compatible[i][j] = is_subset(a, b)
+                    || is_subset(b, a)
+                    || if allow_shared_vertices {
+                        a.iter().all(|x| !b.contains(x))
+                    } else {
+                        nodes[i].iter().all(|x| !nodes[j].contains(x))
+                    };
+            }
+        }
+
+        let mut out = vec![];
+        let mut forest = vec![];
+        Self::forests_impl(&compatible, 0, &mut forest, &mut out);
+        out
+    }
+
+    fn forests_impl(
+        compatible: &[Vec<bool>],
+        start: usize,
+        forest: &mut Vec<usize>,
+        out: &mut Vec<Vec<usize>>,
+    ) {
+        out.push(forest.clone());
+        for i in start..compatible.len() {
+            if forest.iter().all(|f| compatible[*f][i]) {
+                forest.push(i);
+                Self::forests_impl(compatible, i + 1, forest, out);
+                forest.pop();
+            }
+        }
+    }
+}
+
+impl<N: Clone, E: Clone> Graph<N, E> {
+    /// Create a copy of the graph with the edge `edge` removed.
+    pub fn delete_edge(&self, edge: usize) -> Graph<N, E> {
+        self.delete_edges(&[edge])
+    }
+
+    /// Create a copy of the graph with the edges `edges` removed.
+    /// All nodes are kept.
+    pub fn delete_edges(&self, edges: &[usize]) -> Graph<N, E> {
+        let mut g = Graph::new();
+        for n in &self.nodes {
+            g.add_node(n.data.clone());
+        }
+        for (i, e) in self.edges.iter().enumerate() {
+            if !edges.contains(&i) {
+                g.add_edge(e.vertices.0, e.vertices.1, e.directed, e.data.clone())
+                    .unwrap();
+            }
+        }
+        g
+    }
+
+    /// Create a copy of the graph with the edge `edge` contracted.
+    /// See [Self::contract_edges].
+    pub fn contract_edge(&self, edge: usize) -> Graph<N, E> {
+        self.contract_edges(&[edge])
+    }
+
+    /// Create a copy of the graph in which the edges `edges` are contracted, which
+    /// yields the quotient graph `G/γ` for a subgraph `γ`. Every set of nodes that is
+    /// connected by the contracted edges is merged into a single node that keeps the
+    /// data of the node with the lowest index. Other edges between merged nodes become self-loops.
+    pub fn contract_edges(&self, edges: &[usize]) -> Graph<N, E> {
+        let mut parent: Vec<_> = (0..self.nodes.len()).collect();
+        for e in edges {
+            let (a, b) = self.edges[*e].vertices;
+            let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+            parent[ra.max(rb)] = ra.min(rb);
+        }
+
+        let mut g = Graph::new();
+        let mut map = vec![0; self.nodes.len()];
+        for (i, n) in self.nodes.iter().enumerate() {
+            let r = find(&mut parent, i);
+            map[i] = if r == i {
+                g.add_node(n.data.clone())
+            } else {
+                map[r]
+            };
+        }
+
+        for (i, e) in self.edges.iter().enumerate() {
+            if !edges.contains(&i) {
+                g.add_edge(
+                    map[e.vertices.0],
+                    map[e.vertices.1],
+                    e.directed,
+                    e.data.clone(),
+                )
+                .unwrap();
+            }
+        }
+        g
+    }
+
+    /// Create the subgraph that consists of the edges `edges` and their incident nodes,
+    /// keeping the data and the relative order of the nodes and edges.
+    pub fn subgraph(&self, edges: &[usize]) -> Graph<N, E> {
+        let nodes = self.get_subgraph_nodes(edges);
+        let mut g = Graph::new();
+        for n in &nodes {
+            g.add_node(self.nodes[*n].data.clone());
+        }
+
+        let mut edges = edges.to_vec();
+        edges.sort_unstable();
+        for e in edges {
+            let e = &self.edges[e];
+            let a = nodes.binary_search(&e.vertices.0).unwrap();
+            let b = nodes.binary_search(&e.vertices.1).unwrap();
+            g.add_edge(a, b, e.directed, e.data.clone()).unwrap();
+        }
+        g
+    }
+}
+
+impl<N: Clone + Ord + Hash, E: Clone + Ord + Hash> Graph<N, E> {
+    /// Group the `subgraphs` by their canonical form. For every distinct canonical form,
+    /// the indices of the isomorphic subgraphs in `subgraphs` are returned.
+    pub fn group_isomorphic_subgraphs(
+        &self,
+        subgraphs: &[Vec<usize>],
+    ) -> HashMap<Graph<N, E>, Vec<usize>> {
+        let mut groups: HashMap<_, Vec<_>> = HashMap::default();
+        for (i, s) in subgraphs.iter().enumerate() {
+            groups
+                .entry(self.subgraph(s).canonize().graph)
+                .or_default()
+                .push(i);
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::graph::Graph;
+
+    fn double_bubble() -> Graph<usize, usize> {
+        let mut g = Graph::new();
+        let n: Vec<_> = (0..3).map(|i| g.add_node(i)).collect();
+        g.add_edge(n[0], n[1], false, 0).unwrap();
+        g.add_edge(n[0], n[1], false, 1).unwrap();
+        g.add_edge(n[1], n[2], false, 0).unwrap();
+        g.add_edge(n[1], n[2], false, 1).unwrap();
+        g
+    }
+
+    #[test]
+    fn subgraphs() {
+        let mut g = double_bubble();
+        for n in 0..3 {
+            g.set_node_data(n, 0);
+        }
+
+        assert_eq!(g.get_subgraphs(false, |_| true).len(), 15);
+
+        let one_pi = g.get_subgraphs(true, |_| true);
+        assert_eq!(one_pi, [vec![0, 1], vec![0, 1, 2, 3], vec![2, 3]]);
+        assert_eq!(g.get_subgraph_loop_count(&one_pi[1]), 2);
+
+        let groups = g.group_isomorphic_subgraphs(&one_pi);
+        assert_eq!(groups.len(), 2);
+
+        assert_eq!(g.get_forests(&one_pi, true).len(), 8);
+        assert_eq!(g.get_forests(&one_pi, false).len(), 6);
+    }
+
+    #[test]
+    fn minors() {
+        let g = double_bubble();
+
+        let c = g.contract_edges(&[0, 1]);
+        assert_eq!(c.nodes().len(), 2);
+        assert_eq!(c.edges().len(), 2);
+        assert_eq!(c.node(1).data, 2);
+
+        let c = g.contract_edges(&[0]);
+        assert_eq!(c.nodes().len(), 2);
+        assert!(c.edge(0).is_self_loop());
+
+        let d = g.delete_edges(&[1, 3]);
+        assert_eq!(d.edges().len(), 2);
+        assert_eq!(d.edge(1).data, 0);
+
+        let s = g.subgraph(&[2, 3]);
+        assert_eq!(s.nodes().len(), 2);
+        assert_eq!(s.node(0).data, 1);
+
+        assert_eq!(g.get_spanning_forests(1).len(), 4);
+        assert_eq!(g.get_spanning_forests(2).len(), 4);
+    }
+}
@@ -23,7 +23,7 @@ use dyn_clone::DynClone;
 
 use crate::{
     atom::{
-        Atom, AtomCore, AtomType, AtomView, Num, SliceType, Symbol,
+        Assumption, Atom, AtomCore, AtomType, AtomView, Num, SliceType, Symbol,
         representation::{InlineVar, ListSlice},
     },
     state::{RecycledAtom, Workspace},
@@ -1044,13 +1044,21 @@ impl<'a> AtomView<'a> {
                         AtomView::Mul(m) => {
                             let out = out.to_mul();
 
+                            // insert the rhs at the position of the first matched factor
+                            // to preserve the order of noncommutative factors
+                            let mut rhs_inserted = false;
                             for (child, used) in m.iter().zip(used_flags) {
                                 if !used {
                                     out.extend(child);
+                                } else if !rhs_inserted {
+                                    out.extend(rhs_subs.as_view());
+                                    rhs_inserted = true;
                                 }
                             }
 
-                            out.extend(rhs_subs.as_view());
+                            if !rhs_inserted {
+                                out.extend(rhs_subs.as_view());
+                            }
                         }
                         AtomView::Add(a) => {
                             let out = out.to_add();
@@ -1967,6 +1975,8 @@ pub enum WildcardRestriction {
     Filter(Box<dyn FilterFn>),
     Cmp(Symbol, Box<dyn CmpFn>),
     NotGreedy,
+    /// The value satisfies the assumption, given the assumptions on its symbols.
+    Assumption(Box<Assumption>),
 }
 
 impl WildcardRestriction {
@@ -1986,6 +1996,19 @@ impl WildcardRestriction {
     pub fn cmp(s: Symbol, f: impl CmpFn + 'static) -> Self {
         WildcardRestriction::Cmp(s, Box::new(f))
     }
+
+    /// Restrict wildcard values to those that satisfy an assumption,
+    /// given the assumptions on the symbols they contain.
+    ///
+    /// # Examples
+    /// Only match positive values:
+    /// ```
+    /// # use symbolica::{atom::Assumption, id::WildcardRestriction};
+    /// WildcardRestriction::assumption(Assumption::Positive);
+    /// ```
+    pub fn assumption(a: Assumption) -> Self {
+        WildcardRestriction::Assumption(Box::new(a))
+    }
 }
 
 impl std::fmt::Display for WildcardRestriction {
@@ -1998,6 +2021,7 @@ impl std::fmt::Display for WildcardRestriction {
             WildcardRestriction::Filter(_) => write!(f, "filter"),
             WildcardRestriction::Cmp(s, _) => write!(f, "cmp with {}", s),
             WildcardRestriction::NotGreedy => write!(f, "not greedy"),
+            WildcardRestriction::Assumption(a) => write!(f, "assume {:?}", a),
         }
     }
 }
@@ -2430,6 +2454,7 @@ impl Evaluate for Condition<PatternRestriction> {
                                 }
                             }
                             WildcardRestriction::NotGreedy => true,
+                            WildcardRestriction::Assumption(a) => value.satisfies(a),
                         }
                         .into()
                     } else {
@@ -2530,6 +2555,7 @@ impl Condition<PatternRestriction> {
                         }
                     }
                     WildcardRestriction::NotGreedy => true.into(),
+                    WildcardRestriction::Assumption(a) => value.satisfies(a).into(),
                 }
             }
         }
@@ -2614,6 +2640,7 @@ impl Clone for WildcardRestriction {
             Self::Filter(f) => Self::Filter(dyn_clone::clone_box(f)),
             Self::Cmp(i, f) => Self::Cmp(*i, dyn_clone::clone_box(f)),
             Self::NotGreedy => Self::NotGreedy,
+            Self::Assumption(a) => Self::Assumption(a.clone()),
         }
     }
 }
@@ -2629,6 +2656,7 @@ impl std::fmt::Debug for WildcardRestriction {
             Self::Filter(_) => f.debug_tuple("Filter").finish(),
             Self::Cmp(arg0, _) => f.debug_tuple("Cmp").field(arg0).finish(),
             Self::NotGreedy => write!(f, "NotGreedy"),
+            Self::Assumption(a) => f.debug_tuple("Assumption").field(a).finish(),
         }
     }
 }
@@ -2701,6 +2729,18 @@ impl std::fmt::Debug for Match<'_> {
 }
 
 impl Match<'_> {
+    /// Returns `true` iff the matched value satisfies the assumption `a`,
+    /// given the assumptions on its symbols.
+    fn satisfies(&self, a: &Assumption) -> bool {
+        match self {
+            Self::Single(v) => v.properties().satisfies(a),
+            Self::Multiple(SliceType::Add | SliceType::Mul, _) => {
+                self.to_atom().as_view().properties().satisfies(a)
+            }
+            _ => false,
+        }
+    }
+
     /// Create a new atom from a matched subexpression.
     /// Arguments lists are wrapped in the function `arg`.
     pub fn to_atom(&self) -> Atom {
@@ -3264,6 +3304,38 @@ impl<'a, 'b> SubSliceIterator<'a, 'b> {
         }
     }
 
+    /// Check if the noncommutative factors of a product that are matched appear in the same
+    /// order as in the pattern, without any unmatched noncommutative factors in between.
+    fn has_valid_noncommutative_order(&self) -> bool {
+        if self.target.get_type() != SliceType::Mul {
+            return true;
+        }
+
+        let mut last: Option<usize> = None;
+        let mut check = |index: usize| {
+            if !self.target.get(index).is_noncommutative() {
+                return true;
+            }
+
+            if let Some(l) = last {
+                if index <= l || (l + 1..index).any(|i| self.target.get(i).is_noncommutative()) {
+                    return false;
+                }
+            }
+
+            last = Some(index);
+            true
+        };
+
+        self.iterators.iter().all(|it| match it {
+            PatternIter::Wildcard(w) => w.indices.iter().all(|i| check(*i as usize)),
+            PatternIter::Literal(Some(i), _)
+            | PatternIter::Fn(Some(i), ..)
+            | PatternIter::Sequence(Some(i), ..) => check(*i),
+            _ => true,
+        })
+    }
+
     /// Get the next matches, where the map of matches is written into `match_stack`.
     /// The function returns the length of the match stack before the last subiterator
     /// matched. This value can be ignored by the end-user. If `None` is returned,
@@ -3290,6 +3362,9 @@ impl<'a, 'b> SubSliceIterator<'a, 'b> {
                 {
                     // not done as the entire target is not used
                     forward_pass = false;
+                } else if !self.has_valid_noncommutative_order() {
+                    // the matched noncommutative factors are out of order
+                    forward_pass = false;
                 } else {
                     // yield the current match
                     return Some((*self.matches.last().unwrap(), &self.used_flag));
@@ -4088,13 +4163,21 @@ impl<'a: 'b, 'b> ReplaceIterator<'a, 'b> {
                 AtomView::Mul(m) => {
                     let out = out.to_mul();
 
+                    // insert the rhs at the position of the first matched factor
+                    // to preserve the order of noncommutative factors
+                    let mut rhs_inserted = false;
                     for (child, used) in m.iter().zip(used_flags) {
                         if !used {
                             out.extend(child);
+                        } else if !rhs_inserted {
+                            out.extend(rhs);
+                            rhs_inserted = true;
                         }
                     }
 
-                    out.extend(rhs);
+                    if !rhs_inserted {
+                        out.extend(rhs);
+                    }
                 }
                 AtomView::Add(a) => {
                     let out = out.to_add();
@@ -4173,11 +4256,11 @@ impl<'a: 'b, 'b> Iterator for ReplaceIterator<'a, 'b> {
 #[cfg(test)]
 mod test {
     use crate::{
-        atom::{Atom, AtomCore},
-        id::{Condition, ConditionResult, Match, Replacement},
+        atom::{Assumption, Atom, AtomCore, Symbol},
+        id::{Condition, ConditionResult, Match, Replacement, WildcardRestriction},
         parse,
         printer::PrintOptions,
-        symbol,
+        symbol, wrap_symbol,
     };
 
     #[test]
@@ -4419,4 +4502,48 @@ mod test {
         let vars = e.as_view().is_polynomial(false, false).unwrap();
         assert_eq!(vars.len(), 5);
     }
+
+    #[test]
+    fn match_noncommutative() {
+        symbol!("nc1"; Noncommutative).unwrap();
+        symbol!("nc2"; Noncommutative).unwrap();
+        symbol!("nc3"; Noncommutative).unwrap();
+        symbol!("nc4_"; Noncommutative).unwrap();
+
+        let expr = parse!("v1*nc3*nc1*nc2").unwrap();
+        let p = parse!("nc1*nc2").unwrap().to_pattern();
+        let rhs = parse!("nc2*nc1").unwrap().to_pattern();
+        let r = expr.replace(&p).with(&rhs);
+        assert_eq!(r, parse!("v1*nc3*nc2*nc1").unwrap());
+
+        // wrong order
+        let r = expr.replace(parse!("nc2*nc1").unwrap()).with(&rhs);
+        assert_eq!(r, expr);
+
+        // gap
+        let expr = parse!("nc1*nc3*nc2").unwrap();
+        let r = expr.replace(&p).with(&rhs);
+        assert_eq!(r, expr);
+
+        let expr = parse!("nc1*nc3*nc2").unwrap();
+        let r = expr
+            .replace(parse!("nc3*nc4_").unwrap())
+            .with(parse!("f1(nc4_)").unwrap());
+        assert_eq!(r, parse!("nc1*f1(nc2)").unwrap());
+    }
+
+    #[test]
+    fn match_assumption() {
+        Symbol::new(wrap_symbol!("xpos"))
+            .with_assumptions(&[Assumption::Positive])
+            .build()
+            .unwrap();
+
+        let expr = parse!("f1(xpos)+f1(-xpos)+f1(2)+f1(v1)").unwrap();
+        let r = expr
+            .replace(parse!("f1(x_)").unwrap())
+            .when(symbol!("x_").restrict(WildcardRestriction::assumption(Assumption::Positive)))
+            .with(parse!("x_").unwrap());
+        assert_eq!(r, parse!("xpos+f1(-xpos)+2+f1(v1)").unwrap());
+    }
 }
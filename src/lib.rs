@@ -50,6 +50,8 @@ use tinyjson::JsonValue;
 pub mod api;
 #[cfg(not(feature = "python_no_module"))]
 mod api;
+pub mod archive;
+mod assumptions;
 pub mod atom;
 pub mod coefficient;
 mod collect;
@@ -64,6 +66,7 @@ mod normalize;
 pub mod numerical_integration;
 pub mod parser;
 pub mod poly;
+pub mod polylog;
 pub mod printer;
 mod solve;
 pub mod state;
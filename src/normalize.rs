@@ -3,13 +3,25 @@ use std::{cmp::Ordering, ops::DerefMut};
 use smallvec::SmallVec;
 
 use crate::{
-    atom::{Atom, AtomView, Fun, Symbol, representation::InlineNum},
+    atom::{Atom, AtomView, Fun, FunctionAttribute, Symbol, representation::InlineNum},
     coefficient::{Coefficient, CoefficientView},
-    domains::{float::Real, integer::Z, rational::Q},
+    domains::{
+        float::{Float, NumericalFloatLike, Real, SingleFloat},
+        integer::{Integer, Z},
+        rational::{Q, Rational},
+    },
     poly::Variable,
     state::{RecycledAtom, State, Workspace},
 };
 
+/// The largest argument for which factorial-like special functions such as `gamma`
+/// are evaluated exactly during normalization. Larger arguments are kept unevaluated,
+/// as the exact result would be too expensive to compute.
+const EXACT_EVALUATION_LIMIT: i64 = 1000;
+/// The largest argument for which `zeta` is evaluated exactly, as the
+/// Bernoulli numbers are much more expensive to compute than factorials.
+const EXACT_ZETA_LIMIT: i64 = 100;
+
 impl AtomView<'_> {
     /// Compare two atoms.
     pub fn cmp(&self, other: &AtomView<'_>) -> Ordering {
@@ -100,6 +112,32 @@ impl AtomView<'_> {
         }
     }
 
+    /// Compare factors in a term whose order partially matters. Commutative factors are
+    /// sorted before ordered factors, which are the noncommutative factors and, if `keep_graded_order`
+    /// is set, the factors with a Grassmann-odd part. Ordered factors compare as equal, so that a
+    /// stable sort preserves their relative order. The coefficient is still placed last.
+    pub(crate) fn cmp_factors_ordered(
+        &self,
+        other: &AtomView<'_>,
+        keep_graded_order: bool,
+    ) -> Ordering {
+        let is_ordered = |a: &AtomView| {
+            a.is_noncommutative() || keep_graded_order && a.grassmann_parity() != Some(false)
+        };
+
+        match (self, other) {
+            (AtomView::Num(_), AtomView::Num(_)) => Ordering::Equal,
+            (AtomView::Num(_), _) => Ordering::Greater,
+            (_, AtomView::Num(_)) => Ordering::Less,
+            _ => match (is_ordered(self), is_ordered(other)) {
+                (true, true) => Ordering::Equal,
+                (false, true) => Ordering::Less,
+                (true, false) => Ordering::Greater,
+                (false, false) => self.cmp_factors(other),
+            },
+        }
+    }
+
     /// Compare factors in a term. `x` and `x^2` are placed next to each other by sorting a power based on the base only.
     pub(crate) fn cmp_factors(&self, other: &AtomView<'_>) -> Ordering {
         match (&self, other) {
@@ -345,6 +383,362 @@ impl AtomView<'_> {
 
         false
     }
+
+    /// Simplify `log(x)`, `sqrt(x)` or `abs(x)` with the normalized argument `self`,
+    /// using the assumptions on the symbols.
+    fn simplify_with_assumptions(&self, id: Symbol, ws: &Workspace, out: &mut Atom) -> bool {
+        match id {
+            Atom::LOG => {
+                // log(exp(x)) = x for real x
+                if let AtomView::Fun(f) = self {
+                    if f.get_symbol() == Atom::EXP && f.get_nargs() == 1 {
+                        let x = f.iter().next().unwrap();
+                        if x.properties().real {
+                            out.set_from_view(&x);
+                            return true;
+                        }
+                    }
+                }
+            }
+            Atom::SQRT => {
+                if let AtomView::Pow(p) = self {
+                    let (b, e) = p.get_base_exp();
+                    let b_props = b.properties();
+
+                    let mut half = ws.new_atom();
+                    half.to_num((1, 2).into());
+                    let mut new_exp = ws.new_atom();
+                    let mut new_pow = ws.new_atom();
+
+                    if b_props.is_nonnegative() && e.properties().real {
+                        // sqrt(x^y) = x^(y/2) for nonnegative x
+                        let m = new_exp.to_mul();
+                        m.extend(e);
+                        m.extend(half.as_view());
+                        new_pow.to_pow(b, new_exp.as_view());
+                        new_pow.as_view().normalize(ws, out);
+                        return true;
+                    }
+
+                    if b_props.real && e.properties().even == Some(true) {
+                        // sqrt(x^(2n)) = abs(x)^n for real x, where abs can be dropped for even n
+                        let m = new_exp.to_mul();
+                        m.extend(e);
+                        m.extend(half.as_view());
+                        let mut n = ws.new_atom();
+                        new_exp.as_view().normalize(ws, &mut n);
+
+                        if n.as_view().properties().even == Some(true) {
+                            new_pow.to_pow(b, n.as_view());
+                        } else {
+                            let mut abs = ws.new_atom();
+                            abs.to_fun(Atom::ABS).add_arg(b);
+                            new_pow.to_pow(abs.as_view(), n.as_view());
+                        }
+                        new_pow.as_view().normalize(ws, out);
+                        return true;
+                    }
+                }
+            }
+            Atom::ABS if !matches!(self, AtomView::Num(_)) => {
+                let props = self.properties();
+                if props.is_nonnegative() {
+                    out.set_from_view(self);
+                    return true;
+                } else if props.is_nonpositive() {
+                    let mut neg = ws.new_atom();
+                    let mut m_one = ws.new_atom();
+                    m_one.to_num((-1).into());
+                    let m = neg.to_mul();
+                    m.extend(*self);
+                    m.extend(m_one.as_view());
+                    neg.as_view().normalize(ws, out);
+                    return true;
+                }
+            }
+            _ => {}
+        }
+
+        false
+    }
+
+    /// Evaluate the builtin special function `id` with normalized arguments `args`
+    /// at special values, or numerically if one of the arguments is a floating point number.
+    /// Returns `None` if the function should remain unevaluated.
+    fn normalize_special_function(id: Symbol, args: &[AtomView<'_>]) -> Option<Atom> {
+        if id == Atom::MPL || id == Atom::HSUM {
+            return Self::normalize_polylog(id, args);
+        }
+
+        if id == Atom::POLYLOG && args.len() > 2 && args.len() % 2 == 0 {
+            // li(m_1,...,m_k,x_1,...,x_k) vanishes if one of the x_i is 0
+            if args[args.len() / 2..].iter().any(|x| x.is_zero()) {
+                return Some(Atom::new_num(0));
+            }
+            return None;
+        }
+
+        if id == Atom::POLYLOG && args.len() == 2 {
+            if let AtomView::Num(n) = args[0] {
+                if n.is_zero() {
+                    // li(0, x) = x/(1-x)
+                    let x = args[1].to_owned();
+                    return Some(&x / (Atom::new_num(1) - &x));
+                } else if n.is_one() {
+                    // li(1, x) = -log(1-x)
+                    return Some(-(Atom::new_num(1) - args[1]).log());
+                }
+            }
+        }
+
+        let mut coeffs: SmallVec<[CoefficientView; 3]> = SmallVec::new();
+        for a in args {
+            let AtomView::Num(n) = a else {
+                return None;
+            };
+
+            match n.get_coeff_view() {
+                c @ (CoefficientView::Natural(_, _)
+                | CoefficientView::Large(_)
+                | CoefficientView::Float(_)) => coeffs.push(c),
+                _ => return None,
+            }
+        }
+
+        let to_rat = |c: &CoefficientView| match c {
+            CoefficientView::Natural(n, d) => Rational::from_unchecked(*n, *d),
+            CoefficientView::Large(r) => r.to_rat(),
+            CoefficientView::Float(f) => f.to_float().to_rational(),
+            _ => unreachable!(),
+        };
+
+        let prec = coeffs
+            .iter()
+            .filter_map(|c| match c {
+                CoefficientView::Float(f) => Some(f.to_float().prec()),
+                _ => None,
+            })
+            .min();
+
+        if let Some(prec) = prec {
+            let integer_order = coeffs[0].is_integer();
+            let x: SmallVec<[Float; 3]> = coeffs
+                .iter()
+                .map(|c| match c {
+                    CoefficientView::Float(f) => f.to_float(),
+                    _ => to_rat(c).to_multi_prec_float(prec),
+                })
+                .collect();
+
+            let r = match (id, x.as_slice()) {
+                (Atom::TAN, [x]) => x.tan(),
+                (Atom::ARCSIN, [x]) => x.asin(),
+                (Atom::ARCCOS, [x]) => x.acos(),
+                (Atom::ARCTAN, [x]) => x.atan2(&x.one()),
+                (Atom::ARCTAN, [y, x]) => y.atan2(x),
+                (Atom::SINH, [x]) => x.sinh(),
+                (Atom::COSH, [x]) => x.cosh(),
+                (Atom::TANH, [x]) => x.tanh(),
+                (Atom::ARCSINH, [x]) => x.asinh(),
+                (Atom::ARCCOSH, [x]) => x.acosh(),
+                (Atom::ARCTANH, [x]) => x.atanh(),
+                (Atom::ABS, [x]) => x.norm(),
+                (Atom::SIGN, [x]) => {
+                    if x.is_zero() {
+                        x.zero()
+                    } else {
+                        x.clone() / x.norm()
+                    }
+                }
+                (Atom::HEAVISIDE, [x]) => {
+                    if x.is_zero() {
+                        x.from_rational(&(1, 2).into())
+                    } else if x.is_negative() {
+                        x.zero()
+                    } else {
+                        x.one()
+                    }
+                }
+                (Atom::GAMMA, [x]) => x.gamma(),
+                (Atom::FACTORIAL, [x]) => (x.clone() + x.one()).gamma(),
+                (Atom::POLYGAMMA, [n, x]) if integer_order => x.polygamma(n),
+                (Atom::BETA, [a, b]) => a.gamma() * b.gamma() / (a.clone() + b).gamma(),
+                (Atom::ERF, [x]) => x.erf(),
+                (Atom::ZETA, [x]) => x.zeta(),
+                (Atom::POLYLOG, [n, x]) if integer_order => x.polylog(n),
+                (Atom::BESSEL_J, [nu, x]) => x.bessel_j(nu),
+                (Atom::BESSEL_Y, [nu, x]) => x.bessel_y(nu),
+                (Atom::BESSEL_I, [nu, x]) => x.bessel_i(nu),
+                (Atom::BESSEL_K, [nu, x]) => x.bessel_k(nu),
+                _ => return None,
+            };
+
+            return Some(Atom::new_num(r));
+        }
+
+        let x: SmallVec<[Rational; 3]> = coeffs.iter().map(to_rat).collect();
+        let to_int = |r: &Rational| {
+            if r.is_integer() {
+                r.numerator_ref().to_i64()
+            } else {
+                None
+            }
+        };
+        let zero = || Some(Atom::new_num(0));
+
+        match (id, x.as_slice()) {
+            (Atom::ABS, [x]) => Some(Atom::new_num(x.abs())),
+            (Atom::SIGN, [x]) => Some(Atom::new_num(if x.is_zero() {
+                0
+            } else if x.is_negative() {
+                -1
+            } else {
+                1
+            })),
+            (Atom::HEAVISIDE, [x]) => Some(Atom::new_num(if x.is_zero() {
+                (1, 2).into()
+            } else if x.is_negative() {
+                Rational::zero()
+            } else {
+                Rational::one()
+            })),
+            (
+                Atom::TAN
+                | Atom::ARCSIN
+                | Atom::ARCTAN
+                | Atom::SINH
+                | Atom::TANH
+                | Atom::ARCSINH
+                | Atom::ARCTANH
+                | Atom::ERF,
+                [x],
+            ) if x.is_zero() => zero(),
+            (Atom::ARCTAN, [y, x]) if y.is_zero() && !x.is_zero() && !x.is_negative() => zero(),
+            (Atom::COSH, [x]) if x.is_zero() => Some(Atom::new_num(1)),
+            (Atom::ARCCOS | Atom::ARCCOSH, [x]) if x.is_one() => zero(),
+            (Atom::GAMMA, [x]) => Self::gamma_rational(x),
+            (Atom::FACTORIAL, [x]) => Self::gamma_rational(&(x + &Rational::one())),
+            (Atom::DOUBLE_FACTORIAL, [x]) => {
+                let n = to_int(x)?;
+                if !(-1..=EXACT_EVALUATION_LIMIT).contains(&n) {
+                    return None;
+                }
+
+                Some(Atom::new_num(Integer::double_factorial(n.max(0) as u32)))
+            }
+            (Atom::BETA, [a, b]) => {
+                let (a, b) = (to_int(a)?, to_int(b)?);
+                if a <= 0 || b <= 0 || a + b > EXACT_EVALUATION_LIMIT {
+                    return None;
+                }
+
+                let r = Rational::from(
+                    Integer::factorial(a as u32 - 1) * Integer::factorial(b as u32 - 1),
+                ) / Rational::from(Integer::factorial((a + b) as u32 - 1));
+                Some(Atom::new_num(r))
+            }
+            (Atom::ZETA, [s]) => Self::zeta_rational(to_int(s)?),
+            (Atom::POLYGAMMA, [n, x]) if x.is_one() => {
+                // polygamma(n, 1) = (-1)^(n+1) n! zeta(n+1)
+                let n = to_int(n)?;
+                if !(1..EXACT_EVALUATION_LIMIT).contains(&n) {
+                    return None;
+                }
+
+                let f = Integer::factorial(n as u32);
+                let f = if n % 2 == 0 { -f } else { f };
+                Some(crate::function!(Atom::ZETA, n + 1) * f)
+            }
+            (Atom::POLYLOG, [_, x]) if x.is_zero() => zero(),
+            (Atom::POLYLOG, [n, x]) if x.is_one() || x.neg().is_one() => {
+                let n = to_int(n)?;
+                if !(2..=EXACT_EVALUATION_LIMIT).contains(&n) {
+                    return None;
+                }
+
+                let z = crate::function!(Atom::ZETA, n);
+                if x.is_one() {
+                    Some(z)
+                } else {
+                    // li(n, -1) = -(1-2^(1-n)) zeta(n)
+                    let f = Rational::from_unchecked(1, 2).pow(n as u64 - 1) - Rational::one();
+                    Some(z * f)
+                }
+            }
+            (Atom::BESSEL_J | Atom::BESSEL_I, [nu, x]) if x.is_zero() => {
+                if nu.is_zero() {
+                    Some(Atom::new_num(1))
+                } else if nu.is_integer() || !nu.is_negative() {
+                    zero()
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Evaluate `gamma(x)` for integer and half-integer `x`.
+    fn gamma_rational(x: &Rational) -> Option<Atom> {
+        if x.is_integer() {
+            let n = x.numerator_ref().to_i64()?;
+            if n <= 0 || n > EXACT_EVALUATION_LIMIT {
+                return None;
+            }
+
+            return Some(Atom::new_num(Integer::factorial(n as u32 - 1)));
+        }
+
+        if x.denominator_ref() != &Integer::from(2) {
+            return None;
+        }
+
+        // x = m + 1/2
+        let m = (x.clone() - Rational::from_unchecked(1, 2))
+            .numerator_ref()
+            .to_i64()?;
+        let k = m.unsigned_abs();
+        if k > EXACT_EVALUATION_LIMIT as u64 / 2 {
+            return None;
+        }
+
+        let r = Rational::from(Integer::factorial(2 * k as u32))
+            / Rational::from(Integer::from(4).pow(k) * Integer::factorial(k as u32));
+        let r = if m >= 0 {
+            r
+        } else if k % 2 == 0 {
+            r.inv()
+        } else {
+            r.inv().neg()
+        };
+
+        Some(Atom::new_var(Atom::PI).npow((1, 2)) * r)
+    }
+
+    /// Evaluate `zeta(s)` for non-positive and even integers `s`
+    /// in terms of Bernoulli numbers.
+    fn zeta_rational(s: i64) -> Option<Atom> {
+        if s <= 0 {
+            // zeta(-n) = (-1)^n B_(n+1)/(n+1)
+            let n = -s;
+            if n >= EXACT_ZETA_LIMIT {
+                return None;
+            }
+
+            let r = Rational::bernoulli(n as u32 + 1) / Rational::from(n + 1);
+            return Some(Atom::new_num(if n % 2 == 0 { r } else { r.neg() }));
+        }
+
+        if s % 2 == 1 || s > EXACT_ZETA_LIMIT {
+            return None;
+        }
+
+        // zeta(2k) = (-1)^(k+1) B_(2k) (2 pi)^(2k) / (2 (2k)!)
+        let r = Rational::bernoulli(s as u32) * Rational::from(Integer::from(2).pow(s as u64 - 1))
+            / Rational::from(Integer::factorial(s as u32));
+        let r = if s % 4 == 0 { r.neg() } else { r };
+        Some(Atom::new_var(Atom::PI).npow(s) * r)
+    }
 }
 
 impl Atom {
@@ -749,7 +1143,61 @@ impl AtomView<'_> {
                     return;
                 }
 
-                atom_test_buf.sort_by(|a, b| a.as_view().cmp_factors(&b.as_view()));
+                // the relative order of factors with a Grassmann-odd part is kept if
+                // a factor is a sum of even and odd terms, as it cannot be moved with a fixed sign
+                let mut has_odd = false;
+                let mut keep_graded_order = false;
+                for a in &atom_test_buf {
+                    match a.as_view().grassmann_parity() {
+                        Some(true) => has_odd = true,
+                        None => keep_graded_order = true,
+                        Some(false) => {}
+                    }
+                }
+
+                let ordered = keep_graded_order
+                    || atom_test_buf
+                        .iter()
+                        .any(|a| a.as_view().is_noncommutative());
+                let cmp = |a: &AtomView, b: &AtomView| {
+                    if ordered {
+                        a.cmp_factors_ordered(b, keep_graded_order)
+                    } else {
+                        a.cmp_factors(b)
+                    }
+                };
+
+                // Grassmann-odd factors anticommute, so the product vanishes if an odd
+                // factor appears twice and sorting them yields the sign of the permutation
+                let mut flip_sign = false;
+                if has_odd {
+                    let odd: SmallVec<[_; 20]> = atom_test_buf
+                        .iter()
+                        .map(|a| a.as_view())
+                        .filter(|a| a.grassmann_parity() == Some(true))
+                        .collect();
+
+                    for (i, a) in odd.iter().enumerate() {
+                        for b in &odd[i + 1..] {
+                            if a == b {
+                                out.to_num(Coefficient::zero());
+                                return;
+                            }
+
+                            if cmp(a, b) == Ordering::Greater {
+                                flip_sign = !flip_sign;
+                            }
+                        }
+                    }
+                }
+
+                atom_test_buf.sort_by(|a, b| cmp(&a.as_view(), &b.as_view()));
+
+                if flip_sign {
+                    let mut sign = workspace.new_atom();
+                    sign.to_num((-1).into());
+                    atom_test_buf.push(sign);
+                }
 
                 let mut second_pass = false;
                 if !atom_test_buf.is_empty() {
@@ -871,18 +1319,57 @@ impl AtomView<'_> {
                     }
                 }
 
+                let attributes = State::get_function_attributes(id);
+                let associative = attributes.contains(&FunctionAttribute::Associative);
+
                 let mut handle = workspace.new_atom();
                 for a in f {
-                    if a.needs_normalization() {
+                    let a = if a.needs_normalization() {
                         a.normalize(workspace, &mut handle);
-                        add_arg(out_f, handle.as_view());
+                        handle.as_view()
                     } else {
-                        add_arg(out_f, a);
+                        a
+                    };
+
+                    // flatten f(a, f(b, c)) = f(a, b, c)
+                    if let AtomView::Fun(fa) = a {
+                        if associative && fa.get_symbol() == id {
+                            for aa in fa.iter() {
+                                add_arg(out_f, aa);
+                            }
+                            continue;
+                        }
                     }
+
+                    add_arg(out_f, a);
                 }
 
                 out_f.set_normalized(true);
 
+                if out_f.to_fun_view().get_nargs() == 1 {
+                    if let AtomView::Fun(fa) = out_f.to_fun_view().iter().next().unwrap() {
+                        if fa.get_symbol() == id {
+                            if attributes.contains(&FunctionAttribute::Idempotent) {
+                                // f(f(x)) = f(x)
+                                let mut buffer = workspace.new_atom();
+                                buffer.set_from_view(&fa.as_view());
+                                out.set_from_view(&buffer.as_view());
+                                return;
+                            }
+
+                            if attributes.contains(&FunctionAttribute::Involution)
+                                && fa.get_nargs() == 1
+                            {
+                                // f(f(x)) = x
+                                let mut buffer = workspace.new_atom();
+                                buffer.set_from_view(&fa.iter().next().unwrap());
+                                out.set_from_view(&buffer.as_view());
+                                return;
+                            }
+                        }
+                    }
+                }
+
                 if [Atom::COS, Atom::SIN, Atom::EXP, Atom::LOG].contains(&id)
                     && out_f.to_fun_view().get_nargs() == 1
                 {
@@ -926,6 +1413,29 @@ impl AtomView<'_> {
                     }
                 }
 
+                if State::is_builtin(id) && id.get_id() >= Atom::TAN.get_id() {
+                    let args: SmallVec<[AtomView; 3]> = out_f.to_fun_view().iter().collect();
+                    let r = AtomView::normalize_special_function(id, &args);
+                    drop(args);
+                    if let Some(r) = r {
+                        out.set_from_view(&r.as_view());
+                        return;
+                    }
+                }
+
+                if [Atom::LOG, Atom::SQRT, Atom::ABS].contains(&id)
+                    && out_f.to_fun_view().get_nargs() == 1
+                {
+                    let arg = out_f.to_fun_view().iter().next().unwrap();
+                    let mut buffer = workspace.new_atom();
+                    if arg.has_assumptions()
+                        && arg.simplify_with_assumptions(id, workspace, &mut buffer)
+                    {
+                        out.set_from_view(&buffer.as_view());
+                        return;
+                    }
+                }
+
                 if id == Atom::EXP && out_f.to_fun_view().get_nargs() == 1 {
                     let arg = out_f.to_fun_view().iter().next().unwrap();
                     // simplify logs inside exp
@@ -963,23 +1473,30 @@ impl AtomView<'_> {
                     }
                 }
 
-                if id.is_linear() {
+                let linear_arguments = State::get_linear_arguments(id);
+                let homogeneous = attributes.contains(&FunctionAttribute::Homogeneous);
+                let is_linear_arg =
+                    |i: usize| id.is_linear() || linear_arguments.binary_search(&i).is_ok();
+
+                if id.is_linear() || !linear_arguments.is_empty() || homogeneous {
                     // linearize sums
                     if out_f
                         .to_fun_view()
                         .iter()
-                        .any(|a| matches!(a, AtomView::Add(_)))
+                        .enumerate()
+                        .any(|(i, a)| is_linear_arg(i) && matches!(a, AtomView::Add(_)))
                     {
                         let mut arg_buf = Vec::with_capacity(out_f.to_fun_view().get_nargs());
 
-                        for a in out_f.to_fun_view().iter() {
+                        for (i, a) in out_f.to_fun_view().iter().enumerate() {
                             let mut vec = vec![];
-                            if let AtomView::Add(aa) = a {
-                                for a in aa.iter() {
-                                    vec.push(a);
+                            match a {
+                                AtomView::Add(aa) if is_linear_arg(i) => {
+                                    for a in aa.iter() {
+                                        vec.push(a);
+                                    }
                                 }
-                            } else {
-                                vec.push(a);
+                                _ => vec.push(a),
                             }
                             arg_buf.push(vec);
                         }
@@ -1001,9 +1518,9 @@ impl AtomView<'_> {
                     }
 
                     // linearize products
-                    if out_f.to_fun_view().iter().any(|a| {
+                    if out_f.to_fun_view().iter().enumerate().any(|(i, a)| {
                         if let AtomView::Mul(m) = a {
-                            m.has_coefficient()
+                            (homogeneous || is_linear_arg(i)) && m.has_coefficient()
                         } else {
                             false
                         }
@@ -1013,9 +1530,11 @@ impl AtomView<'_> {
                         let mut new_fun = workspace.new_atom();
                         let nf = new_fun.to_fun(id);
                         let mut coeff: Coefficient = 1.into();
-                        for a in out_f.to_fun_view().iter() {
-                            if let AtomView::Mul(m) = a {
-                                if m.has_coefficient() {
+                        for (i, a) in out_f.to_fun_view().iter().enumerate() {
+                            match a {
+                                AtomView::Mul(m)
+                                    if (homogeneous || is_linear_arg(i)) && m.has_coefficient() =>
+                                {
                                     let mut stripped = workspace.new_atom();
                                     let mul = stripped.to_mul();
 
@@ -1028,11 +1547,8 @@ impl AtomView<'_> {
                                     }
 
                                     nf.add_arg(stripped.as_view());
-                                } else {
-                                    nf.add_arg(a);
                                 }
-                            } else {
-                                nf.add_arg(a);
+                                _ => nf.add_arg(a),
                             }
                         }
 
@@ -1042,9 +1558,9 @@ impl AtomView<'_> {
                         return;
                     }
 
-                    for a in out_f.to_fun_view() {
+                    for (i, a) in out_f.to_fun_view().iter().enumerate() {
                         if let AtomView::Num(n) = a {
-                            if n.is_zero() {
+                            if n.is_zero() && (homogeneous || is_linear_arg(i)) {
                                 out.to_num(Coefficient::zero());
                                 return;
                             }
@@ -1196,6 +1712,12 @@ impl AtomView<'_> {
                             // remove power of 1
                             out.set_from_view(&base_handle.as_view());
                             break 'pow_simplify;
+                        } else if matches!(exp_num, CoefficientView::Natural(n, 1) if n > 1)
+                            && base_handle.as_view().grassmann_parity() == Some(true)
+                        {
+                            // a Grassmann-odd element squares to zero
+                            out.to_num(Coefficient::zero());
+                            break 'pow_simplify;
                         } else if let AtomView::Num(n) = base_handle.as_view() {
                             // simplify a number to a numerical power
                             let (new_base_num, new_exp_num) = n.get_coeff_view().pow(&exp_num);
@@ -1259,8 +1781,10 @@ impl AtomView<'_> {
                                 break 'pow_simplify;
                             }
                         } else if let AtomView::Mul(m) = base_handle.as_view() {
-                            // rewrite (x*y)^2 as x^2*y^2
-                            if exp_num.is_integer() {
+                            // rewrite (x*y)^2 as x^2*y^2, unless the order of the factors matters
+                            if exp_num.is_integer()
+                                && m.iter().filter(|x| x.is_noncommutative()).count() < 2
+                            {
                                 let mut mul_h = workspace.new_atom();
                                 let mul = mul_h.to_mul();
                                 for arg in m {
@@ -1274,6 +1798,43 @@ impl AtomView<'_> {
                             }
                         }
                     }
+                    if let AtomView::Pow(p_base) = base_handle.as_view() {
+                        // rewrite (x^y)^z as x^(y*z) for positive x and real y
+                        let (p_base_base, p_base_exp) = p_base.get_base_exp();
+                        if (p_base_base.has_assumptions() || p_base_exp.has_assumptions())
+                            && p_base_base.properties().is_positive()
+                            && p_base_exp.properties().real
+                        {
+                            let mut mul_h = workspace.new_atom();
+                            let mul = mul_h.to_mul();
+                            mul.extend(p_base_exp);
+                            mul.extend(exp_handle.as_view());
+                            let mut exp_h = workspace.new_atom();
+                            mul.as_view().normalize(workspace, &mut exp_h);
+
+                            mul_h.to_pow(p_base_base, exp_h.as_view());
+                            mul_h.as_view().normalize(workspace, out);
+                            break 'pow_simplify;
+                        }
+                    } else if let AtomView::Num(n) = base_handle.as_view() {
+                        // simplify (-1)^n for an even or odd integer n
+                        if n.get_coeff_view() == CoefficientView::Natural(-1, 1)
+                            && exp_handle.as_view().has_assumptions()
+                        {
+                            match exp_handle.as_view().properties().even {
+                                Some(true) => {
+                                    out.to_num(1.into());
+                                    break 'pow_simplify;
+                                }
+                                Some(false) => {
+                                    out.to_num((-1).into());
+                                    break 'pow_simplify;
+                                }
+                                None => {}
+                            }
+                        }
+                    }
+
                     out.to_pow(base_handle.as_view(), exp_handle.as_view());
                 }
 
@@ -1559,9 +2120,12 @@ impl AtomView<'_> {
 #[cfg(test)]
 mod test {
     use crate::{
-        atom::{Atom, AtomCore},
+        atom::{Atom, AtomCore, AtomView, Symbol},
+        coefficient::CoefficientView,
+        domains::float::RealNumberLike,
         parse,
         printer::PrintOptions,
+        symbol, wrap_symbol,
     };
 
     #[test]
@@ -1642,4 +2206,206 @@ mod test {
         let b = parse!("v1").unwrap();
         assert_eq!(a + b, parse!("v1+v2+v3+v4").unwrap());
     }
+
+    #[test]
+    fn noncommutative() {
+        symbol!("nc1"; Noncommutative).unwrap();
+        symbol!("nc2"; Noncommutative).unwrap();
+
+        let a = parse!("nc2*v1*nc1*2*v2").unwrap();
+        assert_eq!(
+            a.to_plain_string(),
+            "2*symbolica::v1*symbolica::v2*symbolica::nc2*symbolica::nc1"
+        );
+        assert_ne!(a, parse!("2*v1*v2*nc1*nc2").unwrap());
+
+        assert_eq!(
+            parse!("nc1*nc1*nc2*nc1").unwrap(),
+            parse!("nc1^2*nc2*nc1").unwrap()
+        );
+        assert_ne!(
+            parse!("(nc1*nc2)^2").unwrap(),
+            parse!("nc1^2*nc2^2").unwrap()
+        );
+        assert_eq!(parse!("(v1*nc1)^2").unwrap(), parse!("v1^2*nc1^2").unwrap());
+        assert_eq!(
+            parse!("nc1*nc2-nc2*nc1+nc1*nc2").unwrap(),
+            parse!("2*nc1*nc2-nc2*nc1").unwrap()
+        );
+    }
+
+    #[test]
+    fn grassmann() {
+        symbol!("gt1"; Grassmann).unwrap();
+        symbol!("gt2"; Grassmann).unwrap();
+        symbol!("gt3"; Grassmann).unwrap();
+
+        assert_eq!(parse!("gt2*gt1").unwrap(), parse!("-gt1*gt2").unwrap());
+        assert_eq!(
+            parse!("gt3*v1*gt2*gt1").unwrap(),
+            parse!("-v1*gt1*gt2*gt3").unwrap()
+        );
+        assert_eq!(parse!("gt1*gt2*gt1").unwrap(), Atom::new_num(0));
+        assert_eq!(parse!("gt1^2").unwrap(), Atom::new_num(0));
+        assert_eq!(parse!("(gt1+gt2)^2").unwrap(), Atom::new_num(0));
+        assert_eq!(parse!("gt1*gt2+gt2*gt1").unwrap(), Atom::new_num(0));
+
+        // inhomogeneous factors keep their order
+        assert_ne!(
+            parse!("gt2*(gt1+v1)").unwrap(),
+            parse!("(gt1+v1)*gt2").unwrap()
+        );
+    }
+
+    #[test]
+    fn extended_attributes() {
+        Symbol::new(wrap_symbol!("fml"))
+            .with_linear_arguments(&[2, 0])
+            .build()
+            .unwrap();
+        symbol!("fid"; Idempotent).unwrap();
+        symbol!("fin"; Involution).unwrap();
+        symbol!("fas"; Associative).unwrap();
+        symbol!("fho"; Homogeneous).unwrap();
+
+        assert_eq!(
+            parse!("fml(v1+v2,v3+v4,2*v5)").unwrap(),
+            parse!("2*fml(v1,v3+v4,v5)+2*fml(v2,v3+v4,v5)").unwrap()
+        );
+        assert_eq!(
+            parse!("fml(v1,3*v2,v3)").unwrap(),
+            parse!("fml(v1,3*v2,v3)").unwrap()
+        );
+        assert_eq!(parse!("fml(v1,v2,0)").unwrap(), Atom::new_num(0));
+        assert_eq!(
+            parse!("fid(fid(fid(v1)))").unwrap(),
+            parse!("fid(v1)").unwrap()
+        );
+        assert_eq!(
+            parse!("fin(fin(fin(v1)))").unwrap(),
+            parse!("fin(v1)").unwrap()
+        );
+        assert_eq!(parse!("fin(fin(v1))").unwrap(), parse!("v1").unwrap());
+        assert_eq!(
+            parse!("fas(v1,fas(v2,fas(v3,v4)))").unwrap(),
+            parse!("fas(v1,v2,v3,v4)").unwrap()
+        );
+        assert_eq!(
+            parse!("fho(2*v1,3*v2,v1+v2)").unwrap(),
+            parse!("6*fho(v1,v2,v1+v2)").unwrap()
+        );
+    }
+
+    #[test]
+    fn composed_normalization_functions() {
+        Symbol::new(wrap_symbol!("fcn"))
+            .with_normalization_function(|view, out| {
+                // fcn(x, x) = fcn(x)
+                if let AtomView::Fun(f) = view {
+                    if f.get_nargs() == 2 {
+                        let mut it = f.iter();
+                        let a = it.next().unwrap();
+                        if a == it.next().unwrap() {
+                            let fun = out.to_fun(f.get_symbol());
+                            fun.add_arg(a);
+                            fun.set_normalized(true);
+                            return true;
+                        }
+                    }
+                }
+                false
+            })
+            .add_normalization_function(|view, out| {
+                // fcn(1) = 0
+                if let AtomView::Fun(f) = view {
+                    if f.get_nargs() == 1 && f.iter().next().unwrap() == Atom::new_num(1).as_view()
+                    {
+                        *out = Atom::new_num(0);
+                        return true;
+                    }
+                }
+                false
+            })
+            .build()
+            .unwrap();
+
+        assert_eq!(parse!("fcn(1,1)").unwrap(), Atom::new_num(0));
+        assert_eq!(parse!("fcn(1)").unwrap(), Atom::new_num(0));
+        assert_eq!(parse!("fcn(v1,v1)").unwrap(), parse!("fcn(v1)").unwrap());
+    }
+
+    #[test]
+    fn special_function_values() {
+        let cases = [
+            ("gamma(5)", "24"),
+            ("gamma(1/2)", "𝜋^(1/2)"),
+            ("gamma(-3/2)", "4/3*𝜋^(1/2)"),
+            ("beta(2,3)", "1/12"),
+            ("zeta(0)", "-1/2"),
+            ("zeta(-3)", "1/120"),
+            ("zeta(4)", "𝜋^4/90"),
+            ("polygamma(2,1)", "-2*zeta(3)"),
+            ("li(3,1)", "zeta(3)"),
+            ("li(2,-1)", "-𝜋^2/12"),
+            ("li(1,v1)", "-log(1-v1)"),
+            ("abs(-3/4)+sign(-2)+heaviside(0)", "1/4"),
+            ("sinh(0)+cosh(0)+arccosh(1)+bessel_j(0,0)", "2"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(parse!(input).unwrap(), parse!(expected).unwrap(), "{input}");
+        }
+
+        // large arguments are kept unevaluated
+        for input in [
+            "gamma(1000000000)",
+            "factorial(100000)",
+            "zeta(1000)",
+            "beta(600,600)",
+        ] {
+            assert!(matches!(parse!(input).unwrap(), Atom::Fun(_)), "{input}");
+        }
+        assert!(matches!(parse!("zeta(100)").unwrap(), Atom::Mul(_)));
+
+        let r = parse!("gamma(1/2*5.0)").unwrap();
+        let AtomView::Num(n) = r.as_view() else {
+            panic!("gamma was not evaluated numerically: {r}");
+        };
+        let CoefficientView::Float(f) = n.get_coeff_view() else {
+            panic!("gamma was not evaluated numerically: {r}");
+        };
+        assert!((f.to_float().to_f64() - 1.329340388179137).abs() < 1e-14);
+    }
+
+    #[test]
+    fn assumptions() {
+        use crate::atom::Assumption;
+
+        Symbol::new(wrap_symbol!("xas"))
+            .with_assumptions(&[Assumption::Positive])
+            .build()
+            .unwrap();
+        Symbol::new(wrap_symbol!("yas"))
+            .with_assumptions(&[Assumption::Real])
+            .build()
+            .unwrap();
+        Symbol::new(wrap_symbol!("nas"))
+            .with_assumptions(&[Assumption::Integer])
+            .build()
+            .unwrap();
+
+        assert_eq!(parse!("sqrt(xas^2)").unwrap(), parse!("xas").unwrap());
+        assert_eq!(parse!("(xas^2)^z").unwrap(), parse!("xas^(2*z)").unwrap());
+        assert_eq!(parse!("sqrt(yas^2)").unwrap(), parse!("abs(yas)").unwrap());
+        assert_eq!(parse!("sqrt(yas^4)").unwrap(), parse!("yas^2").unwrap());
+        assert_eq!(parse!("(-1)^(2*nas)").unwrap(), Atom::new_num(1));
+        assert_eq!(parse!("(-1)^(2*nas+1)").unwrap(), Atom::new_num(-1));
+        assert_eq!(parse!("log(exp(yas))").unwrap(), parse!("yas").unwrap());
+        assert_eq!(parse!("abs(-xas^3)").unwrap(), parse!("xas^3").unwrap());
+
+        // nothing is known about v1
+        assert_ne!(parse!("sqrt(v1^2)").unwrap(), parse!("v1").unwrap());
+        assert_ne!(parse!("(-1)^(2*v1)").unwrap(), Atom::new_num(1));
+        assert_ne!(parse!("log(exp(v1))").unwrap(), parse!("v1").unwrap());
+    }
 }
@@ -5,7 +5,8 @@
 //! will adapt to the function based on the samples added.
 //!
 //! To use multichanneling methods, a [DiscreteGrid] can be used, which contains multiple
-//! [Grid]s that approximate different channels.
+//! [Grid]s that approximate different channels. For channels that have their own phase-space
+//! mapping, a [MultiChannelSampler] can be used.
 //!
 //! # Examples
 //!
@@ -39,10 +40,28 @@
 //! }
 //! ```
 
+pub mod cubature;
+
+use std::time::{Duration, Instant};
+#[cfg(feature = "bincode")]
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
 use rand::{Rng, RngCore, SeedableRng};
 use rand_xoshiro::Xoshiro256StarStar;
+use rayon::{prelude::*, ThreadPool};
 
-use crate::domains::float::{ConstructibleFloat, Real, RealNumberLike};
+use crate::{
+    domains::{
+        finite_field::PrimeIteratorU64,
+        float::{ConstructibleFloat, Real, RealNumberLike},
+    },
+    evaluate::ExpressionEvaluator,
+    LicenseManager,
+};
 
 /// Keep track of statistical quantities, such as the average,
 /// the error and the chi-squared of samples added over multiple
@@ -85,6 +104,14 @@ pub struct StatisticsAccumulator<T: Real + ConstructibleFloat + Copy + RealNumbe
     pub max_eval_negative: T,
     pub max_eval_negative_xs: Option<Sample<T>>,
     pub num_zero_evaluations: usize,
+    replica_start_sum: T,
+    replica_start_samples: usize,
+    replica_sum: T,
+    replica_sum_sq: T,
+    replicas: usize,
+    total_replica_sum: T,
+    total_replica_sum_sq: T,
+    pub total_replicas: usize,
 }
 
 impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> StatisticsAccumulator<T> {
@@ -112,6 +139,14 @@ impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> Statisti
             max_eval_negative: T::new_zero(),
             max_eval_negative_xs: None,
             num_zero_evaluations: 0,
+            replica_start_sum: T::new_zero(),
+            replica_start_samples: 0,
+            replica_sum: T::new_zero(),
+            replica_sum_sq: T::new_zero(),
+            replicas: 0,
+            total_replica_sum: T::new_zero(),
+            total_replica_sum_sq: T::new_zero(),
+            total_replicas: 0,
         }
     }
 
@@ -142,6 +177,14 @@ impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> Statisti
             max_eval_negative: self.max_eval_negative,
             max_eval_negative_xs: None,
             num_zero_evaluations: self.num_zero_evaluations,
+            replica_start_sum: self.replica_start_sum,
+            replica_start_samples: self.replica_start_samples,
+            replica_sum: self.replica_sum,
+            replica_sum_sq: self.replica_sum_sq,
+            replicas: self.replicas,
+            total_replica_sum: self.total_replica_sum,
+            total_replica_sum_sq: self.total_replica_sum_sq,
+            total_replicas: self.total_replicas,
         }
     }
 
@@ -167,6 +210,29 @@ impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> Statisti
         }
     }
 
+    /// End the current randomized quasi-Monte Carlo replica. All samples added since the
+    /// last call form one replica, i.e. one pass over a point set with a fresh random shift
+    /// (see [`PointGenerator::randomize()`]).
+    ///
+    /// If at least two replicas are completed in an iteration, the error of the
+    /// iteration is estimated from the spread of the replica means instead of
+    /// from the variance of the individual samples, which would overestimate the error of
+    /// low-discrepancy point sets. All replicas should contain the same number of samples.
+    pub fn end_replica(&mut self) {
+        let n = self.new_samples - self.replica_start_samples;
+        if n == 0 {
+            return;
+        }
+
+        let mean = (self.sum - self.replica_start_sum) / T::new_from_usize(n);
+        self.replica_sum += mean;
+        self.replica_sum_sq += mean * mean;
+        self.replicas += 1;
+
+        self.replica_start_sum = self.sum;
+        self.replica_start_samples = self.new_samples;
+    }
+
     /// Add the non-processed samples of `other` to non-processed samples of this
     /// accumulator. The non-processed samples are removed from `other`.
     pub fn merge_samples(&mut self, other: &mut StatisticsAccumulator<T>) {
@@ -177,6 +243,11 @@ impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> Statisti
         other.sum_sq = T::new_zero();
         other.new_samples = 0;
         other.new_zero_evaluations = 0;
+        other.replica_start_sum = T::new_zero();
+        other.replica_start_samples = 0;
+        other.replica_sum = T::new_zero();
+        other.replica_sum_sq = T::new_zero();
+        other.replicas = 0;
     }
 
     /// Add the non-processed samples of `other` to non-processed samples of this
@@ -187,6 +258,13 @@ impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> Statisti
         self.new_samples += other.new_samples;
         self.new_zero_evaluations += other.new_zero_evaluations;
 
+        // the unfinished replica of `other` joins the unfinished replica of `self`
+        self.replica_start_sum += &other.replica_start_sum;
+        self.replica_start_samples += other.replica_start_samples;
+        self.replica_sum += &other.replica_sum;
+        self.replica_sum_sq += &other.replica_sum_sq;
+        self.replicas += other.replicas;
+
         if other.max_eval_positive > self.max_eval_positive {
             self.max_eval_positive = other.max_eval_positive;
             self.max_eval_positive_xs
@@ -205,6 +283,10 @@ impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> Statisti
     ///
     /// When `weighted_average=True`, a weighted average and error is computed using
     /// the iteration variances as a weight.
+    ///
+    /// If replicas were recorded with [`Self::end_replica()`], the variances are
+    /// estimated from the replica means. Samples of an unfinished replica
+    /// only contribute to the average.
     pub fn update_iter(&mut self, weighted_average: bool) -> bool {
         // TODO: we could be throwing away events that are very rare
         if self.new_samples < 2 {
@@ -212,16 +294,33 @@ impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> Statisti
             return false;
         }
 
+        let replica_variance = if self.replicas > 1 {
+            let r = T::new_from_usize(self.replicas);
+            let mean = self.replica_sum / r;
+            Some((self.replica_sum_sq / r - mean * mean) / (r - T::new_one()))
+        } else {
+            None
+        };
+
         self.processed_samples += self.new_samples;
         self.num_zero_evaluations += self.new_zero_evaluations;
         let n = T::new_from_usize(self.new_samples);
         self.total_sum += self.sum;
         self.total_sum_sq += self.sum_sq;
+        if replica_variance.is_some() {
+            self.total_replica_sum += self.replica_sum;
+            self.total_replica_sum_sq += self.replica_sum_sq;
+            self.total_replicas += self.replicas;
+        }
         self.sum /= n;
         self.sum_sq /= n;
         let mut w = self.sum_sq.sqrt();
 
-        w = ((w + self.sum) * (w - self.sum)) / (n - T::new_one());
+        w = if let Some(v) = replica_variance {
+            v
+        } else {
+            ((w + self.sum) * (w - self.sum)) / (n - T::new_one())
+        };
         if w == T::new_zero() {
             // all sampled points are the same
             // set the weight to a large number
@@ -240,7 +339,16 @@ impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> Statisti
         } else {
             let n = T::new_from_usize(self.processed_samples);
             self.avg = self.total_sum / n;
-            self.err = ((self.total_sum_sq / n - self.avg * self.avg) / (n - T::new_one())).sqrt();
+
+            if self.total_replicas > 1 {
+                let r = T::new_from_usize(self.total_replicas);
+                let mean = self.total_replica_sum / r;
+                self.err =
+                    ((self.total_replica_sum_sq / r - mean * mean) / (r - T::new_one())).sqrt();
+            } else {
+                self.err =
+                    ((self.total_sum_sq / n - self.avg * self.avg) / (n - T::new_one())).sqrt();
+            }
         }
 
         if self.cur_iter == 0 {
@@ -256,6 +364,11 @@ impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> Statisti
         self.sum_sq = T::new_zero();
         self.new_samples = 0;
         self.new_zero_evaluations = 0;
+        self.replica_start_sum = T::new_zero();
+        self.replica_start_samples = 0;
+        self.replica_sum = T::new_zero();
+        self.replica_sum_sq = T::new_zero();
+        self.replicas = 0;
         self.cur_iter += 1;
 
         true
@@ -542,6 +655,15 @@ impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> Grid<T>
             Grid::Discrete(g) => &g.accumulator,
         }
     }
+
+    /// End the current randomized quasi-Monte Carlo replica of this grid.
+    /// See [`StatisticsAccumulator::end_replica()`].
+    pub fn end_replica(&mut self) {
+        match self {
+            Grid::Continuous(g) => g.accumulator.end_replica(),
+            Grid::Discrete(g) => g.accumulator.end_replica(),
+        }
+    }
 }
 /// A bin of a discrete grid, which may contain a subgrid.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -1039,6 +1161,11 @@ impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> Continuo
 
         if self.counter.iter().sum::<usize>() < self.min_samples_for_update {
             // do not train the grid if there is a lack of samples
+            // the samples are kept in the importance and counter only, so that
+            // they are not counted again at the next update
+            self.bin_accumulator.clear();
+            self.bin_accumulator
+                .resize(self.partitioning.len() - 1, StatisticsAccumulator::new());
             return;
         }
 
@@ -1167,91 +1294,1898 @@ impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> Continuo
     }
 }
 
-/// A reproducible, fast, non-cryptographic random number generator suitable for parallel Monte Carlo simulations.
-/// A `seed` has to be set, which can be any `u64` number (small numbers work just as well as large numbers).
+/// A phase-space mapping of a channel of a [MultiChannelSampler], which maps points
+/// from the unit hypercube to the integration domain.
+pub trait ChannelMapping<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd>:
+    Send + Sync
+{
+    /// Get the number of random numbers that are needed to generate a point.
+    fn get_dimension(&self) -> usize;
+    /// Map the random numbers `r` to a point in the integration domain, writing the
+    /// result in `point`, and return the density of this channel at that point, i.e.
+    /// the inverse of the Jacobian of the mapping.
+    fn map(&self, r: &[T], point: &mut Vec<T>) -> T;
+    /// Get the density with which this channel generates `point`. The density
+    /// must be zero if the point cannot be generated by this channel.
+    fn density(&self, point: &[T]) -> T;
+}
+
+/// A sample point of a [MultiChannelSampler].
+#[derive(Debug, Clone)]
+pub struct MultiChannelSample<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> {
+    /// The weight of the sample, which is the inverse of the combined density.
+    pub weight: T,
+    /// The channel that generated the sample.
+    pub channel: usize,
+    /// The point in the integration domain.
+    pub point: Vec<T>,
+    /// The density of every channel at the point.
+    pub densities: Vec<T>,
+    random: Vec<T>,
+}
+
+impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> Default
+    for MultiChannelSample<T>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> MultiChannelSample<T> {
+    /// Create a new empty sample.
+    pub fn new() -> MultiChannelSample<T> {
+        MultiChannelSample {
+            weight: T::new_zero(),
+            channel: 0,
+            point: vec![],
+            densities: vec![],
+            random: vec![],
+        }
+    }
+}
+
+/// A multichannel sampler, where every channel has its own phase-space mapping.
+/// A point is generated by selecting a channel `i` with probability `α_i` and mapping
+/// random numbers with the mapping of that channel. The weight of the point is
+/// the inverse of the combined density `g(x) = Σ_i α_i g_i(x)` of all channels,
+/// so that every channel contributes to every point.
 ///
-/// Each thread or instance generating samples should use the same `seed` but a different `stream_id`,
-/// which is an instance counter starting at 0.
-pub struct MonteCarloRng {
-    state: Xoshiro256StarStar,
+/// The a-priori channel weights `α_i` are optimized after every iteration to
+/// minimize the variance, following Kleiss and Pittau.
+///
+/// # Examples
+///
+/// ```
+/// use symbolica::numerical_integration::{
+///     ChannelMapping, MonteCarloRng, MultiChannelSample, MultiChannelSampler,
+/// };
+///
+/// /// Sample `x^p` on `[0, 1]`.
+/// struct Power(f64);
+///
+/// impl ChannelMapping<f64> for Power {
+///     fn get_dimension(&self) -> usize {
+///         1
+///     }
+///
+///     fn map(&self, r: &[f64], point: &mut Vec<f64>) -> f64 {
+///         point.clear();
+///         let x = r[0].powf(1. / (self.0 + 1.));
+///         point.push(x);
+///         self.density(point)
+///     }
+///
+///     fn density(&self, point: &[f64]) -> f64 {
+///         (self.0 + 1.) * point[0].powf(self.0)
+///     }
+/// }
+///
+/// let mut sampler = MultiChannelSampler::new(vec![Box::new(Power(0.)), Box::new(Power(4.))], 1e-3);
+/// let mut rng = MonteCarloRng::new(0, 0);
+/// let mut sample = MultiChannelSample::new();
+/// for _ in 0..10 {
+///     for _ in 0..10_000 {
+///         sampler.sample(&mut rng, &mut sample);
+///         let x = sample.point[0];
+///         sampler.add_training_sample(&sample, 1. + x.powi(4)).unwrap();
+///     }
+///     sampler.update(0.5);
+/// }
+///
+/// let stats = sampler.get_statistics();
+/// assert!((stats.avg - 1.2).abs() < 5. * stats.err);
+/// ```
+pub struct MultiChannelSampler<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> {
+    channels: Vec<Box<dyn ChannelMapping<T>>>,
+    channel_weights: Vec<T>,
+    min_channel_weight: T,
+    variance_estimates: Vec<T>,
+    channel_samples: Vec<usize>,
+    pub accumulator: StatisticsAccumulator<T>,
 }
 
-impl RngCore for MonteCarloRng {
-    #[inline]
-    fn next_u32(&mut self) -> u32 {
-        self.state.next_u32()
+impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> MultiChannelSampler<T> {
+    /// Create a new multichannel sampler with the given channels, which start with equal
+    /// a-priori weights. The weight of a channel will never drop
+    /// below `min_channel_weight` times the uniform weight, so that every channel
+    /// keeps being sampled.
+    pub fn new(
+        channels: Vec<Box<dyn ChannelMapping<T>>>,
+        min_channel_weight: T,
+    ) -> MultiChannelSampler<T> {
+        let n = channels.len();
+        assert!(n > 0, "A multichannel sampler needs at least one channel");
+
+        MultiChannelSampler {
+            channels,
+            channel_weights: vec![T::new_from_usize(n).inv(); n],
+            min_channel_weight,
+            variance_estimates: vec![T::new_zero(); n],
+            channel_samples: vec![0; n],
+            accumulator: StatisticsAccumulator::new(),
+        }
     }
 
-    #[inline]
-    fn next_u64(&mut self) -> u64 {
-        self.state.next_u64()
+    /// Get the a-priori weights of the channels.
+    pub fn get_channel_weights(&self) -> &[T] {
+        &self.channel_weights
     }
 
-    #[inline]
-    fn fill_bytes(&mut self, dest: &mut [u8]) {
-        self.state.fill_bytes(dest)
+    /// Set the a-priori weights of the channels. The weights are normalized.
+    pub fn set_channel_weights(&mut self, weights: &[T]) -> Result<(), String> {
+        if weights.len() != self.channels.len() {
+            return Err(format!(
+                "Expected {} channel weights, got {}",
+                self.channels.len(),
+                weights.len()
+            ));
+        }
+
+        let mut sum = T::new_zero();
+        for w in weights {
+            if *w < T::new_zero() {
+                return Err("Channel weights must be non-negative".to_owned());
+            }
+            sum += w;
+        }
+
+        if sum == T::new_zero() {
+            return Err("At least one channel weight must be positive".to_owned());
+        }
+
+        for (c, w) in self.channel_weights.iter_mut().zip(weights) {
+            *c = *w / sum;
+        }
+        Ok(())
     }
-}
 
-impl MonteCarloRng {
-    /// Create a new random number generator with a given `seed` and `stream_id`. For parallel runs,
-    /// each thread or instance generating samples should use the same `seed` but a different `stream_id`.
-    pub fn new(seed: u64, stream_id: usize) -> Self {
-        let mut state = Xoshiro256StarStar::seed_from_u64(seed);
-        for _ in 0..stream_id {
-            state.jump();
+    /// Get the statistics of this sampler.
+    pub fn get_statistics(&self) -> &StatisticsAccumulator<T> {
+        &self.accumulator
+    }
+
+    /// Sample a point, writing the result in `sample`.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R, sample: &mut MultiChannelSample<T>) {
+        let r: T = T::new_sample_unit(rng);
+        let mut cumulative = T::new_zero();
+        sample.channel = self.channels.len() - 1;
+        for (i, w) in self.channel_weights.iter().enumerate() {
+            cumulative += w;
+            if r < cumulative {
+                sample.channel = i;
+                break;
+            }
         }
 
-        Self { state }
+        let channel = &self.channels[sample.channel];
+        sample.random.clear();
+        for _ in 0..channel.get_dimension() {
+            sample.random.push(T::new_sample_unit(rng));
+        }
+
+        let g = channel.map(&sample.random, &mut sample.point);
+
+        sample.densities.clear();
+        let mut total = T::new_zero();
+        for (i, (c, w)) in self.channels.iter().zip(&self.channel_weights).enumerate() {
+            let d = if i == sample.channel {
+                g
+            } else {
+                c.density(&sample.point)
+            };
+            total += *w * d;
+            sample.densities.push(d);
+        }
+
+        sample.weight = if total == T::new_zero() {
+            T::new_zero()
+        } else {
+            total.inv()
+        };
     }
-}
 
-#[cfg(test)]
-mod test {
-    use std::f64::consts::PI;
+    /// Add a sample point and its corresponding evaluation `eval` as training.
+    pub fn add_training_sample(
+        &mut self,
+        sample: &MultiChannelSample<T>,
+        eval: T,
+    ) -> Result<(), String> {
+        if !eval.is_finite() {
+            return Err(format!(
+                "Added training sample that is not finite: sample={:?}, fx={}",
+                sample, eval
+            ));
+        }
 
-    use super::{ContinuousGrid, DiscreteGrid, Grid, MonteCarloRng, Sample};
+        let w = eval * sample.weight;
+        self.accumulator.add_sample(w, None);
 
-    #[test]
-    fn multichannel() {
-        // Integrate x*pi + x^2 using multi-channeling:
-        // x*pi and x^2 will have their own Vegas grid
-        let fs = [|x: f64| (x * PI).sin(), |x: f64| x * x];
+        // estimate W_i = ∫ g_i f^2 / g^2 dx, the derivative of the variance with respect to α_i
+        let w_sq = w * w * sample.weight;
+        for (v, d) in self.variance_estimates.iter_mut().zip(&sample.densities) {
+            *v += w_sq * d;
+        }
+        self.channel_samples[sample.channel] += 1;
 
-        let mut grid = DiscreteGrid::new(
-            vec![
-                Some(Grid::Continuous(ContinuousGrid::new(
-                    1, 10, 1000, None, false,
-                ))),
-                Some(Grid::Continuous(ContinuousGrid::new(
-                    1, 10, 1000, None, false,
-                ))),
-            ],
-            0.01,
-            false,
-        );
+        Ok(())
+    }
 
-        let mut rng = MonteCarloRng::new(0, 0);
+    /// Merge the samples of a sampler with the same channels.
+    pub fn merge(&mut self, other: &MultiChannelSampler<T>) -> Result<(), String> {
+        if self.channels.len() != other.channels.len()
+            || self.channel_weights != other.channel_weights
+        {
+            return Err("Cannot merge samplers with different channels".to_owned());
+        }
 
-        let mut sample = Sample::new();
-        for _ in 1..20 {
-            // sample 10_000 times per iteration
-            for _ in 0..10_000 {
-                grid.sample(&mut rng, &mut sample);
+        self.accumulator.merge_samples_no_reset(&other.accumulator);
+        for (v, o) in self
+            .variance_estimates
+            .iter_mut()
+            .zip(&other.variance_estimates)
+        {
+            *v += o;
+        }
+        for (c, o) in self.channel_samples.iter_mut().zip(&other.channel_samples) {
+            *c += o;
+        }
+        Ok(())
+    }
 
-                if let Sample::Discrete(_weight, i, cont_sample) = &sample {
-                    if let Sample::Continuous(_cont_weight, xs) =
-                        cont_sample.as_ref().unwrap().as_ref()
-                    {
-                        grid.add_training_sample(&sample, fs[*i](xs[0])).unwrap();
-                    }
+    /// Update the a-priori channel weights based on the added training samples
+    /// using `α_i → α_i W_i^β`, where `W_i` is the contribution of channel `i` to the variance and
+    /// `β` is the `learning_rate`. If it is set to `0`, no training will be performed.
+    pub fn update(&mut self, learning_rate: T) {
+        let n_samples = self.accumulator.new_samples;
+        self.accumulator.update_iter(false);
+
+        if !learning_rate.is_zero()
+            && n_samples > 0
+            && self.variance_estimates.iter().any(|v| !v.is_zero())
+        {
+            let n = T::new_from_usize(n_samples);
+            let mut sum = T::new_zero();
+            for (a, v) in self
+                .channel_weights
+                .iter_mut()
+                .zip(&self.variance_estimates)
+            {
+                *a *= (*v / n).powf(&learning_rate);
+                sum += *a;
+            }
+
+            let min_weight = self.min_channel_weight / T::new_from_usize(self.channels.len());
+            let mut clamped_sum = T::new_zero();
+            for a in &mut self.channel_weights {
+                *a /= sum;
+                if *a < min_weight {
+                    *a = min_weight;
                 }
+                clamped_sum += *a;
             }
 
-            grid.update(1.5, 1.5);
+            for a in &mut self.channel_weights {
+                *a /= clamped_sum;
+            }
         }
 
-        assert_eq!(grid.accumulator.avg, 0.9718412953459551);
-        assert_eq!(grid.accumulator.err, 0.0009349254838085983)
+        for v in &mut self.variance_estimates {
+            *v = T::new_zero();
+        }
+        for c in &mut self.channel_samples {
+            *c = 0;
+        }
+    }
+
+    /// Get the number of samples that each channel generated in the current iteration.
+    pub fn get_channel_samples(&self) -> &[usize] {
+        &self.channel_samples
+    }
+}
+
+/// A generator of unweighted events from a trained [Grid], using acceptance-rejection
+/// sampling with respect to the largest absolute value `w_max` of the maximal positive and negative
+/// weighted evaluations.
+///
+/// An accepted event has weight `1` or `-1`, depending on the sign of the weighted
+/// evaluation. If the weighted evaluation exceeds the maximum, the event is accepted with
+/// weight `w / w_max` and it is counted as overweight.
+///
+/// # Examples
+///
+/// ```
+/// use symbolica::numerical_integration::{ContinuousGrid, EventGenerator, Grid, MonteCarloRng, Sample};
+///
+/// let f = |s: &Sample<f64>| match s {
+///     Sample::Continuous(_, xs) => 2. * xs[0],
+///     _ => unreachable!(),
+/// };
+///
+/// let mut grid = Grid::Continuous(ContinuousGrid::new(1, 16, 100, None, false));
+/// let mut rng = MonteCarloRng::new(0, 0);
+/// let mut sample = Sample::new();
+/// for _ in 0..5 {
+///     for _ in 0..10_000 {
+///         grid.sample(&mut rng, &mut sample);
+///         grid.add_training_sample(&sample, f(&sample)).unwrap();
+///     }
+///     grid.update(1.5, 1.5);
+/// }
+///
+/// let mut generator = EventGenerator::new(grid.get_statistics());
+/// for _ in 0..100 {
+///     let weight = generator.generate(&mut grid, &mut rng, f, &mut sample).unwrap();
+///     assert!(weight >= 1.);
+/// }
+/// assert!(generator.get_efficiency() > 0.5);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+#[derive(Debug, Clone)]
+pub struct EventGenerator<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> {
+    max_eval_positive: T,
+    max_eval_negative: T,
+    /// The maximal number of trials for a single event.
+    pub max_trials: usize,
+    /// The number of generated samples.
+    pub trials: usize,
+    /// The number of accepted events.
+    pub accepted: usize,
+    /// The number of accepted events whose weight exceeded the maximum.
+    pub overweight: usize,
+}
+
+impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> EventGenerator<T> {
+    /// Create an event generator that uses the maximal weighted evaluations that
+    /// are tracked by the statistics accumulator of a trained grid.
+    pub fn new(statistics: &StatisticsAccumulator<T>) -> EventGenerator<T> {
+        Self::with_max_weights(statistics.max_eval_positive, statistics.max_eval_negative)
+    }
+
+    /// Create an event generator with a maximal positive weight `max_eval_positive`
+    /// and a maximal negative weight `max_eval_negative`, which should not be positive.
+    pub fn with_max_weights(max_eval_positive: T, max_eval_negative: T) -> EventGenerator<T> {
+        EventGenerator {
+            max_eval_positive,
+            max_eval_negative,
+            max_trials: 1_000_000,
+            trials: 0,
+            accepted: 0,
+            overweight: 0,
+        }
+    }
+
+    /// Get the weight that normalizes the events, or `None` if the maximal weights
+    /// are zero or not finite, for example because the grid was not trained.
+    fn get_max_weight(&self) -> Option<T> {
+        // positive and negative events must have the same normalization
+        let max = if self.max_eval_positive > -self.max_eval_negative {
+            self.max_eval_positive
+        } else {
+            -self.max_eval_negative
+        };
+
+        if max > T::new_zero() && max.is_finite() {
+            Some(max)
+        } else {
+            None
+        }
+    }
+
+    /// Decide if a sample with weighted evaluation `weighted_eval`, i.e. the evaluation times
+    /// the weight of the sample, is accepted as an event. The weight of the event is returned
+    /// if it is accepted. No event is accepted if the maximal weights are zero or not finite.
+    pub fn accept<R: Rng + ?Sized>(&mut self, weighted_eval: T, rng: &mut R) -> Option<T> {
+        self.trials += 1;
+
+        if weighted_eval == T::new_zero() {
+            return None;
+        }
+
+        let max = self.get_max_weight()?;
+        let ratio = weighted_eval / max;
+        let abs_ratio = ratio.norm();
+
+        if abs_ratio > T::new_one() {
+            self.accepted += 1;
+            self.overweight += 1;
+            return Some(ratio);
+        }
+
+        if T::new_sample_unit(rng) < abs_ratio {
+            self.accepted += 1;
+            if ratio > T::new_zero() {
+                Some(T::new_one())
+            } else {
+                Some(-T::new_one())
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Sample the grid until an event of the integrand `f` is accepted. The event is written
+    /// in `sample` and its weight is returned. If no event is accepted within
+    /// `max_trials` samples, or if the maximal weights are zero or not finite,
+    /// `None` is returned.
+    pub fn generate<R: Rng + ?Sized, F: FnMut(&Sample<T>) -> T>(
+        &mut self,
+        grid: &mut Grid<T>,
+        rng: &mut R,
+        mut f: F,
+        sample: &mut Sample<T>,
+    ) -> Option<T> {
+        self.get_max_weight()?;
+
+        for _ in 0..self.max_trials {
+            grid.sample(rng, sample);
+            let weighted_eval = f(sample) * sample.get_weight();
+            if let Some(w) = self.accept(weighted_eval, rng) {
+                return Some(w);
+            }
+        }
+
+        None
+    }
+
+    /// Get the fraction of samples that were accepted.
+    pub fn get_efficiency(&self) -> f64 {
+        if self.trials == 0 {
+            0.
+        } else {
+            self.accepted as f64 / self.trials as f64
+        }
+    }
+}
+
+/// A histogram of an observable, whose bins accumulate the weighted evaluations of the samples
+/// during integration. The value of a bin is its contribution to the integral.
+///
+/// Every bin is a [StatisticsAccumulator], so that the error of every bin is estimated
+/// per sample, including the samples that do not land in the bin. Multiple entries can be
+/// filled for one sample with [`Histogram::fill()`], after which the sample is completed with
+/// [`Histogram::end_sample()`]. Histograms that are filled in different threads
+/// can be merged with [`Histogram::merge()`].
+///
+/// # Examples
+///
+/// ```
+/// use symbolica::numerical_integration::{ContinuousGrid, Grid, Histogram, MonteCarloRng, Sample};
+///
+/// let mut grid = Grid::Continuous(ContinuousGrid::new(1, 16, 100, None, false));
+/// let mut histogram = Histogram::uniform("x", 0., 1., 4).unwrap();
+/// let mut rng = MonteCarloRng::new(0, 0);
+/// let mut sample = Sample::new();
+/// for _ in 0..10_000 {
+///     grid.sample(&mut rng, &mut sample);
+///     if let Sample::Continuous(_, xs) = &sample {
+///         let eval = 2. * xs[0];
+///         grid.add_training_sample(&sample, eval).unwrap();
+///         histogram.add_sample(xs[0], &sample, eval);
+///     }
+/// }
+/// grid.update(1.5, 1.5);
+/// histogram.update();
+///
+/// assert!((histogram.bins[0].avg - 1. / 16.).abs() < 5. * histogram.bins[0].err);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+#[derive(Debug, Clone)]
+pub struct Histogram<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> {
+    pub name: String,
+    pub edges: Vec<T>,
+    pub bins: Vec<StatisticsAccumulator<T>>,
+    pub underflow: StatisticsAccumulator<T>,
+    pub overflow: StatisticsAccumulator<T>,
+    sample_values: Vec<T>,
+    touched_bins: Vec<usize>,
+    new_samples: usize,
+}
+
+impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> Histogram<T> {
+    /// Create a new histogram with bins delimited by the increasing `edges`.
+    pub fn new(name: &str, edges: Vec<T>) -> Result<Histogram<T>, String> {
+        if edges.len() < 2 {
+            return Err("A histogram needs at least two edges".to_owned());
+        }
+
+        if edges.iter().any(|e| !e.is_finite()) {
+            return Err("The edges of a histogram must be finite".to_owned());
+        }
+
+        if edges.windows(2).any(|w| w[0] >= w[1]) {
+            return Err("The edges of a histogram must be increasing".to_owned());
+        }
+
+        let n_bins = edges.len() - 1;
+        Ok(Histogram {
+            name: name.to_owned(),
+            edges,
+            bins: vec![StatisticsAccumulator::new(); n_bins],
+            underflow: StatisticsAccumulator::new(),
+            overflow: StatisticsAccumulator::new(),
+            sample_values: vec![T::new_zero(); n_bins + 2],
+            touched_bins: vec![],
+            new_samples: 0,
+        })
+    }
+
+    /// Create a new histogram with `n_bins` bins of equal size between `min` and `max`.
+    pub fn uniform(name: &str, min: T, max: T, n_bins: usize) -> Result<Histogram<T>, String> {
+        if n_bins == 0 {
+            return Err("A histogram needs at least one bin".to_owned());
+        }
+
+        let width = (max - min) / T::new_from_usize(n_bins);
+        let edges = (0..=n_bins)
+            .map(|i| {
+                if i == n_bins {
+                    max
+                } else {
+                    min + width * T::new_from_usize(i)
+                }
+            })
+            .collect();
+        Self::new(name, edges)
+    }
+
+    /// Add an entry with observable value `x` and weight `weight`, i.e. the evaluation of the
+    /// integrand times the weight of the sample, to the current sample.
+    pub fn fill(&mut self, x: T, weight: T) {
+        let index = if x < self.edges[0] {
+            0
+        } else if x >= self.edges[self.edges.len() - 1] {
+            self.edges.len()
+        } else {
+            self.edges.partition_point(|e| *e <= x)
+        };
+
+        if !self.touched_bins.contains(&index) {
+            self.touched_bins.push(index);
+        }
+        self.sample_values[index] += weight;
+    }
+
+    /// Complete the current sample.
+    pub fn end_sample(&mut self) {
+        for &i in &self.touched_bins {
+            let v = self.sample_values[i];
+            self.sample_values[i] = T::new_zero();
+
+            let acc = if i == 0 {
+                &mut self.underflow
+            } else if i == self.edges.len() {
+                &mut self.overflow
+            } else {
+                &mut self.bins[i - 1]
+            };
+            acc.add_sample(v, None);
+        }
+        self.touched_bins.clear();
+        self.new_samples += 1;
+    }
+
+    /// Add a single entry with observable value `x` for the sample `sample` whose
+    /// integrand evaluates to `eval`, and complete the sample.
+    pub fn add_sample(&mut self, x: T, sample: &Sample<T>, eval: T) {
+        self.fill(x, eval * sample.get_weight());
+        self.end_sample();
+    }
+
+    /// Process the samples of the current iteration and update the average
+    /// and error of every bin.
+    pub fn update(&mut self) {
+        let n = self.new_samples;
+        if n < 2 {
+            return;
+        }
+
+        for acc in self
+            .bins
+            .iter_mut()
+            .chain([&mut self.underflow, &mut self.overflow])
+        {
+            // samples that did not land in the bin contribute zero
+            acc.new_zero_evaluations += n - acc.new_samples;
+            acc.new_samples = n;
+            acc.update_iter(false);
+        }
+        self.new_samples = 0;
+    }
+
+    /// Merge the non-processed samples of a histogram with the same bins.
+    pub fn merge(&mut self, other: &Histogram<T>) -> Result<(), String> {
+        if self.edges != other.edges {
+            return Err("Cannot merge histograms with different bins".to_owned());
+        }
+
+        for (b, o) in self.bins.iter_mut().zip(&other.bins) {
+            b.merge_samples_no_reset(o);
+        }
+        self.underflow.merge_samples_no_reset(&other.underflow);
+        self.overflow.merge_samples_no_reset(&other.overflow);
+        self.new_samples += other.new_samples;
+        Ok(())
+    }
+
+    /// Export the histogram in a YODA-like format, with the lower and upper edge,
+    /// the value and the error of every bin.
+    pub fn to_yoda(&self) -> String {
+        let mut out = format!(
+            "BEGIN HISTO1D /{}\nPath: /{}\nUnderflow: {:e}\t{:e}\nOverflow: {:e}\t{:e}\n# xlow\txhigh\tval\terr\n",
+            self.name,
+            self.name,
+            self.underflow.avg.to_f64(),
+            self.underflow.err.to_f64(),
+            self.overflow.avg.to_f64(),
+            self.overflow.err.to_f64()
+        );
+
+        for (e, b) in self.edges.windows(2).zip(&self.bins) {
+            out += &format!(
+                "{:e}\t{:e}\t{:e}\t{:e}\n",
+                e[0].to_f64(),
+                e[1].to_f64(),
+                b.avg.to_f64(),
+                b.err.to_f64()
+            );
+        }
+
+        out += "END HISTO1D\n";
+        out
+    }
+
+    /// Export the histogram as CSV, with the lower and upper edge, the
+    /// value and the error of every bin.
+    pub fn to_csv(&self) -> String {
+        let mut out = "xlow,xhigh,value,error\n".to_owned();
+        for (e, b) in self.edges.windows(2).zip(&self.bins) {
+            out += &format!(
+                "{:e},{:e},{:e},{:e}\n",
+                e[0].to_f64(),
+                e[1].to_f64(),
+                b.avg.to_f64(),
+                b.err.to_f64()
+            );
+        }
+        out
+    }
+}
+
+/// A reproducible, fast, non-cryptographic random number generator suitable for parallel Monte Carlo simulations.
+/// A `seed` has to be set, which can be any `u64` number (small numbers work just as well as large numbers).
+///
+/// Each thread or instance generating samples should use the same `seed` but a different `stream_id`,
+/// which is an instance counter starting at 0.
+pub struct MonteCarloRng {
+    state: Xoshiro256StarStar,
+}
+
+impl RngCore for MonteCarloRng {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        self.state.next_u32()
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        self.state.next_u64()
+    }
+
+    #[inline]
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.state.fill_bytes(dest)
+    }
+}
+
+impl MonteCarloRng {
+    /// Create a new random number generator with a given `seed` and `stream_id`. For parallel runs,
+    /// each thread or instance generating samples should use the same `seed` but a different `stream_id`.
+    pub fn new(seed: u64, stream_id: usize) -> Self {
+        let mut state = Xoshiro256StarStar::seed_from_u64(seed);
+        for _ in 0..stream_id {
+            state.jump();
+        }
+
+        Self { state }
+    }
+}
+
+/// A generator of (low-discrepancy) points in the unit hypercube `[0,1)^d`.
+///
+/// A point generator can be used to sample a [ContinuousGrid] through
+/// the [QuasiMonteCarloRng] adapter.
+pub trait PointGenerator {
+    /// Get the dimension of the generated points.
+    fn get_dimension(&self) -> usize;
+    /// Write the next point of the sequence into `point`, which must have
+    /// the length of the dimension.
+    fn next_point(&mut self, point: &mut [f64]);
+    /// Restart the sequence with a new random shift drawn from `rng`.
+    /// Every randomization yields an independent, unbiased replica of the point set.
+    fn randomize<R: Rng + ?Sized>(&mut self, rng: &mut R);
+}
+
+/// Joe–Kuo direction numbers (`new-joe-kuo-6.21201`) for dimensions 2 to 21, in
+/// the format `d s a m_1 ... m_s`.
+const JOE_KUO_DIRECTION_NUMBERS: &str = "d s a m_i
+2 1 0 1
+3 2 1 1 3
+4 3 1 1 3 1
+5 3 2 1 1 1
+6 4 1 1 1 3 3
+7 4 4 1 3 5 13
+8 5 2 1 1 5 5 17
+9 5 4 1 1 5 5 5
+10 5 7 1 1 7 11 19
+11 5 11 1 1 5 1 1
+12 5 13 1 1 1 3 11
+13 5 14 1 3 5 5 31
+14 6 1 1 3 3 9 7 49
+15 6 13 1 1 1 15 21 21
+16 6 16 1 3 1 13 27 49
+17 6 19 1 1 1 15 7 5
+18 6 22 1 3 1 15 13 25
+19 6 25 1 1 5 5 19 61
+20 7 1 1 3 7 11 23 15 103
+21 7 4 1 3 7 13 13 15 69";
+
+/// A Sobol' sequence with Joe–Kuo direction numbers, generated in Gray code order.
+/// The sequence can be randomized with a random digital shift.
+///
+/// # Examples
+///
+/// ```
+/// use symbolica::numerical_integration::{PointGenerator, SobolSequence};
+///
+/// let mut sobol = SobolSequence::new(2).unwrap();
+/// let mut p = [0.; 2];
+/// sobol.next_point(&mut p);
+/// sobol.next_point(&mut p);
+/// assert_eq!(p, [0.5, 0.5]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SobolSequence {
+    direction_numbers: Vec<[u32; 32]>,
+    state: Vec<u32>,
+    shift: Vec<u32>,
+    index: u64,
+}
+
+impl SobolSequence {
+    /// Create a Sobol' sequence in `dimension` dimensions, using the built-in
+    /// direction numbers. At most 21 dimensions are supported; use
+    /// [`Self::with_direction_numbers()`] for higher dimensions.
+    pub fn new(dimension: usize) -> Result<SobolSequence, String> {
+        Self::with_direction_numbers(dimension, JOE_KUO_DIRECTION_NUMBERS)
+    }
+
+    /// Create a Sobol' sequence in `dimension` dimensions using direction
+    /// numbers in the format of the Joe–Kuo files, i.e. one line `d s a m_1 ... m_s`
+    /// per dimension, starting at dimension 2. Lines that do not start with a number
+    /// are skipped.
+    pub fn with_direction_numbers(
+        dimension: usize,
+        direction_numbers: &str,
+    ) -> Result<SobolSequence, String> {
+        if dimension == 0 {
+            return Err("The dimension of a Sobol sequence must be positive".to_owned());
+        }
+
+        // the first dimension is the van der Corput sequence
+        let mut v = vec![std::array::from_fn(|i| 1 << (31 - i))];
+
+        for line in direction_numbers.lines() {
+            if v.len() == dimension {
+                break;
+            }
+
+            let mut tokens = line.split_whitespace();
+            if tokens
+                .next()
+                .and_then(|d| d.parse::<usize>().ok())
+                .is_none()
+            {
+                continue;
+            }
+
+            let nums = tokens
+                .map(|t| t.parse::<u32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Invalid direction number in '{}': {}", line, e))?;
+
+            if nums.len() < 2 || nums[0] == 0 || nums[0] > 31 || nums.len() != nums[0] as usize + 2
+            {
+                return Err(format!("Invalid direction number line: '{}'", line));
+            }
+
+            let (s, a, m) = (nums[0] as usize, nums[1], &nums[2..]);
+
+            let mut d = [0u32; 32];
+            for i in 0..32 {
+                if i < s {
+                    d[i] = m[i] << (31 - i);
+                } else {
+                    d[i] = d[i - s] ^ (d[i - s] >> s);
+                    for k in 1..s {
+                        if (a >> (s - 1 - k)) & 1 == 1 {
+                            d[i] ^= d[i - k];
+                        }
+                    }
+                }
+            }
+            v.push(d);
+        }
+
+        if v.len() < dimension {
+            return Err(format!(
+                "Not enough direction numbers for {} dimensions: only {} are available",
+                dimension,
+                v.len()
+            ));
+        }
+
+        Ok(SobolSequence {
+            direction_numbers: v,
+            state: vec![0; dimension],
+            shift: vec![0; dimension],
+            index: 0,
+        })
+    }
+}
+
+impl PointGenerator for SobolSequence {
+    fn get_dimension(&self) -> usize {
+        self.state.len()
+    }
+
+    fn next_point(&mut self, point: &mut [f64]) {
+        if self.index > 0 {
+            let c = (self.index.trailing_zeros() as usize).min(31);
+            for (s, d) in self.state.iter_mut().zip(&self.direction_numbers) {
+                *s ^= d[c];
+            }
+        }
+        self.index += 1;
+
+        for ((p, s), shift) in point.iter_mut().zip(&self.state).zip(&self.shift) {
+            *p = (s ^ shift) as f64 / 4294967296.;
+        }
+    }
+
+    fn randomize<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        for s in &mut self.shift {
+            *s = rng.random();
+        }
+        for s in &mut self.state {
+            *s = 0;
+        }
+        self.index = 0;
+    }
+}
+
+/// A Halton sequence, which uses the radical inverse in the first `d` prime bases.
+/// The sequence can be randomized with a random Cranley–Patterson shift.
+#[derive(Debug, Clone)]
+pub struct HaltonSequence {
+    bases: Vec<u64>,
+    shift: Vec<f64>,
+    index: u64,
+}
+
+impl HaltonSequence {
+    /// Create a Halton sequence in `dimension` dimensions.
+    pub fn new(dimension: usize) -> HaltonSequence {
+        HaltonSequence {
+            bases: PrimeIteratorU64::new(1).take(dimension).collect(),
+            shift: vec![0.; dimension],
+            index: 0,
+        }
+    }
+
+    fn radical_inverse(mut index: u64, base: u64) -> f64 {
+        let inv_base = 1. / base as f64;
+        let mut f = inv_base;
+        let mut r = 0.;
+        while index > 0 {
+            r += (index % base) as f64 * f;
+            index /= base;
+            f *= inv_base;
+        }
+        r
+    }
+}
+
+impl PointGenerator for HaltonSequence {
+    fn get_dimension(&self) -> usize {
+        self.bases.len()
+    }
+
+    fn next_point(&mut self, point: &mut [f64]) {
+        for ((p, b), s) in point.iter_mut().zip(&self.bases).zip(&self.shift) {
+            *p = (Self::radical_inverse(self.index, *b) + s).fract();
+        }
+        self.index += 1;
+    }
+
+    fn randomize<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        for s in &mut self.shift {
+            *s = rng.random();
+        }
+        self.index = 0;
+    }
+}
+
+/// A rank-1 lattice rule with `n` points `x_i = frac(i z / n + Δ)`, with
+/// generating vector `z` and random shift `Δ`. After `n` points,
+/// the lattice is repeated.
+#[derive(Debug, Clone)]
+pub struct LatticeRule {
+    generating_vector: Vec<u64>,
+    n: u64,
+    shift: Vec<f64>,
+    index: u64,
+}
+
+impl LatticeRule {
+    /// Create a lattice rule with `n` points and generating vector `z`.
+    pub fn new(z: Vec<u64>, n: u64) -> LatticeRule {
+        assert!(n > 0, "A lattice rule needs at least one point");
+        LatticeRule {
+            shift: vec![0.; z.len()],
+            generating_vector: z.into_iter().map(|x| x % n).collect(),
+            n,
+            index: 0,
+        }
+    }
+
+    /// Create a Korobov lattice rule with `n` points in `dimension` dimensions, whose
+    /// generating vector is `(1, a, a^2, ..., a^(d-1)) mod n`.
+    pub fn korobov(dimension: usize, n: u64, a: u64) -> LatticeRule {
+        let mut z = Vec::with_capacity(dimension);
+        let mut c = 1 % n;
+        for _ in 0..dimension {
+            z.push(c);
+            c = ((c as u128 * a as u128) % n as u128) as u64;
+        }
+        Self::new(z, n)
+    }
+
+    /// Get the number of points in the lattice.
+    pub fn get_size(&self) -> u64 {
+        self.n
+    }
+}
+
+impl PointGenerator for LatticeRule {
+    fn get_dimension(&self) -> usize {
+        self.generating_vector.len()
+    }
+
+    fn next_point(&mut self, point: &mut [f64]) {
+        for ((p, z), s) in point
+            .iter_mut()
+            .zip(&self.generating_vector)
+            .zip(&self.shift)
+        {
+            let k = (self.index as u128 * *z as u128) % self.n as u128;
+            *p = (k as f64 / self.n as f64 + s).fract();
+        }
+        self.index = (self.index + 1) % self.n;
+    }
+
+    fn randomize<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        for s in &mut self.shift {
+            *s = rng.random();
+        }
+        self.index = 0;
+    }
+}
+
+/// A random number generator that yields the coordinates of the points of a
+/// [PointGenerator] one after the other, so that quasi-Monte Carlo points can be used
+/// everywhere a random number generator is accepted, for example
+/// in [`ContinuousGrid::sample()`], where the points are transformed by the VEGAS map.
+///
+/// Every call to the random number generator consumes one coordinate. Therefore, the
+/// dimension of the point generator must be equal to the number of random numbers
+/// drawn per sample, which for a [ContinuousGrid] is its number of dimensions.
+///
+/// For randomized quasi-Monte Carlo error estimation, randomize the generator
+/// with [`Self::randomize()`] before every replica and call [`Grid::end_replica()`]
+/// after it.
+///
+/// # Examples
+///
+/// ```
+/// use symbolica::numerical_integration::{
+///     ContinuousGrid, Grid, MonteCarloRng, QuasiMonteCarloRng, Sample, SobolSequence,
+/// };
+///
+/// let f = |x: &[f64]| x[0] * x[1];
+///
+/// let mut grid = Grid::Continuous(ContinuousGrid::new(2, 16, 100, None, false));
+/// let mut qmc = QuasiMonteCarloRng::new(SobolSequence::new(2).unwrap());
+/// let mut rng = MonteCarloRng::new(0, 0);
+///
+/// let mut sample = Sample::new();
+/// for _ in 0..8 {
+///     qmc.randomize(&mut rng);
+///     for _ in 0..1024 {
+///         grid.sample(&mut qmc, &mut sample);
+///         if let Sample::Continuous(_, xs) = &sample {
+///             grid.add_training_sample(&sample, f(xs)).unwrap();
+///         }
+///     }
+///     grid.end_replica();
+/// }
+/// grid.update(1.5, 0.);
+///
+/// let stats = grid.get_statistics();
+/// assert!((stats.avg - 0.25).abs() < 1e-4 && stats.err < 1e-4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct QuasiMonteCarloRng<G: PointGenerator> {
+    generator: G,
+    point: Vec<f64>,
+    pos: usize,
+}
+
+impl<G: PointGenerator> QuasiMonteCarloRng<G> {
+    /// Create a new random number generator from a point generator.
+    pub fn new(generator: G) -> Self {
+        let dim = generator.get_dimension();
+        QuasiMonteCarloRng {
+            generator,
+            point: vec![0.; dim],
+            pos: dim,
+        }
+    }
+
+    /// Restart the point set with a new random shift drawn from `rng`.
+    pub fn randomize<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.generator.randomize(rng);
+        self.pos = self.point.len();
+    }
+
+    /// Get the underlying point generator.
+    pub fn get_generator(&self) -> &G {
+        &self.generator
+    }
+}
+
+impl<G: PointGenerator> RngCore for QuasiMonteCarloRng<G> {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        if self.pos == self.point.len() {
+            self.generator.next_point(&mut self.point);
+            self.pos = 0;
+        }
+
+        let x = self.point[self.pos];
+        self.pos += 1;
+        (x * 18446744073709551616.) as u64
+    }
+
+    #[inline]
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand::rand_core::impls::fill_bytes_via_next(self, dest)
+    }
+}
+
+/// A function that can be integrated by an [Integrator].
+///
+/// It is implemented for closures that take a [Sample] and for
+/// an [ExpressionEvaluator] with a single output, whose parameters are the continuous
+/// coordinates of the sample. For a discrete sample, the parameters are
+/// the bin index followed by the parameters of the nested sample.
+pub trait Integrand<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd>:
+    Clone + Send + Sync
+{
+    /// Evaluate the integrand at a sample point.
+    fn evaluate(&mut self, sample: &Sample<T>) -> T;
+}
+
+impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd, F> Integrand<T> for F
+where
+    F: FnMut(&Sample<T>) -> T + Clone + Send + Sync,
+{
+    fn evaluate(&mut self, sample: &Sample<T>) -> T {
+        self(sample)
+    }
+}
+
+impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd + Send + Sync> Integrand<T>
+    for ExpressionEvaluator<T>
+{
+    fn evaluate(&mut self, sample: &Sample<T>) -> T {
+        if let Sample::Continuous(_, xs) = sample {
+            return self.evaluate_single(xs);
+        }
+
+        let mut params = vec![];
+        let mut s = sample;
+        while let Sample::Discrete(_, index, sub_sample) = s {
+            params.push(T::new_from_usize(*index));
+            match sub_sample {
+                Some(sub) => s = sub,
+                None => break,
+            }
+        }
+
+        if let Sample::Continuous(_, xs) = s {
+            params.extend_from_slice(xs);
+        }
+
+        self.evaluate_single(&params)
+    }
+}
+
+/// Settings for an [Integrator].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+#[derive(Debug, Clone)]
+pub struct IntegratorSettings<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> {
+    /// The number of cores to use.
+    pub n_cores: usize,
+    /// The number of workers that each sample a part of an iteration with their own
+    /// random number stream. The result only depends on the number of workers, not on
+    /// the number of cores.
+    pub n_workers: usize,
+    /// The seed of the random number generator.
+    pub seed: u64,
+    /// The number of samples per iteration.
+    pub samples_per_iteration: usize,
+    /// The maximum number of iterations.
+    pub max_iterations: usize,
+    /// Stop when the relative error is below this target.
+    pub target_relative_error: Option<f64>,
+    /// Stop after the first iteration that ends when this time budget is exceeded.
+    pub max_time: Option<Duration>,
+    /// The learning rate of the discrete grids.
+    pub discrete_learning_rate: T,
+    /// The learning rate of the continuous grids.
+    pub continuous_learning_rate: T,
+    /// Print the result after every iteration.
+    pub show_progress: bool,
+}
+
+impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> Default
+    for IntegratorSettings<T>
+{
+    fn default() -> Self {
+        IntegratorSettings {
+            n_cores: 4,
+            n_workers: 16,
+            seed: 0,
+            samples_per_iteration: 10_000,
+            max_iterations: 20,
+            target_relative_error: None,
+            max_time: None,
+            discrete_learning_rate: T::new_from_usize(3) / T::new_from_usize(2),
+            continuous_learning_rate: T::new_from_usize(3) / T::new_from_usize(2),
+            show_progress: false,
+        }
+    }
+}
+
+/// An integration driver that samples a [Grid] over multiple iterations,
+/// distributing the samples of every iteration over workers that run in parallel.
+/// Every worker has its own [MonteCarloRng] stream and the worker grids are merged in a fixed
+/// order, so that the result is reproducible.
+///
+/// The integration stops when the maximum number of iterations is reached,
+/// when the target relative error is reached or when the time budget is exceeded.
+///
+/// With the `bincode` feature, the full state can be checkpointed to disk using
+/// [`Integrator::save_checkpoint()`] and restored with [`Integrator::load_checkpoint()`],
+/// after which the integration can be continued.
+///
+/// # Examples
+///
+/// ```
+/// use symbolica::numerical_integration::{ContinuousGrid, Grid, Integrator, IntegratorSettings, Sample};
+///
+/// let f = |s: &Sample<f64>| match s {
+///     Sample::Continuous(_, xs) => (xs[0] * std::f64::consts::PI).sin() + xs[1],
+///     _ => unreachable!(),
+/// };
+///
+/// let mut integrator = Integrator::new(
+///     Grid::Continuous(ContinuousGrid::new(2, 128, 100, None, false)),
+///     IntegratorSettings {
+///         max_iterations: 10,
+///         target_relative_error: Some(1e-3),
+///         ..Default::default()
+///     },
+/// );
+///
+/// let result = integrator.integrate(&f).unwrap();
+/// assert!((result.avg - 2. / std::f64::consts::PI - 0.5).abs() < 5. * result.err);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+#[derive(Debug, Clone)]
+pub struct Integrator<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd> {
+    pub grid: Grid<T>,
+    pub settings: IntegratorSettings<T>,
+    iteration: usize,
+}
+
+impl<T: Real + ConstructibleFloat + Copy + RealNumberLike + PartialOrd + Send + Sync>
+    Integrator<T>
+{
+    /// Create a new integrator for the grid `grid`.
+    pub fn new(grid: Grid<T>, settings: IntegratorSettings<T>) -> Integrator<T> {
+        Integrator {
+            grid,
+            settings,
+            iteration: 0,
+        }
+    }
+
+    /// Get the number of completed iterations.
+    pub fn get_iteration(&self) -> usize {
+        self.iteration
+    }
+
+    /// Get the statistics of the integration.
+    pub fn get_statistics(&mut self) -> &StatisticsAccumulator<T> {
+        self.grid.get_statistics()
+    }
+
+    /// Integrate `integrand` until one of the stopping criteria in the settings is met.
+    /// An integration that was resumed from a checkpoint continues
+    /// from its last completed iteration.
+    pub fn integrate<I: Integrand<T>>(
+        &mut self,
+        integrand: &I,
+    ) -> Result<&StatisticsAccumulator<T>, String> {
+        let start = Instant::now();
+        let pool = self.thread_pool()?;
+
+        while self.iteration < self.settings.max_iterations {
+            self.iterate_with_pool(integrand, pool.as_ref())?;
+
+            if self.settings.show_progress {
+                println!(
+                    "Integral at iteration {}: {}",
+                    self.iteration,
+                    self.grid.get_statistics().format_uncertainty()
+                );
+            }
+
+            if let Some(target) = self.settings.target_relative_error {
+                let stats = self.grid.get_statistics();
+                if stats.processed_samples > 1
+                    && stats.err.to_f64() <= target * stats.avg.to_f64().abs()
+                {
+                    break;
+                }
+            }
+
+            if let Some(max_time) = self.settings.max_time {
+                if start.elapsed() >= max_time {
+                    break;
+                }
+            }
+        }
+
+        Ok(self.grid.get_statistics())
+    }
+
+    /// Perform a single iteration: sample the grid, evaluate `integrand` and update the grid.
+    pub fn iterate<I: Integrand<T>>(&mut self, integrand: &I) -> Result<(), String> {
+        let pool = self.thread_pool()?;
+        self.iterate_with_pool(integrand, pool.as_ref())
+    }
+
+    fn thread_pool(&self) -> Result<Option<ThreadPool>, String> {
+        if self.settings.n_cores < 2 || !LicenseManager::is_licensed() {
+            return Ok(None);
+        }
+
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(self.settings.n_cores)
+            .build()
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+
+    fn iterate_with_pool<I: Integrand<T>>(
+        &mut self,
+        integrand: &I,
+        pool: Option<&ThreadPool>,
+    ) -> Result<(), String> {
+        let n_workers = self.settings.n_workers.max(1);
+        let n_samples = self.settings.samples_per_iteration;
+        let seed = self
+            .settings
+            .seed
+            .wrapping_add((self.iteration as u64).wrapping_mul(0x9e3779b97f4a7c15));
+
+        let grid = &self.grid;
+        let work = |worker: usize| -> Result<Grid<T>, String> {
+            let mut grid = grid.clone();
+            let mut integrand = integrand.clone();
+            let mut rng = MonteCarloRng::new(seed, worker);
+
+            let n = n_samples / n_workers + usize::from(worker < n_samples % n_workers);
+            let mut sample = Sample::new();
+            for _ in 0..n {
+                grid.sample(&mut rng, &mut sample);
+                let eval = integrand.evaluate(&sample);
+                grid.add_training_sample(&sample, eval)?;
+            }
+
+            Ok(grid)
+        };
+
+        let worker_grids: Vec<_> = if let Some(pool) = pool {
+            pool.install(|| (0..n_workers).into_par_iter().map(work).collect())
+        } else {
+            (0..n_workers).map(work).collect()
+        };
+
+        for g in worker_grids {
+            self.grid.merge_unchecked(&g?);
+        }
+
+        self.grid.update(
+            self.settings.discrete_learning_rate,
+            self.settings.continuous_learning_rate,
+        );
+        self.iteration += 1;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<T> Integrator<T>
+where
+    T: Real
+        + ConstructibleFloat
+        + Copy
+        + RealNumberLike
+        + PartialOrd
+        + Send
+        + Sync
+        + bincode::Encode
+        + bincode::Decode<()>,
+{
+    /// Write the full state of the integrator, including the grids and their
+    /// accumulators, to the file `path`. The file is replaced atomically.
+    pub fn save_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let path = path.as_ref();
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+
+        let mut f = BufWriter::new(File::create(&tmp_path).map_err(|e| e.to_string())?);
+        bincode::encode_into_std_write(self, &mut f, bincode::config::standard())
+            .map_err(|e| e.to_string())?;
+        f.flush().map_err(|e| e.to_string())?;
+        drop(f);
+
+        std::fs::rename(&tmp_path, path).map_err(|e| e.to_string())
+    }
+
+    /// Restore an integrator from a checkpoint written by [`Integrator::save_checkpoint()`].
+    pub fn load_checkpoint<P: AsRef<Path>>(path: P) -> Result<Integrator<T>, String> {
+        let mut f = BufReader::new(File::open(path).map_err(|e| e.to_string())?);
+        bincode::decode_from_std_read(&mut f, bincode::config::standard())
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use crate::{
+        atom::AtomCore,
+        evaluate::{FunctionMap, OptimizationSettings},
+        parse,
+    };
+
+    use super::{
+        ChannelMapping, ContinuousGrid, DiscreteGrid, EventGenerator, Grid, HaltonSequence,
+        Histogram, Integrator, IntegratorSettings, LatticeRule, MonteCarloRng, MultiChannelSample,
+        MultiChannelSampler, PointGenerator, QuasiMonteCarloRng, Sample, SobolSequence,
+    };
+
+    #[test]
+    fn skipped_update() {
+        let mut grid = ContinuousGrid::new(1, 4, 100, None, false);
+        for i in 0..10 {
+            let sample = Sample::Continuous(1., vec![i as f64 / 10.]);
+            grid.add_training_sample(&sample, 1.).unwrap();
+        }
+
+        // the grid is not trained, but the samples should be counted only once
+        grid.update(1.5);
+        grid.update(1.5);
+        let d = &grid.continuous_dimensions[0];
+        assert_eq!(d.counter.iter().sum::<usize>(), 10);
+        assert_eq!(d.bin_importance.iter().sum::<f64>(), 10.);
+    }
+
+    #[test]
+    fn multichannel() {
+        // Integrate x*pi + x^2 using multi-channeling:
+        // x*pi and x^2 will have their own Vegas grid
+        let fs = [|x: f64| (x * PI).sin(), |x: f64| x * x];
+
+        let mut grid = DiscreteGrid::new(
+            vec![
+                Some(Grid::Continuous(ContinuousGrid::new(
+                    1, 10, 1000, None, false,
+                ))),
+                Some(Grid::Continuous(ContinuousGrid::new(
+                    1, 10, 1000, None, false,
+                ))),
+            ],
+            0.01,
+            false,
+        );
+
+        let mut rng = MonteCarloRng::new(0, 0);
+
+        let mut sample = Sample::new();
+        for _ in 1..20 {
+            // sample 10_000 times per iteration
+            for _ in 0..10_000 {
+                grid.sample(&mut rng, &mut sample);
+
+                if let Sample::Discrete(_weight, i, cont_sample) = &sample {
+                    if let Sample::Continuous(_cont_weight, xs) =
+                        cont_sample.as_ref().unwrap().as_ref()
+                    {
+                        grid.add_training_sample(&sample, fs[*i](xs[0])).unwrap();
+                    }
+                }
+            }
+
+            grid.update(1.5, 1.5);
+        }
+
+        assert_eq!(grid.accumulator.avg, 0.9718412953459551);
+        assert_eq!(grid.accumulator.err, 0.0009349254838085983)
+    }
+
+    #[test]
+    fn sobol() {
+        let mut sobol = SobolSequence::new(3).unwrap();
+        let mut p = [0.; 3];
+        let mut points = vec![];
+        for _ in 0..8 {
+            sobol.next_point(&mut p);
+            points.push(p);
+        }
+
+        assert_eq!(
+            points,
+            [
+                [0., 0., 0.],
+                [0.5, 0.5, 0.5],
+                [0.75, 0.25, 0.25],
+                [0.25, 0.75, 0.75],
+                [0.375, 0.375, 0.625],
+                [0.875, 0.875, 0.125],
+                [0.625, 0.125, 0.875],
+                [0.125, 0.625, 0.375]
+            ]
+        );
+
+        // every elementary interval of length 1/16 contains exactly one point in each dimension
+        let mut sobol = SobolSequence::new(21).unwrap();
+        sobol.randomize(&mut MonteCarloRng::new(0, 0));
+        let mut p = [0.; 21];
+        let mut counts = vec![[0; 16]; 21];
+        for _ in 0..16 {
+            sobol.next_point(&mut p);
+            for (c, x) in counts.iter_mut().zip(&p) {
+                c[(x * 16.) as usize] += 1;
+            }
+        }
+        assert!(counts.iter().all(|c| c.iter().all(|x| *x == 1)));
+
+        assert!(SobolSequence::new(22).is_err());
+    }
+
+    #[test]
+    fn halton_lattice() {
+        let mut halton = HaltonSequence::new(2);
+        let mut p = [0.; 2];
+        let mut points = vec![];
+        for _ in 0..4 {
+            halton.next_point(&mut p);
+            points.push(p);
+        }
+        assert_eq!(
+            points,
+            [
+                [0., 0.],
+                [0.5, 1. / 3.],
+                [0.25, 2. / 3.],
+                [0.75, 1. / 3. / 3.]
+            ]
+        );
+
+        let mut lattice = LatticeRule::korobov(2, 5, 2);
+        let mut points = vec![];
+        for _ in 0..5 {
+            lattice.next_point(&mut p);
+            points.push(p);
+        }
+        assert_eq!(
+            points,
+            [[0., 0.], [0.2, 0.4], [0.4, 0.8], [0.6, 0.2], [0.8, 0.6]]
+        );
+    }
+
+    #[test]
+    fn rqmc() {
+        let f = |x: &[f64]| (x[0] * PI).sin() * x[1] * x[1] * (x[2] * 2.).exp();
+        let exact = 2. / PI / 3. * (2f64.exp() - 1.) / 2.;
+
+        let mut grid = Grid::Continuous(ContinuousGrid::new(3, 16, 10, None, false));
+        let mut qmc = QuasiMonteCarloRng::new(LatticeRule::korobov(3, 4093, 1397));
+        let mut rng = MonteCarloRng::new(0, 0);
+
+        let mut sample = Sample::new();
+        for _ in 0..10 {
+            qmc.randomize(&mut rng);
+            for _ in 0..qmc.get_generator().get_size() {
+                grid.sample(&mut qmc, &mut sample);
+                if let Sample::Continuous(_, xs) = &sample {
+                    grid.add_training_sample(&sample, f(xs)).unwrap();
+                }
+            }
+            grid.end_replica();
+        }
+        grid.update(0., 0.);
+
+        let stats = grid.get_statistics();
+        assert_eq!(stats.total_replicas, 10);
+        assert!((stats.avg - exact).abs() < 5. * stats.err);
+        assert!(stats.err < 1e-3);
+    }
+
+    #[test]
+    fn integrator() {
+        let f = |s: &Sample<f64>| match s {
+            Sample::Discrete(_, i, Some(sub)) => match sub.as_ref() {
+                Sample::Continuous(_, xs) => (xs[0] * PI).sin() * (*i as f64 + 1.),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+
+        let new_integrator = |n_cores| {
+            Integrator::new(
+                Grid::Discrete(DiscreteGrid::new(
+                    vec![
+                        Some(Grid::Continuous(ContinuousGrid::new(
+                            1, 10, 1000, None, false,
+                        ))),
+                        Some(Grid::Continuous(ContinuousGrid::new(
+                            1, 10, 1000, None, false,
+                        ))),
+                    ],
+                    0.01,
+                    false,
+                )),
+                IntegratorSettings {
+                    n_cores,
+                    n_workers: 7,
+                    max_iterations: 10,
+                    ..Default::default()
+                },
+            )
+        };
+
+        let mut i1 = new_integrator(1);
+        let r1 = i1.integrate(&f).unwrap().clone();
+        let mut i2 = new_integrator(4);
+        let r2 = i2.integrate(&f).unwrap();
+
+        assert_eq!(i1.get_iteration(), 10);
+        assert_eq!(r1.processed_samples, 100_000);
+        assert_eq!((r1.avg, r1.err), (r2.avg, r2.err));
+        assert!((r1.avg - 6. / PI).abs() < 5. * r1.err);
+
+        // stop at a target error
+        let mut i3 = new_integrator(1);
+        i3.settings.target_relative_error = Some(1e-2);
+        let r3 = i3.integrate(&f).unwrap();
+        assert!(r3.err < 1e-2 * r3.avg);
+        assert!(i3.get_iteration() < 10);
+    }
+
+    #[test]
+    fn integrator_evaluator() {
+        let expr = parse!("x^2*y").unwrap();
+        let params = vec![parse!("x").unwrap(), parse!("y").unwrap()];
+        let eval = expr
+            .evaluator(
+                &FunctionMap::new(),
+                &params,
+                OptimizationSettings::default(),
+            )
+            .unwrap()
+            .map_coeff(&|x| x.to_f64());
+
+        let mut integrator = Integrator::new(
+            Grid::Continuous(ContinuousGrid::new(2, 16, 100, None, false)),
+            IntegratorSettings {
+                n_cores: 1,
+                max_iterations: 5,
+                ..Default::default()
+            },
+        );
+
+        let r = integrator.integrate(&eval).unwrap();
+        assert!((r.avg - 1. / 6.).abs() < 5. * r.err);
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn integrator_checkpoint() {
+        let f = |s: &Sample<f64>| match s {
+            Sample::Continuous(_, xs) => xs[0] * xs[1].exp(),
+            _ => unreachable!(),
+        };
+
+        let new_integrator = |max_iterations| {
+            Integrator::new(
+                Grid::Continuous(ContinuousGrid::new(2, 16, 100, None, false)),
+                IntegratorSettings {
+                    n_cores: 1,
+                    max_iterations,
+                    ..Default::default()
+                },
+            )
+        };
+
+        let mut full = new_integrator(6);
+        full.integrate(&f).unwrap();
+
+        let path = std::env::temp_dir().join(format!("integrator_{}.bin", std::process::id()));
+        let mut first = new_integrator(3);
+        first.integrate(&f).unwrap();
+        first.save_checkpoint(&path).unwrap();
+
+        let mut resumed = Integrator::<f64>::load_checkpoint(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(resumed.get_iteration(), 3);
+
+        resumed.settings.max_iterations = 6;
+        let r = resumed.integrate(&f).unwrap().clone();
+        let r_full = full.get_statistics();
+        assert_eq!((r.avg, r.err), (r_full.avg, r_full.err));
+    }
+
+    /// A Breit-Wigner mapping on `[0, 1]`.
+    struct BreitWigner {
+        mass: f64,
+        width: f64,
+    }
+
+    impl BreitWigner {
+        fn range(&self) -> (f64, f64) {
+            (
+                (-self.mass / self.width).atan(),
+                ((1. - self.mass) / self.width).atan(),
+            )
+        }
+    }
+
+    impl ChannelMapping<f64> for BreitWigner {
+        fn get_dimension(&self) -> usize {
+            1
+        }
+
+        fn map(&self, r: &[f64], point: &mut Vec<f64>) -> f64 {
+            let (a0, a1) = self.range();
+            point.clear();
+            point.push(self.mass + self.width * (a0 + r[0] * (a1 - a0)).tan());
+            self.density(point)
+        }
+
+        fn density(&self, point: &[f64]) -> f64 {
+            let (a0, a1) = self.range();
+            let y = (point[0] - self.mass) / self.width;
+            1. / ((a1 - a0) * self.width * (1. + y * y))
+        }
+    }
+
+    struct Flat;
+
+    impl ChannelMapping<f64> for Flat {
+        fn get_dimension(&self) -> usize {
+            1
+        }
+
+        fn map(&self, r: &[f64], point: &mut Vec<f64>) -> f64 {
+            point.clear();
+            point.push(r[0]);
+            1.
+        }
+
+        fn density(&self, _point: &[f64]) -> f64 {
+            1.
+        }
+    }
+
+    #[test]
+    fn multichannel_mapping() {
+        let width = 0.01;
+        let f = |x: f64| {
+            1. / ((x - 0.2).powi(2) + width * width) + 1. / ((x - 0.8).powi(2) + width * width)
+        };
+        let exact = [0.2f64, 0.8]
+            .iter()
+            .map(|m| (((1. - m) / width).atan() + (m / width).atan()) / width)
+            .sum::<f64>();
+
+        let mut sampler = MultiChannelSampler::new(
+            vec![
+                Box::new(Flat),
+                Box::new(BreitWigner { mass: 0.2, width }),
+                Box::new(BreitWigner { mass: 0.8, width }),
+            ],
+            1e-3,
+        );
+
+        let mut rng = MonteCarloRng::new(0, 0);
+        let mut sample = MultiChannelSample::new();
+        for _ in 0..10 {
+            for _ in 0..10_000 {
+                sampler.sample(&mut rng, &mut sample);
+                sampler
+                    .add_training_sample(&sample, f(sample.point[0]))
+                    .unwrap();
+            }
+            sampler.update(0.5);
+        }
+
+        let weights = sampler.get_channel_weights();
+        assert!(weights[0] < 0.1);
+        assert!((weights[1] - weights[2]).abs() < 0.1);
+
+        let stats = sampler.get_statistics();
+        assert!((stats.avg - exact).abs() < 5. * stats.err);
+        assert!(stats.err < 1e-3 * stats.avg);
+    }
+
+    #[test]
+    fn histogram() {
+        let f = |x: f64| 3. * x * x;
+
+        let mut grid = Grid::Continuous(ContinuousGrid::new(1, 16, 100, None, false));
+        let mut histograms = vec![Histogram::uniform("x", 0., 1., 4).unwrap(); 2];
+        let mut single = Histogram::uniform("x", 0., 1., 4).unwrap();
+        assert!(Histogram::<f64>::uniform("x", 1., 0., 4).is_err());
+        assert!(Histogram::<f64>::uniform("x", 0., f64::INFINITY, 4).is_err());
+        assert!(Histogram::<f64>::uniform("x", 0., 1., 0).is_err());
+
+        let mut rng = MonteCarloRng::new(0, 0);
+        let mut sample = Sample::new();
+        for i in 0..20_000 {
+            grid.sample(&mut rng, &mut sample);
+            if let Sample::Continuous(_, xs) = &sample {
+                let eval = f(xs[0]);
+                grid.add_training_sample(&sample, eval).unwrap();
+                histograms[i % 2].add_sample(xs[0], &sample, eval);
+
+                // fill the same observable twice for one sample
+                single.fill(xs[0], eval * sample.get_weight() / 2.);
+                single.fill(xs[0], eval * sample.get_weight() / 2.);
+                single.end_sample();
+            }
+        }
+
+        let h1 = histograms.pop().unwrap();
+        let mut h0 = histograms.pop().unwrap();
+        h0.merge(&h1).unwrap();
+        h0.update();
+        single.update();
+        grid.update(0., 0.);
+
+        assert!(h0
+            .merge(&Histogram::uniform("y", 0., 2., 4).unwrap())
+            .is_err());
+        assert_eq!(h0.underflow.processed_samples, 20_000);
+
+        let mut total = 0.;
+        for (i, (b, s)) in h0.bins.iter().zip(&single.bins).enumerate() {
+            let exact = ((i + 1) as f64 / 4.).powi(3) - (i as f64 / 4.).powi(3);
+            assert!((b.avg - exact).abs() < 5. * b.err);
+            assert!((b.avg - s.avg).abs() < 1e-12 && (b.err - s.err).abs() < 1e-12);
+            total += b.avg;
+        }
+        assert!((total - grid.get_statistics().avg).abs() < 1e-12);
+
+        let csv = h0.to_csv();
+        assert_eq!(csv.lines().count(), 5);
+        assert!(csv.starts_with("xlow,xhigh,value,error\n0e0,2.5e-1,"));
+        assert!(h0.to_yoda().starts_with("BEGIN HISTO1D /x\n"));
+    }
+
+    #[test]
+    fn event_generation() {
+        let f = |s: &Sample<f64>| match s {
+            Sample::Continuous(_, xs) => 3. * xs[0] * xs[0] - 0.1,
+            _ => unreachable!(),
+        };
+
+        let mut grid = Grid::Continuous(ContinuousGrid::new(1, 16, 100, None, false));
+        let mut rng = MonteCarloRng::new(0, 0);
+        let mut sample = Sample::new();
+        for _ in 0..5 {
+            for _ in 0..10_000 {
+                grid.sample(&mut rng, &mut sample);
+                grid.add_training_sample(&sample, f(&sample)).unwrap();
+            }
+            grid.update(1.5, 1.5);
+        }
+
+        let mut untrained = EventGenerator::with_max_weights(0., 0.);
+        assert!(untrained.accept(1., &mut rng).is_none());
+        assert!(untrained
+            .generate(&mut grid, &mut rng, f, &mut sample)
+            .is_none());
+
+        let mut generator = EventGenerator::new(grid.get_statistics());
+        let mut histogram = Histogram::uniform("x", 0., 1., 2).unwrap();
+        let n_events = 20_000;
+        let mut weight_sum = 0.;
+        for _ in 0..n_events {
+            let w = generator
+                .generate(&mut grid, &mut rng, f, &mut sample)
+                .unwrap();
+            weight_sum += w;
+            if let Sample::Continuous(_, xs) = &sample {
+                histogram.fill(xs[0], w);
+                histogram.end_sample();
+            }
+        }
+        histogram.update();
+
+        assert_eq!(generator.accepted, n_events);
+        assert!(generator.get_efficiency() > 0.1);
+
+        // the fraction of the events per bin follows the distribution
+        let fraction = histogram.bins[0].avg / (weight_sum / n_events as f64);
+        let exact = (0.125 - 0.05) / (1. - 0.1);
+        assert!((fraction - exact).abs() < 0.02);
     }
 }
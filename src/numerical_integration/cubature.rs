@@ -0,0 +1,703 @@
+//! Deterministic adaptive cubature for smooth, low-dimensional integrands.
+//!
+//! Multidimensional integrals over a hyperrectangle are computed with the Genz–Malik
+//! embedded degree-7/5 rule and global adaptive subdivision: the region with the
+//! largest error estimate is split in two along the dimension in which the integrand
+//! varies most, until the requested tolerance is reached.
+//!
+//! One-dimensional integrals can be computed with an adaptive 7-15 point Gauss–Kronrod
+//! rule, or with the tanh-sinh (double exponential) rule, which handles
+//! integrable endpoint singularities.
+//!
+//! All rules are generic over the floating point type, so that they can be used
+//! with `f64`, [F64](crate::domains::float::F64) or an arbitrary-precision
+//! [Float](crate::domains::float::Float).
+//!
+//! # Examples
+//!
+//! ```
+//! use symbolica::numerical_integration::cubature::{cubature, CubatureSettings};
+//!
+//! let r = cubature(
+//!     |x: &[f64]| (x[0] + x[1] + x[2]).exp(),
+//!     &[0., 0., 0.],
+//!     &[1., 1., 1.],
+//!     &CubatureSettings::default(),
+//! )
+//! .unwrap();
+//!
+//! assert!(r.converged);
+//! assert!((r.avg - (std::f64::consts::E - 1.).powi(3)).abs() < 1e-7);
+//! ```
+
+use std::{cmp::Ordering, collections::BinaryHeap, str::FromStr};
+
+use crate::domains::{
+    float::{Real, RealNumberLike, SingleFloat},
+    integer::Integer,
+    rational::Rational,
+};
+
+use super::StatisticsAccumulator;
+
+/// The nodes of the 15-point Kronrod rule on `[-1, 1]`, in decreasing order.
+const KRONROD_NODES: [&str; 8] = [
+    "0.991455371120812639206854697526329",
+    "0.949107912342758524526189684047851",
+    "0.864864423359769072789712788640926",
+    "0.741531185599394439863864773280788",
+    "0.586087235467691130294144838258730",
+    "0.405845151377397166906606412076961",
+    "0.207784955007898467600689403773245",
+    "0",
+];
+
+/// The weights of the 15-point Kronrod rule.
+const KRONROD_WEIGHTS: [&str; 8] = [
+    "0.022935322010529224963732008058970",
+    "0.063092092629978553290700663189204",
+    "0.104790010322250183839876322541518",
+    "0.140653259715525918745189590510238",
+    "0.169004726639267902826583426598550",
+    "0.190350578064785409913256402421014",
+    "0.204432940075298892414161999234649",
+    "0.209482141084727828012999174891714",
+];
+
+/// The weights of the embedded 7-point Gauss rule, whose nodes are
+/// the odd Kronrod nodes and the center.
+const GAUSS_WEIGHTS: [&str; 4] = [
+    "0.129484966168869693270611432679082",
+    "0.279705391489276667901467771423780",
+    "0.381830050505118944950369775488975",
+    "0.417959183673469387755102040816327",
+];
+
+/// The maximum number of dimensions for the Genz–Malik rule,
+/// which uses `2^n` points per region.
+const MAX_GENZ_MALIK_DIMENSION: usize = 20;
+
+/// Settings for adaptive cubature.
+#[derive(Debug, Clone)]
+pub struct CubatureSettings {
+    /// The absolute error that is considered converged.
+    pub absolute_tolerance: f64,
+    /// The error relative to the integral that is considered converged.
+    pub relative_tolerance: f64,
+    /// The maximum number of integrand evaluations.
+    pub max_evaluations: usize,
+}
+
+impl Default for CubatureSettings {
+    fn default() -> Self {
+        CubatureSettings {
+            absolute_tolerance: 0.,
+            relative_tolerance: 1e-8,
+            max_evaluations: 1_000_000,
+        }
+    }
+}
+
+impl CubatureSettings {
+    fn is_converged<T: RealNumberLike>(&self, avg: &T, err: &T) -> bool {
+        err.to_f64()
+            <= self
+                .absolute_tolerance
+                .max(self.relative_tolerance * avg.to_f64().abs())
+    }
+}
+
+/// The result of a cubature, consisting of the estimate `avg` of the
+/// integral and its error estimate `err`.
+#[derive(Debug, Clone)]
+pub struct CubatureResult<T> {
+    pub avg: T,
+    pub err: T,
+    /// The number of integrand evaluations.
+    pub evaluations: usize,
+    /// The number of regions of the final subdivision.
+    pub regions: usize,
+    /// Whether the requested tolerance was reached.
+    pub converged: bool,
+}
+
+impl<T: RealNumberLike> CubatureResult<T> {
+    /// Format `avg ± err` as `avg(err)` in a human-readable way with the correct number of digits.
+    pub fn format_uncertainty(&self) -> String {
+        StatisticsAccumulator::<f64>::format_uncertainty_impl(self.avg.to_f64(), self.err.to_f64())
+    }
+}
+
+/// Convert a decimal number to a float with the precision of `one`.
+fn decimal<T: SingleFloat>(one: &T, s: &str) -> T {
+    let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+    let num = Integer::from_str(&format!("{}{}", int, frac)).unwrap();
+    let den = Integer::from(10).pow(frac.len() as u64);
+    one.from_rational(&Rational::from((num, den)))
+}
+
+/// A rule that yields an estimate of an integral over a region and an error estimate.
+enum Rule<T> {
+    GaussKronrod {
+        nodes: Vec<T>,
+        kronrod_weights: Vec<T>,
+        gauss_weights: Vec<T>,
+    },
+    GenzMalik {
+        lambda2: T,
+        lambda4: T,
+        lambda5: T,
+        weights7: [T; 5],
+        weights5: [T; 4],
+    },
+}
+
+impl<T: Real + RealNumberLike> Rule<T> {
+    fn gauss_kronrod(one: &T) -> Self {
+        Rule::GaussKronrod {
+            nodes: KRONROD_NODES.iter().map(|x| decimal(one, x)).collect(),
+            kronrod_weights: KRONROD_WEIGHTS.iter().map(|x| decimal(one, x)).collect(),
+            gauss_weights: GAUSS_WEIGHTS.iter().map(|x| decimal(one, x)).collect(),
+        }
+    }
+
+    fn genz_malik(one: &T, n: usize) -> Self {
+        let n = n as i64;
+        let r = |num: i64, den: i64| one.from_rational(&(num, den).into());
+        Rule::GenzMalik {
+            lambda2: r(9, 70).sqrt(),
+            lambda4: r(9, 10).sqrt(),
+            lambda5: r(9, 19).sqrt(),
+            weights7: [
+                r(12824 - 9120 * n + 400 * n * n, 19683),
+                r(980, 6561),
+                r(1820 - 400 * n, 19683),
+                r(200, 19683),
+                r(6859, 19683) / one.from_usize(2).pow(n as u64),
+            ],
+            weights5: [
+                r(729 - 950 * n + 50 * n * n, 729),
+                r(245, 486),
+                r(265 - 100 * n, 1458),
+                r(25, 729),
+            ],
+        }
+    }
+
+    fn evaluations_per_region(&self, n: usize) -> usize {
+        match self {
+            Rule::GaussKronrod { .. } => 15,
+            Rule::GenzMalik { .. } => 1 + 4 * n + 2 * n * (n - 1) + (1 << n),
+        }
+    }
+
+    /// Integrate over the region with center `center` and half widths `half_width`,
+    /// returning the estimate, the error and the dimension that should be split.
+    fn integrate<F: FnMut(&[T]) -> T>(
+        &self,
+        f: &mut F,
+        center: &[T],
+        half_width: &[T],
+        point: &mut Vec<T>,
+    ) -> (T, T, usize) {
+        match self {
+            Rule::GaussKronrod {
+                nodes,
+                kronrod_weights,
+                gauss_weights,
+            } => {
+                let (c, h) = (&center[0], &half_width[0]);
+                let fc = f(center);
+                let mut kronrod = kronrod_weights[7].clone() * &fc;
+                let mut gauss = gauss_weights[3].clone() * &fc;
+
+                for (j, (x, w)) in nodes[..7].iter().zip(kronrod_weights).enumerate() {
+                    let dx = h.clone() * x;
+                    let f_sum = f(&[c.clone() - &dx]) + f(&[c.clone() + &dx]);
+                    if j % 2 == 1 {
+                        gauss += gauss_weights[j / 2].clone() * &f_sum;
+                    }
+                    kronrod += w.clone() * &f_sum;
+                }
+
+                kronrod *= h;
+                gauss *= h;
+                let err = (kronrod.clone() - &gauss).norm();
+                (kronrod, err, 0)
+            }
+            Rule::GenzMalik {
+                lambda2,
+                lambda4,
+                lambda5,
+                weights7,
+                weights5,
+            } => {
+                let n = center.len();
+                point.clear();
+                point.extend_from_slice(center);
+
+                let two = center[0].from_usize(2);
+                let mut volume = center[0].one();
+                for h in half_width {
+                    volume *= two.clone() * h;
+                }
+
+                let f0 = f(point);
+                let two_f0 = two.clone() * &f0;
+                // the ratio of the fourth differences is lambda2^2 / lambda4^2
+                let ratio = center[0].from_usize(7).inv();
+
+                let mut sum2 = f0.zero();
+                let mut sum3 = f0.zero();
+                let mut split_dim = 0;
+                let mut max_diff = -1.;
+                for i in 0..n {
+                    let mut eval_pair = |lambda: &T| {
+                        let dx = lambda.clone() * &half_width[i];
+                        point[i] = center[i].clone() - &dx;
+                        let f1 = f(point);
+                        point[i] = center[i].clone() + &dx;
+                        let f2 = f(point);
+                        point[i] = center[i].clone();
+                        f1 + f2
+                    };
+
+                    let a = eval_pair(lambda2);
+                    let b = eval_pair(lambda4);
+
+                    let diff = (a.clone() - &two_f0 - ratio.clone() * (b.clone() - &two_f0))
+                        .norm()
+                        .to_f64();
+                    if diff > max_diff {
+                        max_diff = diff;
+                        split_dim = i;
+                    }
+
+                    sum2 += a;
+                    sum3 += b;
+                }
+
+                let mut sum4 = f0.zero();
+                for i in 0..n {
+                    for j in i + 1..n {
+                        for (si, sj) in [(false, false), (false, true), (true, false), (true, true)]
+                        {
+                            let di = lambda4.clone() * &half_width[i];
+                            let dj = lambda4.clone() * &half_width[j];
+                            point[i] = if si {
+                                center[i].clone() + &di
+                            } else {
+                                center[i].clone() - &di
+                            };
+                            point[j] = if sj {
+                                center[j].clone() + &dj
+                            } else {
+                                center[j].clone() - &dj
+                            };
+                            sum4 += f(point);
+                        }
+                        point[i] = center[i].clone();
+                        point[j] = center[j].clone();
+                    }
+                }
+
+                let mut sum5 = f0.zero();
+                for signs in 0..1usize << n {
+                    for (i, p) in point.iter_mut().enumerate() {
+                        let d = lambda5.clone() * &half_width[i];
+                        *p = if signs & (1 << i) != 0 {
+                            center[i].clone() + &d
+                        } else {
+                            center[i].clone() - &d
+                        };
+                    }
+                    sum5 += f(point);
+                }
+
+                let i7 = (weights7[0].clone() * &f0
+                    + weights7[1].clone() * &sum2
+                    + weights7[2].clone() * &sum3
+                    + weights7[3].clone() * &sum4
+                    + weights7[4].clone() * &sum5)
+                    * &volume;
+                let i5 = (weights5[0].clone() * &f0
+                    + weights5[1].clone() * &sum2
+                    + weights5[2].clone() * &sum3
+                    + weights5[3].clone() * &sum4)
+                    * &volume;
+
+                let err = (i7.clone() - &i5).norm();
+                (i7, err, split_dim)
+            }
+        }
+    }
+}
+
+/// A region of the subdivision, ordered by its error estimate.
+struct Region<T> {
+    center: Vec<T>,
+    half_width: Vec<T>,
+    avg: T,
+    err: T,
+    split_dim: usize,
+}
+
+impl<T: RealNumberLike> PartialEq for Region<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: RealNumberLike> Eq for Region<T> {}
+
+impl<T: RealNumberLike> PartialOrd for Region<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: RealNumberLike> Ord for Region<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.err.to_f64().total_cmp(&other.err.to_f64())
+    }
+}
+
+/// Integrate using global adaptive subdivision with the rule `rule`.
+fn adaptive<T: Real + RealNumberLike, F: FnMut(&[T]) -> T>(
+    rule: Rule<T>,
+    mut f: F,
+    lower: &[T],
+    upper: &[T],
+    settings: &CubatureSettings,
+) -> Result<CubatureResult<T>, String> {
+    if lower.len() != upper.len() {
+        return Err("The lower and upper bounds have a different dimension".to_owned());
+    }
+
+    let n = lower.len();
+    let per_region = rule.evaluations_per_region(n);
+    let two = lower[0].from_usize(2);
+    let mut point = Vec::with_capacity(n);
+
+    let center: Vec<_> = lower
+        .iter()
+        .zip(upper)
+        .map(|(l, u)| (l.clone() + u) / &two)
+        .collect();
+    let half_width: Vec<_> = lower
+        .iter()
+        .zip(upper)
+        .map(|(l, u)| (u.clone() - l) / &two)
+        .collect();
+
+    let (avg, err, split_dim) = rule.integrate(&mut f, &center, &half_width, &mut point);
+    let mut evaluations = per_region;
+    let mut total_avg = avg.clone();
+    let mut total_err = err.clone();
+
+    let mut regions = BinaryHeap::new();
+    regions.push(Region {
+        center,
+        half_width,
+        avg,
+        err,
+        split_dim,
+    });
+
+    let mut converged = settings.is_converged(&total_avg, &total_err);
+    while !converged && evaluations + 2 * per_region <= settings.max_evaluations {
+        let r = regions.pop().unwrap();
+        total_avg -= &r.avg;
+        total_err -= &r.err;
+
+        let d = r.split_dim;
+        let half_width_d = r.half_width[d].clone() / &two;
+        for sign in [false, true] {
+            let mut center = r.center.clone();
+            center[d] = if sign {
+                center[d].clone() + &half_width_d
+            } else {
+                center[d].clone() - &half_width_d
+            };
+            let mut half_width = r.half_width.clone();
+            half_width[d] = half_width_d.clone();
+
+            let (avg, err, split_dim) = rule.integrate(&mut f, &center, &half_width, &mut point);
+            total_avg += &avg;
+            total_err += &err;
+            regions.push(Region {
+                center,
+                half_width,
+                avg,
+                err,
+                split_dim,
+            });
+        }
+        evaluations += 2 * per_region;
+
+        converged = settings.is_converged(&total_avg, &total_err);
+    }
+
+    // sum again to remove the rounding errors of the updates
+    let mut avg = lower[0].zero();
+    let mut err = lower[0].zero();
+    for r in &regions {
+        avg += &r.avg;
+        err += &r.err;
+    }
+
+    Ok(CubatureResult {
+        converged: settings.is_converged(&avg, &err),
+        avg,
+        err,
+        evaluations,
+        regions: regions.len(),
+    })
+}
+
+/// Integrate `f` over the hyperrectangle `[lower, upper]` using adaptive cubature.
+/// In more than one dimension, the Genz–Malik degree-7/5 rule is used, and in one dimension
+/// the 7-15 Gauss–Kronrod rule.
+///
+/// The Genz–Malik rule uses `2^n + 2n^2 + 2n + 1` evaluations per region in `n`
+/// dimensions and is therefore only suited for low-dimensional integrals.
+/// For high-dimensional integrals, use a [ContinuousGrid](super::ContinuousGrid).
+pub fn cubature<T: Real + RealNumberLike, F: FnMut(&[T]) -> T>(
+    f: F,
+    lower: &[T],
+    upper: &[T],
+    settings: &CubatureSettings,
+) -> Result<CubatureResult<T>, String> {
+    match lower.len() {
+        0 => Err("Cannot integrate over zero dimensions".to_owned()),
+        1 => adaptive(
+            Rule::gauss_kronrod(&lower[0].one()),
+            f,
+            lower,
+            upper,
+            settings,
+        ),
+        n if n > MAX_GENZ_MALIK_DIMENSION => Err(format!(
+            "Cubature is limited to {} dimensions",
+            MAX_GENZ_MALIK_DIMENSION
+        )),
+        n => adaptive(
+            Rule::genz_malik(&lower[0].one(), n),
+            f,
+            lower,
+            upper,
+            settings,
+        ),
+    }
+}
+
+/// Integrate `f` over `[a, b]` using an adaptive 7-15 point Gauss–Kronrod rule.
+///
+/// # Examples
+///
+/// ```
+/// use symbolica::domains::float::{Float, Real, RealNumberLike};
+/// use symbolica::numerical_integration::cubature::{gauss_kronrod, CubatureSettings};
+///
+/// let a = Float::with_val(100, 0);
+/// let b = a.pi();
+/// let settings = CubatureSettings {
+///     relative_tolerance: 1e-25,
+///     ..Default::default()
+/// };
+/// let r = gauss_kronrod(|x: &Float| x.sin(), &a, &b, &settings).unwrap();
+/// assert!((r.avg - Float::with_val(100, 2)).norm().to_f64() < 1e-25);
+/// ```
+pub fn gauss_kronrod<T: Real + RealNumberLike, F: FnMut(&T) -> T>(
+    mut f: F,
+    a: &T,
+    b: &T,
+    settings: &CubatureSettings,
+) -> Result<CubatureResult<T>, String> {
+    adaptive(
+        Rule::gauss_kronrod(&a.one()),
+        |x: &[T]| f(&x[0]),
+        std::slice::from_ref(a),
+        std::slice::from_ref(b),
+        settings,
+    )
+}
+
+/// Integrate `f` over `[a, b]` using the tanh-sinh (double exponential) rule.
+/// The step size is halved until the tolerance is reached.
+///
+/// The integrand is never evaluated at the endpoints, so that integrable singularities
+/// at `a` or `b` are allowed.
+///
+/// # Examples
+///
+/// ```
+/// use symbolica::numerical_integration::cubature::{tanh_sinh, CubatureSettings};
+///
+/// let r = tanh_sinh(|x: &f64| 1. / x.sqrt(), &0., &1., &CubatureSettings::default()).unwrap();
+/// assert!((r.avg - 2.).abs() < 1e-10);
+/// ```
+pub fn tanh_sinh<T: Real + RealNumberLike, F: FnMut(&T) -> T>(
+    mut f: F,
+    a: &T,
+    b: &T,
+    settings: &CubatureSettings,
+) -> Result<CubatureResult<T>, String> {
+    const MAX_LEVEL: usize = 20;
+
+    let one = a.one();
+    let two = one.from_usize(2);
+    let half_pi = one.pi() / &two;
+    let center = (a.clone() + b) / &two;
+    let half_width = (b.clone() - a) / &two;
+    let min_weight = a.get_epsilon() * a.get_epsilon();
+
+    let mut evaluations = 1;
+    let mut sum = half_pi.clone() * f(&center);
+    let mut avg = sum.clone() * &half_width;
+    let mut err = avg.clone();
+    let mut h = one.clone();
+    let mut converged = false;
+
+    for level in 0..=MAX_LEVEL {
+        if level > 0 {
+            h /= &two;
+        }
+
+        let mut new_sum = one.zero();
+        let mut j = 1;
+        loop {
+            let t = h.clone() * one.from_usize(j);
+            let u = half_pi.clone() * t.sinh();
+            let cosh_u = u.cosh();
+            let w = half_pi.clone() * t.cosh() / (cosh_u.clone() * &cosh_u);
+
+            // the distance to the endpoints, (b - a) / 2 * (1 - tanh(u))
+            let d = half_width.clone() * &two / ((two.clone() * &u).exp() + &one);
+            if w.to_f64() < min_weight || d.is_zero() {
+                break;
+            }
+
+            new_sum += w * (f(&(a.clone() + &d)) + f(&(b.clone() - &d)));
+            evaluations += 2;
+
+            j += if level == 0 { 1 } else { 2 };
+        }
+
+        sum += new_sum;
+        let new_avg = sum.clone() * &h * &half_width;
+        if level > 0 {
+            err = (new_avg.clone() - &avg).norm();
+        }
+        avg = new_avg;
+
+        if level > 2 && settings.is_converged(&avg, &err) {
+            converged = true;
+            break;
+        }
+
+        if evaluations * 2 > settings.max_evaluations {
+            break;
+        }
+    }
+
+    Ok(CubatureResult {
+        avg,
+        err,
+        evaluations,
+        regions: 1,
+        converged,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::E;
+
+    use crate::domains::float::{Float, NumericalFloatLike, Real, RealNumberLike};
+
+    use super::{cubature, gauss_kronrod, tanh_sinh, CubatureSettings};
+
+    #[test]
+    fn gauss_kronrod_exactness() {
+        // a single 15-point Kronrod rule integrates polynomials up to degree 22 exactly
+        let settings = CubatureSettings {
+            max_evaluations: 15,
+            ..Default::default()
+        };
+        let r = gauss_kronrod(|x: &f64| x.powi(22), &0., &1., &settings).unwrap();
+        assert_eq!(r.evaluations, 15);
+        assert!((r.avg - 1. / 23.).abs() < 1e-15);
+
+        let a = Float::with_val(120, 0);
+        let b = Float::with_val(120, 1);
+        let r = gauss_kronrod(|x: &Float| x.clone().pow(22), &a, &b, &settings).unwrap();
+        assert!((r.avg - Float::with_val(120, 1) / 23).norm().to_f64() < 1e-32);
+    }
+
+    #[test]
+    fn genz_malik() {
+        // a single region integrates polynomials up to degree 7 exactly
+        let settings = CubatureSettings {
+            max_evaluations: 1,
+            ..Default::default()
+        };
+        let r = cubature(
+            |x: &[f64]| x[0].powi(3) * x[1].powi(4),
+            &[0., 0.],
+            &[1., 1.],
+            &settings,
+        )
+        .unwrap();
+        assert!((r.avg - 1. / 20.).abs() < 1e-15);
+
+        let r = cubature(
+            |x: &[f64]| (x[0] + x[1] + x[2]).exp(),
+            &[0., 0., 0.],
+            &[1., 1., 1.],
+            &CubatureSettings {
+                relative_tolerance: 1e-10,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(r.converged);
+        assert!((r.avg - (E - 1.).powi(3)).abs() < 1e-9);
+
+        // a peak that needs adaptive refinement
+        let r = cubature(
+            |x: &[f64]| 1. / (1e-2 + x[0] * x[0] + x[1] * x[1]),
+            &[-1., -1.],
+            &[1., 1.],
+            &CubatureSettings {
+                relative_tolerance: 1e-8,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(r.converged);
+        assert!(r.regions > 10);
+        assert!((r.avg - 15.184478315739315).abs() < 1e-6);
+    }
+
+    #[test]
+    fn tanh_sinh_singular() {
+        let settings = CubatureSettings {
+            relative_tolerance: 1e-12,
+            ..Default::default()
+        };
+        let r = tanh_sinh(|x: &f64| x.ln(), &0., &1., &settings).unwrap();
+        assert!(r.converged);
+        assert!((r.avg + 1.).abs() < 1e-12);
+
+        let a = Float::with_val(100, 0);
+        let b = Float::with_val(100, 1);
+        let settings = CubatureSettings {
+            relative_tolerance: 1e-25,
+            ..Default::default()
+        };
+        let r = tanh_sinh(|x: &Float| x.sqrt(), &a, &b, &settings).unwrap();
+        assert!(r.converged);
+        assert!((r.avg - Float::with_val(100, 2) / 3).norm().to_f64() < 1e-25);
+    }
+}
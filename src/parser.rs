@@ -5,17 +5,27 @@
 //! Use [Token::parse] to tokenize an expression, and
 //! [Token::to_polynomial], [Token::to_rational_polynomial] or [Token::to_factorized_rational_polynomial] for accelerated parsing of polynomials written
 //! in Symbolica's fast format.
+//!
+//! Use [Token::parse_with_mode] to parse expressions written in the syntax of
+//! Mathematica, LaTeX, SymPy or FORM.
+
+mod import;
 
-use std::{fmt::Write, string::String, sync::Arc};
+use std::{
+    fmt::Write,
+    string::String,
+    sync::{Arc, RwLock},
+};
 
 use bytes::Buf;
+use once_cell::sync::Lazy;
 use rug::Integer as MultiPrecisionInteger;
 
 use smallvec::SmallVec;
 use smartstring::{LazyCompact, SmartString};
 
 use crate::{
-    atom::{Atom, DefaultNamespace},
+    atom::{Atom, DefaultNamespace, Symbol},
     coefficient::{Coefficient, ConvertToRing},
     domains::{float::Float, integer::Integer, Ring},
     poly::{polynomial::MultivariatePolynomial, PositiveExponent, Variable},
@@ -23,6 +33,8 @@ use crate::{
     LicenseManager,
 };
 
+pub use import::ParseMode;
+
 const HEX_DIGIT_MASK: [bool; 255] = [
     false, false, false, false, false, false, false, false, false, false, false, false, false,
     false, false, false, false, false, false, false, false, false, false, false, false, false,
@@ -91,6 +103,15 @@ pub enum Operator {
     Argument, // comma
     Neg,      // left side should be tagged as 'finished'
     Inv,      // left side should be tagged as 'finished', for internal use
+    Equal,
+    Unequal,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Factorial, // right side should be tagged as 'finished'
+    DoubleFactorial,
+    Custom(CustomOperator),
 }
 
 impl std::fmt::Display for Operator {
@@ -102,6 +123,15 @@ impl std::fmt::Display for Operator {
             Operator::Argument => f.write_char(','),
             Operator::Neg => f.write_char('-'),
             Operator::Inv => f.write_char('/'),
+            Operator::Equal => f.write_str("=="),
+            Operator::Unequal => f.write_str("!="),
+            Operator::Less => f.write_char('<'),
+            Operator::LessEqual => f.write_str("<="),
+            Operator::Greater => f.write_char('>'),
+            Operator::GreaterEqual => f.write_str(">="),
+            Operator::Factorial => f.write_char('!'),
+            Operator::DoubleFactorial => f.write_str("!!"),
+            Operator::Custom(c) => f.write_str(&c.name),
         }
     }
 }
@@ -110,7 +140,8 @@ impl Operator {
     #[inline]
     pub fn get_arity(&self) -> usize {
         match self {
-            Operator::Neg | Operator::Inv => 1,
+            Operator::Neg | Operator::Inv | Operator::Factorial | Operator::DoubleFactorial => 1,
+            Operator::Custom(c) if c.postfix => 1,
             _ => 2,
         }
     }
@@ -118,12 +149,20 @@ impl Operator {
     #[inline]
     pub fn get_precedence(&self) -> u8 {
         match self {
-            Operator::Mul => 8,
-            Operator::Add => 7,
-            Operator::Pow => 11,
-            Operator::Argument => 6,
-            Operator::Neg => 10,
-            Operator::Inv => 9,
+            Operator::Mul => 40,
+            Operator::Add => 30,
+            Operator::Pow => 60,
+            Operator::Argument => 10,
+            Operator::Neg => 50,
+            Operator::Inv => 45,
+            Operator::Equal
+            | Operator::Unequal
+            | Operator::Less
+            | Operator::LessEqual
+            | Operator::Greater
+            | Operator::GreaterEqual => 20,
+            Operator::Factorial | Operator::DoubleFactorial => 70,
+            Operator::Custom(c) => c.precedence,
         }
     }
 
@@ -136,6 +175,16 @@ impl Operator {
             Operator::Argument => true,
             Operator::Neg => true,
             Operator::Inv => true,
+            Operator::Equal
+            | Operator::Unequal
+            | Operator::Less
+            | Operator::LessEqual
+            | Operator::Greater
+            | Operator::GreaterEqual => true,
+            Operator::Factorial | Operator::DoubleFactorial => false,
+            Operator::Custom(c) => {
+                !c.postfix && matches!(c.associativity, Associativity::Left | Associativity::Flat)
+            }
         }
     }
 
@@ -148,7 +197,315 @@ impl Operator {
             Operator::Argument => true,
             Operator::Neg => true,
             Operator::Inv => true,
+            Operator::Equal
+            | Operator::Unequal
+            | Operator::Less
+            | Operator::LessEqual
+            | Operator::Greater
+            | Operator::GreaterEqual => false,
+            Operator::Factorial | Operator::DoubleFactorial => false,
+            Operator::Custom(c) => {
+                !c.postfix && matches!(c.associativity, Associativity::Right | Associativity::Flat)
+            }
+        }
+    }
+
+    /// Get the builtin function that represents the relation or postfix operator.
+    fn get_builtin_symbol(&self) -> Option<Symbol> {
+        match self {
+            Operator::Equal => Some(Atom::EQUAL),
+            Operator::Unequal => Some(Atom::UNEQUAL),
+            Operator::Less => Some(Atom::LESS),
+            Operator::LessEqual => Some(Atom::LESS_EQUAL),
+            Operator::Greater => Some(Atom::GREATER),
+            Operator::GreaterEqual => Some(Atom::GREATER_EQUAL),
+            Operator::Factorial => Some(Atom::FACTORIAL),
+            Operator::DoubleFactorial => Some(Atom::DOUBLE_FACTORIAL),
+            _ => None,
+        }
+    }
+}
+
+/// The associativity of a custom infix operator `~` that maps to the function `f`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Associativity {
+    /// `a ~ b ~ c` is parsed as `f(f(a, b), c)`.
+    Left,
+    /// `a ~ b ~ c` is parsed as `f(a, f(b, c))`.
+    Right,
+    /// `a ~ b ~ c` is parsed as `f(a, b, c)`.
+    Flat,
+}
+
+/// A user-defined infix or postfix operator that is parsed into
+/// a function with the symbol `symbol`. Register one with [Syntax::register_infix_operator]
+/// or [Syntax::register_postfix_operator].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CustomOperator {
+    pub name: SmartString<LazyCompact>,
+    pub symbol: Symbol,
+    pub precedence: u8,
+    pub associativity: Associativity,
+    pub postfix: bool,
+}
+
+/// The relations and the postfix operators that are always recognized by the parser.
+const BUILTIN_OPERATORS: [(&str, Operator); 8] = [
+    ("==", Operator::Equal),
+    ("!=", Operator::Unequal),
+    ("<=", Operator::LessEqual),
+    (">=", Operator::GreaterEqual),
+    ("<", Operator::Less),
+    (">", Operator::Greater),
+    ("!", Operator::Factorial),
+    ("!!", Operator::DoubleFactorial),
+];
+
+static SYNTAX: Lazy<RwLock<Syntax>> = Lazy::new(|| RwLock::new(Syntax::default()));
+
+/// User-defined extensions of the syntax that are used by the parser and the printer.
+///
+/// The operators registered with [Syntax::register_infix_operator] and
+/// [Syntax::register_postfix_operator] and the subscript setting of [Syntax::set_subscripts]
+/// apply to the whole process. A local syntax that is only used for a single parse can be
+/// built with [Syntax::add_infix_operator] and [Syntax::with_subscripts], and used with
+/// [Token::parse_with_syntax]. Printing always uses the operators of the global syntax.
+///
+/// Operators are assigned a precedence, where a higher precedence binds more tightly.
+/// The builtin operators have the following precedences:
+///
+/// | Operator | Precedence |
+/// |----------|------------|
+/// | `,` | 10 |
+/// | `==`, `!=`, `<`, `<=`, `>`, `>=` | 20 |
+/// | `+`, `-` | 30 |
+/// | `*` | 40 |
+/// | `/` | 45 |
+/// | unary `-` | 50 |
+/// | `^` | 60 |
+/// | `!`, `!!` | 70 |
+///
+/// # Examples
+///
+/// ```
+/// use symbolica::{parse, symbol};
+/// use symbolica::parser::{Associativity, Syntax};
+///
+/// Syntax::register_infix_operator("<>", symbol!("concat"), 25, Associativity::Flat).unwrap();
+/// Syntax::register_postfix_operator("'", symbol!("prime"), 70).unwrap();
+///
+/// let a = parse!("x <> y' <> z + 1").unwrap();
+/// assert_eq!(a, parse!("concat(x, prime(y), z + 1)").unwrap());
+/// assert_eq!(a.to_string(), "x<>y'<>z+1");
+/// ```
+#[derive(Clone, Default)]
+pub struct Syntax {
+    operators: Vec<CustomOperator>,
+    subscripts: bool,
+}
+
+impl Syntax {
+    /// Create a syntax without custom operators and with subscripts disabled.
+    pub fn new() -> Syntax {
+        Syntax::default()
+    }
+
+    /// Register the infix operator `name` that is parsed into a function with symbol `symbol`
+    /// in the global syntax. The name must consist of ASCII punctuation and the precedence must be
+    /// larger than that of the argument separator `,` and smaller than 255.
+    ///
+    /// Registering the same operator twice is allowed, but an
+    /// operator with the same name or symbol and different settings yields an error.
+    pub fn register_infix_operator(
+        name: &str,
+        symbol: Symbol,
+        precedence: u8,
+        associativity: Associativity,
+    ) -> Result<(), String> {
+        let mut syntax = SYNTAX.write().unwrap();
+        syntax.add_infix_operator(name, symbol, precedence, associativity)?;
+        syntax.set_print_operator(symbol);
+        Ok(())
+    }
+
+    /// Register the postfix operator `name` that is parsed into a function with symbol `symbol`
+    /// in the global syntax. See [Syntax::register_infix_operator] for the restrictions.
+    pub fn register_postfix_operator(
+        name: &str,
+        symbol: Symbol,
+        precedence: u8,
+    ) -> Result<(), String> {
+        let mut syntax = SYNTAX.write().unwrap();
+        syntax.add_postfix_operator(name, symbol, precedence)?;
+        syntax.set_print_operator(symbol);
+        Ok(())
+    }
+
+    /// Store the operator of `symbol` in its symbol data, so that the printer
+    /// does not have to lock the global syntax.
+    fn set_print_operator(&self, symbol: Symbol) {
+        if let Some(op) = self.operators.iter().find(|o| o.symbol == symbol) {
+            // an identical operator may have been registered before
+            let _ = State::get_symbol_data(symbol).operator.set(op.clone());
+        }
+    }
+
+    /// Add the infix operator `name` that is parsed into a function with symbol `symbol`
+    /// to this syntax. See [Syntax::register_infix_operator] for the restrictions.
+    pub fn add_infix_operator(
+        &mut self,
+        name: &str,
+        symbol: Symbol,
+        precedence: u8,
+        associativity: Associativity,
+    ) -> Result<(), String> {
+        self.add(CustomOperator {
+            name: name.into(),
+            symbol,
+            precedence,
+            associativity,
+            postfix: false,
+        })
+    }
+
+    /// Add the postfix operator `name` that is parsed into a function with symbol `symbol`
+    /// to this syntax. See [Syntax::register_infix_operator] for the restrictions.
+    pub fn add_postfix_operator(
+        &mut self,
+        name: &str,
+        symbol: Symbol,
+        precedence: u8,
+    ) -> Result<(), String> {
+        self.add(CustomOperator {
+            name: name.into(),
+            symbol,
+            precedence,
+            associativity: Associativity::Left,
+            postfix: true,
+        })
+    }
+
+    fn add(&mut self, op: CustomOperator) -> Result<(), String> {
+        if op.name.is_empty()
+            || !op
+                .name
+                .chars()
+                .all(|c| c.is_ascii_punctuation() && !"()[]{},_`\\\"".contains(c))
+        {
+            return Err(format!(
+                "Operator '{}' must consist of ASCII punctuation, excluding brackets, \
+                 commas, underscores, backslashes, backticks and double quotes",
+                op.name
+            ));
+        }
+
+        if op.name.len() == 1 && "+-*/^".contains(op.name.as_str())
+            || BUILTIN_OPERATORS.iter().any(|(n, _)| *n == op.name)
+        {
+            return Err(format!("Operator '{}' is a builtin operator", op.name));
+        }
+
+        if op.precedence <= Operator::Argument.get_precedence() || op.precedence == u8::MAX {
+            return Err(format!(
+                "Precedence {} of operator '{}' is out of range",
+                op.precedence, op.name
+            ));
+        }
+
+        if let Some(o) = self
+            .operators
+            .iter()
+            .find(|o| o.name == op.name || o.symbol == op.symbol)
+        {
+            if *o == op {
+                return Ok(());
+            }
+
+            return Err(format!(
+                "Operator '{}' or symbol {} is already registered with different settings",
+                op.name, op.symbol
+            ));
+        }
+
+        self.operators.push(op);
+        Ok(())
+    }
+
+    /// Enable or disable subscript notation in the global syntax, where `p_mu` is parsed
+    /// as `p(mu)` and `T_mu_nu` as `T(mu,nu)`. Trailing underscores of wildcards
+    /// and the names of builtin symbols are unaffected. Subscripts are disabled by default.
+    pub fn set_subscripts(enable: bool) {
+        SYNTAX.write().unwrap().subscripts = enable;
+    }
+
+    /// Enable or disable subscript notation in this syntax. See [Syntax::set_subscripts].
+    pub fn with_subscripts(mut self, enable: bool) -> Syntax {
+        self.subscripts = enable;
+        self
+    }
+
+    /// Get the custom operator of the global syntax that is parsed into
+    /// a function with symbol `symbol`.
+    pub fn get_operator(symbol: Symbol) -> Option<CustomOperator> {
+        State::get_symbol_data(symbol).operator.get().cloned()
+    }
+
+    /// Returns `true` if `c` is the first character of an operator that
+    /// is not a single-character builtin operator.
+    fn is_operator_start(&self, c: char) -> bool {
+        matches!(c, '=' | '!' | '<' | '>') || self.operators.iter().any(|o| o.name.starts_with(c))
+    }
+
+    /// Find the longest operator that is a prefix of `input`.
+    fn match_operator(&self, input: &str) -> Option<(usize, Operator)> {
+        let builtin = BUILTIN_OPERATORS
+            .iter()
+            .filter(|(n, _)| input.starts_with(n))
+            .map(|(n, o)| (n.len(), o.clone()));
+        let custom = self
+            .operators
+            .iter()
+            .filter(|o| input.starts_with(o.name.as_str()))
+            .map(|o| (o.name.len(), Operator::Custom(o.clone())));
+
+        builtin.chain(custom).max_by_key(|(l, _)| *l)
+    }
+
+    /// Split an identifier `p_mu_nu` into the function `p(mu,nu)`, if subscripts are enabled.
+    fn split_subscripts(&self, id: &str) -> Token {
+        let namespaced_builtin = id
+            .strip_prefix("symbolica::")
+            .is_some_and(|n| State::NAMESPACED_BUILTIN_SYMBOL_NAMES.contains(&n));
+        if !self.subscripts || State::is_builtin_name(id) || namespaced_builtin {
+            return Token::ID(id.into());
         }
+
+        let body = id.trim_end_matches('_');
+        let wildcard = &id[body.len()..];
+
+        let mut parts = body.split('_');
+        let head = parts.next().unwrap();
+        let indices: Vec<_> = parts.collect();
+        if head.is_empty() || indices.is_empty() || indices.iter().any(|x| x.is_empty()) {
+            return Token::ID(id.into());
+        }
+
+        let mut args = vec![Token::ID(head.into())];
+        for (i, x) in indices.iter().enumerate() {
+            let x = if i + 1 == indices.len() {
+                format!("{}{}", x, wildcard)
+            } else {
+                x.to_string()
+            };
+
+            if x.chars().all(|c| c.is_ascii_digit()) {
+                args.push(Token::Number(x.into()));
+            } else {
+                args.push(Token::ID(x.into()));
+            }
+        }
+
+        Token::Fn(false, false, args)
     }
 }
 
@@ -169,10 +526,12 @@ pub enum Token {
     RationalPolynomial(SmartString<LazyCompact>),
     Op(bool, bool, Operator, Vec<Token>),
     Fn(bool, bool, Vec<Token>),
+    List(bool, Vec<Token>),
     Start,
     OpenParenthesis,
     CloseParenthesis,
     CloseBracket,
+    CloseBrace,
     EOF,
 }
 
@@ -199,6 +558,7 @@ impl std::fmt::Display for Token {
                             Operator::Argument => f.write_char(',')?,
                             Operator::Neg => f.write_char('-')?,
                             Operator::Inv => f.write_str("1/")?,
+                            o => o.fmt(f)?,
                         }
                     } else if *o == Operator::Neg {
                         f.write_char('-')?;
@@ -209,6 +569,10 @@ impl std::fmt::Display for Token {
 
                     mm.fmt(f)?;
                 }
+
+                if o.get_arity() == 1 && *o != Operator::Neg && *o != Operator::Inv {
+                    o.fmt(f)?;
+                }
                 f.write_char(')')
             }
             Token::Fn(_, _, args) => {
@@ -230,10 +594,21 @@ impl std::fmt::Display for Token {
                 }
                 f.write_char(')')
             }
+            Token::List(_, args) => {
+                f.write_char('{')?;
+                for (i, aa) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    aa.fmt(f)?;
+                }
+                f.write_char('}')
+            }
             Token::Start => f.write_str("START"),
             Token::OpenParenthesis => f.write_char('('),
             Token::CloseParenthesis => f.write_char(')'),
             Token::CloseBracket => f.write_char(']'),
+            Token::CloseBrace => f.write_char('}'),
             Token::EOF => f.write_str("EOF"),
         }
     }
@@ -248,6 +623,7 @@ impl Token {
             Token::RationalPolynomial(_) => true,
             Token::Op(more_left, more_right, _, _) => !more_left && !more_right,
             Token::Fn(more_right, _, _) => !more_right,
+            Token::List(more_right, _) => !more_right,
             _ => false,
         }
     }
@@ -256,14 +632,16 @@ impl Token {
     #[inline]
     fn get_precedence(&self) -> u8 {
         match self {
-            Token::Number(_) => 11,
-            Token::ID(_) => 11,
-            Token::RationalPolynomial(_) => 11,
+            Token::Number(_) => u8::MAX,
+            Token::ID(_) => u8::MAX,
+            Token::RationalPolynomial(_) => u8::MAX,
             Token::Op(_, _, o, _) => o.get_precedence(),
             Token::Fn(_, _, _)
+            | Token::List(_, _)
             | Token::OpenParenthesis
             | Token::CloseParenthesis
-            | Token::CloseBracket => 5,
+            | Token::CloseBracket
+            | Token::CloseBrace => 5,
             Token::Start | Token::EOF => 4,
         }
     }
@@ -473,6 +851,48 @@ impl Token {
 
                     out.to_pow(base.as_view(), num.as_view());
                 }
+                Operator::Custom(c) if !c.postfix && c.associativity != Associativity::Flat => {
+                    let mut atoms = Vec::with_capacity(args.len());
+                    for a in args {
+                        let mut atom = workspace.new_atom();
+                        a.to_atom_with_output_no_norm(namespace, state, workspace, &mut atom)?;
+                        atoms.push(atom);
+                    }
+
+                    // fold the arguments into nested binary functions
+                    if c.associativity == Associativity::Right {
+                        atoms.reverse();
+                    }
+
+                    let mut atoms = atoms.into_iter();
+                    out.set_from_view(&atoms.next().unwrap().as_view());
+                    for a in atoms {
+                        let mut fun_h = workspace.new_atom();
+                        let fun = fun_h.to_fun(c.symbol);
+                        if c.associativity == Associativity::Left {
+                            fun.add_arg(out.as_view());
+                            fun.add_arg(a.as_view());
+                        } else {
+                            fun.add_arg(a.as_view());
+                            fun.add_arg(out.as_view());
+                        }
+                        out.set_from_view(&fun_h.as_view());
+                    }
+                }
+                _ => {
+                    // relations, postfix operators and flat operators map to a single function
+                    let symbol = match op {
+                        Operator::Custom(c) => c.symbol,
+                        _ => op.get_builtin_symbol().unwrap(),
+                    };
+
+                    let fun = out.to_fun(symbol);
+                    let mut atom = workspace.new_atom();
+                    for a in args {
+                        a.to_atom_with_output_no_norm(namespace, state, workspace, &mut atom)?;
+                        fun.add_arg(atom.as_view());
+                    }
+                }
             },
             Token::Fn(_, _, args) => {
                 let name = match &args[0] {
@@ -487,6 +907,14 @@ impl Token {
                     fun.add_arg(atom.as_view());
                 }
             }
+            Token::List(_, args) => {
+                let fun = out.to_fun(Atom::LIST);
+                let mut atom = workspace.new_atom();
+                for a in args {
+                    a.to_atom_with_output_no_norm(namespace, state, workspace, &mut atom)?;
+                    fun.add_arg(atom.as_view());
+                }
+            }
             Token::RationalPolynomial(_) => Err(format!(
                 "Optimized rational polynomial input cannot be parsed yet as atom: {}",
                 self
@@ -621,6 +1049,7 @@ impl Token {
                     pow_h.to_pow(base.as_view(), num.as_view());
                     pow_h.as_view().normalize(workspace, out);
                 }
+                _ => return Err(format!("Unsupported operator '{}'", op)),
             },
             Token::Fn(_, _, args) => {
                 let name = match &args[0] {
@@ -657,17 +1086,46 @@ impl Token {
         Ok(())
     }
 
-    /// Parse a Symbolica expression.
+    /// Parse a Symbolica expression, using the global syntax.
     pub fn parse(input: &str) -> Result<Token, String> {
+        Self::parse_with_syntax(input, &SYNTAX.read().unwrap())
+    }
+
+    /// Parse a Symbolica expression, using the custom operators and
+    /// subscript setting of `syntax` instead of those of the global syntax.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolica::{atom::Atom, parse, symbol, wrap_input};
+    /// use symbolica::parser::{Associativity, Syntax};
+    ///
+    /// let mut syntax = Syntax::new().with_subscripts(true);
+    /// syntax.add_infix_operator("<>", symbol!("concat"), 25, Associativity::Flat).unwrap();
+    ///
+    /// let a = Atom::parse_with_syntax(wrap_input!("p_mu <> q"), &syntax).unwrap();
+    /// assert_eq!(a, parse!("concat(p(mu), q)").unwrap());
+    /// ```
+    pub fn parse_with_syntax(input: &str, syntax: &Syntax) -> Result<Token, String> {
         LicenseManager::check();
 
+        let max_operator_len = syntax
+            .operators
+            .iter()
+            .map(|o| o.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(2);
+
         let mut stack: Vec<_> = Vec::with_capacity(20);
         stack.push(Token::Start);
         let mut state = ParseState::Any;
 
-        let ops = ['\0', '^', '+', '*', '-', '(', ')', '/', ',', '[', ']'];
+        let ops = [
+            '\0', '^', '+', '*', '-', '(', ')', '/', ',', '[', ']', '{', '}',
+        ];
         let whitespace = [' ', '\t', '\n', '\r', '\\'];
-        let forbidden = [';', '&', '!', '%', '.', '"'];
+        let forbidden = [';', '&', '%', '.', '"'];
 
         let mut char_iter = input.chars();
         let mut c = char_iter.next().unwrap_or('\0'); // add EOF as a token
@@ -681,9 +1139,9 @@ impl Token {
         loop {
             match state {
                 ParseState::Identifier => {
-                    if ops.contains(&c) || whitespace.contains(&c) {
+                    if ops.contains(&c) || whitespace.contains(&c) || syntax.is_operator_start(c) {
                         state = ParseState::Any;
-                        stack.push(Token::ID(id_buffer.as_str().into()));
+                        stack.push(syntax.split_subscripts(&id_buffer));
                         id_buffer.clear();
                     } else if !forbidden.contains(&c) {
                         id_buffer.push(c);
@@ -791,13 +1249,52 @@ impl Token {
                     continue;
                 }
 
+                // match relations and custom operators, which may consist of multiple characters
+                let mut operator = None;
+                if syntax.is_operator_start(c) {
+                    let lookahead: String = std::iter::once(c)
+                        .chain(extra_ops.iter().cloned())
+                        .chain(char_iter.clone())
+                        .take(max_operator_len)
+                        .collect();
+                    operator = syntax.match_operator(&lookahead);
+                }
+
                 match c {
+                    _ if operator.is_some() => {
+                        let (len, op) = operator.take().unwrap();
+
+                        // consume the remaining characters of the operator
+                        for _ in 1..len {
+                            if extra_ops.is_empty() {
+                                column_counter += 1;
+                                char_iter.next();
+                            } else {
+                                extra_ops.remove(0);
+                            }
+                        }
+
+                        if op.get_arity() == 1 {
+                            if !unsafe { stack.last().unwrap_unchecked() }.is_normal() {
+                                Err(format!(
+                                    "Error at line {} and position {}: operator '{}' is missing left-hand side",
+                                    line_counter, column_counter, op,
+                                ))?;
+                            }
+
+                            // postfix operator only requires an argument to the left
+                            stack.push(Token::Op(true, false, op, vec![]));
+                        } else {
+                            stack.push(Token::Op(true, true, op, vec![]));
+                        }
+                    }
                     '+' => {
                         if matches!(
                             unsafe { stack.last().unwrap_unchecked() },
                             Token::Start
                                 | Token::OpenParenthesis
                                 | Token::Fn(true, _, _)
+                                | Token::List(true, _)
                                 | Token::Op(_, true, _, _)
                         ) {
                             // unary + operator, can be ignored as plus is the default
@@ -813,6 +1310,7 @@ impl Token {
                             Token::Start
                                 | Token::OpenParenthesis
                                 | Token::Fn(true, _, _)
+                                | Token::List(true, _)
                                 | Token::Op(_, true, _, _)
                         ) {
                             // unary minus only requires an argument to the right
@@ -844,6 +1342,7 @@ impl Token {
                             Token::Start
                                 | Token::OpenParenthesis
                                 | Token::Fn(true, _, _)
+                                | Token::List(true, _)
                                 | Token::Op(_, true, _, _)
                         ) {
                             // unary inv only requires an argument to the right
@@ -872,6 +1371,16 @@ impl Token {
                         }
                     }
                     ']' => stack.push(Token::CloseBracket),
+                    '{' => {
+                        if unsafe { stack.last().unwrap_unchecked() }.is_normal() {
+                            // insert multiplication: x{1,2} -> x*{1,2}
+                            stack.push(Token::Op(true, true, Operator::Mul, vec![]));
+                            extra_ops.push(c);
+                        } else {
+                            stack.push(Token::List(true, vec![]));
+                        }
+                    }
+                    '}' => stack.push(Token::CloseBrace),
                     _ => {
                         if unsafe { stack.last().unwrap_unchecked() }.is_normal()
                             && (!c.is_ascii_digit()
@@ -952,6 +1461,21 @@ impl Token {
                                 ))?;
                             }
                         }
+                        Token::CloseBrace => {
+                            // check if we have an empty list
+                            let pos = stack.len() - 2;
+                            if let Token::List(more_right, _) =
+                                unsafe { stack.get_unchecked_mut(pos) }
+                            {
+                                *more_right = false;
+                                stack.pop();
+                            } else {
+                                Err(format!(
+                                    "Error at line {} and position {}: unexpected '}}'",
+                                    line_counter, column_counter
+                                ))?;
+                            }
+                        }
                         _ => {}
                     }
 
@@ -1018,8 +1542,23 @@ impl Token {
                                     args.push(mid);
                                 }
                             }
+                            (Token::List(mr, args), mid, Token::CloseBrace) => {
+                                debug_assert!(*mr);
+                                *mr = false;
+
+                                if let Token::Op(_, _, Operator::Argument, arg2) = mid {
+                                    args.extend(arg2);
+                                } else {
+                                    args.push(mid);
+                                }
+                            }
                             (Token::OpenParenthesis, mid, Token::CloseParenthesis) => {
-                                *first = mid;
+                                // a parenthesized sequence such as `(1,2)` is a tuple, which is a list
+                                if let Token::Op(_, _, Operator::Argument, args) = mid {
+                                    *first = Token::List(false, args);
+                                } else {
+                                    *first = mid;
+                                }
                             }
                             (
                                 Token::Op(ml1, mr1, o1, m),
@@ -1095,6 +1634,9 @@ impl Token {
                     "Unexpected end of input: Missing closing parenthesis for function '{}'",
                     args[0]
                 )),
+                Some(Token::List(true, _)) => {
+                    Err("Unexpected end of input: list is not closed".to_string())
+                }
                 Some(Token::Start) => Err("Expression is empty".to_string()),
                 _ => Err(format!("Unknown parsing error: {:?}", stack)),
             }
@@ -1316,7 +1858,12 @@ mod test {
     use std::sync::Arc;
 
     use crate::{
-        atom::AtomCore, domains::integer::Z, parse, parser::Token, printer::PrintOptions, symbol,
+        atom::{Atom, AtomCore},
+        domains::integer::Z,
+        function, parse,
+        parser::{Associativity, Syntax, Token},
+        printer::PrintOptions,
+        symbol, wrap_input,
     };
 
     #[test]
@@ -1375,6 +1922,123 @@ mod test {
         assert_eq!(input, res);
     }
 
+    #[test]
+    fn relations_and_lists() {
+        let input = parse!("v1+1 <= v2^2 < 3 != {v1, {}, -v2!}").unwrap();
+        let res =
+            parse!("unequal(less(less_equal(v1+1, v2^2), 3), list(v1, list(), -factorial(v2)))")
+                .unwrap();
+        assert_eq!(input, res);
+        assert_eq!(
+            format!("{}", input.printer(PrintOptions::file_no_namespace())),
+            "v1+1<=v2^2<3!={v1,{},-v2!}"
+        );
+
+        assert_eq!(
+            parse!("5!+(1/2)!").unwrap(),
+            parse!("120+1/2*𝜋^(1/2)").unwrap()
+        );
+        assert_eq!(
+            parse!("5!! + (3!)! + v1!! + (v2!)!").unwrap(),
+            parse!("735 + factorial2(v1) + factorial(factorial(v2))").unwrap()
+        );
+        for x in ["v1!!", "(v1!)!", "(v1!!)!"] {
+            let input = parse!(x).unwrap();
+            assert_eq!(
+                format!("{}", input.printer(PrintOptions::file_no_namespace())),
+                x
+            );
+        }
+        assert_eq!(
+            parse!("v1 == v2 == v3").unwrap(),
+            parse!("equal(v1,v2,v3)").unwrap()
+        );
+        assert_eq!(
+            parse!("(1, v1 + 1) + f1((v1, v2), v3)").unwrap(),
+            parse!("{1, v1 + 1} + f1({v1, v2}, v3)").unwrap()
+        );
+        assert!(parse!("{v1, v2").is_err());
+        assert!(parse!("(v1, v2").is_err());
+        assert!(parse!("!v1").is_err());
+    }
+
+    #[test]
+    fn custom_operators() {
+        // use symbols and a local syntax that no other test uses,
+        // as the global syntax is shared by the whole process
+        let (f, g, h) = (symbol!("fop1"), symbol!("fop2"), symbol!("fop3"));
+        let mut syntax = Syntax::new();
+        syntax
+            .add_infix_operator("**", f, 65, Associativity::Right)
+            .unwrap();
+        syntax
+            .add_infix_operator("<.>", g, 35, Associativity::Left)
+            .unwrap();
+        syntax.add_postfix_operator("'", h, 70).unwrap();
+        for (name, associativity) in [
+            ("**", Associativity::Right),
+            ("<", Associativity::Left),
+            ("a", Associativity::Left),
+        ] {
+            let mut syntax = syntax.clone();
+            assert!(syntax
+                .add_infix_operator(name, h, 65, associativity)
+                .is_err());
+        }
+
+        let parse = |input: &str| Atom::parse_with_syntax(wrap_input!(input), &syntax).unwrap();
+        let input = parse("v1**v2**v3 <.> v1' <.> (v2+v3) < v1");
+        let (v1, v2, v3) = (
+            parse!("v1").unwrap(),
+            parse!("v2").unwrap(),
+            parse!("v3").unwrap(),
+        );
+        let res = function!(
+            symbol!("less"),
+            function!(
+                g,
+                function!(
+                    g,
+                    function!(f, &v1, function!(f, &v2, &v3)),
+                    function!(h, &v1)
+                ),
+                &v2 + &v3
+            ),
+            &v1
+        );
+        assert_eq!(input, res);
+        assert!(parse!("v1 <.> v2").is_err());
+
+        // the printer uses the global syntax, so register operators that no other test uses
+        Syntax::register_infix_operator("<^>", f, 65, Associativity::Right).unwrap();
+        Syntax::register_infix_operator("<:>", g, 35, Associativity::Left).unwrap();
+        Syntax::register_postfix_operator("~", h, 70).unwrap();
+        let input2 = parse("(v1**v2)**v3 + (v1<.>v2)*v3");
+        assert_eq!(
+            format!("{}", input.printer(PrintOptions::file_no_namespace())),
+            "v1<^>v2<^>v3<:>v1~<:>(v2+v3)<v1"
+        );
+        assert_eq!(
+            format!("{}", input2.printer(PrintOptions::file_no_namespace())),
+            "(v1<^>v2)<^>v3+v3*(v1<:>v2)"
+        );
+    }
+
+    #[test]
+    fn subscripts() {
+        let syntax = Syntax::new().with_subscripts(true);
+        let input = Atom::parse_with_syntax(
+            wrap_input!("p_mu_1 + x_ + symbolica::bessel_j(0, v1)"),
+            &syntax,
+        )
+        .unwrap();
+
+        let res = parse!("p(mu, 1) + x_ + bessel_j(0, v1)").unwrap();
+        assert_eq!(input, res);
+        assert_eq!(parse!("p_mu").unwrap(), parse!("p_mu").unwrap());
+        assert_ne!(parse!("p_mu").unwrap(), parse!("p(mu)").unwrap());
+    }
+
     #[test]
     fn poly() {
         let var_names = ["v1".into(), "v2".into()];
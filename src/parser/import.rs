@@ -0,0 +1,1075 @@
+//! Parsing of expressions written in the syntax of other computer algebra systems.
+//!
+//! The input is converted into a [Token] tree in which the builtin functions and
+//! constants of the other system are replaced by their Symbolica counterparts,
+//! such as `Sin[x]` by `sin(x)` and `Pi` by `𝜋`.
+
+use smartstring::{LazyCompact, SmartString};
+
+use super::{Operator, Syntax, Token};
+use crate::state::State;
+
+/// The syntax of an expression that is parsed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    #[default]
+    Symbolica,
+    /// The InputForm or FullForm of Mathematica, e.g. `Sin[x]^2` or `Power[Sin[x], 2]`.
+    Mathematica,
+    /// A subset of LaTeX math, including `\frac`, `\sqrt`, `^{}`, `\left(` and `\sin`.
+    /// The letters `e` and `i` are read as the constants `𝑒` and `𝑖`.
+    Latex,
+    /// The output of `str` and `srepr` of SymPy, e.g. `sin(x)**2` or `Pow(Symbol('x'), Integer(2))`.
+    Sympy,
+    /// The output of FORM, e.g. `F = x^2*sqrt_(2) + i_;`.
+    Form,
+}
+
+impl Token {
+    /// Parse an expression written in the syntax `mode`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolica::parser::{ParseMode, Token};
+    ///
+    /// let t = Token::parse_with_mode("Sqrt[x] + Pi", ParseMode::Mathematica).unwrap();
+    /// assert_eq!(t, Token::parse("sqrt(x)+𝜋").unwrap());
+    /// ```
+    pub fn parse_with_mode(input: &str, mode: ParseMode) -> Result<Token, String> {
+        // the custom operators and subscripts of the global syntax
+        // only apply to Symbolica's own syntax
+        let default = Syntax::default();
+        match mode {
+            ParseMode::Symbolica => Token::parse(input),
+            ParseMode::Mathematica => {
+                let t = Token::parse_with_syntax(&preprocess_mathematica(input), &default)?;
+                rewrite(t, mathematica_variable, mathematica_function)
+            }
+            ParseMode::Latex => {
+                Token::parse_with_syntax(&LatexParser::new(input)?.parse()?, &default)
+            }
+            ParseMode::Sympy => {
+                let t = Token::parse_with_syntax(&preprocess_sympy(input), &default)?;
+                rewrite(t, sympy_variable, sympy_function)
+            }
+            ParseMode::Form => {
+                let t = Token::parse_with_syntax(&preprocess_form(input), &default)?;
+                rewrite(t, form_variable, form_function)
+            }
+        }
+    }
+}
+
+type VariableRule = fn(&str) -> Option<&'static str>;
+type FunctionRule = fn(&str, Vec<Token>) -> Result<Token, String>;
+
+/// Rewrite the variables and functions of the token tree `t` with the rules of another system.
+fn rewrite(t: Token, variable: VariableRule, function: FunctionRule) -> Result<Token, String> {
+    let rewrite_all = |args: Vec<Token>| {
+        args.into_iter()
+            .map(|a| rewrite(a, variable, function))
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    match t {
+        Token::ID(name) => Ok(match variable(&name) {
+            Some(n) => Token::ID(n.into()),
+            None => Token::ID(name),
+        }),
+        Token::Op(ml, mr, o, args) => Ok(Token::Op(ml, mr, o, rewrite_all(args)?)),
+        Token::List(mr, args) => Ok(Token::List(mr, rewrite_all(args)?)),
+        Token::Fn(_, _, mut args) => {
+            let Token::ID(name) = args.remove(0) else {
+                unreachable!()
+            };
+            function(&name, rewrite_all(args)?)
+        }
+        t => Ok(t),
+    }
+}
+
+fn op(o: Operator, args: Vec<Token>) -> Token {
+    Token::Op(false, false, o, args)
+}
+
+fn fun(name: &str, args: Vec<Token>) -> Token {
+    let mut f = vec![Token::ID(name.into())];
+    f.extend(args);
+    Token::Fn(false, false, f)
+}
+
+/// Build `a/b`.
+fn div(a: Token, b: Token) -> Token {
+    op(Operator::Mul, vec![a, op(Operator::Inv, vec![b])])
+}
+
+/// Build a sum or product, which is `empty` without arguments.
+fn nary(o: Operator, args: Vec<Token>, empty: &str) -> Token {
+    match args.len() {
+        0 => Token::Number(empty.into()),
+        1 => args.into_iter().next().unwrap(),
+        _ => op(o, args),
+    }
+}
+
+/// Check that the function `name` has `n` arguments.
+fn check_args(name: &str, args: &[Token], n: usize) -> Result<(), String> {
+    if args.len() == n {
+        Ok(())
+    } else {
+        Err(format!(
+            "Function {} expects {} arguments, but {} were given",
+            name,
+            n,
+            args.len()
+        ))
+    }
+}
+
+/// Rename a builtin function that has a direct counterpart in Symbolica.
+fn builtin_function(name: &str, args: Vec<Token>, renamed: Option<&str>) -> Token {
+    match renamed {
+        Some(r) => fun(&builtin_name(r), args),
+        None => fun(name, args),
+    }
+}
+
+/// Get the name of the Symbolica builtin `name`, including the namespace if the name is not reserved.
+fn builtin_name(name: &str) -> String {
+    if State::NAMESPACED_BUILTIN_SYMBOL_NAMES.contains(&name) {
+        format!("symbolica::{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Remove comments, convert numbers in scientific notation such as `2.5*^10` and
+/// strip the trailing dot of a precision specification `1.5`20.`.
+fn preprocess_mathematica(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    let mut last = ' ';
+    let mut in_precision = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '(' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == ')' {
+                        break;
+                    }
+                    prev = c;
+                }
+                out.push(' ');
+                last = ' ';
+                continue;
+            }
+            '*' if chars.peek() == Some(&'^') && (last.is_ascii_digit() || last == '.') => {
+                chars.next();
+                out.push('e');
+                last = 'e';
+                continue;
+            }
+            '`' => in_precision = true,
+            '.' if in_precision => {
+                in_precision = false;
+                continue;
+            }
+            c if !c.is_ascii_digit() => in_precision = false,
+            _ => {}
+        }
+
+        out.push(c);
+        last = c;
+    }
+
+    out
+}
+
+fn mathematica_variable(name: &str) -> Option<&'static str> {
+    match name {
+        "Pi" => Some("𝜋"),
+        "E" => Some("𝑒"),
+        "I" => Some("𝑖"),
+        _ => None,
+    }
+}
+
+fn mathematica_function(name: &str, mut args: Vec<Token>) -> Result<Token, String> {
+    let renamed = match name {
+        "Plus" => return Ok(nary(Operator::Add, args, "0")),
+        "Times" => return Ok(nary(Operator::Mul, args, "1")),
+        "Power" if args.len() >= 2 => return Ok(op(Operator::Pow, args)),
+        "Rational" => {
+            check_args(name, &args, 2)?;
+            let b = args.pop().unwrap();
+            return Ok(div(args.pop().unwrap(), b));
+        }
+        "Complex" => {
+            check_args(name, &args, 2)?;
+            let b = args.pop().unwrap();
+            let i = Token::ID("𝑖".into());
+            return Ok(op(
+                Operator::Add,
+                vec![args.pop().unwrap(), op(Operator::Mul, vec![b, i])],
+            ));
+        }
+        "List" => return Ok(Token::List(false, args)),
+        "Equal" => return Ok(op(Operator::Equal, args)),
+        "Unequal" => return Ok(op(Operator::Unequal, args)),
+        "Less" => return Ok(op(Operator::Less, args)),
+        "LessEqual" => return Ok(op(Operator::LessEqual, args)),
+        "Greater" => return Ok(op(Operator::Greater, args)),
+        "GreaterEqual" => return Ok(op(Operator::GreaterEqual, args)),
+        "Log" if args.len() == 2 => {
+            // Log[b, x] is the logarithm of x in base b
+            let x = args.pop().unwrap();
+            let b = args.pop().unwrap();
+            return Ok(div(fun("log", vec![x]), fun("log", vec![b])));
+        }
+        "ArcTan" if args.len() == 2 => {
+            // ArcTan[x, y] is the arc tangent of y/x
+            args.swap(0, 1);
+            Some("arctan")
+        }
+        "Sin" => Some("sin"),
+        "Cos" => Some("cos"),
+        "Tan" => Some("tan"),
+        "ArcSin" => Some("arcsin"),
+        "ArcCos" => Some("arccos"),
+        "ArcTan" => Some("arctan"),
+        "Sinh" => Some("sinh"),
+        "Cosh" => Some("cosh"),
+        "Tanh" => Some("tanh"),
+        "ArcSinh" => Some("arcsinh"),
+        "ArcCosh" => Some("arccosh"),
+        "ArcTanh" => Some("arctanh"),
+        "Exp" => Some("exp"),
+        "Log" => Some("log"),
+        "Sqrt" => Some("sqrt"),
+        "Abs" => Some("abs"),
+        "Sign" => Some("sign"),
+        "HeavisideTheta" => Some("heaviside"),
+        "Gamma" => Some("gamma"),
+        "PolyGamma" => Some("polygamma"),
+        "Beta" => Some("beta"),
+        "Erf" => Some("erf"),
+        "Zeta" => Some("zeta"),
+        "PolyLog" => Some("li"),
+        "BesselJ" => Some("bessel_j"),
+        "BesselY" => Some("bessel_y"),
+        "BesselI" => Some("bessel_i"),
+        "BesselK" => Some("bessel_k"),
+        "Factorial" => Some("factorial"),
+        "Factorial2" => Some("factorial2"),
+        _ => None,
+    };
+
+    Ok(builtin_function(name, args, renamed))
+}
+
+/// Convert `**` into `^`, strip quotes and keyword arguments, write lists
+/// with curly brackets and write undefined functions `Function('f')(x)` as `f(x)`.
+fn preprocess_sympy(input: &str) -> String {
+    let input = input
+        .replace("**", "^")
+        .replace(['\'', '"'], "")
+        .replace('[', "{")
+        .replace(']', "}");
+
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input.as_str();
+    while let Some((before, after)) = rest.split_once(',') {
+        out.push_str(before);
+
+        // skip a keyword argument `, name=value`
+        let trimmed = after.trim_start();
+        let name_len = trimmed
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(trimmed.len());
+        let value = trimmed[name_len..].trim_start();
+        if name_len > 0 && value.starts_with('=') && !value.starts_with("==") {
+            let mut depth = 0;
+            let end = value
+                .find(|c| {
+                    match c {
+                        '(' | '{' => depth += 1,
+                        ')' | '}' if depth > 0 => depth -= 1,
+                        ')' | '}' | ',' if depth == 0 => return true,
+                        _ => {}
+                    }
+                    false
+                })
+                .unwrap_or(value.len());
+            rest = &value[end..];
+        } else {
+            out.push(',');
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+
+    let mut input = out;
+    while let Some(pos) = input.find("Function(") {
+        let start = pos + "Function(".len();
+        let Some(len) = input[start..].find(')') else {
+            break;
+        };
+
+        let name = input[start..start + len].trim().to_string();
+        input.replace_range(pos..start + len + 1, &name);
+    }
+
+    input
+}
+
+fn sympy_variable(name: &str) -> Option<&'static str> {
+    match name {
+        "pi" => Some("𝜋"),
+        "E" => Some("𝑒"),
+        "I" => Some("𝑖"),
+        _ => None,
+    }
+}
+
+fn sympy_function(name: &str, mut args: Vec<Token>) -> Result<Token, String> {
+    let renamed = match name {
+        "Add" => return Ok(nary(Operator::Add, args, "0")),
+        "Mul" => return Ok(nary(Operator::Mul, args, "1")),
+        "Pow" => {
+            check_args(name, &args, 2)?;
+            return Ok(op(Operator::Pow, args));
+        }
+        "Symbol" | "Integer" | "Float" | "Dummy" => {
+            check_args(name, &args, 1)?;
+            return Ok(args.pop().unwrap());
+        }
+        "Rational" => {
+            check_args(name, &args, 2)?;
+            let b = args.pop().unwrap();
+            return Ok(div(args.pop().unwrap(), b));
+        }
+        "Tuple" | "List" => return Ok(Token::List(false, args)),
+        "Eq" | "Equality" => return Ok(op(Operator::Equal, args)),
+        "Ne" | "Unequality" => return Ok(op(Operator::Unequal, args)),
+        "Lt" | "StrictLessThan" => return Ok(op(Operator::Less, args)),
+        "Le" | "LessThan" => return Ok(op(Operator::LessEqual, args)),
+        "Gt" | "StrictGreaterThan" => return Ok(op(Operator::Greater, args)),
+        "Ge" | "GreaterThan" => return Ok(op(Operator::GreaterEqual, args)),
+        "log" if args.len() == 2 => {
+            // log(x, b) is the logarithm of x in base b
+            let b = args.pop().unwrap();
+            let x = args.pop().unwrap();
+            return Ok(div(fun("log", vec![x]), fun("log", vec![b])));
+        }
+        "tan" | "sinh" | "cosh" | "tanh" | "sign" | "gamma" | "polygamma" | "beta" | "erf"
+        | "zeta" | "factorial" | "factorial2" => Some(name),
+        "asin" => Some("arcsin"),
+        "acos" => Some("arccos"),
+        "atan" | "atan2" => Some("arctan"),
+        "asinh" => Some("arcsinh"),
+        "acosh" => Some("arccosh"),
+        "atanh" => Some("arctanh"),
+        "Abs" => Some("abs"),
+        "Heaviside" => Some("heaviside"),
+        "polylog" => Some("li"),
+        "besselj" => Some("bessel_j"),
+        "bessely" => Some("bessel_y"),
+        "besseli" => Some("bessel_i"),
+        "besselk" => Some("bessel_k"),
+        _ => None,
+    };
+
+    Ok(builtin_function(name, args, renamed))
+}
+
+/// Remove the name of the expression `F =`, the terminating `;` and
+/// the line continuations of long numbers.
+fn preprocess_form(input: &str) -> String {
+    let mut input = input.trim();
+
+    if let Some((name, rest)) = input.split_once('=') {
+        if !name.is_empty()
+            && !rest.starts_with('=')
+            && name
+                .trim()
+                .chars()
+                .all(|c| c.is_alphanumeric() || "_()[],".contains(c))
+        {
+            input = rest;
+        }
+    }
+
+    input
+        .trim_end()
+        .trim_end_matches(';')
+        .replace("\\\n", "")
+        .replace("**", "^")
+}
+
+fn form_variable(name: &str) -> Option<&'static str> {
+    match name {
+        "i_" => Some("𝑖"),
+        "pi_" => Some("𝜋"),
+        "ee_" => Some("𝑒"),
+        _ => None,
+    }
+}
+
+fn form_function(name: &str, mut args: Vec<Token>) -> Result<Token, String> {
+    let renamed = match name {
+        "li2_" => {
+            check_args(name, &args, 1)?;
+            args.insert(0, Token::Number("2".into()));
+            Some("li")
+        }
+        "sqrt_" => Some("sqrt"),
+        "eexp_" => Some("exp"),
+        "ln_" => Some("log"),
+        "sin_" => Some("sin"),
+        "cos_" => Some("cos"),
+        "tan_" => Some("tan"),
+        "asin_" => Some("arcsin"),
+        "acos_" => Some("arccos"),
+        "atan_" | "atan2_" => Some("arctan"),
+        "sinh_" => Some("sinh"),
+        "cosh_" => Some("cosh"),
+        "tanh_" => Some("tanh"),
+        "asinh_" => Some("arcsinh"),
+        "acosh_" => Some("arccosh"),
+        "atanh_" => Some("arctanh"),
+        "abs_" => Some("abs"),
+        "fac_" => Some("factorial"),
+        _ => None,
+    };
+
+    Ok(builtin_function(name, args, renamed))
+}
+
+/// A token of a LaTeX expression.
+#[derive(Debug, Clone, PartialEq)]
+enum LatexToken {
+    Number(SmartString<LazyCompact>),
+    Identifier(SmartString<LazyCompact>),
+    Command(SmartString<LazyCompact>),
+    Char(char),
+}
+
+/// A converter of LaTeX math into Symbolica syntax.
+struct LatexParser {
+    /// The tokens and whether they are preceded by whitespace.
+    tokens: Vec<(LatexToken, bool)>,
+    pos: usize,
+}
+
+impl LatexParser {
+    fn new(input: &str) -> Result<LatexParser, String> {
+        let mut tokens = vec![];
+        let mut chars = input.chars().peekable();
+        let mut space = false;
+
+        while let Some(c) = chars.next() {
+            let t = match c {
+                c if c.is_whitespace() => {
+                    space = true;
+                    continue;
+                }
+                '\\' => {
+                    let mut name = SmartString::new();
+                    while let Some(&c) = chars.peek() {
+                        if !c.is_ascii_alphabetic() {
+                            break;
+                        }
+                        name.push(c);
+                        chars.next();
+                    }
+
+                    if name.is_empty() {
+                        match chars.next() {
+                            Some('!') => continue, // negative thin space, used before function arguments
+                            Some(',' | ';' | ':' | ' ') | None => {
+                                space = true;
+                                continue;
+                            }
+                            Some(c) => name.push(c),
+                        }
+                    } else if matches!(name.as_str(), "quad" | "qquad") {
+                        space = true;
+                        continue;
+                    }
+
+                    LatexToken::Command(name)
+                }
+                c if c.is_ascii_digit() || c == '.' => {
+                    let mut n = SmartString::new();
+                    n.push(c);
+                    while let Some(&c) = chars.peek() {
+                        if !c.is_ascii_digit() && c != '.' {
+                            break;
+                        }
+                        n.push(c);
+                        chars.next();
+                    }
+                    LatexToken::Number(n)
+                }
+                c if c.is_alphabetic() => {
+                    let mut n = SmartString::new();
+                    n.push(c);
+                    while let Some(&c) = chars.peek() {
+                        if !c.is_alphanumeric() {
+                            break;
+                        }
+                        n.push(c);
+                        chars.next();
+                    }
+                    LatexToken::Identifier(n)
+                }
+                c if "+-*/^_()[]{}=<>!,|".contains(c) => LatexToken::Char(c),
+                c => return Err(format!("Unexpected character '{}' in LaTeX input", c)),
+            };
+
+            tokens.push((t, space));
+            space = false;
+        }
+
+        Ok(LatexParser { tokens, pos: 0 })
+    }
+
+    fn peek(&self) -> Option<&LatexToken> {
+        self.tokens.get(self.pos).map(|t| &t.0)
+    }
+
+    fn next(&mut self) -> Option<LatexToken> {
+        let t = self.tokens.get(self.pos).map(|t| t.0.clone());
+        self.pos += 1;
+        t
+    }
+
+    /// Returns `true` if the next token is preceded by whitespace.
+    fn has_space(&self) -> bool {
+        self.tokens.get(self.pos).map(|t| t.1).unwrap_or(false)
+    }
+
+    fn is_char(&self, c: char) -> bool {
+        self.peek() == Some(&LatexToken::Char(c))
+    }
+
+    fn is_command(&self, name: &str) -> bool {
+        matches!(self.peek(), Some(LatexToken::Command(n)) if n == name)
+    }
+
+    fn expect_char(&mut self, c: char) -> Result<(), String> {
+        match self.next() {
+            Some(LatexToken::Char(d)) if c == d => Ok(()),
+            t => Err(format!(
+                "Expected '{}' in LaTeX input, but found {:?}",
+                c, t
+            )),
+        }
+    }
+
+    fn expect_command(&mut self, name: &str) -> Result<(), String> {
+        match self.next() {
+            Some(LatexToken::Command(n)) if n == name => Ok(()),
+            t => Err(format!(
+                "Expected '\\{}' in LaTeX input, but found {:?}",
+                name, t
+            )),
+        }
+    }
+
+    fn parse(&mut self) -> Result<String, String> {
+        let r = self.expression()?;
+        if let Some(t) = self.peek() {
+            return Err(format!("Unexpected {:?} in LaTeX input", t));
+        }
+        Ok(r)
+    }
+
+    /// Parse a relation or a sum.
+    fn expression(&mut self) -> Result<String, String> {
+        let mut out = self.sum()?;
+
+        loop {
+            let op = match self.peek() {
+                Some(LatexToken::Char('=')) => "==",
+                Some(LatexToken::Char('<')) => "<",
+                Some(LatexToken::Char('>')) => ">",
+                Some(LatexToken::Command(c)) => match c.as_str() {
+                    "neq" | "ne" => "!=",
+                    "leq" | "le" => "<=",
+                    "geq" | "ge" => ">=",
+                    _ => break,
+                },
+                _ => break,
+            };
+            self.next();
+
+            out.push_str(op);
+            out.push_str(&self.sum()?);
+        }
+
+        Ok(out)
+    }
+
+    fn sum(&mut self) -> Result<String, String> {
+        let mut out = String::new();
+
+        loop {
+            if self.is_char('+') || self.is_char('-') {
+                if let Some(LatexToken::Char(c)) = self.next() {
+                    out.push(c);
+                }
+            } else if !out.is_empty() {
+                break;
+            }
+
+            out.push_str(&self.product()?);
+
+            if !self.is_char('+') && !self.is_char('-') {
+                break;
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Returns `true` if the next token starts a factor of a product.
+    fn starts_factor(&self) -> bool {
+        match self.peek() {
+            Some(LatexToken::Number(_) | LatexToken::Identifier(_)) => true,
+            Some(LatexToken::Char(c)) => matches!(c, '(' | '{' | '|'),
+            Some(LatexToken::Command(c)) => !matches!(
+                c.as_str(),
+                "right" | "cdot" | "times" | "neq" | "ne" | "leq" | "le" | "geq" | "ge" | "}"
+            ),
+            None => false,
+        }
+    }
+
+    fn product(&mut self) -> Result<String, String> {
+        let mut out = self.power()?;
+
+        loop {
+            if self.is_command("cdot") || self.is_command("times") || self.is_char('*') {
+                self.next();
+                out.push('*');
+            } else if self.is_char('/') {
+                self.next();
+                out.push('/');
+            } else if self.starts_factor() {
+                out.push('*');
+            } else {
+                break;
+            }
+
+            out.push_str(&self.power()?);
+        }
+
+        Ok(out)
+    }
+
+    /// Parse a factor with optional exponents and (double) factorials.
+    fn power(&mut self) -> Result<String, String> {
+        let mut out = self.primary()?;
+
+        loop {
+            if self.is_char('^') {
+                self.next();
+                out = format!("({})^({})", out, self.script()?);
+            } else if self.is_char('!') {
+                self.next();
+                if self.is_char('!') {
+                    self.next();
+                    out = format!("({})!!", out);
+                } else {
+                    out = format!("({})!", out);
+                }
+            } else {
+                break;
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Parse the argument of a superscript or subscript, which is a group or a single character.
+    fn script(&mut self) -> Result<String, String> {
+        if self.is_char('{') {
+            return self.group();
+        }
+
+        if let Some(LatexToken::Number(n)) = self.peek() {
+            if n.len() > 1 {
+                // only the first digit is part of the script
+                let first = n[..1].to_string();
+                let rest = n[1..].into();
+                self.tokens[self.pos].0 = LatexToken::Number(rest);
+                return Ok(first);
+            }
+        }
+
+        self.primary()
+    }
+
+    /// Parse `{expr}`.
+    fn group(&mut self) -> Result<String, String> {
+        self.expect_char('{')?;
+        let r = self.expression()?;
+        self.expect_char('}')?;
+        Ok(r)
+    }
+
+    /// Parse the raw text inside `{}`, such as the name in `\text{name}`.
+    fn raw_group(&mut self) -> Result<String, String> {
+        self.expect_char('{')?;
+        let mut out = String::new();
+        loop {
+            match self.next() {
+                Some(LatexToken::Char('}')) => return Ok(out),
+                Some(LatexToken::Identifier(n) | LatexToken::Number(n)) => out.push_str(&n),
+                Some(LatexToken::Char('_')) => out.push('_'),
+                Some(LatexToken::Command(c)) if c == "tiny" || c == "text" => {}
+                t => return Err(format!("Unexpected {:?} in LaTeX name", t)),
+            }
+        }
+    }
+
+    /// Parse a comma-separated list of expressions that ends with `\right` followed by `close`,
+    /// or with `close` if `left` is false.
+    fn arguments(&mut self, left: bool, close: char) -> Result<String, String> {
+        let mut out = String::new();
+        let is_close = |p: &Self| {
+            if left {
+                p.is_command("right")
+            } else {
+                p.is_char(close)
+            }
+        };
+
+        while !is_close(self) {
+            if !out.is_empty() {
+                self.expect_char(',')?;
+                out.push(',');
+            }
+            out.push_str(&self.expression()?);
+        }
+
+        if left {
+            self.expect_command("right")?;
+            match self.next() {
+                Some(LatexToken::Char(c)) if c == close => {}
+                Some(LatexToken::Command(c)) if c.len() == 1 && c.starts_with(close) => {}
+                t => Err(format!(
+                    "Expected '{}' in LaTeX input, but found {:?}",
+                    close, t
+                ))?,
+            }
+        } else {
+            self.next();
+        }
+
+        Ok(out)
+    }
+
+    /// Parse a bracketed function argument list `(x,y)` or `\left(x,y\right)`, if present.
+    fn function_arguments(&mut self) -> Result<Option<String>, String> {
+        if self.has_space() {
+            return Ok(None);
+        }
+
+        if self.is_char('(') {
+            self.next();
+            Ok(Some(self.arguments(false, ')')?))
+        } else if self.is_command("left")
+            && self.tokens.get(self.pos + 1).map(|t| &t.0) == Some(&LatexToken::Char('('))
+        {
+            self.pos += 2;
+            Ok(Some(self.arguments(true, ')')?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parse an identifier with an optional subscript and function arguments.
+    fn identifier(&mut self, mut name: String) -> Result<String, String> {
+        if self.is_char('_') {
+            self.next();
+            let sub = if self.is_char('{') {
+                self.raw_group()?
+            } else {
+                match self.next() {
+                    Some(LatexToken::Identifier(n) | LatexToken::Number(n)) => n[..1].to_string(),
+                    t => Err(format!("Unexpected {:?} in LaTeX subscript", t))?,
+                }
+            };
+
+            if self.tokens[..self.pos]
+                .iter()
+                .rev()
+                .take_while(|t| t.0 != LatexToken::Char('_'))
+                .any(|t| t.0 == LatexToken::Command("tiny".into()))
+            {
+                // a namespace, as printed by Symbolica
+                name = format!("{}::{}", sub, name);
+            } else {
+                name = format!("{}_{}", name, sub);
+            }
+        }
+
+        if let Some(args) = self.function_arguments()? {
+            return Ok(format!("{}({})", name, args));
+        }
+
+        Ok(match name.as_str() {
+            "e" => "𝑒".to_string(),
+            "i" => "𝑖".to_string(),
+            _ => name,
+        })
+    }
+
+    /// Parse the application of a builtin function such as `\sin x` or `\sin^2\left(x\right)`.
+    fn builtin_function(&mut self, name: &str) -> Result<String, String> {
+        let power = if self.is_char('^') {
+            self.next();
+            Some(self.script()?)
+        } else {
+            None
+        };
+
+        let arg = if let Some(args) = self.function_arguments()? {
+            args
+        } else if self.is_char('{') {
+            self.group()?
+        } else {
+            self.power()?
+        };
+
+        let f = format!("{}({})", builtin_name(name), arg);
+        Ok(match power {
+            Some(p) => format!("{}^({})", f, p),
+            None => f,
+        })
+    }
+
+    fn primary(&mut self) -> Result<String, String> {
+        let Some(t) = self.next() else {
+            return Err("Unexpected end of LaTeX input".to_string());
+        };
+
+        match t {
+            LatexToken::Number(n) => Ok(n.to_string()),
+            LatexToken::Identifier(n) => self.identifier(n.to_string()),
+            LatexToken::Char('(') => {
+                let r = self.expression()?;
+                self.expect_char(')')?;
+                Ok(format!("({})", r))
+            }
+            LatexToken::Char('{') => {
+                let r = self.expression()?;
+                self.expect_char('}')?;
+                Ok(format!("({})", r))
+            }
+            LatexToken::Char('|') => {
+                let r = self.expression()?;
+                self.expect_char('|')?;
+                Ok(format!("symbolica::abs({})", r))
+            }
+            LatexToken::Command(c) => match c.as_str() {
+                "left" => match self.next() {
+                    Some(LatexToken::Char('(' | '[')) => {
+                        let r = self.expression()?;
+                        self.expect_command("right")?;
+                        self.next();
+                        Ok(format!("({})", r))
+                    }
+                    Some(LatexToken::Command(c)) if c == "{" => {
+                        Ok(format!("{{{}}}", self.arguments(true, '}')?))
+                    }
+                    Some(LatexToken::Char('|')) => {
+                        let r = self.expression()?;
+                        self.expect_command("right")?;
+                        self.expect_char('|')?;
+                        Ok(format!("symbolica::abs({})", r))
+                    }
+                    t => Err(format!("Unsupported delimiter {:?} in LaTeX input", t)),
+                },
+                "{" => Ok(format!("{{{}}}", self.arguments(false, '}')?)),
+                "frac" | "dfrac" | "tfrac" => {
+                    let num = self.group()?;
+                    let den = self.group()?;
+                    Ok(format!("(({})/({}))", num, den))
+                }
+                "sqrt" => {
+                    if self.is_char('[') {
+                        self.next();
+                        let n = self.expression()?;
+                        self.expect_char(']')?;
+                        Ok(format!("({})^(1/({}))", self.group()?, n))
+                    } else {
+                        Ok(format!("sqrt({})", self.group()?))
+                    }
+                }
+                "mathrm" | "text" | "operatorname" | "mathit" => {
+                    let name = self.raw_group()?;
+                    self.identifier(name)
+                }
+                "pi" => Ok("𝜋".to_string()),
+                "sin" | "cos" | "tan" | "exp" | "log" | "sinh" | "cosh" | "tanh" | "arcsin"
+                | "arccos" | "arctan" | "zeta" => self.builtin_function(&c),
+                "ln" => self.builtin_function("log"),
+                "Gamma" if self.function_arguments_follow() => self.builtin_function("gamma"),
+                c if GREEK_LETTERS.contains(&c) => self.identifier(c.to_string()),
+                c => Err(format!("Unsupported LaTeX command \\{}", c)),
+            },
+            t => Err(format!("Unexpected {:?} in LaTeX input", t)),
+        }
+    }
+
+    /// Returns `true` if the next token starts a bracketed argument list.
+    fn function_arguments_follow(&self) -> bool {
+        !self.has_space() && (self.is_char('(') || self.is_command("left"))
+    }
+}
+
+/// The Greek letters that are read as symbols with the same name.
+const GREEK_LETTERS: [&str; 35] = [
+    "alpha",
+    "beta",
+    "gamma",
+    "delta",
+    "epsilon",
+    "varepsilon",
+    "zeta",
+    "eta",
+    "theta",
+    "vartheta",
+    "iota",
+    "kappa",
+    "lambda",
+    "mu",
+    "nu",
+    "xi",
+    "rho",
+    "sigma",
+    "tau",
+    "upsilon",
+    "phi",
+    "varphi",
+    "chi",
+    "psi",
+    "omega",
+    "Gamma",
+    "Delta",
+    "Theta",
+    "Lambda",
+    "Xi",
+    "Pi",
+    "Sigma",
+    "Phi",
+    "Psi",
+    "Omega",
+];
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        atom::{Atom, AtomCore},
+        parse,
+        parser::ParseMode,
+        printer::PrintOptions,
+        wrap_input,
+    };
+
+    fn import(input: &str, mode: ParseMode) -> Atom {
+        Atom::parse_with_mode(wrap_input!(input), mode).unwrap()
+    }
+
+    #[test]
+    fn mathematica() {
+        let a = import(
+            "Sin[x]^2 + Sqrt[y] (* comment *) + 2.5*^2 Pi + {E, I} + ArcTan[x, y] + n!",
+            ParseMode::Mathematica,
+        );
+        let r = parse!("sin(x)^2 + sqrt(y) + 2.5e2 𝜋 + {𝑒, 𝑖} + arctan(y, x) + n!").unwrap();
+        assert_eq!(a, r);
+
+        let a = import(
+            "Plus[Times[Rational[1, 2], Power[x, 2]], Complex[0, 1], Log[2, x], BesselJ[0, x]]",
+            ParseMode::Mathematica,
+        );
+        let r = parse!("x^2/2 + 𝑖 + log(x)/log(2) + bessel_j(0, x)").unwrap();
+        assert_eq!(a, r);
+
+        let a = import("5!! + Factorial2[x] + (n!)!", ParseMode::Mathematica);
+        let r = parse!("15 + factorial2(x) + factorial(factorial(n))").unwrap();
+        assert_eq!(a, r);
+
+        let e = parse!("f(x,y)^2*cos(x)+x==y").unwrap();
+        let s = e.printer(PrintOptions::mathematica()).to_string();
+        assert_eq!(import(&s, ParseMode::Mathematica), e);
+    }
+
+    #[test]
+    fn latex() {
+        let a = import(
+            "\\frac{x^{2}}{2} + \\sqrt{y} + \\sqrt[3]{z} - \\sin^2 x + \\pi e^{i \\theta} \\cdot 2",
+            ParseMode::Latex,
+        );
+        let r = parse!("x^2/2 + sqrt(y) + z^(1/3) - sin(x)^2 + 2𝜋 𝑒^(𝑖 theta)").unwrap();
+        assert_eq!(a, r);
+
+        let a = import(
+            "f\\!\\left(x_{1}, \\left|y\\right|\\right) \\leq \\left\\{n!, \\ln(x)\\right\\}",
+            ParseMode::Latex,
+        );
+        let r = parse!("f(x_1, abs(y)) <= {n!, log(x)}").unwrap();
+        assert_eq!(a, r);
+
+        let a = import("5!! + x!! + (n!)!", ParseMode::Latex);
+        let r = parse!("15 + factorial2(x) + factorial(factorial(n))").unwrap();
+        assert_eq!(a, r);
+
+        let e = parse!("f(x,y)^2*cos(x)/(x+1)^3+5/2*x^(-2)+𝑒^x").unwrap();
+        let s = e.printer(PrintOptions::latex()).to_string();
+        assert_eq!(import(&s, ParseMode::Latex), e);
+    }
+
+    #[test]
+    fn sympy() {
+        let a = import(
+            "sin(x)**2 + Rational(1, 2)*pi + atan2(y, x) + Function('f')(x) + [E, I]",
+            ParseMode::Sympy,
+        );
+        let r = parse!("sin(x)^2 + 𝜋/2 + arctan(y, x) + f(x) + {𝑒, 𝑖}").unwrap();
+        assert_eq!(a, r);
+
+        let a = import("factorial2(5) + factorial2(x)", ParseMode::Sympy);
+        assert_eq!(a, parse!("15 + factorial2(x)").unwrap());
+
+        let a = import(
+            "Add(Mul(Integer(2), Symbol('x', positive=True)), Pow(Symbol('y'), \
+             Float('1.5', precision=53)), Eq(Symbol('x'), Integer(-1)))",
+            ParseMode::Sympy,
+        );
+        let r = parse!("2x + y^1.5 + (x == -1)").unwrap();
+        assert_eq!(a, r);
+
+        let e = parse!("f(x,y)^2*cos(x)/(x+1)^3+5/2*x^(-2)").unwrap();
+        let s = e.printer(PrintOptions::sympy()).to_string();
+        assert_eq!(import(&s, ParseMode::Sympy), e);
+    }
+
+    #[test]
+    fn form() {
+        let a = import(
+            "   F =\n      + 12345678901234567890\\\n12345*x^2*sqrt_(2) + i_*pi_ + fac_(3) + li2_(x);",
+            ParseMode::Form,
+        );
+        let r = parse!("1234567890123456789012345 x^2 sqrt(2) + 𝑖 𝜋 + 6 + li(2, x)").unwrap();
+        assert_eq!(a, r);
+    }
+}
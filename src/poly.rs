@@ -32,7 +32,7 @@ use crate::domains::rational_polynomial::{FromNumeratorAndDenominator, RationalP
 use crate::domains::{EuclideanDomain, Ring, SelfRing};
 use crate::parser::{Operator, Token};
 use crate::printer::{PrintOptions, PrintState};
-use crate::state::Workspace;
+use crate::state::{State, Workspace};
 
 use self::factor::Factorize;
 use self::gcd::PolynomialGCD;
@@ -747,6 +747,22 @@ impl Variable {
     }
 }
 
+/// Create a polynomial that is the variable `var`, adding it to `var_map` if it is not present.
+fn variable_polynomial<R: Ring, E: Exponent>(
+    field: &R,
+    var_map: &Arc<Vec<Variable>>,
+    var: Variable,
+) -> MultivariatePolynomial<R, E> {
+    let mut var_map = var_map.clone();
+    if !var_map.contains(&var) {
+        Arc::make_mut(&mut var_map).push(var.clone());
+    }
+
+    MultivariatePolynomial::new(field, None, var_map)
+        .variable(&var)
+        .unwrap()
+}
+
 impl AtomView<'_> {
     /// Convert an expanded expression to a polynomial.
     fn to_polynomial_expanded<R: Ring + ConvertToRing, E: Exponent>(
@@ -959,7 +975,103 @@ impl AtomView<'_> {
         field: &R,
         var_map: Option<Arc<Vec<Variable>>>,
     ) -> MultivariatePolynomial<R, E> {
-        self.to_polynomial_impl(field, var_map.as_ref().unwrap_or(&Arc::new(Vec::new())))
+        let var_map = var_map.unwrap_or_else(|| Arc::new(Vec::new()));
+
+        let Some(terms) = self.split_grassmann_terms() else {
+            return self.to_polynomial_impl(field, &var_map);
+        };
+
+        let mut r = MultivariatePolynomial::new(field, None, var_map);
+        for (even, odd) in terms {
+            let mut t = even.as_view().to_polynomial_impl(field, &r.variables);
+            if let Some(odd) = odd {
+                let mut o = variable_polynomial(field, &t.variables, odd);
+                t.unify_variables(&mut o);
+                t = &t * &o;
+            }
+            r.unify_variables(&mut t);
+            r = &r + &t;
+        }
+        r
+    }
+
+    /// Split the terms of the expression into a Grassmann-even part and a Grassmann-odd
+    /// part, if the latter cannot be represented as a single polynomial variable. Since
+    /// anticommuting factors cannot be multiplied in a polynomial, such an odd part
+    /// is converted to an opaque variable.
+    ///
+    /// Returns `None` if no term has such a part. The expression is only traversed
+    /// if a Grassmann-odd symbol has been defined.
+    fn split_grassmann_terms(&self) -> Option<Vec<(Atom, Option<Variable>)>> {
+        if !State::has_grassmann_symbols() {
+            return None;
+        }
+
+        fn has_grassmann_variable(a: AtomView) -> bool {
+            match a {
+                AtomView::Num(_) => false,
+                AtomView::Var(v) => v.is_grassmann(),
+                AtomView::Fun(f) => f.is_grassmann(),
+                AtomView::Pow(p) => {
+                    let (b, e) = p.get_base_exp();
+                    has_grassmann_variable(b) || has_grassmann_variable(e)
+                }
+                AtomView::Mul(m) => m.iter().any(has_grassmann_variable),
+                AtomView::Add(a) => a.iter().any(has_grassmann_variable),
+            }
+        }
+
+        fn split_term(term: AtomView) -> (Atom, Option<Variable>) {
+            match term {
+                AtomView::Mul(m) => {
+                    let mut odd = m.iter().filter(|f| has_grassmann_variable(*f));
+                    match (odd.next(), odd.next()) {
+                        (None, _) | (Some(AtomView::Var(_) | AtomView::Fun(_)), None) => {
+                            return (term.to_owned(), None);
+                        }
+                        _ => {}
+                    }
+
+                    // the odd factors keep their relative order, so that no sign is introduced
+                    Workspace::get_local().with(|ws| {
+                        let mut even_h = ws.new_atom();
+                        let even_mul = even_h.to_mul();
+                        let mut odd_h = ws.new_atom();
+                        let odd_mul = odd_h.to_mul();
+                        for f in m {
+                            if has_grassmann_variable(f) {
+                                odd_mul.extend(f);
+                            } else {
+                                even_mul.extend(f);
+                            }
+                        }
+
+                        let mut even = Atom::new();
+                        even_h.as_view().normalize(ws, &mut even);
+                        let mut odd = Atom::new();
+                        odd_h.as_view().normalize(ws, &mut odd);
+                        (even, Some(Variable::Other(Arc::new(odd))))
+                    })
+                }
+                AtomView::Pow(_) | AtomView::Add(_) if has_grassmann_variable(term) => (
+                    Atom::new_num(1),
+                    Some(Variable::Other(Arc::new(term.to_owned()))),
+                ),
+                _ => (term.to_owned(), None),
+            }
+        }
+
+        let terms: Vec<_> = if let AtomView::Add(a) = self {
+            a.iter().map(split_term).collect()
+        } else {
+            vec![split_term(*self)]
+        };
+
+        if terms.iter().all(|(_, odd)| odd.is_none()) {
+            None
+        } else {
+            Some(terms)
+        }
     }
 
     pub(crate) fn to_polynomial_impl<R: EuclideanDomain + ConvertToRing, E: Exponent>(
@@ -1100,6 +1212,12 @@ impl AtomView<'_> {
         &self,
         var_map: &Arc<Vec<Variable>>,
     ) -> MultivariatePolynomial<AtomField, E> {
+        if var_map.iter().any(
+            |v| matches!(v, Variable::Symbol(s) | Variable::Function(s, _) if s.is_grassmann()),
+        ) {
+            panic!("Grassmann-odd symbols cannot be polynomial variables");
+        }
+
         let poly = MultivariatePolynomial::<_, E>::new(&AtomField::new(), None, var_map.clone());
         self.to_polynomial_in_vars_impl(var_map, &poly)
     }
@@ -1210,11 +1328,30 @@ impl AtomView<'_> {
         RationalPolynomial<RO, E>:
             FromNumeratorAndDenominator<R, RO, E> + FromNumeratorAndDenominator<RO, RO, E>,
     {
-        self.to_rational_polynomial_impl(
-            field,
-            out_field,
-            var_map.as_ref().unwrap_or(&Arc::new(Vec::new())),
-        )
+        let var_map = var_map.unwrap_or_else(|| Arc::new(Vec::new()));
+
+        let Some(terms) = self.split_grassmann_terms() else {
+            return self.to_rational_polynomial_impl(field, out_field, &var_map);
+        };
+
+        let mut r = RationalPolynomial::new(out_field, var_map);
+        for (even, odd) in terms {
+            let mut t = even.as_view().to_rational_polynomial_impl(
+                field,
+                out_field,
+                &r.numerator.variables,
+            );
+            if let Some(odd) = odd {
+                let o = variable_polynomial(field, &t.numerator.variables, odd);
+                let den = o.one();
+                let mut o = RationalPolynomial::from_num_den(o, den, out_field, false);
+                t.unify_variables(&mut o);
+                t = &t * &o;
+            }
+            r.unify_variables(&mut t);
+            r = &r + &t;
+        }
+        r
     }
 
     fn to_rational_polynomial_impl<
@@ -1354,11 +1491,29 @@ impl AtomView<'_> {
             + FromNumeratorAndFactorizedDenominator<RO, RO, E>,
         MultivariatePolynomial<RO, E>: Factorize,
     {
-        self.to_factorized_rational_polynomial_impl(
-            field,
-            out_field,
-            var_map.as_ref().unwrap_or(&Arc::new(Vec::new())),
-        )
+        let var_map = var_map.unwrap_or_else(|| Arc::new(Vec::new()));
+
+        let Some(terms) = self.split_grassmann_terms() else {
+            return self.to_factorized_rational_polynomial_impl(field, out_field, &var_map);
+        };
+
+        let mut r = FactorizedRationalPolynomial::new(out_field, var_map);
+        for (even, odd) in terms {
+            let mut t = even.as_view().to_factorized_rational_polynomial_impl(
+                field,
+                out_field,
+                &r.numerator.variables,
+            );
+            if let Some(odd) = odd {
+                let o = variable_polynomial(field, &t.numerator.variables, odd);
+                let mut o = FactorizedRationalPolynomial::from_num_den(o, vec![], out_field, false);
+                t.unify_variables(&mut o);
+                t = &t * &o;
+            }
+            r.unify_variables(&mut t);
+            r = &r + &t;
+        }
+        r
     }
 
     pub fn to_factorized_rational_polynomial_impl<
@@ -14,6 +14,7 @@ use crate::domains::algebraic_number::AlgebraicExtension;
 use crate::domains::integer::{Integer, IntegerRing};
 use crate::domains::rational::{Q, RationalField};
 use crate::domains::{Derivable, EuclideanDomain, Field, InternalOrdering, Ring, SelfRing};
+use crate::printer::pretty::{self, Block};
 use crate::printer::{PrintOptions, PrintState};
 
 use super::gcd::PolynomialGCD;
@@ -749,6 +750,61 @@ impl<F: Ring, E: Exponent, O: MonomialOrder> MultivariatePolynomial<F, E, O> {
     }
 }
 
+impl<F: Ring, E: Exponent, O: MonomialOrder> MultivariatePolynomial<F, E, O> {
+    /// Lay out the polynomial for [PrintMode::Pretty](crate::printer::PrintMode::Pretty).
+    /// If `top_level` is set, the terms are distributed over multiple rows if the
+    /// polynomial is wider than [PrintOptions::max_line_length].
+    pub(crate) fn to_pretty_block(&self, opts: &PrintOptions, top_level: bool) -> Block {
+        let vars: Vec<Block> = self
+            .variables
+            .iter()
+            .map(|v| {
+                // variables such as `x+1` need parentheses when they are raised to a power
+                let s = v.format_string(opts, PrintState::new());
+                let b = Block::from_text(&s);
+                if b.height() > 1 || s.contains(' ') {
+                    pretty::delimit(b, pretty::Delimiter::Parenthesis, opts)
+                } else {
+                    b
+                }
+            })
+            .collect();
+
+        let terms = self
+            .into_iter()
+            .map(|monomial| {
+                let factors = vars
+                    .iter()
+                    .zip(monomial.exponents)
+                    .filter(|(_, e)| !e.is_zero())
+                    .map(|(v, e)| {
+                        if e.to_i32() == 1 {
+                            v.clone()
+                        } else {
+                            pretty::power(v.clone(), Block::text(&e.to_string()))
+                        }
+                    })
+                    .collect();
+
+                let coeff = pretty::ring_element(&self.ring, monomial.coefficient, opts);
+                pretty::term(coeff, factors, opts)
+            })
+            .collect();
+
+        let max_width = if top_level {
+            opts.max_line_length
+        } else {
+            None
+        };
+        let b = pretty::sum(terms, max_width);
+        if opts.print_finite_field {
+            Block::hcat(&[b, Block::text(&self.ring.to_string())])
+        } else {
+            b
+        }
+    }
+}
+
 impl<F: Ring, E: Exponent, O: MonomialOrder> SelfRing for MultivariatePolynomial<F, E, O> {
     #[inline]
     fn is_zero(&self) -> bool {
@@ -766,6 +822,10 @@ impl<F: Ring, E: Exponent, O: MonomialOrder> SelfRing for MultivariatePolynomial
         mut state: PrintState,
         f: &mut W,
     ) -> Result<bool, std::fmt::Error> {
+        if opts.mode.is_pretty() {
+            return pretty::write(f, &self.to_pretty_block(opts, state.level == 0));
+        }
+
         if self.is_constant() {
             if self.is_zero() {
                 if state.in_sum {
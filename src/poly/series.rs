@@ -29,7 +29,10 @@ use crate::{
         integer::Integer,
         rational::{Q, Rational},
     },
-    printer::{PrintOptions, PrintState},
+    printer::{
+        PrintOptions, PrintState,
+        pretty::{self, Block},
+    },
 };
 
 use super::Variable;
@@ -526,6 +529,38 @@ impl<F: Ring> SelfRing for Series<F> {
             },
         );
 
+        if opts.mode.is_pretty() {
+            let v = Block::from_text(&v);
+            let mut terms = vec![];
+            for (e, c) in self.coefficients.iter().enumerate() {
+                if self.field.is_zero(c) {
+                    continue;
+                }
+
+                let e = self.get_exponent(e);
+                let factors = if e.is_zero() {
+                    vec![]
+                } else if e.is_one() {
+                    vec![v.clone()]
+                } else {
+                    vec![pretty::power(v.clone(), pretty::ring_element(&Q, &e, opts))]
+                };
+
+                let c = pretty::ring_element(&self.field, c, opts);
+                terms.push(pretty::term(c, factors, opts));
+            }
+
+            let o = pretty::ring_element(&Q, &self.absolute_order(), opts);
+            terms.push(pretty::order(pretty::power(v, o), opts));
+
+            let max_width = if state.level == 0 {
+                opts.max_line_length
+            } else {
+                None
+            };
+            return pretty::write(f, &pretty::sum(terms, max_width));
+        }
+
         if self.coefficients.is_empty() {
             let o = self.absolute_order();
             if opts.mode.is_latex() {
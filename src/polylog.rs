@@ -0,0 +1,943 @@
+//! Multiple polylogarithms and nested harmonic sums.
+//!
+//! The multiple (Goncharov) polylogarithm `G(a_1,...,a_n; x)` is represented by the builtin function
+//! [Atom::MPL] as `mpl(a_1,...,a_n,x)`. It is defined recursively as
+//! ```plain
+//! G(a_1,...,a_n; x) = ∫_0^x dt/(t - a_1) G(a_2,...,a_n; t)
+//! ```
+//! with `G(;x) = 1` and `G(0,...,0; x) = log(x)^n/n!`. The nested harmonic sum `S_{m_1,...,m_k}(n)`
+//! is represented by [Atom::HSUM] as `hsum(m_1,...,m_k,n)` and is defined as
+//! ```plain
+//! S_{m_1,...,m_k}(n) = Σ_{i=1}^n sign(m_1)^i / i^|m_1| S_{m_2,...,m_k}(i)
+//! ```
+//! with `S(n) = 1`. The classical multiple polylogarithms
+//! `Li_{m_1,...,m_k}(x_1,...,x_k) = Σ_{n_1>...>n_k>0} x_1^n_1/n_1^m_1 ... x_k^n_k/n_k^m_k`
+//! are represented by [Atom::POLYLOG] as `li(m_1,...,m_k,x_1,...,x_k)`.
+//!
+//! Products of multiple polylogarithms with the same argument `x` satisfy the shuffle algebra and products
+//! of harmonic sums with the same upper bound satisfy the quasi-shuffle (stuffle) algebra. Both can be
+//! expanded using [AtomCore::expand_polylog_products] and a basis can be obtained with [AtomCore::to_lyndon_basis].
+//!
+//! # Example
+//!
+//! ```
+//! use symbolica::{atom::AtomCore, parse};
+//! let e = parse!("symbolica::mpl(a, x) * symbolica::mpl(b, x)").unwrap();
+//! assert_eq!(
+//!     e.expand_polylog_products(),
+//!     parse!("symbolica::mpl(a, b, x) + symbolica::mpl(b, a, x)").unwrap()
+//! );
+//! ```
+use std::hash::Hash;
+
+use ahash::HashMap;
+
+use crate::{
+    atom::{Atom, AtomCore, AtomView, FunctionBuilder, Symbol},
+    coefficient::CoefficientView,
+    combinatorics::{lyndon_factorization, quasi_shuffle, shuffle},
+    domains::{
+        float::{Float, NumericalFloatLike, Real, RealNumberLike, SingleFloat},
+        integer::Integer,
+        rational::Rational,
+    },
+};
+
+/// The number of extra bits used in intermediate numerical computations.
+const GUARD_BITS: u32 = 32;
+
+/// The maximal ratio `|x/a_i|` for which the nested series of a multiple polylogarithm is summed.
+const MAX_CONVERGENCE_RATIO: f64 = 0.95;
+
+/// The largest upper bound `n` for which a harmonic sum is evaluated exactly during normalization.
+const MAX_HSUM_UPPER_BOUND: i64 = 1000;
+
+/// A linear combination of products of words.
+type WordPolynomial<T> = Vec<(Rational, Vec<Vec<T>>)>;
+
+/// Write the word `word` as a polynomial in Lyndon words, using the product `product` of the algebra.
+/// The word is the largest word in the product of its Lyndon factors, which allows to solve for it
+/// recursively.
+fn lyndon_decomposition<T: Clone + Hash + Ord, F: Fn(&[T], &[T]) -> Vec<(Integer, Vec<T>)>>(
+    word: &[T],
+    product: &F,
+    cache: &mut HashMap<Vec<T>, WordPolynomial<T>>,
+) -> WordPolynomial<T> {
+    let factors = lyndon_factorization(word);
+    if factors.is_empty() {
+        return vec![(Rational::one(), vec![])];
+    } else if factors.len() == 1 {
+        return vec![(Rational::one(), vec![word.to_vec()])];
+    }
+
+    if let Some(r) = cache.get(word) {
+        return r.clone();
+    }
+
+    let mut prod = vec![(Integer::one(), factors[0].to_vec())];
+    for f in &factors[1..] {
+        let mut words: HashMap<Vec<T>, Integer> = HashMap::default();
+        for (c, w) in &prod {
+            for (c2, w2) in product(w, f) {
+                *words.entry(w2).or_insert(Integer::zero()) += &(c * &c2);
+            }
+        }
+        prod = words
+            .into_iter()
+            .filter(|(_, c)| !c.is_zero())
+            .map(|(w, c)| (c, w))
+            .collect();
+    }
+
+    let norm = Rational::from(
+        prod.iter()
+            .find(|(_, w)| w == word)
+            .map(|(c, _)| c.clone())
+            .unwrap(),
+    );
+
+    let mut monomial: Vec<Vec<T>> = factors.iter().map(|f| f.to_vec()).collect();
+    monomial.sort();
+
+    let mut res: HashMap<Vec<Vec<T>>, Rational> = HashMap::default();
+    res.insert(monomial, norm.inv());
+    for (c, w) in prod {
+        if w == word {
+            continue;
+        }
+
+        let c = Rational::from(c) / &norm;
+        for (c2, m) in lyndon_decomposition(&w, product, cache) {
+            *res.entry(m).or_default() -= &(&c * &c2);
+        }
+    }
+
+    let mut res: Vec<_> = res
+        .into_iter()
+        .filter(|(_, c)| !c.is_zero())
+        .map(|(m, c)| (c, m))
+        .collect();
+    res.sort_by(|a, b| a.1.cmp(&b.1));
+
+    cache.insert(word.to_vec(), res.clone());
+    res
+}
+
+/// Write `G(word; x)` as a linear combination of `G(v; x) G(0; x)^k`, where
+/// `v` has no trailing zeros, using the shuffle algebra.
+fn remove_trailing_zeros<T: Clone + Hash + Ord>(
+    word: &[T],
+    is_zero: &impl Fn(&T) -> bool,
+) -> Vec<(Rational, Vec<T>, usize)> {
+    let r = word.iter().rev().take_while(|l| is_zero(l)).count();
+    if r == 0 {
+        return vec![(Rational::one(), word.to_vec(), 0)];
+    }
+
+    // G(0) G(u, 0^(r-1)) = r G(u, 0^r) + sum of insertions of 0 into u
+    let u = &word[..word.len() - r];
+    let zero = &word[word.len() - 1];
+    let r_inv = Rational::from(r as i64).inv();
+
+    let mut res: HashMap<(Vec<T>, usize), Rational> = HashMap::default();
+    for (c, v, k) in remove_trailing_zeros(&word[..word.len() - 1], is_zero) {
+        *res.entry((v, k + 1)).or_default() += &(c * &r_inv);
+    }
+
+    for i in 0..u.len() {
+        let mut w = u[..i].to_vec();
+        w.push(zero.clone());
+        w.extend_from_slice(&word[i..word.len() - 1]);
+
+        for (c, v, k) in remove_trailing_zeros(&w, is_zero) {
+            *res.entry((v, k)).or_default() -= &(c * &r_inv);
+        }
+    }
+
+    let mut res: Vec<_> = res
+        .into_iter()
+        .filter(|(_, c)| !c.is_zero())
+        .map(|((v, k), c)| (c, v, k))
+        .collect();
+    res.sort_by(|a, b| (&a.1, a.2).cmp(&(&b.1, b.2)));
+    res
+}
+
+/// The quasi-shuffle product of the indices of two harmonic sums.
+fn stuffle(a: &[i64], b: &[i64]) -> Vec<(Integer, Vec<i64>)> {
+    quasi_shuffle(a, b, &|x: &i64, y: &i64| {
+        let s = x.signum() * y.signum();
+        ((-1).into(), s * (x.abs() + y.abs()))
+    })
+}
+
+/// Get the value of `a` if it is a non-zero integer that fits in an `i64`.
+fn nonzero_integer(a: AtomView) -> Option<i64> {
+    if let AtomView::Num(n) = a {
+        if let CoefficientView::Natural(n, 1) = n.get_coeff_view() {
+            if n != 0 {
+                return Some(n);
+            }
+        }
+    }
+    None
+}
+
+/// Get the indices of the harmonic sum `hsum(m_1,...,m_k,n)`, if they are all non-zero integers.
+fn hsum_indices(args: &[AtomView]) -> Option<Vec<i64>> {
+    args[..args.len() - 1]
+        .iter()
+        .map(|a| nonzero_integer(*a))
+        .collect()
+}
+
+fn mpl(letters: &[Atom], x: &Atom) -> Atom {
+    FunctionBuilder::new(Atom::MPL)
+        .add_args(letters)
+        .add_arg(x)
+        .finish()
+}
+
+fn hsum(indices: &[i64], n: &Atom) -> Atom {
+    let mut f = FunctionBuilder::new(Atom::HSUM);
+    for i in indices {
+        f = f.add_arg(Atom::new_num(*i));
+    }
+    f.add_arg(n).finish()
+}
+
+impl AtomView<'_> {
+    /// Expand products of multiple polylogarithms with the same argument using the shuffle product
+    /// and products of harmonic sums with the same upper bound using the quasi-shuffle product.
+    pub(crate) fn expand_polylog_products(&self) -> Atom {
+        self.replace_map(|a, _, out| match a {
+            AtomView::Mul(_) | AtomView::Pow(_) => {
+                if let Some(r) = a.expand_polylog_product() {
+                    *out = r;
+                    true
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        })
+    }
+
+    /// Expand the product or power `self` if it contains multiple polylogarithms or harmonic sums.
+    fn expand_polylog_product(&self) -> Option<Atom> {
+        let mut mpls: Vec<(Atom, Vec<Vec<Atom>>)> = vec![];
+        let mut hsums: Vec<(Atom, Vec<Vec<i64>>)> = vec![];
+        let mut rest = Atom::new_num(1);
+
+        let mut add_factor = |f: AtomView, rest: &mut Atom| {
+            let mut pow = 1;
+            let mut base = f;
+            if let AtomView::Pow(p) = f {
+                let (b, e) = p.get_base_exp();
+                if let Some(e) = nonzero_integer(e) {
+                    if e > 0 {
+                        pow = e as usize;
+                        base = b;
+                    }
+                }
+            }
+
+            if let AtomView::Fun(ff) = base {
+                let args: Vec<_> = ff.iter().collect();
+                if !args.is_empty() {
+                    let x = args[args.len() - 1].to_owned();
+                    if ff.get_symbol() == Atom::MPL {
+                        let w: Vec<_> = args[..args.len() - 1]
+                            .iter()
+                            .map(|a| a.to_owned())
+                            .collect();
+                        let group = if let Some(g) = mpls.iter_mut().find(|g| g.0 == x) {
+                            g
+                        } else {
+                            mpls.push((x, vec![]));
+                            mpls.last_mut().unwrap()
+                        };
+                        group.1.extend(std::iter::repeat_n(w, pow));
+                        return;
+                    } else if ff.get_symbol() == Atom::HSUM {
+                        if let Some(w) = hsum_indices(&args) {
+                            let group = if let Some(g) = hsums.iter_mut().find(|g| g.0 == x) {
+                                g
+                            } else {
+                                hsums.push((x, vec![]));
+                                hsums.last_mut().unwrap()
+                            };
+                            group.1.extend(std::iter::repeat_n(w, pow));
+                            return;
+                        }
+                    }
+                }
+            }
+
+            *rest = &*rest * f.expand_polylog_products();
+        };
+
+        match self {
+            AtomView::Mul(m) => {
+                for f in m {
+                    add_factor(f, &mut rest);
+                }
+            }
+            _ => add_factor(*self, &mut rest),
+        }
+
+        if mpls.iter().all(|g| g.1.len() < 2) && hsums.iter().all(|g| g.1.len() < 2) {
+            return None;
+        }
+
+        fn multiply<T: Clone + Hash + Ord>(
+            words: &[Vec<T>],
+            product: impl Fn(&[T], &[T]) -> Vec<(Integer, Vec<T>)>,
+        ) -> Vec<(Integer, Vec<T>)> {
+            let mut res = vec![(Integer::one(), words[0].clone())];
+            for w in &words[1..] {
+                let mut new: HashMap<Vec<T>, Integer> = HashMap::default();
+                for (c, w1) in &res {
+                    for (c2, w2) in product(w1, w) {
+                        *new.entry(w2).or_insert(Integer::zero()) += &(c * &c2);
+                    }
+                }
+                res = new.into_iter().map(|(w, c)| (c, w)).collect();
+                res.sort_by(|a, b| a.1.cmp(&b.1));
+            }
+            res
+        }
+
+        let mut polylogs = Atom::new_num(1);
+        for (x, words) in &mpls {
+            let mut sum = Atom::new_num(0);
+            for (c, w) in multiply(words, shuffle) {
+                sum += mpl(&w, x) * &Atom::new_num(c);
+            }
+            polylogs *= sum;
+        }
+
+        for (n, words) in &hsums {
+            let mut sum = Atom::new_num(0);
+            for (c, w) in multiply(words, stuffle) {
+                sum += hsum(&w, n) * &Atom::new_num(c);
+            }
+            polylogs *= sum;
+        }
+
+        Some(polylogs.expand() * rest)
+    }
+
+    /// Write all multiple polylogarithms and harmonic sums in terms of products of
+    /// functions whose indices form Lyndon words.
+    pub(crate) fn to_lyndon_basis(self) -> Atom {
+        let mut mpl_cache = HashMap::default();
+        let mut hsum_cache = HashMap::default();
+
+        self.replace_map(|a, _, out| {
+            let AtomView::Fun(f) = a else {
+                return false;
+            };
+
+            let args: Vec<_> = f.iter().collect();
+            if args.is_empty() {
+                return false;
+            }
+            let x = args[args.len() - 1].to_owned();
+
+            if f.get_symbol() == Atom::MPL {
+                let w: Vec<_> = args[..args.len() - 1]
+                    .iter()
+                    .map(|a| a.to_owned())
+                    .collect();
+                let mut sum = Atom::new_num(0);
+                for (c, m) in lyndon_decomposition(&w, &|a, b| shuffle(a, b), &mut mpl_cache) {
+                    let mut t = Atom::new_num(c);
+                    for w in &m {
+                        t *= mpl(w, &x);
+                    }
+                    sum += t;
+                }
+                *out = sum;
+                true
+            } else if f.get_symbol() == Atom::HSUM {
+                let Some(w) = hsum_indices(&args) else {
+                    return false;
+                };
+
+                let mut sum = Atom::new_num(0);
+                for (c, m) in lyndon_decomposition(&w, &stuffle, &mut hsum_cache) {
+                    let mut t = Atom::new_num(c);
+                    for w in &m {
+                        t *= hsum(w, &x);
+                    }
+                    sum += t;
+                }
+                *out = sum;
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    /// Convert multiple polylogarithms `G(a_1,...,a_n; x)` to classical multiple polylogarithms
+    /// `Li_{m_1,...,m_k}(x_1,...,x_k)`, extracting trailing zeros as powers of `log(x)`.
+    pub(crate) fn mpl_to_li(&self) -> Atom {
+        self.replace_map(|a, _, out| {
+            let AtomView::Fun(f) = a else {
+                return false;
+            };
+
+            if f.get_symbol() != Atom::MPL || f.get_nargs() == 0 {
+                return false;
+            }
+
+            let args: Vec<_> = f.iter().map(|a| a.to_owned()).collect();
+            let (x, letters) = args.split_last().unwrap();
+            let x = x.mpl_to_li();
+            let letters: Vec<_> = letters.iter().map(|l| l.mpl_to_li()).collect();
+
+            let mut sum = Atom::new_num(0);
+            for (c, v, k) in remove_trailing_zeros(&letters, &|l: &Atom| l.is_zero()) {
+                // G(0^(m_1-1), a_1, ..., 0^(m_k-1), a_k; x) = (-1)^k Li_{m_1,...,m_k}(x/a_1, a_1/a_2, ..., a_(k-1)/a_k)
+                let mut orders = vec![];
+                let mut args = vec![];
+                let mut m = 1;
+                let mut prev = &x;
+                for l in &v {
+                    if l.is_zero() {
+                        m += 1;
+                    } else {
+                        orders.push(Atom::new_num(m));
+                        args.push(prev / l);
+                        prev = l;
+                        m = 1;
+                    }
+                }
+
+                let mut t = Atom::new_num(if orders.len() % 2 == 0 { c } else { -c })
+                    * crate::function!(Atom::LOG, &x).npow(k as i64);
+                if !orders.is_empty() {
+                    t *= FunctionBuilder::new(Atom::POLYLOG)
+                        .add_args(&orders)
+                        .add_args(&args)
+                        .finish();
+                }
+                sum += t;
+            }
+
+            *out = sum;
+            true
+        })
+    }
+
+    /// Convert classical multiple polylogarithms `Li_{m_1,...,m_k}(x_1,...,x_k)` with positive
+    /// integer orders to multiple polylogarithms `G(a_1,...,a_n; 1)`.
+    pub(crate) fn li_to_mpl(&self) -> Atom {
+        self.replace_map(|a, _, out| {
+            let AtomView::Fun(f) = a else {
+                return false;
+            };
+
+            if f.get_symbol() != Atom::POLYLOG || f.get_nargs() == 0 || f.get_nargs() % 2 == 1 {
+                return false;
+            }
+
+            let args: Vec<_> = f.iter().collect();
+            let (orders, xs) = args.split_at(args.len() / 2);
+            let Some(orders) = orders
+                .iter()
+                .map(|o| nonzero_integer(*o).filter(|o| *o > 0))
+                .collect::<Option<Vec<_>>>()
+            else {
+                return false;
+            };
+
+            // Li_{m_1,...,m_k}(x_1,...,x_k) = (-1)^k G(0^(m_1-1), 1/x_1, ..., 0^(m_k-1), 1/(x_1...x_k); 1)
+            let mut letters = vec![];
+            let mut prod = Atom::new_num(1);
+            for (m, x) in orders.iter().zip(xs) {
+                letters.extend(std::iter::repeat_n(Atom::new_num(0), *m as usize - 1));
+                prod *= x.li_to_mpl();
+                letters.push(prod.npow(-1));
+            }
+
+            let g = mpl(&letters, &Atom::new_num(1));
+            *out = if orders.len() % 2 == 0 { g } else { -g };
+            true
+        })
+    }
+
+    /// Evaluate a multiple polylogarithm or harmonic sum with normalized arguments `args`
+    /// at special values, or numerically if all arguments are numbers and one of them is a floating point number.
+    /// Returns `None` if the function should remain unevaluated.
+    pub(crate) fn normalize_polylog(id: Symbol, args: &[AtomView<'_>]) -> Option<Atom> {
+        let (x, letters) = args.split_last()?;
+        if letters.is_empty() {
+            return Some(Atom::new_num(1));
+        }
+
+        if id == Atom::HSUM {
+            let AtomView::Num(n) = x else {
+                return None;
+            };
+            let CoefficientView::Natural(n, 1) = n.get_coeff_view() else {
+                return None;
+            };
+
+            if n == 0 {
+                return Some(Atom::new_num(0));
+            }
+
+            if n > 0 && n <= MAX_HSUM_UPPER_BOUND {
+                let indices = hsum_indices(args)?;
+                return Some(Atom::new_num(harmonic_sum(&indices, n as u64)));
+            }
+
+            return None;
+        }
+
+        if letters.iter().all(|l| l.is_zero()) {
+            // G(0^n; x) = log(x)^n/n!
+            let n = letters.len() as u32;
+            return Some(
+                crate::function!(Atom::LOG, x).npow(n as i64)
+                    / &Atom::new_num(Integer::factorial(n)),
+            );
+        }
+
+        if x.is_zero() {
+            return Some(Atom::new_num(0));
+        }
+
+        let mut prec = None;
+        for a in args {
+            let AtomView::Num(n) = a else {
+                return None;
+            };
+
+            match n.get_coeff_view() {
+                CoefficientView::Float(f) => {
+                    let p = f.to_float().prec();
+                    prec = Some(prec.map(|q: u32| q.min(p)).unwrap_or(p));
+                }
+                CoefficientView::Natural(..) | CoefficientView::Large(_) => {}
+                _ => return None,
+            }
+        }
+
+        let prec = prec?;
+        let v: Vec<Float> = args
+            .iter()
+            .map(|a| {
+                let AtomView::Num(n) = a else { unreachable!() };
+                match n.get_coeff_view() {
+                    CoefficientView::Float(f) => f.to_float(),
+                    CoefficientView::Natural(n, d) => {
+                        Rational::from_unchecked(n, d).to_multi_prec_float(prec)
+                    }
+                    CoefficientView::Large(r) => r.to_rat().to_multi_prec_float(prec),
+                    _ => unreachable!(),
+                }
+            })
+            .collect();
+
+        let (x, letters) = v.split_last().unwrap();
+        evaluate_mpl(letters, x).map(Atom::new_num)
+    }
+}
+
+/// Compute the harmonic sum `S_{m_1,...,m_k}(n)` exactly. Negative indices
+/// denote alternating sums.
+///
+/// # Example
+///
+/// ```
+/// # use symbolica::polylog::harmonic_sum;
+/// # use symbolica::domains::rational::Rational;
+/// assert_eq!(harmonic_sum(&[1], 3), Rational::from_unchecked(11, 6));
+/// assert_eq!(harmonic_sum(&[-1], 2), Rational::from_unchecked(-1, 2));
+/// ```
+pub fn harmonic_sum(indices: &[i64], n: u64) -> Rational {
+    assert!(
+        indices.iter().all(|m| *m != 0),
+        "The indices of a harmonic sum must be non-zero"
+    );
+
+    let mut prev = vec![Rational::one(); n as usize + 1];
+    for m in indices.iter().rev() {
+        let mut cur = vec![Rational::zero(); n as usize + 1];
+        for i in 1..=n as usize {
+            let t = Rational::from(Integer::from(i as i64).pow(m.unsigned_abs())).inv() * &prev[i];
+            cur[i] = if *m < 0 && i % 2 == 1 {
+                &cur[i - 1] - &t
+            } else {
+                &cur[i - 1] + &t
+            };
+        }
+        prev = cur;
+    }
+
+    prev.swap_remove(n as usize)
+}
+
+/// Evaluate the multiple polylogarithm `G(a_1,...,a_n; x)` for real arguments with the
+/// smallest precision of the arguments.
+///
+/// The nested sum representation is used if it converges quickly, otherwise
+/// the Hölder convolution is used to map the arguments into the region of fast convergence.
+/// Returns `None` if the result is divergent or complex, or if no convergent
+/// representation could be found.
+///
+/// # Example
+///
+/// ```
+/// # use symbolica::polylog::evaluate_mpl;
+/// # use symbolica::domains::float::{Float, RealNumberLike};
+/// // G(0, 1; 1) = -ζ(2)
+/// let r = evaluate_mpl(&[Float::with_val(100, 0), Float::with_val(100, 1)], &Float::with_val(100, 1)).unwrap();
+/// assert!((r.to_f64() + std::f64::consts::PI.powi(2) / 6.).abs() < 1e-15);
+/// ```
+pub fn evaluate_mpl(a: &[Float], x: &Float) -> Option<Float> {
+    let prec = a.iter().map(|l| l.prec()).fold(x.prec(), u32::min);
+    let wp = prec + GUARD_BITS + 4 * a.len() as u32;
+
+    if a.is_empty() {
+        return Some(Float::with_val(prec, 1));
+    }
+
+    if x.is_zero() {
+        return if a.iter().all(|l| l.is_zero()) {
+            None
+        } else {
+            Some(Float::new(prec))
+        };
+    }
+
+    // map the letters to indices so that the word algebra can be used,
+    // where the index 0 is reserved for the letter 0
+    let mut table: Vec<Float> = vec![Float::new(wp)];
+    let word: Vec<usize> = a
+        .iter()
+        .map(|l| {
+            if let Some(p) = table.iter().position(|t| t == l) {
+                p
+            } else {
+                let mut l = l.clone();
+                l.set_prec(wp);
+                table.push(l);
+                table.len() - 1
+            }
+        })
+        .collect();
+
+    let mut x = x.clone();
+    x.set_prec(wp);
+
+    let mut log_x = None;
+    let mut res = Float::new(wp);
+    for (c, v, k) in remove_trailing_zeros(&word, &|l| *l == 0) {
+        let mut t = Float::with_val(wp, 1) * c;
+        if k > 0 {
+            if log_x.is_none() {
+                if x.is_negative() {
+                    return None;
+                }
+                log_x = Some(x.log());
+            }
+            for _ in 0..k {
+                t *= log_x.as_ref().unwrap();
+            }
+        }
+
+        if !v.is_empty() {
+            let b: Vec<_> = v.iter().map(|l| table[*l].clone() / &x).collect();
+            t *= mpl_at_one(&b)?;
+        }
+
+        res += t;
+    }
+
+    res.set_prec(prec);
+    Some(res)
+}
+
+/// Evaluate `G(b_1,...,b_n; 1)` where `b_n` is non-zero.
+fn mpl_at_one(b: &[Float]) -> Option<Float> {
+    let wp = b[0].prec();
+    let one = Float::with_val(wp, 1);
+    if b[0] == one {
+        return None;
+    }
+
+    // the convergence ratio of the direct nested sum
+    let direct = b
+        .iter()
+        .filter(|l| !l.is_zero())
+        .map(|l| l.norm().inv().to_f64())
+        .fold(0., f64::max);
+
+    // the convergence ratio of the Hölder convolution for a splitting point q, which
+    // evaluates G(1-b; 1-q) and G(b; q)
+    let holder_ratio = |q: f64| {
+        b.iter()
+            .map(|l| {
+                let l = l.to_f64();
+                let r1 = if l != 0. { q / l.abs() } else { 0. };
+                let r2 = if l != 1. {
+                    (1. - q) / (1. - l).abs()
+                } else {
+                    0.
+                };
+                r1.max(r2)
+            })
+            .fold(0., f64::max)
+    };
+
+    let (q, holder) = (1..64)
+        .map(|i| {
+            let q = i as f64 / 64.;
+            (i, holder_ratio(q))
+        })
+        .fold((32, f64::INFINITY), |a, b| if b.1 < a.1 { b } else { a });
+
+    if direct <= holder {
+        if direct > MAX_CONVERGENCE_RATIO {
+            return None;
+        }
+        return Some(mpl_series(b, &one));
+    }
+
+    if holder > MAX_CONVERGENCE_RATIO {
+        return None;
+    }
+
+    // G(b_1,...,b_n; 1) = Σ_j (-1)^j G(1-b_j,...,1-b_1; 1-q) G(b_(j+1),...,b_n; q)
+    let q = Float::with_val(wp, q) / 64;
+    let q_c = one.clone() - &q;
+    let c: Vec<_> = b.iter().map(|l| one.clone() - l).collect();
+
+    let mut res = Float::new(wp);
+    for j in 0..=b.len() {
+        let left = if j == 0 {
+            one.clone()
+        } else {
+            let w: Vec<_> = c[..j].iter().rev().cloned().collect();
+            mpl_series(&w, &q_c)
+        };
+
+        let right = if j == b.len() {
+            one.clone()
+        } else {
+            mpl_series(&b[j..], &q)
+        };
+
+        if j % 2 == 0 {
+            res += left * right;
+        } else {
+            res -= left * right;
+        }
+    }
+
+    Some(res)
+}
+
+/// Evaluate `G(a_1,...,a_n; y)` with `a_n` non-zero using the nested sum representation
+/// `(-1)^k Li_{m_1,...,m_k}(y/a_1, a_1/a_2, ..., a_(k-1)/a_k)`,
+/// which converges if `|y/a_i| < 1` for all non-zero `a_i`.
+fn mpl_series(a: &[Float], y: &Float) -> Float {
+    let wp = y.prec();
+
+    let mut orders = vec![];
+    let mut z = vec![];
+    let mut m = 1;
+    let mut prev = y.clone();
+    for l in a {
+        if l.is_zero() {
+            m += 1;
+        } else {
+            orders.push(m);
+            z.push(prev / l);
+            prev = l.clone();
+            m = 1;
+        }
+    }
+
+    let ratio = a
+        .iter()
+        .filter(|l| !l.is_zero())
+        .map(|l| (y.clone() / l).norm().to_f64())
+        .fold(0., f64::max);
+
+    let n_terms = if ratio == 0. {
+        1
+    } else {
+        (wp as f64 / -ratio.log2()).ceil() as usize + 10
+    };
+
+    let k = z.len();
+    let mut acc = vec![Float::new(wp); k];
+    let mut pow = vec![Float::with_val(wp, 1); k];
+    for n in 1..=n_terms {
+        let n_f = Float::with_val(wp, n as u64);
+        for (j, (pow, z)) in pow.iter_mut().zip(&z).enumerate() {
+            *pow *= z;
+
+            let mut t = pow.clone();
+            for _ in 0..orders[j] {
+                t /= &n_f;
+            }
+
+            // use the sums over the inner indices up to n - 1
+            if j + 1 < k {
+                t *= &acc[j + 1];
+            }
+
+            acc[j] += t;
+        }
+    }
+
+    let r = acc.swap_remove(0);
+    if k % 2 == 0 { r } else { -r }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        atom::{Atom, AtomCore, AtomView},
+        coefficient::CoefficientView,
+        domains::{
+            float::{Float, RealNumberLike},
+            rational::Rational,
+        },
+        parse, symbol,
+    };
+
+    use super::{evaluate_mpl, harmonic_sum};
+
+    #[test]
+    fn shuffle_stuffle() {
+        let e = parse!("mpl(v1, v2, v3) * mpl(v4, v3) + hsum(1, v5)^2").unwrap();
+        let r = parse!(
+            "mpl(v1, v2, v4, v3) + mpl(v1, v4, v2, v3) + mpl(v4, v1, v2, v3) + 2*hsum(1, 1, v5) - hsum(2, v5)"
+        )
+        .unwrap();
+        assert_eq!(e.expand_polylog_products(), r);
+
+        // the products with a different argument are not touched
+        let e = parse!("mpl(v1, v2) * mpl(v1, v3)").unwrap();
+        assert_eq!(e.expand_polylog_products(), e);
+    }
+
+    #[test]
+    fn lyndon_basis() {
+        let e = parse!("mpl(1, 0, v1)").unwrap();
+        let r = parse!("mpl(0, v1) * mpl(1, v1) - mpl(0, 1, v1)").unwrap();
+        assert_eq!(e.to_lyndon_basis(), r);
+
+        let e = parse!("hsum(2, 1, v1)").unwrap();
+        let r = parse!("hsum(1, v1) * hsum(2, v1) - hsum(1, 2, v1) + hsum(3, v1)").unwrap();
+        assert_eq!(e.to_lyndon_basis(), r);
+
+        // the decomposition is an identity
+        let e = parse!("hsum(3, -1, 2, 10)").unwrap();
+        assert_eq!(e.to_lyndon_basis(), e);
+    }
+
+    #[test]
+    fn li_conversion() {
+        let e = parse!("mpl(0, 1, v1)").unwrap();
+        assert_eq!(e.mpl_to_li(), parse!("-li(2, v1)").unwrap());
+
+        let e = parse!("mpl(1, 0, v1)").unwrap();
+        assert_eq!(
+            e.mpl_to_li(),
+            parse!("log(v1)*log(1-v1) + li(2, v1)").unwrap()
+        );
+
+        let e = parse!("mpl(0, v2, 0, v3, v1)").unwrap();
+        assert_eq!(e.mpl_to_li(), parse!("li(2, 2, v1/v2, v2/v3)").unwrap());
+
+        let e = parse!("li(2, 1, v1, v2)").unwrap();
+        assert_eq!(
+            e.li_to_mpl(),
+            parse!("mpl(0, v1^-1, v1^-1*v2^-1, 1)").unwrap()
+        );
+        assert_eq!(e.li_to_mpl().mpl_to_li(), e);
+    }
+
+    #[test]
+    fn derivative() {
+        let e = parse!("mpl(v2, 0, v1^2)").unwrap();
+        let r = parse!("2*v1*mpl(0, v1^2)/(v1^2-v2)").unwrap();
+        assert_eq!(e.derivative(symbol!("v1")), r);
+    }
+
+    #[test]
+    fn special_values() {
+        assert_eq!(parse!("mpl(v1)").unwrap(), Atom::new_num(1));
+        assert_eq!(
+            parse!("mpl(0, 0, v1)").unwrap(),
+            parse!("1/2*log(v1)^2").unwrap()
+        );
+        assert_eq!(parse!("mpl(v2, 0, 0)").unwrap(), Atom::new_num(0));
+        assert_eq!(parse!("hsum(1, 2, 0)").unwrap(), Atom::new_num(0));
+        assert_eq!(
+            parse!("hsum(1, 2, 3)").unwrap(),
+            Atom::new_num(Rational::from_unchecked(449, 216))
+        );
+        assert!(matches!(
+            parse!("hsum(1, 2, 1000000000000)").unwrap(),
+            Atom::Fun(_)
+        ));
+        assert_eq!(
+            harmonic_sum(&[-2, 1], 2),
+            Rational::from_unchecked(-1, 1) + Rational::from_unchecked(3, 8)
+        );
+    }
+
+    #[test]
+    fn numerical_evaluation() {
+        let f = |x: f64| Float::with_val(200, x);
+        let pi2 = std::f64::consts::PI.powi(2);
+        let l2 = 2f64.ln();
+
+        // G(0, 0, 1; 1) = -ζ(3)
+        let r = evaluate_mpl(&[f(0.), f(0.), f(1.)], &f(1.)).unwrap();
+        assert!((r.to_f64() + 1.2020569031595942).abs() < 1e-15);
+
+        // G(0, -1; 1) = ζ(2)/2
+        let r = evaluate_mpl(&[f(0.), f(-1.)], &f(1.)).unwrap();
+        assert!((r.to_f64() - pi2 / 12.).abs() < 1e-15);
+
+        // G(1, 0; 1/2) = ζ(2)/2 + log(2)^2/2
+        let r = evaluate_mpl(&[f(1.), f(0.)], &f(0.5)).unwrap();
+        assert!((r.to_f64() - pi2 / 12. - l2 * l2 / 2.).abs() < 1e-15);
+
+        // G(-1/4; 1) = log(5)
+        let r = evaluate_mpl(&[f(-0.25)], &f(1.)).unwrap();
+        assert!((r.to_f64() - 5f64.ln()).abs() < 1e-15);
+
+        // the shuffle relation holds numerically at high precision
+        let (a, b, x) = (f(-2.), [f(3.), f(-1.5)], f(0.7));
+        let lhs =
+            evaluate_mpl(std::slice::from_ref(&a), &x).unwrap() * evaluate_mpl(&b, &x).unwrap();
+        let rhs = evaluate_mpl(&[a.clone(), b[0].clone(), b[1].clone()], &x).unwrap()
+            + evaluate_mpl(&[b[0].clone(), a.clone(), b[1].clone()], &x).unwrap()
+            + evaluate_mpl(&[b[0].clone(), b[1].clone(), a.clone()], &x).unwrap();
+        assert!(((lhs - rhs).to_f64()).abs() < 1e-55);
+
+        // the result is complex
+        assert!(evaluate_mpl(&[f(0.5)], &f(1.)).is_none());
+
+        let r = parse!("mpl(0, 1, 1.0`30)").unwrap();
+        let AtomView::Num(n) = r.as_view() else {
+            panic!("mpl was not evaluated numerically: {r}");
+        };
+        let CoefficientView::Float(f) = n.get_coeff_view() else {
+            panic!("mpl was not evaluated numerically: {r}");
+        };
+        assert!((f.to_float().to_f64() + pi2 / 6.).abs() < 1e-15);
+    }
+}
@@ -1,13 +1,19 @@
 //! Methods for printing atoms and polynomials.
 
+pub(crate) mod code;
+pub(crate) mod lines;
+pub(crate) mod mathml;
+pub(crate) mod pretty;
+
 use std::fmt::{self, Error, Write};
 
 use colored::Colorize;
 
 use crate::{
-    atom::{AddView, AtomView, MulView, NumView, PowView, VarView, representation::FunView},
+    atom::{AddView, Atom, AtomView, MulView, NumView, PowView, VarView, representation::FunView},
     coefficient::CoefficientView,
     domains::{SelfRing, finite_field::FiniteFieldCore},
+    parser::{Associativity, Operator},
     state::State,
 };
 
@@ -25,6 +31,23 @@ pub enum PrintMode {
     Latex,
     Mathematica,
     Sympy,
+    /// A two-dimensional layout for terminals, with stacked fractions,
+    /// raised exponents and radicals.
+    Pretty,
+    /// A C/C++ expression that uses the functions of `<cmath>`. Expressions
+    /// that contain the imaginary unit use `std::complex<double>`.
+    C,
+    /// A Fortran 90 expression in double precision.
+    Fortran,
+    /// A Python expression that uses vectorized NumPy functions.
+    NumPy,
+    /// Presentation MathML.
+    MathML,
+    /// Content MathML.
+    ContentMathML,
+    /// The input format of FORM, with FORM names for builtin symbols
+    /// such as `sqrt_` and `pi_`.
+    Form,
 }
 
 impl PrintMode {
@@ -43,9 +66,46 @@ impl PrintMode {
     pub fn is_sympy(&self) -> bool {
         *self == PrintMode::Sympy
     }
+
+    pub fn is_pretty(&self) -> bool {
+        *self == PrintMode::Pretty
+    }
+
+    pub fn is_c(&self) -> bool {
+        *self == PrintMode::C
+    }
+
+    pub fn is_fortran(&self) -> bool {
+        *self == PrintMode::Fortran
+    }
+
+    pub fn is_numpy(&self) -> bool {
+        *self == PrintMode::NumPy
+    }
+
+    pub fn is_mathml(&self) -> bool {
+        *self == PrintMode::MathML
+    }
+
+    pub fn is_content_mathml(&self) -> bool {
+        *self == PrintMode::ContentMathML
+    }
+
+    pub fn is_form(&self) -> bool {
+        *self == PrintMode::Form
+    }
+
+    /// Returns `true` if the mode prints source code of a programming language.
+    pub fn is_code(&self) -> bool {
+        matches!(self, PrintMode::C | PrintMode::Fortran | PrintMode::NumPy)
+    }
 }
 
 /// Various options for printing expressions.
+///
+/// Create the options with one of the constructors, such as [PrintOptions::new] or
+/// [PrintOptions::pretty], and adjust them with the struct update syntax or with
+/// the `with_` methods, since new options may be added in future versions.
 #[derive(Debug, Copy, Clone)]
 pub struct PrintOptions {
     pub mode: PrintMode,
@@ -62,6 +122,15 @@ pub struct PrintOptions {
     pub num_exp_as_superscript: bool,
     pub precision: Option<usize>,
     pub pretty_matrix: bool,
+    /// Only use ASCII characters in the [PrintMode::Pretty] layout.
+    pub pretty_ascii: bool,
+    /// Write user functions `sum(f, i)`, `sum(f, i, a, b)` and the corresponding `prod`
+    /// functions with a large operator in the [PrintMode::Pretty] layout.
+    pub pretty_big_operators: bool,
+    /// The maximum width of a line. In the [PrintMode::Pretty] layout, a top-level sum
+    /// is broken into multiple rows. In the other modes, except for MathML, long lines
+    /// are broken at the operators and separators with the lowest nesting depth.
+    pub max_line_length: Option<usize>,
     pub hide_namespace: Option<&'static str>,
     pub hide_all_namespaces: bool,
     pub color_namespace: bool,
@@ -88,6 +157,9 @@ impl PrintOptions {
             mode: PrintMode::Symbolica,
             precision: None,
             pretty_matrix: false,
+            pretty_ascii: false,
+            pretty_big_operators: false,
+            max_line_length: None,
             hide_namespace: None,
             hide_all_namespaces: true,
             color_namespace: true,
@@ -113,6 +185,9 @@ impl PrintOptions {
             mode: PrintMode::Mathematica,
             precision: None,
             pretty_matrix: false,
+            pretty_ascii: false,
+            pretty_big_operators: false,
+            max_line_length: None,
             hide_namespace: None,
             hide_all_namespaces: true,
             color_namespace: false,
@@ -138,6 +213,9 @@ impl PrintOptions {
             mode: PrintMode::Latex,
             precision: None,
             pretty_matrix: false,
+            pretty_ascii: false,
+            pretty_big_operators: false,
+            max_line_length: None,
             hide_namespace: None,
             hide_all_namespaces: true,
             color_namespace: false,
@@ -163,6 +241,9 @@ impl PrintOptions {
             mode: PrintMode::Symbolica,
             precision: None,
             pretty_matrix: false,
+            pretty_ascii: false,
+            pretty_big_operators: false,
+            max_line_length: None,
             hide_namespace: None,
             hide_all_namespaces: false,
             color_namespace: false,
@@ -179,6 +260,31 @@ impl PrintOptions {
         }
     }
 
+    /// Set the maximum width of a line.
+    pub const fn with_max_line_length(self, max_line_length: Option<usize>) -> PrintOptions {
+        Self {
+            max_line_length,
+            ..self
+        }
+    }
+
+    /// Only use ASCII characters in the [PrintMode::Pretty] layout.
+    pub const fn with_pretty_ascii(self, pretty_ascii: bool) -> PrintOptions {
+        Self {
+            pretty_ascii,
+            ..self
+        }
+    }
+
+    /// Write user functions named `sum` and `prod` with a large operator
+    /// in the [PrintMode::Pretty] layout.
+    pub const fn with_pretty_big_operators(self, pretty_big_operators: bool) -> PrintOptions {
+        Self {
+            pretty_big_operators,
+            ..self
+        }
+    }
+
     /// Print the output with namespaces suppressed.
     pub const fn short() -> PrintOptions {
         Self {
@@ -187,14 +293,80 @@ impl PrintOptions {
         }
     }
 
+    /// Print the output in a two-dimensional layout for terminals.
+    pub const fn pretty() -> PrintOptions {
+        Self {
+            mode: PrintMode::Pretty,
+            color_top_level_sum: false,
+            color_builtin_symbols: false,
+            color_namespace: false,
+            max_line_length: Some(80),
+            ..Self::short()
+        }
+    }
+
     /// Print the output in a sympy input format.
     pub const fn sympy() -> PrintOptions {
         Self {
+            mode: PrintMode::Sympy,
             double_star_for_exponentiation: true,
             ..Self::file()
         }
     }
 
+    /// Print the output as a C/C++ expression.
+    pub const fn c() -> PrintOptions {
+        Self {
+            mode: PrintMode::C,
+            ..Self::file_no_namespace()
+        }
+    }
+
+    /// Print the output as a Fortran 90 expression, with lines
+    /// of at most 132 characters.
+    pub const fn fortran() -> PrintOptions {
+        Self {
+            mode: PrintMode::Fortran,
+            double_star_for_exponentiation: true,
+            max_line_length: Some(132),
+            ..Self::file_no_namespace()
+        }
+    }
+
+    /// Print the output as a Python expression that uses NumPy.
+    pub const fn numpy() -> PrintOptions {
+        Self {
+            mode: PrintMode::NumPy,
+            double_star_for_exponentiation: true,
+            ..Self::file_no_namespace()
+        }
+    }
+
+    /// Print the output in a FORM input format, with lines of at most 80 characters.
+    pub const fn form() -> PrintOptions {
+        Self {
+            mode: PrintMode::Form,
+            max_line_length: Some(80),
+            ..Self::file_no_namespace()
+        }
+    }
+
+    /// Print the output in Presentation MathML.
+    pub const fn mathml() -> PrintOptions {
+        Self {
+            mode: PrintMode::MathML,
+            ..Self::file_no_namespace()
+        }
+    }
+
+    /// Print the output in Content MathML.
+    pub const fn content_mathml() -> PrintOptions {
+        Self {
+            mode: PrintMode::ContentMathML,
+            ..Self::file_no_namespace()
+        }
+    }
+
     pub fn from_fmt(f: &std::fmt::Formatter) -> PrintOptions {
         PrintOptions {
             precision: f.precision(),
@@ -205,7 +377,9 @@ impl PrintOptions {
     }
 
     pub fn update_with_fmt(mut self, f: &std::fmt::Formatter) -> Self {
-        self.precision = f.precision();
+        if let Some(p) = f.precision() {
+            self.precision = Some(p);
+        }
 
         if f.alternate() {
             self.hide_all_namespaces = false;
@@ -373,6 +547,32 @@ impl AtomView<'_> {
         opts: &PrintOptions,
         print_state: PrintState,
     ) -> Result<bool, Error> {
+        if opts.mode.is_pretty() {
+            let b = pretty::atom(*self, opts, print_state.level == 0);
+            return pretty::write(fmt, &b);
+        }
+
+        if opts.mode.is_mathml() || opts.mode.is_content_mathml() {
+            return mathml::write(fmt, *self, opts, print_state.level == 0);
+        }
+
+        if let Some(width) = opts.max_line_length {
+            if print_state.level == 0 {
+                let opts = PrintOptions {
+                    max_line_length: None,
+                    ..*opts
+                };
+                let mut s = String::new();
+                let r = self.format(&mut s, &opts, print_state)?;
+                fmt.write_str(&lines::break_lines(&s, width, opts.mode))?;
+                return Ok(r);
+            }
+        }
+
+        if opts.mode.is_code() {
+            return code::write(fmt, *self, opts);
+        }
+
         match self {
             AtomView::Num(n) => n.fmt_output(fmt, opts, print_state),
             AtomView::Var(v) => v.fmt_output(fmt, opts, print_state),
@@ -465,6 +665,7 @@ impl AtomView<'_> {
             }
             AtomView::Mul(m) => {
                 let mut terms = vec![];
+                let mut noncommutative_terms = vec![];
 
                 for x in m.iter() {
                     let mut term = if add_paren(*self, x) {
@@ -479,10 +680,16 @@ impl AtomView<'_> {
                         term.push(')');
                     }
 
-                    terms.push(term);
+                    if x.is_noncommutative() {
+                        noncommutative_terms.push(term);
+                    } else {
+                        terms.push(term);
+                    }
                 }
 
+                // noncommutative factors keep their relative order
                 terms.sort();
+                terms.extend(noncommutative_terms);
 
                 for (i, term) in terms.iter().enumerate() {
                     if i > 0 {
@@ -772,21 +979,27 @@ impl FormattedPrintMul for MulView<'_> {
         print_state.top_level_add_child = false;
         print_state.level += 1;
         print_state.in_sum = false;
+        let mut last_noncommutative = false;
         for x in self.iter().take(if skip_num {
             self.get_nargs() - 1
         } else {
             self.get_nargs()
         }) {
+            let noncommutative = x.is_noncommutative();
             if !first {
-                if opts.mode.is_latex() {
+                if opts.mode.is_mathematica() && last_noncommutative && noncommutative {
+                    f.write_str("**")?;
+                } else if opts.mode.is_latex() {
                     f.write_char(' ')?;
                 } else {
                     f.write_char(opts.multiplication_operator)?;
                 }
             }
             first = false;
+            last_noncommutative = noncommutative;
 
-            if let AtomView::Add(_) = x {
+            // sums and relations need parentheses
+            if OperatorForm::get_print_precedence(x, opts) < Operator::Mul.get_precedence() {
                 if opts.mode.is_latex() {
                     f.write_str("\\left(")?;
                 } else {
@@ -810,6 +1023,228 @@ impl FormattedPrintMul for MulView<'_> {
     }
 }
 
+/// The operator notation of a function, such as `a==b` for `equal(a,b)`.
+enum OperatorForm {
+    /// The arguments are separated by the operator, which has the given precedence.
+    Infix(String, u8, Associativity),
+    /// The single argument is followed by the operator, which has the given precedence.
+    Postfix(String, u8),
+    List,
+    /// A chained relation `a<b<c`, which is written as `And(Lt(a,b),Lt(b,c))` in SymPy.
+    Chain,
+}
+
+impl OperatorForm {
+    /// Get the operator notation of the function `f`, if it has one in the print mode of `opts`.
+    /// Custom operators are only printed as operators in the Symbolica and pretty modes.
+    fn new(f: FunView, opts: &PrintOptions) -> Option<OperatorForm> {
+        let id = f.get_symbol();
+        let nargs = f.get_nargs();
+
+        match id {
+            Atom::LIST => Some(OperatorForm::List),
+            Atom::EQUAL
+            | Atom::UNEQUAL
+            | Atom::LESS
+            | Atom::LESS_EQUAL
+            | Atom::GREATER
+            | Atom::GREATER_EQUAL
+                if opts.mode.is_sympy() && nargs > 2 =>
+            {
+                Some(OperatorForm::Chain)
+            }
+            _ if opts.mode.is_sympy() => None,
+            Atom::EQUAL
+            | Atom::UNEQUAL
+            | Atom::LESS
+            | Atom::LESS_EQUAL
+            | Atom::GREATER
+            | Atom::GREATER_EQUAL
+                if nargs >= 2 =>
+            {
+                let (op, latex) = match id {
+                    Atom::EQUAL => ("==", "="),
+                    Atom::UNEQUAL => ("!=", "\\neq "),
+                    Atom::LESS => ("<", "<"),
+                    Atom::LESS_EQUAL => ("<=", "\\leq "),
+                    Atom::GREATER => (">", ">"),
+                    _ => (">=", "\\geq "),
+                };
+
+                // chained relations are parsed into a single function
+                Some(OperatorForm::Infix(
+                    if opts.mode.is_latex() { latex } else { op }.into(),
+                    Operator::Equal.get_precedence(),
+                    Associativity::Flat,
+                ))
+            }
+            Atom::FACTORIAL | Atom::DOUBLE_FACTORIAL if nargs == 1 && !opts.mode.is_form() => {
+                Some(OperatorForm::Postfix(
+                    if id == Atom::FACTORIAL { "!" } else { "!!" }.into(),
+                    Operator::Factorial.get_precedence(),
+                ))
+            }
+            _ if (opts.mode.is_symbolica() || opts.mode.is_pretty()) && !State::is_builtin(id) => {
+                let op = State::get_symbol_data(id).operator.get()?;
+                if op.postfix && nargs == 1 {
+                    Some(OperatorForm::Postfix(op.name.to_string(), op.precedence))
+                } else if !op.postfix
+                    && (nargs == 2 || nargs > 2 && op.associativity == Associativity::Flat)
+                {
+                    Some(OperatorForm::Infix(
+                        op.name.to_string(),
+                        op.precedence,
+                        op.associativity,
+                    ))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn get_precedence(&self) -> u8 {
+        match self {
+            OperatorForm::Infix(_, p, _) | OperatorForm::Postfix(_, p) => *p,
+            OperatorForm::List | OperatorForm::Chain => u8::MAX,
+        }
+    }
+
+    /// Get the precedence of the outermost operator of `x` when it is printed.
+    /// Negative and fractional numbers have the precedence of a multiplication.
+    fn get_print_precedence(x: AtomView, opts: &PrintOptions) -> u8 {
+        match x {
+            AtomView::Add(_) => Operator::Add.get_precedence(),
+            AtomView::Mul(_) => Operator::Mul.get_precedence(),
+            AtomView::Pow(_) => Operator::Pow.get_precedence(),
+            AtomView::Num(n) => match n.get_coeff_view() {
+                CoefficientView::Natural(n, d) if n >= 0 && d == 1 => u8::MAX,
+                CoefficientView::Large(r) if !r.is_negative() && r.to_rat().is_integer() => u8::MAX,
+                CoefficientView::FiniteField(_, _) | CoefficientView::RationalPolynomial(_) => {
+                    u8::MAX
+                }
+                _ => Operator::Mul.get_precedence(),
+            },
+            AtomView::Fun(f) => OperatorForm::new(f, opts)
+                .map(|o| o.get_precedence())
+                .unwrap_or(u8::MAX),
+            AtomView::Var(_) => u8::MAX,
+        }
+    }
+
+    /// Returns `true` if the `i`th operand `x` of the infix operator `fun` does not need
+    /// parentheses when it has the same precedence as the operator.
+    fn is_associative_operand(
+        associativity: Associativity,
+        fun: FunView,
+        i: usize,
+        x: AtomView,
+    ) -> bool {
+        // operators with the same precedence are parsed left to right,
+        // except for repeated right-associative operators
+        let same = matches!(x, AtomView::Fun(g) if g.get_symbol() == fun.get_symbol());
+        if i == 0 {
+            !same || associativity == Associativity::Left
+        } else {
+            i + 1 == fun.get_nargs() && same && associativity == Associativity::Right
+        }
+    }
+
+    /// Print the operand `x` of an operator with precedence `precedence`. If `associative` is
+    /// set, an operand with the same precedence does not need parentheses.
+    fn fmt_operand<W: std::fmt::Write>(
+        x: AtomView,
+        precedence: u8,
+        associative: bool,
+        f: &mut W,
+        opts: &PrintOptions,
+        print_state: PrintState,
+    ) -> Result<bool, Error> {
+        let p = Self::get_print_precedence(x, opts);
+        if p > precedence || p == precedence && associative {
+            return x.format(f, opts, print_state);
+        }
+
+        if opts.mode.is_latex() {
+            f.write_str("\\left(")?;
+        } else {
+            f.write_char('(')?;
+        }
+        x.format(f, opts, print_state)?;
+        if opts.mode.is_latex() {
+            f.write_str("\\right)")?;
+        } else {
+            f.write_char(')')?;
+        }
+        Ok(false)
+    }
+
+    fn fmt_output<W: std::fmt::Write>(
+        &self,
+        fun: FunView,
+        f: &mut W,
+        opts: &PrintOptions,
+        print_state: PrintState,
+    ) -> Result<bool, Error> {
+        match self {
+            OperatorForm::Infix(op, precedence, associativity) => {
+                for (i, x) in fun.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(op)?;
+                    }
+
+                    let associative = Self::is_associative_operand(*associativity, fun, i, x);
+                    Self::fmt_operand(x, *precedence, associative, f, opts, print_state)?;
+                }
+            }
+            OperatorForm::Postfix(op, precedence) => {
+                // nested postfix operators get parentheses, so that `(x!)!` is not written as `x!!`
+                let x = fun.iter().next().unwrap();
+                Self::fmt_operand(x, *precedence, false, f, opts, print_state)?;
+                f.write_str(op)?;
+            }
+            OperatorForm::List => {
+                let (open, close) = if opts.mode.is_latex() {
+                    ("\\left\\{", "\\right\\}")
+                } else if opts.mode.is_sympy() {
+                    ("[", "]")
+                } else {
+                    ("{", "}")
+                };
+
+                f.write_str(open)?;
+                for (i, x) in fun.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    x.format(f, opts, print_state)?;
+                }
+                f.write_str(close)?;
+            }
+            OperatorForm::Chain => {
+                let args: Vec<_> = fun.iter().collect();
+
+                f.write_str("And(")?;
+                for (i, x) in args.windows(2).enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    fun.get_symbol().format(opts, f)?;
+                    f.write_char('(')?;
+                    x[0].format(f, opts, print_state)?;
+                    f.write_char(',')?;
+                    x[1].format(f, opts, print_state)?;
+                    f.write_char(')')?;
+                }
+                f.write_char(')')?;
+            }
+        }
+
+        Ok(false)
+    }
+}
+
 impl FormattedPrintFn for FunView<'_> {
     fn fmt_output<W: std::fmt::Write>(
         &self,
@@ -836,6 +1271,14 @@ impl FormattedPrintFn for FunView<'_> {
             }
         }
 
+        if let Some(form) = OperatorForm::new(*self, opts) {
+            print_state.top_level_add_child = false;
+            print_state.level += 1;
+            print_state.in_sum = false;
+            print_state.suppress_one = false;
+            return form.fmt_output(*self, f, opts, print_state);
+        }
+
         id.format(opts, f)?;
 
         if opts.mode.is_latex() {
@@ -933,7 +1376,7 @@ impl FormattedPrintPow for PowView<'_> {
                         CoefficientView::RationalPolynomial(_) => true,
                     }
                 } else {
-                    false
+                    OperatorForm::get_print_precedence(b, opts) <= Operator::Pow.get_precedence()
                 };
 
         if base_needs_parentheses {
@@ -971,7 +1414,7 @@ impl FormattedPrintPow for PowView<'_> {
                 || if let AtomView::Num(n) = e {
                     !n.get_coeff_view().is_integer()
                 } else {
-                    false
+                    OperatorForm::get_print_precedence(e, opts) < Operator::Pow.get_precedence()
                 };
 
             if exp_needs_parentheses {
@@ -1036,7 +1479,32 @@ impl FormattedPrintAdd for AddView<'_> {
             }
             first = false;
 
-            x.format(f, opts, print_state)?;
+            if OperatorForm::get_print_precedence(x, opts) < Operator::Add.get_precedence() {
+                // relations need parentheses
+                if print_state.in_sum {
+                    if print_state.top_level_add_child
+                        && opts.mode.is_symbolica()
+                        && opts.color_top_level_sum
+                    {
+                        f.write_fmt(format_args!("{}", "+".yellow()))?;
+                    } else {
+                        f.write_char('+')?;
+                    }
+                }
+
+                f.write_char('(')?;
+                x.format(
+                    f,
+                    opts,
+                    PrintState {
+                        in_sum: false,
+                        ..print_state
+                    },
+                )?;
+                f.write_char(')')?;
+            } else {
+                x.format(f, opts, print_state)?;
+            }
             print_state.in_sum = true;
             count += 1;
         }
@@ -1203,6 +1671,21 @@ mod test {
         )
     }
 
+    #[test]
+    fn sympy() {
+        let a = parse!("{x!, y!!} + (x < y) + (x == y == 2)").unwrap();
+        assert_eq!(
+            format!(
+                "{}",
+                a.printer(PrintOptions {
+                    hide_all_namespaces: true,
+                    ..PrintOptions::sympy()
+                })
+            ),
+            "[factorial(x),factorial2(y)]+And(Eq(x,y),Eq(y,2))+Lt(x,y)"
+        );
+    }
+
     #[test]
     fn canon() {
         let _ = symbol!("canon_f"; Symmetric).unwrap();
@@ -1250,4 +1733,19 @@ mod test {
         let s = format!("{}", e.printer(PrintOptions::latex()));
         assert_eq!(s, "\\mu^{2}+\\mu_{1}+\\mu_{1,2}");
     }
+
+    #[test]
+    fn noncommutative() {
+        symbol!("nc1"; Noncommutative).unwrap();
+        symbol!("nc2"; Noncommutative).unwrap();
+
+        let a = parse!("x*nc2*nc1").unwrap();
+        assert_eq!(
+            a.as_view().to_canonical_string(),
+            "symbolica::x*symbolica::nc2*symbolica::nc1"
+        );
+
+        let s = format!("{}", a.printer(PrintOptions::mathematica()));
+        assert_eq!(s, "x nc2**nc1");
+    }
 }
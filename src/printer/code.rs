@@ -0,0 +1,576 @@
+//! Output of expressions as source code for [PrintMode::C], [PrintMode::Fortran]
+//! and [PrintMode::NumPy].
+//!
+//! Rational numbers are written with floating-point literals in C and Fortran,
+//! so that they are never evaluated with integer division.
+//!
+//! Builtin functions that have no equivalent in the target language, such as `zeta` in C,
+//! are written with the prefix `unsupported_`, so that the code does not compile or run
+//! unless the user provides an implementation.
+
+use std::fmt::{Error, Write};
+
+use crate::{
+    atom::{Atom, AtomView, MulView, Symbol, representation::FunView},
+    coefficient::CoefficientView,
+    state::State,
+};
+
+use super::{PrintMode, PrintOptions, PrintState, pretty::is_negative_number};
+
+/// The largest integer power that is written as a repeated multiplication in C.
+const MAX_EXPANDED_POWER: i64 = 4;
+
+// The precedence of the outermost operator of a piece of code.
+const RELATION: u8 = 0;
+const SUM: u8 = 1;
+const NEGATION: u8 = 2;
+const PRODUCT: u8 = 3;
+const POWER: u8 = 4;
+const ATOM: u8 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+    C,
+    Fortran,
+    NumPy,
+}
+
+/// Code with the precedence of its outermost operator.
+struct Code {
+    s: String,
+    precedence: u8,
+}
+
+impl Code {
+    fn new(s: impl Into<String>, precedence: u8) -> Code {
+        Code {
+            s: s.into(),
+            precedence,
+        }
+    }
+
+    /// Get the code as the operand of an operator with precedence `precedence`,
+    /// adding parentheses if needed.
+    fn operand(&self, precedence: u8) -> String {
+        if self.precedence < precedence {
+            format!("({})", self.s)
+        } else {
+            self.s.clone()
+        }
+    }
+
+    fn negate(&self) -> Code {
+        Code::new(format!("-{}", self.operand(PRODUCT)), NEGATION)
+    }
+}
+
+struct CodePrinter<'a> {
+    language: Language,
+    /// Use the `std::complex` functions in C.
+    complex: bool,
+    opts: &'a PrintOptions,
+}
+
+/// Write the atom `x` as code in the language of the print mode of `opts`.
+pub(crate) fn write<W: Write>(f: &mut W, x: AtomView, opts: &PrintOptions) -> Result<bool, Error> {
+    let language = match opts.mode {
+        PrintMode::Fortran => Language::Fortran,
+        PrintMode::NumPy => Language::NumPy,
+        _ => Language::C,
+    };
+
+    let printer = CodePrinter {
+        language,
+        complex: language == Language::C && x.contains_symbol(Atom::I),
+        opts,
+    };
+
+    f.write_str(&printer.atom(x).s)?;
+    Ok(false)
+}
+
+impl CodePrinter<'_> {
+    fn atom(&self, x: AtomView) -> Code {
+        match x {
+            AtomView::Num(_) => {
+                let (negative, c) = self.number(x);
+                if negative { c.negate() } else { c }
+            }
+            AtomView::Var(v) => self.symbol(v.get_symbol()),
+            AtomView::Fun(f) => self.function(f),
+            AtomView::Pow(p) => {
+                let (b, e) = p.get_base_exp();
+                self.power(b, e)
+            }
+            AtomView::Mul(m) => self.mul(m),
+            AtomView::Add(a) => {
+                let mut s = String::new();
+                for (i, t) in a.iter().enumerate() {
+                    let t = self.atom(t);
+                    if i == 0 {
+                        s.push_str(&t.operand(SUM));
+                    } else if t.precedence == NEGATION {
+                        s.push_str(" - ");
+                        s.push_str(&t.s[1..]);
+                    } else {
+                        s.push_str(" + ");
+                        s.push_str(&t.operand(SUM));
+                    }
+                }
+                Code::new(s, SUM)
+            }
+        }
+    }
+
+    /// Get the code of the absolute value of the number `x` and whether it is negative.
+    fn number(&self, x: AtomView) -> (bool, Code) {
+        let AtomView::Num(n) = x else { unreachable!() };
+
+        match n.get_coeff_view() {
+            CoefficientView::Natural(n, d) => (
+                n < 0,
+                self.rational(&n.unsigned_abs().to_string(), &d.to_string(), false),
+            ),
+            CoefficientView::Large(r) => {
+                let rat = r.to_rat().abs();
+                (
+                    r.is_negative(),
+                    self.rational(
+                        &rat.numerator_ref().to_string(),
+                        &rat.denominator_ref().to_string(),
+                        true,
+                    ),
+                )
+            }
+            CoefficientView::Float(fl) => {
+                let s = if let Some(p) = self.opts.precision {
+                    format!("{:.*}", p, fl.to_float())
+                } else {
+                    format!("{}", fl.to_float())
+                };
+
+                match s.strip_prefix('-') {
+                    Some(s) => (true, self.float(s)),
+                    None => (false, self.float(&s)),
+                }
+            }
+            _ => {
+                // finite field elements and rational polynomials have no equivalent in code
+                let opts = PrintOptions {
+                    mode: PrintMode::Symbolica,
+                    ..*self.opts
+                };
+                let mut s = String::new();
+                x.format(&mut s, &opts, PrintState::new())
+                    .expect("Could not write to string");
+                (false, Code::new(s, ATOM))
+            }
+        }
+    }
+
+    /// Get the code of the integer `n`. A `large` integer may not fit in a machine integer.
+    fn integer(&self, n: &str, large: bool) -> String {
+        match self.language {
+            Language::C if large || self.complex => format!("{}.0", n),
+            // the default integer kind of Fortran has 32 bits
+            Language::Fortran if large || n.parse::<i32>().is_err() => format!("{}d0", n),
+            _ => n.to_string(),
+        }
+    }
+
+    /// Get the code of the argument `x` of an intrinsic function. Fortran does not
+    /// accept integers in functions such as `sqrt`, so integers are written as real numbers.
+    fn argument(&self, x: AtomView) -> Code {
+        if let (Language::Fortran, AtomView::Num(n)) = (self.language, x) {
+            if let CoefficientView::Natural(n, 1) = n.get_coeff_view() {
+                let c = Code::new(format!("{}d0", n.unsigned_abs()), ATOM);
+                return if n < 0 { c.negate() } else { c };
+            }
+        }
+
+        self.atom(x)
+    }
+
+    fn rational(&self, num: &str, den: &str, large: bool) -> Code {
+        if den == "1" {
+            return Code::new(self.integer(num, large), ATOM);
+        }
+
+        match self.language {
+            Language::C => Code::new(format!("{}.0/{}.0", num, den), PRODUCT),
+            Language::Fortran => Code::new(format!("{}d0/{}d0", num, den), PRODUCT),
+            Language::NumPy => Code::new(format!("{}/{}", num, den), PRODUCT),
+        }
+    }
+
+    fn float(&self, s: &str) -> Code {
+        let s = match self.language {
+            Language::C if !s.contains(['.', 'e']) => format!("{}.0", s),
+            Language::Fortran => match s.split_once('e') {
+                Some((m, e)) => format!("{}d{}", m, e),
+                None => format!("{}d0", s),
+            },
+            _ => s.to_string(),
+        };
+        Code::new(s, ATOM)
+    }
+
+    /// The literal `1` as a floating-point number.
+    fn one(&self) -> &'static str {
+        match self.language {
+            Language::C => "1.0",
+            Language::Fortran => "1d0",
+            Language::NumPy => "1",
+        }
+    }
+
+    fn symbol(&self, id: Symbol) -> Code {
+        let s = match (id, self.language) {
+            (Atom::PI, Language::C) => "M_PI",
+            (Atom::PI, Language::Fortran) => "acos(-1d0)",
+            (Atom::PI, Language::NumPy) => "np.pi",
+            (Atom::E, Language::C) => "M_E",
+            (Atom::E, Language::Fortran) => "exp(1d0)",
+            (Atom::E, Language::NumPy) => "np.e",
+            (Atom::I, Language::C) => "std::complex<double>(0, 1)",
+            (Atom::I, Language::Fortran) => "(0d0, 1d0)",
+            (Atom::I, Language::NumPy) => "1j",
+            _ => id.get_stripped_name(),
+        };
+        Code::new(s, ATOM)
+    }
+
+    /// Get the name of a function of the C standard library, which is taken
+    /// from `std` for complex arguments.
+    fn c_function(&self, name: &str) -> String {
+        if self.complex {
+            format!("std::{}", name)
+        } else {
+            name.to_string()
+        }
+    }
+
+    fn call(&self, name: &str, args: &[Code]) -> Code {
+        let args: Vec<_> = args.iter().map(|a| a.s.as_str()).collect();
+        Code::new(format!("{}({})", name, args.join(", ")), ATOM)
+    }
+
+    fn power(&self, b: AtomView, e: AtomView) -> Code {
+        if let AtomView::Var(v) = b {
+            if v.get_symbol() == Atom::E {
+                return self.function_call(Atom::EXP, &[self.argument(e)]);
+            }
+        }
+
+        if let AtomView::Num(n) = e {
+            match n.get_coeff_view() {
+                CoefficientView::Natural(1, 1) => return self.atom(b),
+                CoefficientView::Natural(1, 2) => {
+                    return self.function_call(Atom::SQRT, &[self.argument(b)]);
+                }
+                CoefficientView::Natural(n, 1)
+                    if self.language == Language::C && (2..=MAX_EXPANDED_POWER).contains(&n) =>
+                {
+                    let base = self.atom(b).operand(POWER);
+                    let factors = vec![base; n as usize];
+                    return Code::new(factors.join("*"), PRODUCT);
+                }
+                _ if is_negative_number(e) => {
+                    let e = -e;
+                    let den = self.power(b, e.as_view()).operand(POWER);
+                    return Code::new(format!("{}/{}", self.one(), den), PRODUCT);
+                }
+                _ => {}
+            }
+        }
+
+        if self.language == Language::C {
+            self.call(&self.c_function("pow"), &[self.atom(b), self.atom(e)])
+        } else {
+            let base = self.atom(b).operand(ATOM);
+            let exp = self.atom(e).operand(ATOM);
+            Code::new(format!("{}**{}", base, exp), POWER)
+        }
+    }
+
+    fn mul(&self, m: MulView) -> Code {
+        let mut negative = false;
+        let mut num = vec![];
+        let mut den = vec![];
+
+        for x in m.iter() {
+            match x {
+                AtomView::Num(n) => {
+                    let (neg, c) = self.number(x);
+                    negative = neg;
+                    if !matches!(n.get_coeff_view(), CoefficientView::Natural(1 | -1, 1)) {
+                        num.insert(0, c);
+                    }
+                }
+                AtomView::Pow(p) if is_negative_number(p.get_exp()) => {
+                    let (b, e) = p.get_base_exp();
+                    let e = -e;
+                    den.push(self.power(b, e.as_view()));
+                }
+                _ => num.push(self.atom(x)),
+            }
+        }
+
+        let mut s = if num.is_empty() {
+            self.one().to_string()
+        } else {
+            let num: Vec<_> = num.iter().map(|c| c.operand(PRODUCT)).collect();
+            num.join("*")
+        };
+
+        if den.len() == 1 {
+            s.push('/');
+            s.push_str(&den[0].operand(POWER));
+        } else if !den.is_empty() {
+            let den: Vec<_> = den.iter().map(|c| c.operand(PRODUCT)).collect();
+            s.push_str(&format!("/({})", den.join("*")));
+        }
+
+        if negative {
+            Code::new(format!("-{}", s), NEGATION)
+        } else {
+            Code::new(s, PRODUCT)
+        }
+    }
+
+    /// Get the name of the builtin function `id` with `nargs` arguments in the target language.
+    fn builtin_name(&self, id: Symbol, nargs: usize) -> Option<String> {
+        let name = match (self.language, id, nargs) {
+            (_, Atom::EXP, 1) => "exp",
+            (_, Atom::LOG, 1) => "log",
+            (_, Atom::SIN, 1) => "sin",
+            (_, Atom::COS, 1) => "cos",
+            (_, Atom::TAN, 1) => "tan",
+            (_, Atom::SQRT, 1) => "sqrt",
+            (_, Atom::SINH, 1) => "sinh",
+            (_, Atom::COSH, 1) => "cosh",
+            (_, Atom::TANH, 1) => "tanh",
+            (Language::NumPy, Atom::ARCSIN, 1) => "arcsin",
+            (Language::NumPy, Atom::ARCCOS, 1) => "arccos",
+            (Language::NumPy, Atom::ARCTAN, 1) => "arctan",
+            (Language::NumPy, Atom::ARCSINH, 1) => "arcsinh",
+            (Language::NumPy, Atom::ARCCOSH, 1) => "arccosh",
+            (Language::NumPy, Atom::ARCTANH, 1) => "arctanh",
+            (_, Atom::ARCSIN, 1) => "asin",
+            (_, Atom::ARCCOS, 1) => "acos",
+            (_, Atom::ARCTAN, 1) => "atan",
+            (_, Atom::ARCSINH, 1) => "asinh",
+            (_, Atom::ARCCOSH, 1) => "acosh",
+            (_, Atom::ARCTANH, 1) => "atanh",
+            (Language::C, Atom::ABS, 1) if !self.complex => "fabs",
+            (_, Atom::ABS, 1) => "abs",
+            (Language::NumPy, Atom::SIGN, 1) => "sign",
+            (Language::C, Atom::GAMMA, 1) => "tgamma",
+            (Language::Fortran, Atom::GAMMA, 1) => "gamma",
+            (Language::C | Language::Fortran, Atom::ERF, 1) => "erf",
+            (Language::NumPy, Atom::GAMMA, 1) => return Some("scipy.special.gamma".into()),
+            (Language::NumPy, Atom::ERF, 1) => return Some("scipy.special.erf".into()),
+            (Language::NumPy, Atom::ZETA, 1) => return Some("scipy.special.zeta".into()),
+            (Language::NumPy, Atom::POLYGAMMA, 2) => {
+                return Some("scipy.special.polygamma".into());
+            }
+            (Language::NumPy, Atom::BETA, 2) => return Some("scipy.special.beta".into()),
+            (Language::NumPy, Atom::BESSEL_J, 2) => return Some("scipy.special.jv".into()),
+            (Language::NumPy, Atom::BESSEL_Y, 2) => return Some("scipy.special.yv".into()),
+            (Language::NumPy, Atom::BESSEL_I, 2) => return Some("scipy.special.iv".into()),
+            (Language::NumPy, Atom::BESSEL_K, 2) => return Some("scipy.special.kv".into()),
+            _ => return None,
+        };
+
+        Some(match self.language {
+            Language::C => self.c_function(name),
+            Language::Fortran => name.to_string(),
+            Language::NumPy => format!("np.{}", name),
+        })
+    }
+
+    /// Call the builtin function `id`, which must have a name in the target language.
+    fn function_call(&self, id: Symbol, args: &[Code]) -> Code {
+        let name = self.builtin_name(id, args.len()).unwrap();
+        self.call(&name, args)
+    }
+
+    fn function(&self, f: FunView) -> Code {
+        let id = f.get_symbol();
+        if let Some(custom_print) = &State::get_symbol_data(id).custom_print {
+            if let Some(s) = custom_print(f.as_view(), self.opts) {
+                return Code::new(s, ATOM);
+            }
+        }
+
+        let intrinsic = id == Atom::FACTORIAL || self.builtin_name(id, f.get_nargs()).is_some();
+        let args: Vec<Code> = if intrinsic {
+            f.iter().map(|a| self.argument(a)).collect()
+        } else {
+            f.iter().map(|a| self.atom(a)).collect()
+        };
+
+        match id {
+            Atom::EQUAL
+            | Atom::UNEQUAL
+            | Atom::LESS
+            | Atom::LESS_EQUAL
+            | Atom::GREATER
+            | Atom::GREATER_EQUAL
+                if args.len() >= 2 =>
+            {
+                return self.relation(id, &args);
+            }
+            Atom::FACTORIAL if args.len() == 1 => {
+                if self.language == Language::NumPy {
+                    return self.call("scipy.special.factorial", &args);
+                }
+
+                let arg = format!("{} + {}", args[0].operand(SUM), self.integer("1", false));
+                return self.function_call(Atom::GAMMA, &[Code::new(arg, SUM)]);
+            }
+            Atom::HEAVISIDE if args.len() == 1 && self.language == Language::NumPy => {
+                return self.call("np.heaviside", &[args[0].operand(SUM).into(), "0.5".into()]);
+            }
+            Atom::LIST => {
+                let args: Vec<_> = args.iter().map(|a| a.s.as_str()).collect();
+                let s = match self.language {
+                    Language::C => format!("{{{}}}", args.join(", ")),
+                    Language::Fortran => format!("(/ {} /)", args.join(", ")),
+                    Language::NumPy => format!("np.array([{}])", args.join(", ")),
+                };
+                return Code::new(s, ATOM);
+            }
+            _ => {}
+        }
+
+        match self.builtin_name(id, args.len()) {
+            Some(name) => self.call(&name, &args),
+            None if State::is_builtin(id) => {
+                self.call(&format!("unsupported_{}", id.get_stripped_name()), &args)
+            }
+            None => self.call(id.get_stripped_name(), &args),
+        }
+    }
+
+    /// Write a (chained) relation as a conjunction of comparisons.
+    fn relation(&self, id: Symbol, args: &[Code]) -> Code {
+        let op = match id {
+            Atom::EQUAL => "==",
+            Atom::UNEQUAL if self.language == Language::Fortran => "/=",
+            Atom::UNEQUAL => "!=",
+            Atom::LESS => "<",
+            Atom::LESS_EQUAL => "<=",
+            Atom::GREATER => ">",
+            _ => ">=",
+        };
+
+        let comparisons: Vec<_> = args
+            .windows(2)
+            .map(|w| format!("{} {} {}", w[0].operand(SUM), op, w[1].operand(SUM)))
+            .collect();
+
+        if comparisons.len() == 1 {
+            return Code::new(comparisons.into_iter().next().unwrap(), RELATION);
+        }
+
+        let s = match self.language {
+            Language::C => comparisons.join(" && "),
+            Language::Fortran => comparisons.join(" .and. "),
+            Language::NumPy => {
+                // the elementwise and binds more strongly than a comparison
+                let c: Vec<_> = comparisons.iter().map(|c| format!("({})", c)).collect();
+                c.join(" & ")
+            }
+        };
+        Code::new(s, RELATION)
+    }
+}
+
+impl From<String> for Code {
+    fn from(s: String) -> Code {
+        Code::new(s, ATOM)
+    }
+}
+
+impl From<&str> for Code {
+    fn from(s: &str) -> Code {
+        Code::new(s, ATOM)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{atom::AtomCore, parse, printer::PrintOptions};
+
+    fn code(input: &str, opts: PrintOptions) -> String {
+        parse!(input).unwrap().printer(opts).to_string()
+    }
+
+    #[test]
+    fn c() {
+        assert_eq!(
+            code("x^3/(y+1) - 3/4*sqrt(z)", PrintOptions::c()),
+            "-3.0/4.0*sqrt(z) + x*x*x/(y + 1)"
+        );
+        assert_eq!(
+            code("𝜋*x^7 + exp(x)^-2 + abs(x) - x^(-1/2)", PrintOptions::c()),
+            "-1.0/sqrt(x) + 1.0/(exp(x)*exp(x)) + fabs(x) + M_PI*pow(x, 7)"
+        );
+        assert_eq!(
+            code("2*x + 𝑖*cos(x)^2", PrintOptions::c()),
+            "2.0*x + std::complex<double>(0, 1)*std::cos(x)*std::cos(x)"
+        );
+        assert_eq!(
+            code("zeta(x) + f(x)", PrintOptions::c()),
+            "unsupported_zeta(x) + f(x)"
+        );
+    }
+
+    #[test]
+    fn fortran() {
+        assert_eq!(
+            code(
+                "x^3/(y+1) - 3/4*sqrt(z)*x^(-2) + 𝑖*x",
+                PrintOptions::fortran()
+            ),
+            "(0d0, 1d0)*x - 3d0/4d0*sqrt(z)/x**2 + x**3/(y + 1)"
+        );
+
+        let opts = PrintOptions {
+            precision: Some(5),
+            ..PrintOptions::fortran()
+        };
+        assert_eq!(
+            code("1.2345678*x^(2/3) + 1e-20*𝜋 + x! + 𝑒^x", opts),
+            "1.0000d-20*acos(-1d0) + exp(x) + 1.2346d0*x**(2d0/3d0) + gamma(x + 1)"
+        );
+        assert_eq!(
+            code("sign(x)*bessel_j(1, x)", PrintOptions::fortran()),
+            "unsupported_sign(x)*unsupported_bessel_j(1, x)"
+        );
+        assert_eq!(
+            code(
+                "sqrt(2)*x + exp(2) + log(3) + sin(-1) + 2*x^2 + 10000000000*y",
+                PrintOptions::fortran()
+            ),
+            "10000000000d0*y + 2*x**2 + exp(2d0) + log(3d0) + sin(-1d0) + x*sqrt(2d0)"
+        );
+    }
+
+    #[test]
+    fn numpy() {
+        assert_eq!(
+            code(
+                "arcsin(x)^2/(y+1) - 𝑖*gamma(z) + 1/3",
+                PrintOptions::numpy()
+            ),
+            "-1j*scipy.special.gamma(z) + np.arcsin(x)**2/(y + 1) + 1/3"
+        );
+        assert_eq!(
+            code("{x < y, heaviside(x), x != y}", PrintOptions::numpy()),
+            "np.array([x < y, np.heaviside(x, 0.5), x != y])"
+        );
+    }
+}
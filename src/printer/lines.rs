@@ -0,0 +1,268 @@
+//! Breaking of the lines of linear output that are longer than
+//! [PrintOptions::max_line_length](super::PrintOptions::max_line_length).
+//!
+//! A line is broken before a binary `+` or `-`, or after a `,` or `*`. Of the positions
+//! that fill at least half of the line, the one with the fewest enclosing parentheses is
+//! chosen, so that a sum is split into its terms before the arguments of a function are
+//! split. A continued line is indented by its number of enclosing parentheses.
+
+use super::PrintMode;
+
+/// The number of spaces by which a continued line is indented per enclosing parenthesis.
+const INDENT: usize = 2;
+
+/// A position at which a line can be broken.
+#[derive(Debug, Clone, Copy)]
+struct BreakPoint {
+    /// The byte at which the next line starts.
+    byte: usize,
+    /// The column at which the next line starts.
+    column: usize,
+    /// The number of enclosing parentheses.
+    depth: usize,
+    /// The preference for breaking here: a sum is broken before
+    /// a list of arguments, which is broken before a product.
+    rank: u8,
+}
+
+/// Break the lines of `s` that are longer than `width` characters, using the line
+/// continuation convention of the print mode `mode`.
+pub(crate) fn break_lines(s: &str, width: usize, mode: PrintMode) -> String {
+    let continuation = match mode {
+        PrintMode::Fortran => " &",
+        PrintMode::NumPy => " \\",
+        _ => "",
+    };
+
+    let mut out = String::with_capacity(s.len() + s.len() / width.max(1));
+    for (i, line) in s.split('\n').enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        break_line(line, width, continuation, mode.is_form(), &mut out);
+    }
+    out
+}
+
+/// Returns `true` if `token` is the mantissa of a number in scientific notation,
+/// such as `1.5e` in `1.5e-3` or `2d` in `2d+5`.
+fn is_mantissa(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_ascii_digit()) && token.ends_with(['e', 'E', 'd', 'D'])
+}
+
+/// Find the positions at which `line` can be broken. Also returns the width of the line.
+fn break_points(line: &str) -> (Vec<BreakPoint>, usize) {
+    let mut points = vec![];
+    let mut column = 0;
+    let mut depth: usize = 0;
+    // the last character that is not a space, and the byte and column after it
+    let mut prev = None;
+    let mut prev_end = (0, 0);
+    let mut token = String::new();
+
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == '\x1b' {
+            // skip terminal escape sequences for colors
+            for (_, c) in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            '+' | '-' => {
+                let binary = prev.is_some_and(|p: char| {
+                    p.is_alphanumeric() || "_)]}'.!".contains(p) || !p.is_ascii()
+                });
+                if binary && !is_mantissa(&token) {
+                    points.push(BreakPoint {
+                        byte: prev_end.0,
+                        column: prev_end.1,
+                        depth,
+                        rank: 0,
+                    });
+                }
+            }
+            ',' => points.push(BreakPoint {
+                byte: i + 1,
+                column: column + 1,
+                depth,
+                rank: 1,
+            }),
+            '*' if prev != Some('*') && chars.peek().map(|x| x.1) != Some('*') => {
+                points.push(BreakPoint {
+                    byte: i + 1,
+                    column: column + 1,
+                    depth,
+                    rank: 2,
+                })
+            }
+            _ => {}
+        }
+
+        if c.is_alphanumeric() || c == '_' || c == '.' {
+            token.push(c);
+        } else {
+            token.clear();
+        }
+
+        column += 1;
+        if c != ' ' {
+            prev = Some(c);
+            prev_end = (i + c.len_utf8(), column);
+        }
+    }
+
+    (points, column)
+}
+
+fn break_line(line: &str, width: usize, continuation: &str, form: bool, out: &mut String) {
+    let (points, line_width) = break_points(line);
+
+    let mut start = 0;
+    let mut start_column = 0;
+    let mut indent = 0;
+    let mut next = 0;
+
+    loop {
+        let available = width.saturating_sub(indent + continuation.len()).max(1);
+
+        while next < points.len() && points[next].byte <= start {
+            next += 1;
+        }
+
+        if line_width - start_column <= available || next == points.len() {
+            write_line(out, indent, &line[start..], width, form);
+            return;
+        }
+
+        // prefer the positions that fill at least half of the line
+        let fits = points[next..]
+            .iter()
+            .take_while(|p| p.column - start_column <= available);
+        let best = fits
+            .clone()
+            .filter(|p| 2 * (p.column - start_column) >= available)
+            .min_by_key(|p| (p.depth, p.rank, std::cmp::Reverse(p.byte)))
+            .or_else(|| fits.min_by_key(|p| (p.depth, p.rank, std::cmp::Reverse(p.byte))))
+            .unwrap_or(&points[next]);
+
+        write_line(out, indent, &line[start..best.byte], width, form);
+        out.push_str(continuation);
+        out.push('\n');
+
+        start = best.byte;
+        start_column = best.column;
+        indent = INDENT * best.depth;
+        while line[start..].starts_with(' ') {
+            start += 1;
+            start_column += 1;
+        }
+    }
+}
+
+/// Write an indented line. In FORM, a number that does not fit on the line
+/// is continued on the next line after a backslash.
+fn write_line(out: &mut String, indent: usize, line: &str, width: usize, form: bool) {
+    out.extend(std::iter::repeat_n(' ', indent));
+
+    if !form || indent + line.chars().count() <= width {
+        out.push_str(line);
+        return;
+    }
+
+    let mut column = indent;
+    let mut chars = line.chars().peekable();
+    let mut prev = ' ';
+    while let Some(c) = chars.next() {
+        if column + 1 >= width
+            && prev.is_ascii_digit()
+            && c.is_ascii_digit()
+            && chars.peek().is_some_and(|n| n.is_ascii_digit())
+        {
+            out.push_str("\\\n");
+            column = 0;
+        }
+
+        out.push(c);
+        column += 1;
+        prev = c;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        atom::{Atom, AtomCore},
+        parse,
+        parser::ParseMode,
+        printer::PrintOptions,
+        wrap_input,
+    };
+
+    fn lines(input: &str, opts: PrintOptions) -> String {
+        parse!(input).unwrap().printer(opts).to_string()
+    }
+
+    #[test]
+    fn operator_boundaries() {
+        let opts = PrintOptions {
+            max_line_length: Some(20),
+            ..PrintOptions::file_no_namespace()
+        };
+
+        assert_eq!(
+            lines("x1+x2+x3+x4+x5+x6+x7+x8+x9", opts),
+            "x1+x2+x3+x4+x5+x6+x7\n+x8+x9"
+        );
+        assert_eq!(
+            lines("f(x1+x2+x3+x4+x5+x6,y1*y2*y3*y4*y5*y6*y7*y8)*x^-5", opts),
+            "x^-5*f(x1+x2+x3+x4\n  +x5+x6,y1*y2*y3*\n  y4*y5*y6*y7*y8)"
+        );
+    }
+
+    #[test]
+    fn continuation() {
+        let opts = PrintOptions {
+            max_line_length: Some(30),
+            ..PrintOptions::fortran()
+        };
+        assert_eq!(
+            lines("x1^2+x2^2+x3^2+x4^2+x5^2+x6^2+x7^2", opts),
+            "x1**2 + x2**2 + x3**2 &\n+ x4**2 + x5**2 + x6**2 &\n+ x7**2"
+        );
+
+        let opts = PrintOptions {
+            max_line_length: Some(30),
+            ..PrintOptions::numpy()
+        };
+        assert_eq!(
+            lines("x1^2+x2^2+x3^2+x4^2+x5^2+x6^2+x7^2", opts),
+            "x1**2 + x2**2 + x3**2 \\\n+ x4**2 + x5**2 + x6**2 \\\n+ x7**2"
+        );
+    }
+
+    #[test]
+    fn form() {
+        let opts = PrintOptions {
+            max_line_length: Some(30),
+            ..PrintOptions::form()
+        };
+
+        let input = "123456789012345678901234567890123456789*sqrt(x) + 𝑖*𝜋*x! + exp(y)";
+        let s = lines(input, opts);
+        assert_eq!(
+            s,
+            "eexp_(y)\n+1234567890123456789012345678\\\n90123456789*\nsqrt_(x)+i_*pi_*fac_(x)"
+        );
+        assert_eq!(
+            Atom::parse_with_mode(wrap_input!(&s), ParseMode::Form),
+            parse!(input)
+        );
+    }
+}
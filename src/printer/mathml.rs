@@ -0,0 +1,534 @@
+//! Output of expressions in Presentation MathML for [PrintMode::MathML] and in
+//! Content MathML for [PrintMode::ContentMathML].
+
+use std::fmt::{Error, Write};
+
+use crate::{
+    atom::{Atom, AtomView, MulView, Symbol, representation::FunView},
+    coefficient::CoefficientView,
+    state::State,
+};
+
+use super::{PrintMode, PrintOptions, PrintState, pretty::is_negative_number};
+
+// The precedence of the outermost operator of presentation markup.
+const RELATION: u8 = 0;
+const SUM: u8 = 1;
+const NEGATION: u8 = 2;
+const PRODUCT: u8 = 3;
+const POWER: u8 = 4;
+const ATOM: u8 = 5;
+
+/// The invisible times operator.
+const TIMES: &str = "<mo>&#x2062;</mo>";
+/// The invisible function application operator.
+const APPLY: &str = "<mo>&#x2061;</mo>";
+
+/// Write the atom `x` as MathML. If `top_level` is set, the
+/// markup is wrapped in a `math` element.
+pub(crate) fn write<W: Write>(
+    f: &mut W,
+    x: AtomView,
+    opts: &PrintOptions,
+    top_level: bool,
+) -> Result<bool, Error> {
+    let s = if opts.mode.is_content_mathml() {
+        content(x, opts)
+    } else {
+        presentation(x, opts).render()
+    };
+
+    if top_level {
+        write!(
+            f,
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\">{}</math>",
+            s
+        )?;
+    } else {
+        f.write_str(&s)?;
+    }
+
+    Ok(false)
+}
+
+/// Escape the characters of `s` that have a special meaning in XML.
+fn escape(s: &str) -> String {
+    let mut r = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => r.push_str("&amp;"),
+            '<' => r.push_str("&lt;"),
+            '>' => r.push_str("&gt;"),
+            '"' => r.push_str("&quot;"),
+            _ => r.push(c),
+        }
+    }
+    r
+}
+
+/// Wrap a list of elements in an `mrow`, unless there is only one.
+fn row(elements: &[String]) -> String {
+    if elements.len() == 1 {
+        elements[0].clone()
+    } else {
+        format!("<mrow>{}</mrow>", elements.concat())
+    }
+}
+
+fn fenced(s: &str, open: &str, close: &str) -> String {
+    format!("<mrow><mo>{}</mo>{}<mo>{}</mo></mrow>", open, s, close)
+}
+
+/// Presentation markup of the absolute value of an expression, with the precedence
+/// of its outermost operator.
+struct Markup {
+    s: String,
+    precedence: u8,
+    negative: bool,
+}
+
+impl Markup {
+    fn new(s: impl Into<String>, precedence: u8) -> Markup {
+        Markup {
+            s: s.into(),
+            precedence,
+            negative: false,
+        }
+    }
+
+    fn render(&self) -> String {
+        if self.negative {
+            format!("<mrow><mo>-</mo>{}</mrow>", self.s)
+        } else {
+            self.s.clone()
+        }
+    }
+
+    /// Get the markup as the operand of an operator with precedence `precedence`,
+    /// adding parentheses if needed.
+    fn operand(&self, precedence: u8) -> String {
+        let p = if self.negative {
+            NEGATION
+        } else {
+            self.precedence
+        };
+
+        if p < precedence {
+            fenced(&self.render(), "(", ")")
+        } else {
+            self.render()
+        }
+    }
+}
+
+/// Get the numerator and denominator of the absolute value of a rational number `x`
+/// and whether it is negative.
+fn rational_parts(x: AtomView) -> Option<(bool, String, String)> {
+    let AtomView::Num(n) = x else { return None };
+
+    match n.get_coeff_view() {
+        CoefficientView::Natural(n, d) => {
+            Some((n < 0, n.unsigned_abs().to_string(), d.to_string()))
+        }
+        CoefficientView::Large(r) => {
+            let rat = r.to_rat().abs();
+            Some((
+                r.is_negative(),
+                rat.numerator_ref().to_string(),
+                rat.denominator_ref().to_string(),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Print a number that is not rational in the Symbolica format.
+fn number_text(x: AtomView, opts: &PrintOptions) -> String {
+    let opts = PrintOptions {
+        mode: PrintMode::Symbolica,
+        ..*opts
+    };
+    let mut s = String::new();
+    x.format(&mut s, &opts, PrintState::new())
+        .expect("Could not write to string");
+    s
+}
+
+fn presentation(x: AtomView, opts: &PrintOptions) -> Markup {
+    match x {
+        AtomView::Num(_) => {
+            if let Some((negative, num, den)) = rational_parts(x) {
+                let mut m = if den == "1" {
+                    Markup::new(format!("<mn>{}</mn>", num), ATOM)
+                } else {
+                    Markup::new(
+                        format!("<mfrac><mn>{}</mn><mn>{}</mn></mfrac>", num, den),
+                        PRODUCT,
+                    )
+                };
+                m.negative = negative;
+                return m;
+            }
+
+            let s = number_text(x, opts);
+            let (negative, s) = match s.strip_prefix('-') {
+                Some(s) => (true, s),
+                None => (false, s.as_str()),
+            };
+
+            let mut m = match s.split_once('e') {
+                Some((m, e)) => Markup::new(
+                    format!(
+                        "<mrow><mn>{}</mn><mo>×</mo><msup><mn>10</mn><mn>{}</mn></msup></mrow>",
+                        m, e
+                    ),
+                    PRODUCT,
+                ),
+                None => Markup::new(format!("<mn>{}</mn>", escape(s)), ATOM),
+            };
+            m.negative = negative;
+            m
+        }
+        AtomView::Var(v) => Markup::new(identifier(v.get_symbol()), ATOM),
+        AtomView::Fun(f) => presentation_function(f, opts),
+        AtomView::Pow(p) => {
+            let (b, e) = p.get_base_exp();
+            presentation_power(b, e, opts)
+        }
+        AtomView::Mul(m) => presentation_mul(m, opts),
+        AtomView::Add(a) => {
+            let mut elements = vec![];
+            for (i, t) in a.iter().enumerate() {
+                let t = presentation(t, opts);
+                if i == 0 {
+                    elements.push(t.operand(SUM));
+                } else if t.negative {
+                    elements.push("<mo>-</mo>".into());
+                    elements.push(t.s);
+                } else {
+                    elements.push("<mo>+</mo>".into());
+                    elements.push(t.operand(SUM));
+                }
+            }
+            Markup::new(row(&elements), SUM)
+        }
+    }
+}
+
+fn identifier(id: Symbol) -> String {
+    match id {
+        Atom::PI => "<mi>π</mi>".into(),
+        Atom::E => "<mi>e</mi>".into(),
+        Atom::I => "<mi>i</mi>".into(),
+        _ => format!("<mi>{}</mi>", escape(id.get_stripped_name())),
+    }
+}
+
+fn presentation_power(b: AtomView, e: AtomView, opts: &PrintOptions) -> Markup {
+    if let AtomView::Num(n) = e {
+        match n.get_coeff_view() {
+            CoefficientView::Natural(1, 1) => return presentation(b, opts),
+            CoefficientView::Natural(1, 2) => {
+                let b = presentation(b, opts).render();
+                return Markup::new(format!("<msqrt>{}</msqrt>", b), ATOM);
+            }
+            CoefficientView::Natural(1, d) => {
+                let b = presentation(b, opts).render();
+                return Markup::new(format!("<mroot>{}<mn>{}</mn></mroot>", b, d), ATOM);
+            }
+            _ if is_negative_number(e) => {
+                let e = -e;
+                let den = presentation_power(b, e.as_view(), opts).render();
+                return Markup::new(format!("<mfrac><mn>1</mn>{}</mfrac>", den), PRODUCT);
+            }
+            _ => {}
+        }
+    }
+
+    let base = presentation(b, opts).operand(ATOM);
+    let exp = presentation(e, opts).render();
+    Markup::new(format!("<msup>{}{}</msup>", base, exp), POWER)
+}
+
+fn presentation_mul(m: MulView, opts: &PrintOptions) -> Markup {
+    let mut negative = false;
+    let mut num = vec![];
+    let mut den = vec![];
+
+    for x in m.iter() {
+        if let Some((neg, n, d)) = rational_parts(x) {
+            negative = neg;
+            if n != "1" {
+                num.insert(0, Markup::new(format!("<mn>{}</mn>", n), ATOM));
+            }
+            if d != "1" {
+                den.insert(0, Markup::new(format!("<mn>{}</mn>", d), ATOM));
+            }
+            continue;
+        }
+
+        match x {
+            AtomView::Pow(p) if is_negative_number(p.get_exp()) => {
+                let (b, e) = p.get_base_exp();
+                let e = -e;
+                den.push(presentation_power(b, e.as_view(), opts));
+            }
+            AtomView::Num(_) => {
+                let n = presentation(x, opts);
+                negative = n.negative;
+                num.insert(0, Markup::new(n.s, n.precedence));
+            }
+            _ => num.push(presentation(x, opts)),
+        }
+    }
+
+    /// Join the factors with invisible times. A single factor does not need parentheses.
+    fn product(factors: &[Markup]) -> String {
+        if factors.len() == 1 {
+            return factors[0].render();
+        }
+
+        let mut elements = vec![];
+        for (i, f) in factors.iter().enumerate() {
+            if i > 0 {
+                elements.push(TIMES.to_string());
+            }
+            elements.push(f.operand(PRODUCT));
+        }
+        row(&elements)
+    }
+
+    let num = if num.is_empty() {
+        "<mn>1</mn>".to_string()
+    } else {
+        product(&num)
+    };
+
+    let s = if den.is_empty() {
+        num
+    } else {
+        format!("<mfrac>{}{}</mfrac>", num, product(&den))
+    };
+
+    let mut m = Markup::new(s, PRODUCT);
+    m.negative = negative;
+    m
+}
+
+fn presentation_function(f: FunView, opts: &PrintOptions) -> Markup {
+    let id = f.get_symbol();
+    if let Some(custom_print) = &State::get_symbol_data(id).custom_print {
+        if let Some(s) = custom_print(f.as_view(), opts) {
+            return Markup::new(s, ATOM);
+        }
+    }
+
+    let args: Vec<Markup> = f.iter().map(|a| presentation(a, opts)).collect();
+
+    match id {
+        Atom::EQUAL
+        | Atom::UNEQUAL
+        | Atom::LESS
+        | Atom::LESS_EQUAL
+        | Atom::GREATER
+        | Atom::GREATER_EQUAL
+            if args.len() >= 2 =>
+        {
+            let op = match id {
+                Atom::EQUAL => "=",
+                Atom::UNEQUAL => "≠",
+                Atom::LESS => "&lt;",
+                Atom::LESS_EQUAL => "≤",
+                Atom::GREATER => "&gt;",
+                _ => "≥",
+            };
+
+            let mut elements = vec![];
+            for (i, a) in args.iter().enumerate() {
+                if i > 0 {
+                    elements.push(format!("<mo>{}</mo>", op));
+                }
+                elements.push(a.operand(SUM));
+            }
+            return Markup::new(row(&elements), RELATION);
+        }
+        Atom::FACTORIAL if args.len() == 1 => {
+            let s = format!("<mrow>{}<mo>!</mo></mrow>", args[0].operand(ATOM));
+            return Markup::new(s, POWER);
+        }
+        Atom::SQRT if args.len() == 1 => {
+            return Markup::new(format!("<msqrt>{}</msqrt>", args[0].render()), ATOM);
+        }
+        Atom::ABS if args.len() == 1 => {
+            return Markup::new(fenced(&args[0].render(), "|", "|"), ATOM);
+        }
+        _ => {}
+    }
+
+    let mut elements = vec![];
+    for (i, a) in args.iter().enumerate() {
+        if i > 0 {
+            elements.push("<mo>,</mo>".to_string());
+        }
+        elements.push(a.render());
+    }
+
+    if id == Atom::LIST {
+        return Markup::new(fenced(&elements.concat(), "{", "}"), ATOM);
+    }
+
+    Markup::new(
+        format!(
+            "<mrow>{}{}{}</mrow>",
+            identifier(id),
+            APPLY,
+            fenced(&elements.concat(), "(", ")")
+        ),
+        ATOM,
+    )
+}
+
+fn content(x: AtomView, opts: &PrintOptions) -> String {
+    match x {
+        AtomView::Num(_) => {
+            if let Some((negative, num, den)) = rational_parts(x) {
+                let sign = if negative { "-" } else { "" };
+                return if den == "1" {
+                    format!("<cn type=\"integer\">{}{}</cn>", sign, num)
+                } else {
+                    format!("<cn type=\"rational\">{}{}<sep/>{}</cn>", sign, num, den)
+                };
+            }
+
+            let s = number_text(x, opts);
+            match s.split_once('e') {
+                Some((m, e)) => format!("<cn type=\"e-notation\">{}<sep/>{}</cn>", m, e),
+                None => format!("<cn type=\"real\">{}</cn>", escape(&s)),
+            }
+        }
+        AtomView::Var(v) => match v.get_symbol() {
+            Atom::PI => "<pi/>".into(),
+            Atom::E => "<exponentiale/>".into(),
+            Atom::I => "<imaginaryi/>".into(),
+            id => format!("<ci>{}</ci>", escape(id.get_stripped_name())),
+        },
+        AtomView::Fun(f) => {
+            let id = f.get_symbol();
+            let args: Vec<_> = f.iter().map(|a| content(a, opts)).collect();
+
+            if id == Atom::LIST {
+                return format!("<list>{}</list>", args.concat());
+            }
+
+            let op = match (id, args.len()) {
+                (Atom::EXP, 1) => "<exp/>".into(),
+                (Atom::LOG, 1) => "<ln/>".into(),
+                (Atom::SQRT, 1) => "<root/>".into(),
+                (Atom::ABS, 1) => "<abs/>".into(),
+                (Atom::FACTORIAL, 1) => "<factorial/>".into(),
+                (
+                    Atom::SIN
+                    | Atom::COS
+                    | Atom::TAN
+                    | Atom::ARCSIN
+                    | Atom::ARCCOS
+                    | Atom::ARCTAN
+                    | Atom::SINH
+                    | Atom::COSH
+                    | Atom::TANH
+                    | Atom::ARCSINH
+                    | Atom::ARCCOSH
+                    | Atom::ARCTANH,
+                    1,
+                ) => format!("<{}/>", id.get_stripped_name()),
+                (Atom::EQUAL, 2..) => "<eq/>".into(),
+                (Atom::UNEQUAL, 2) => "<neq/>".into(),
+                (Atom::LESS, 2..) => "<lt/>".into(),
+                (Atom::LESS_EQUAL, 2..) => "<leq/>".into(),
+                (Atom::GREATER, 2..) => "<gt/>".into(),
+                (Atom::GREATER_EQUAL, 2..) => "<geq/>".into(),
+                _ => format!("<ci>{}</ci>", escape(id.get_stripped_name())),
+            };
+
+            format!("<apply>{}{}</apply>", op, args.concat())
+        }
+        AtomView::Pow(p) => {
+            let (b, e) = p.get_base_exp();
+            if let AtomView::Var(v) = b {
+                if v.get_symbol() == Atom::E {
+                    return format!("<apply><exp/>{}</apply>", content(e, opts));
+                }
+            }
+
+            if let AtomView::Num(n) = e {
+                if let CoefficientView::Natural(1, 2) = n.get_coeff_view() {
+                    return format!("<apply><root/>{}</apply>", content(b, opts));
+                }
+            }
+
+            format!(
+                "<apply><power/>{}{}</apply>",
+                content(b, opts),
+                content(e, opts)
+            )
+        }
+        AtomView::Mul(m) => {
+            let args: Vec<_> = m.iter().map(|a| content(a, opts)).collect();
+            format!("<apply><times/>{}</apply>", args.concat())
+        }
+        AtomView::Add(a) => {
+            let args: Vec<_> = a.iter().map(|a| content(a, opts)).collect();
+            format!("<apply><plus/>{}</apply>", args.concat())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{atom::AtomCore, parse, printer::PrintOptions};
+
+    fn mathml(input: &str, opts: PrintOptions) -> String {
+        parse!(input).unwrap().printer(opts).to_string()
+    }
+
+    #[test]
+    fn presentation() {
+        assert_eq!(
+            mathml("x^2/(y+1) - 1/2*sqrt(z)", PrintOptions::mathml()),
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><mrow><mrow><mo>-</mo><mfrac>\
+            <msqrt><mi>z</mi></msqrt><mn>2</mn></mfrac></mrow><mo>+</mo><mfrac><msup><mi>x</mi>\
+            <mn>2</mn></msup><mrow><mi>y</mi><mo>+</mo><mn>1</mn></mrow></mfrac></mrow></math>"
+        );
+        assert_eq!(
+            mathml(
+                "sin(𝜋*x)^2 - 2*x*y^(1/3)/(z*(z+1)) + abs(x)!",
+                PrintOptions::mathml()
+            ),
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><mrow><msup><mrow><mi>sin</mi>\
+            <mo>&#x2061;</mo><mrow><mo>(</mo><mrow><mi>π</mi><mo>&#x2062;</mo><mi>x</mi></mrow>\
+            <mo>)</mo></mrow></mrow><mn>2</mn></msup><mo>+</mo><mrow><mrow><mo>|</mo><mi>x</mi>\
+            <mo>|</mo></mrow><mo>!</mo></mrow><mo>-</mo><mfrac><mrow><mn>2</mn><mo>&#x2062;</mo>\
+            <mi>x</mi><mo>&#x2062;</mo><mroot><mi>y</mi><mn>3</mn></mroot></mrow><mrow><mi>z</mi>\
+            <mo>&#x2062;</mo><mrow><mo>(</mo><mrow><mi>z</mi><mo>+</mo><mn>1</mn></mrow><mo>)</mo>\
+            </mrow></mrow></mfrac></mrow></math>"
+        );
+    }
+
+    #[test]
+    fn content() {
+        assert_eq!(
+            mathml("x^2/(y+1) - 1/2*sqrt(z)", PrintOptions::content_mathml()),
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><apply><plus/><apply><times/>\
+            <apply><root/><ci>z</ci></apply><cn type=\"rational\">-1<sep/>2</cn></apply><apply>\
+            <times/><apply><power/><ci>x</ci><cn type=\"integer\">2</cn></apply><apply><power/>\
+            <apply><plus/><ci>y</ci><cn type=\"integer\">1</cn></apply><cn type=\"integer\">-1</cn>\
+            </apply></apply></apply></math>"
+        );
+        assert_eq!(
+            mathml("exp(𝑖*𝜋) + 1.5 <= log(x)", PrintOptions::content_mathml()),
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><apply><leq/><apply><plus/><apply>\
+            <exp/><apply><times/><imaginaryi/><pi/></apply></apply><cn type=\"real\">\
+            1.50000000000000</cn></apply><apply><ln/><ci>x</ci></apply></apply></math>"
+        );
+    }
+}
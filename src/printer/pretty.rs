@@ -0,0 +1,976 @@
+//! Two-dimensional layout of expressions for [PrintMode::Pretty](super::PrintMode::Pretty).
+//!
+//! An expression is laid out as a [Block] of lines that has a baseline: the line
+//! on which the operators of a surrounding expression are placed. For example,
+//! the baseline of a fraction is its fraction bar.
+
+use std::fmt::{Error, Write};
+
+use crate::{
+    atom::{Atom, AtomView, MulView, Symbol, representation::FunView},
+    coefficient::CoefficientView,
+    domains::Ring,
+    parser::Operator,
+    state::State,
+};
+
+use super::{OperatorForm, PrintMode, PrintOptions, PrintState};
+
+/// A rectangular block of text with a baseline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Block {
+    lines: Vec<String>,
+    baseline: usize,
+    width: usize,
+}
+
+impl Block {
+    /// Create a block from a single line of text.
+    pub(crate) fn text(s: &str) -> Block {
+        Block {
+            lines: vec![s.to_string()],
+            baseline: 0,
+            width: s.chars().count(),
+        }
+    }
+
+    /// Create a block from text that may span multiple lines, such as the pretty
+    /// output of a ring element. The middle line is taken as the baseline.
+    pub(crate) fn from_text(s: &str) -> Block {
+        let lines: Vec<String> = s.lines().map(|l| l.to_string()).collect();
+        if lines.len() < 2 {
+            return Block::text(lines.first().map(|l| l.as_str()).unwrap_or(""));
+        }
+
+        let mut b = Block {
+            width: lines.iter().map(|l| l.chars().count()).max().unwrap_or(0),
+            baseline: lines.len() / 2,
+            lines,
+        };
+        b.pad();
+        b
+    }
+
+    pub(crate) fn height(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Pad all lines with spaces to the width of the block.
+    fn pad(&mut self) {
+        for l in &mut self.lines {
+            let w = l.chars().count();
+            if w < self.width {
+                l.extend(std::iter::repeat_n(' ', self.width - w));
+            }
+        }
+    }
+
+    /// Check if the block consists of the single line `s`.
+    fn is_text(&self, s: &str) -> bool {
+        self.lines.len() == 1 && self.lines[0] == s
+    }
+
+    /// Concatenate blocks horizontally, aligning their baselines.
+    pub(crate) fn hcat(blocks: &[Block]) -> Block {
+        let above = blocks.iter().map(|b| b.baseline).max().unwrap_or(0);
+        let below = blocks
+            .iter()
+            .map(|b| b.height() - b.baseline)
+            .max()
+            .unwrap_or(1);
+
+        let mut lines = vec![String::new(); above + below];
+        for b in blocks {
+            let offset = above - b.baseline;
+            for (i, l) in lines.iter_mut().enumerate() {
+                if i >= offset && i - offset < b.height() {
+                    l.push_str(&b.lines[i - offset]);
+                } else {
+                    l.extend(std::iter::repeat_n(' ', b.width));
+                }
+            }
+        }
+
+        Block {
+            lines,
+            baseline: above,
+            width: blocks.iter().map(|b| b.width).sum(),
+        }
+    }
+
+    /// Stack blocks vertically, either centered or left-aligned.
+    /// The baseline is the baseline of the block with index `base`.
+    fn vstack(blocks: &[Block], base: usize, center: bool) -> Block {
+        let width = blocks.iter().map(|b| b.width).max().unwrap_or(0);
+        let mut lines = vec![];
+        let mut baseline = 0;
+
+        for (i, b) in blocks.iter().enumerate() {
+            if i == base {
+                baseline = lines.len() + b.baseline;
+            }
+
+            let left = if center { (width - b.width) / 2 } else { 0 };
+            for l in &b.lines {
+                let mut line = " ".repeat(left);
+                line.push_str(l);
+                line.extend(std::iter::repeat_n(' ', width - b.width - left));
+                lines.push(line);
+            }
+        }
+
+        Block {
+            lines,
+            baseline,
+            width,
+        }
+    }
+
+    /// Remove a minus sign that starts the baseline of the block,
+    /// returning `true` if it was present. The sign of a sum is kept.
+    pub(crate) fn strip_sign(self) -> (bool, Block) {
+        let line = &self.lines[self.baseline];
+        let is_sign = line.starts_with('-')
+            && self.width > 1
+            && (self.height() > 1 || !line[1..].starts_with('-'))
+            && self
+                .lines
+                .iter()
+                .enumerate()
+                .all(|(i, l)| i == self.baseline || l.starts_with(' '));
+
+        if !is_sign {
+            return (false, self);
+        }
+
+        let mut stripped = self.clone();
+        for l in &mut stripped.lines {
+            l.remove(0);
+        }
+        stripped.width -= 1;
+
+        if stripped.is_compound() {
+            (false, self)
+        } else {
+            (true, stripped)
+        }
+    }
+
+    /// Returns `true` if the baseline of the block contains a sum outside of parentheses,
+    /// so that it needs parentheses when it is used as a factor. A run of minus signs
+    /// is a fraction bar.
+    fn is_compound(&self) -> bool {
+        let line: Vec<char> = self.lines[self.baseline].trim().chars().collect();
+        let mut depth = 0;
+        for (i, c) in line.iter().enumerate().skip(1) {
+            match c {
+                '(' | '[' | '{' | '⎜' | '⎢' | '⎨' => depth += 1,
+                ')' | ']' | '}' | '⎟' | '⎥' | '⎬' => depth -= 1,
+                '+' if depth == 0 && line[i - 1] != 'e' => return true,
+                '-' if depth == 0
+                    && !matches!(line[i - 1], '-' | 'e')
+                    && line.get(i + 1) != Some(&'-') =>
+                {
+                    return true;
+                }
+                _ => {}
+            }
+        }
+
+        false
+    }
+
+    /// Center the block horizontally in a block of width `width`.
+    fn center(self, width: usize) -> Block {
+        if self.width >= width {
+            return self;
+        }
+
+        let left = (width - self.width) / 2;
+        let right = width - self.width - left;
+        Block::hcat(&[
+            Block::text(&" ".repeat(left)),
+            self,
+            Block::text(&" ".repeat(right)),
+        ])
+    }
+}
+
+/// The delimiters that can enclose a block.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Delimiter {
+    Parenthesis,
+    Bracket,
+    Brace,
+    Bar,
+}
+
+impl Delimiter {
+    /// Get the characters for the top, middle, bottom and center of the opening and closing
+    /// delimiter. The center character is placed on the baseline.
+    fn chars(&self, ascii: bool) -> ([char; 4], [char; 4]) {
+        match (self, ascii) {
+            (Delimiter::Parenthesis, false) => (['⎛', '⎜', '⎝', '⎜'], ['⎞', '⎟', '⎠', '⎟']),
+            (Delimiter::Parenthesis, true) => (['/', '|', '\\', '|'], ['\\', '|', '/', '|']),
+            (Delimiter::Bracket, false) => (['⎡', '⎢', '⎣', '⎢'], ['⎤', '⎥', '⎦', '⎥']),
+            (Delimiter::Bracket, true) => (['['; 4], [']'; 4]),
+            (Delimiter::Brace, false) => (['⎧', '⎪', '⎩', '⎨'], ['⎫', '⎪', '⎭', '⎬']),
+            (Delimiter::Brace, true) => (['{'; 4], ['}'; 4]),
+            (Delimiter::Bar, false) => (['│'; 4], ['│'; 4]),
+            (Delimiter::Bar, true) => (['|'; 4], ['|'; 4]),
+        }
+    }
+
+    fn single_line(&self) -> (char, char) {
+        match self {
+            Delimiter::Parenthesis => ('(', ')'),
+            Delimiter::Bracket => ('[', ']'),
+            Delimiter::Brace => ('{', '}'),
+            Delimiter::Bar => ('|', '|'),
+        }
+    }
+}
+
+/// Enclose the block `b` in delimiters that stretch to its height.
+pub(crate) fn delimit(b: Block, delimiter: Delimiter, opts: &PrintOptions) -> Block {
+    let h = b.height();
+    let column = |c: [char; 4], single: char| {
+        let lines = (0..h)
+            .map(|i| {
+                if h == 1 {
+                    single
+                } else if i == 0 {
+                    c[0]
+                } else if i + 1 == h {
+                    c[2]
+                } else if i == b.baseline {
+                    c[3]
+                } else {
+                    c[1]
+                }
+                .to_string()
+            })
+            .collect();
+
+        Block {
+            lines,
+            baseline: b.baseline,
+            width: 1,
+        }
+    };
+
+    let (open, close) = delimiter.chars(opts.pretty_ascii);
+    let (single_open, single_close) = delimiter.single_line();
+    let open = column(open, single_open);
+    let close = column(close, single_close);
+    Block::hcat(&[open, b, close])
+}
+
+/// Put a minus sign in front of the block `b`.
+fn with_sign(b: Block) -> Block {
+    Block::hcat(&[Block::text("-"), b])
+}
+
+/// Join blocks with the separator `sep`.
+fn join(blocks: Vec<Block>, sep: &str) -> Block {
+    if blocks.is_empty() {
+        return Block::text("");
+    }
+
+    let mut parts = Vec::with_capacity(2 * blocks.len());
+    for (i, b) in blocks.into_iter().enumerate() {
+        if i > 0 {
+            parts.push(Block::text(sep));
+        }
+        parts.push(b);
+    }
+
+    Block::hcat(&parts)
+}
+
+/// Lay out a stacked fraction.
+pub(crate) fn fraction(num: Block, den: Block, opts: &PrintOptions) -> Block {
+    let bar = if opts.pretty_ascii { "-" } else { "─" };
+    let bar = Block::text(&bar.repeat(num.width.max(den.width)));
+    Block::vstack(&[num, bar, den], 1, true)
+}
+
+/// Lay out a fraction, moving the sign of the numerator in front of the fraction bar.
+pub(crate) fn signed_fraction(num: Block, den: Block, opts: &PrintOptions) -> Block {
+    let (negative, num) = num.strip_sign();
+    let f = fraction(num, den, opts);
+    if negative { with_sign(f) } else { f }
+}
+
+/// Lay out `base` raised to the power `exp`, with the exponent above the base.
+pub(crate) fn power(base: Block, exp: Block) -> Block {
+    let mut lines = Vec::with_capacity(base.height() + exp.height());
+    for l in &exp.lines {
+        lines.push(format!("{}{}", " ".repeat(base.width), l));
+    }
+    for l in &base.lines {
+        lines.push(format!("{}{}", l, " ".repeat(exp.width)));
+    }
+
+    Block {
+        lines,
+        baseline: exp.height() + base.baseline,
+        width: base.width + exp.width,
+    }
+}
+
+/// Lay out a radical sign over the block `b`, with an optional index for higher roots.
+pub(crate) fn root(b: Block, index: Option<&str>, opts: &PrintOptions) -> Block {
+    let (up, down) = if opts.pretty_ascii {
+        ('/', '\\')
+    } else {
+        ('╱', '╲')
+    };
+
+    let h = b.height();
+    let index: Vec<char> = index.unwrap_or("").chars().collect();
+    let pad = index.len().saturating_sub(h);
+
+    let mut top: Vec<char> = vec![' '; pad + h + 1];
+    top[pad + h - index.len().min(pad + h)..pad + h].copy_from_slice(&index);
+    let mut lines = vec![format!(
+        "{}{}",
+        top.into_iter().collect::<String>(),
+        "_".repeat(b.width)
+    )];
+
+    for (j, l) in b.lines.iter().enumerate() {
+        let mut prefix = vec![' '; pad + h + 1];
+        prefix[pad + h - j] = up;
+        if j + 1 == h {
+            prefix[pad] = down;
+        }
+        lines.push(format!("{}{}", prefix.into_iter().collect::<String>(), l));
+    }
+
+    Block {
+        lines,
+        baseline: b.baseline + 1,
+        width: b.width + pad + h + 1,
+    }
+}
+
+/// Lay out a large summation or product sign in front of `body`, with optional limits.
+pub(crate) fn big_operator(
+    product: bool,
+    body: Block,
+    lower: Option<Block>,
+    upper: Option<Block>,
+    opts: &PrintOptions,
+) -> Block {
+    let k = body.height().div_ceil(2).max(1);
+
+    let sign = if product {
+        let (top, side) = if opts.pretty_ascii {
+            ("_".repeat(k + 3), format!("|{}|", " ".repeat(k + 1)))
+        } else {
+            (
+                format!("┬{}┬", "─".repeat(k + 1)),
+                format!("│{}│", " ".repeat(k + 1)),
+            )
+        };
+
+        let mut lines = vec![top];
+        lines.extend(std::iter::repeat_n(side, 2 * k));
+        Block {
+            lines,
+            baseline: k,
+            width: k + 3,
+        }
+    } else {
+        let (down, up, bottom) = if opts.pretty_ascii {
+            ('\\', '/', '-')
+        } else {
+            ('╲', '╱', '‾')
+        };
+
+        let mut lines = vec!["_".repeat(k + 1)];
+        for j in 0..k {
+            lines.push(format!("{}{}", " ".repeat(j), down));
+        }
+        for j in 0..k {
+            lines.push(format!("{}{}", " ".repeat(k - 1 - j), up));
+        }
+        lines.push(bottom.to_string().repeat(k + 1));
+
+        let mut b = Block {
+            lines,
+            baseline: k,
+            width: k + 1,
+        };
+        b.pad();
+        b
+    };
+
+    let mut parts = vec![];
+    let mut base = 0;
+    if let Some(u) = upper {
+        parts.push(u);
+        base = 1;
+    }
+    parts.push(sign);
+    if let Some(l) = lower {
+        parts.push(l);
+    }
+
+    Block::hcat(&[Block::vstack(&parts, base, true), Block::text(" "), body])
+}
+
+/// Lay out a matrix, with aligned columns and brackets that stretch to its height.
+pub(crate) fn matrix(rows: Vec<Vec<Block>>, opts: &PrintOptions) -> Block {
+    let ncols = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let mut widths = vec![0; ncols];
+    for r in &rows {
+        for (w, c) in widths.iter_mut().zip(r) {
+            *w = c.width.max(*w);
+        }
+    }
+
+    let tall = rows.iter().flatten().any(|c| c.height() > 1);
+    let mut lines = vec![];
+    for (i, r) in rows.into_iter().enumerate() {
+        if i > 0 && tall {
+            lines.push(Block::text(""));
+        }
+
+        let cells = r
+            .into_iter()
+            .zip(&widths)
+            .map(|(c, w)| c.center(*w))
+            .collect();
+        lines.push(join(cells, "  "));
+    }
+
+    let mut b = Block::vstack(&lines, 0, false);
+    b.baseline = b.height() / 2;
+    delimit(b, Delimiter::Bracket, opts)
+}
+
+/// Lay out a product of factors. If there is more than one factor, the factors
+/// that are flagged are put in parentheses.
+fn product(factors: Vec<(Block, bool)>, opts: &PrintOptions) -> Block {
+    let n = factors.len();
+    let factors = factors
+        .into_iter()
+        .map(|(b, paren)| {
+            if paren && n > 1 {
+                delimit(b, Delimiter::Parenthesis, opts)
+            } else {
+                b
+            }
+        })
+        .collect();
+
+    join(factors, if opts.pretty_ascii { "*" } else { "⋅" })
+}
+
+/// Lay out a sum of terms, moving the leading minus sign of a term into the operator.
+/// If `max_width` is set, the terms are distributed over rows of at most that width.
+pub(crate) fn sum(terms: Vec<Block>, max_width: Option<usize>) -> Block {
+    if terms.is_empty() {
+        return Block::text("0");
+    }
+
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut row_width = 0;
+    for (i, t) in terms.into_iter().enumerate() {
+        let (negative, t) = t.strip_sign();
+        let mut op = match (i, negative) {
+            (0, false) => "",
+            (0, true) => "-",
+            (_, false) => " + ",
+            (_, true) => " - ",
+        };
+
+        if let Some(w) = max_width {
+            if i > 0 && row_width + op.len() + t.width > w {
+                rows.push(Block::hcat(&std::mem::take(&mut row)));
+                row_width = 0;
+                op = &op[1..];
+            }
+        }
+
+        row_width += op.len() + t.width;
+        if !op.is_empty() {
+            row.push(Block::text(op));
+        }
+        row.push(t);
+    }
+    rows.push(Block::hcat(&row));
+
+    if rows.len() == 1 {
+        return rows.pop().unwrap();
+    }
+
+    if rows.iter().any(|r| r.height() > 1) {
+        rows = rows
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, r)| {
+                if i > 0 {
+                    vec![Block::text(""), r]
+                } else {
+                    vec![r]
+                }
+            })
+            .collect();
+    }
+
+    Block::vstack(&rows, 0, false)
+}
+
+/// Lay out a term of a polynomial or series: the coefficient `coeff` times the `factors`.
+/// A unit coefficient is omitted and a compound coefficient is put in parentheses.
+pub(crate) fn term(coeff: Block, factors: Vec<Block>, opts: &PrintOptions) -> Block {
+    if factors.is_empty() {
+        return coeff;
+    }
+
+    let (negative, coeff) = coeff.strip_sign();
+    let mut parts = vec![];
+    if !coeff.is_text("1") {
+        let paren = coeff.is_compound();
+        parts.push((coeff, paren));
+    }
+    parts.extend(factors.into_iter().map(|f| (f, false)));
+
+    let b = product(parts, opts);
+    if negative { with_sign(b) } else { b }
+}
+
+/// Lay out the order term `𝒪(x)` of a series.
+pub(crate) fn order(x: Block, opts: &PrintOptions) -> Block {
+    let o = if opts.pretty_ascii { "O" } else { "𝒪" };
+    Block::hcat(&[Block::text(o), delimit(x, Delimiter::Parenthesis, opts)])
+}
+
+/// Lay out the element `e` of the ring `ring`.
+pub(crate) fn ring_element<R: Ring>(ring: &R, e: &R::Element, opts: &PrintOptions) -> Block {
+    let mut s = String::new();
+    ring.format(e, opts, PrintState::new(), &mut s)
+        .expect("Could not write to string");
+    Block::from_text(&s)
+}
+
+/// Write the block `b` to `f`, removing trailing whitespace.
+pub(crate) fn write<W: Write>(f: &mut W, b: &Block) -> Result<bool, Error> {
+    for (i, l) in b.lines.iter().enumerate() {
+        if i > 0 {
+            f.write_char('\n')?;
+        }
+        f.write_str(l.trim_end())?;
+    }
+
+    Ok(false)
+}
+
+/// Get the print options without colors, so that the width of the output is the number of characters.
+fn plain(opts: &PrintOptions) -> PrintOptions {
+    PrintOptions {
+        color_top_level_sum: false,
+        color_builtin_symbols: false,
+        color_namespace: false,
+        ..*opts
+    }
+}
+
+/// Returns `true` if `x` is a negative rational number.
+pub(crate) fn is_negative_number(x: AtomView) -> bool {
+    match x {
+        AtomView::Num(n) => match n.get_coeff_view() {
+            CoefficientView::Natural(n, _) => n < 0,
+            CoefficientView::Large(r) => r.is_negative(),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Lay out the atom `x`. If `top_level` is set, a sum is broken into rows that
+/// are at most [PrintOptions::max_line_length] wide.
+pub(crate) fn atom(x: AtomView, opts: &PrintOptions, top_level: bool) -> Block {
+    match x {
+        AtomView::Num(_) => number(x, opts),
+        AtomView::Var(v) => symbol(v.get_symbol(), opts),
+        AtomView::Fun(f) => function(f, opts),
+        AtomView::Pow(p) => {
+            let (b, e) = p.get_base_exp();
+            power_of(b, e, opts)
+        }
+        AtomView::Mul(m) => mul(m, opts),
+        AtomView::Add(a) => {
+            let terms = a
+                .iter()
+                .map(|t| operand(t, Operator::Add.get_precedence(), true, opts))
+                .collect();
+            sum(
+                terms,
+                if top_level {
+                    opts.max_line_length
+                } else {
+                    None
+                },
+            )
+        }
+    }
+}
+
+fn number(x: AtomView, opts: &PrintOptions) -> Block {
+    let AtomView::Num(n) = x else { unreachable!() };
+
+    let (negative, num, den) = match n.get_coeff_view() {
+        CoefficientView::Natural(n, d) => (n < 0, n.unsigned_abs().to_string(), d.to_string()),
+        CoefficientView::Large(r) => {
+            let rat = r.to_rat().abs();
+            (
+                r.is_negative(),
+                rat.numerator_ref().to_string(),
+                rat.denominator_ref().to_string(),
+            )
+        }
+        _ => {
+            // print other coefficients in the linear notation
+            let opts = PrintOptions {
+                mode: PrintMode::Symbolica,
+                ..plain(opts)
+            };
+            let mut s = String::new();
+            x.format(&mut s, &opts, PrintState::new())
+                .expect("Could not write to string");
+            return Block::text(&s);
+        }
+    };
+
+    let b = if den == "1" {
+        Block::text(&num)
+    } else {
+        fraction(Block::text(&num), Block::text(&den), opts)
+    };
+
+    if negative { with_sign(b) } else { b }
+}
+
+fn symbol(id: Symbol, opts: &PrintOptions) -> Block {
+    if opts.pretty_ascii {
+        match id {
+            Atom::PI => return Block::text("pi"),
+            Atom::E => return Block::text("e"),
+            Atom::I => return Block::text("i"),
+            _ => {}
+        }
+    }
+
+    let mut s = String::new();
+    id.format(&plain(opts), &mut s)
+        .expect("Could not write to string");
+    Block::from_text(&s)
+}
+
+/// Lay out the operand `x` of an operator with precedence `precedence`, adding parentheses
+/// if needed. If `associative` is set, an operand with the same precedence does not need them.
+fn operand(x: AtomView, precedence: u8, associative: bool, opts: &PrintOptions) -> Block {
+    let p = OperatorForm::get_print_precedence(x, opts);
+    let b = atom(x, opts, false);
+    if p > precedence || p == precedence && associative {
+        b
+    } else {
+        delimit(b, Delimiter::Parenthesis, opts)
+    }
+}
+
+fn power_of(b: AtomView, e: AtomView, opts: &PrintOptions) -> Block {
+    if let AtomView::Num(n) = e {
+        match n.get_coeff_view() {
+            CoefficientView::Natural(1, 1) => return atom(b, opts, false),
+            CoefficientView::Natural(1, 2) => return root(atom(b, opts, false), None, opts),
+            CoefficientView::Natural(1, d) if d <= 9 => {
+                return root(atom(b, opts, false), Some(&d.to_string()), opts);
+            }
+            _ if is_negative_number(e) => {
+                let e = -e;
+                return fraction(Block::text("1"), power_of(b, e.as_view(), opts), opts);
+            }
+            _ => {}
+        }
+    }
+
+    let base = operand(b, Operator::Pow.get_precedence(), false, opts);
+    power(base, atom(e, opts, false))
+}
+
+fn mul(m: MulView, opts: &PrintOptions) -> Block {
+    let mut negative = false;
+    let mut num = vec![];
+    let mut den = vec![];
+
+    for x in m.iter() {
+        match x {
+            AtomView::Num(n) => {
+                let (n, d) = match n.get_coeff_view() {
+                    CoefficientView::Natural(n, d) => {
+                        negative = n < 0;
+                        (n.unsigned_abs().to_string(), d.to_string())
+                    }
+                    CoefficientView::Large(r) => {
+                        negative = r.is_negative();
+                        let rat = r.to_rat().abs();
+                        (
+                            rat.numerator_ref().to_string(),
+                            rat.denominator_ref().to_string(),
+                        )
+                    }
+                    _ => {
+                        num.insert(0, (atom(x, opts, false), false));
+                        continue;
+                    }
+                };
+
+                if n != "1" {
+                    num.insert(0, (Block::text(&n), false));
+                }
+                if d != "1" {
+                    den.insert(0, (Block::text(&d), false));
+                }
+            }
+            AtomView::Pow(p) if is_negative_number(p.get_exp()) => {
+                let (b, e) = p.get_base_exp();
+                let e = -e;
+                let paren = e.is_one()
+                    && OperatorForm::get_print_precedence(b, opts) < Operator::Mul.get_precedence();
+                den.push((power_of(b, e.as_view(), opts), paren));
+            }
+            _ => {
+                let paren =
+                    OperatorForm::get_print_precedence(x, opts) < Operator::Mul.get_precedence();
+                num.push((atom(x, opts, false), paren));
+            }
+        }
+    }
+
+    let num = if num.is_empty() {
+        Block::text("1")
+    } else {
+        product(num, opts)
+    };
+
+    let b = if den.is_empty() {
+        num
+    } else {
+        fraction(num, product(den, opts), opts)
+    };
+
+    if negative { with_sign(b) } else { b }
+}
+
+fn function(f: FunView, opts: &PrintOptions) -> Block {
+    let id = f.get_symbol();
+    if let Some(custom_print) = &State::get_symbol_data(id).custom_print {
+        if let Some(s) = custom_print(f.as_view(), opts) {
+            return Block::from_text(&s);
+        }
+    }
+
+    if let Some(form) = OperatorForm::new(f, opts) {
+        return operator_form(form, f, opts);
+    }
+
+    let mut args: Vec<Block> = f.iter().map(|a| atom(a, opts, false)).collect();
+
+    match id {
+        Atom::SQRT if args.len() == 1 => return root(args.pop().unwrap(), None, opts),
+        Atom::ABS if args.len() == 1 => {
+            return delimit(args.pop().unwrap(), Delimiter::Bar, opts);
+        }
+        _ => {}
+    }
+
+    // sum(f, i, a, b) and prod(f, i, a, b) are written with a large operator
+    let name = id.get_stripped_name();
+    if opts.pretty_big_operators
+        && (name == "sum" || name == "prod")
+        && !State::is_builtin(id)
+        && matches!(args.len(), 2 | 4)
+    {
+        let body = f.iter().next().unwrap();
+        let body = operand(body, Operator::Mul.get_precedence(), true, opts);
+        let mut args = args.into_iter().skip(1);
+        let var = args.next().unwrap();
+        let (lower, upper) = match (args.next(), args.next()) {
+            (Some(a), Some(b)) => (Block::hcat(&[var, Block::text(" = "), a]), Some(b)),
+            _ => (var, None),
+        };
+
+        return big_operator(name == "prod", body, Some(lower), upper, opts);
+    }
+
+    Block::hcat(&[
+        symbol(id, opts),
+        delimit(join(args, ", "), Delimiter::Parenthesis, opts),
+    ])
+}
+
+fn operator_form(form: OperatorForm, f: FunView, opts: &PrintOptions) -> Block {
+    match form {
+        OperatorForm::Infix(op, precedence, associativity) => {
+            let op = if opts.pretty_ascii {
+                op
+            } else {
+                match op.as_str() {
+                    "!=" => "≠".into(),
+                    "<=" => "≤".into(),
+                    ">=" => "≥".into(),
+                    _ => op,
+                }
+            };
+
+            let args = f
+                .iter()
+                .enumerate()
+                .map(|(i, x)| {
+                    let associative = OperatorForm::is_associative_operand(associativity, f, i, x);
+                    operand(x, precedence, associative, opts)
+                })
+                .collect();
+            join(args, &format!(" {} ", op))
+        }
+        OperatorForm::Postfix(op, precedence) => {
+            let x = f.iter().next().unwrap();
+            Block::hcat(&[operand(x, precedence, false, opts), Block::text(&op)])
+        }
+        OperatorForm::Chain => unreachable!("Chained relations are only used in SymPy mode"),
+        OperatorForm::List => {
+            // a list of lists of the same length is a matrix
+            let ncols = match f.iter().next() {
+                Some(AtomView::Fun(r)) if r.get_symbol() == Atom::LIST => r.get_nargs(),
+                _ => 0,
+            };
+
+            if ncols > 0
+                && f.iter().all(|r| {
+                    matches!(r, AtomView::Fun(r) if r.get_symbol() == Atom::LIST && r.get_nargs() == ncols)
+                })
+            {
+                let rows = f
+                    .iter()
+                    .map(|r| {
+                        let AtomView::Fun(r) = r else { unreachable!() };
+                        r.iter().map(|c| atom(c, opts, false)).collect()
+                    })
+                    .collect();
+                return matrix(rows, opts);
+            }
+
+            let args = f.iter().map(|x| atom(x, opts, false)).collect();
+            delimit(join(args, ", "), Delimiter::Brace, opts)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        atom::AtomCore,
+        domains::{SelfRing, integer::Z, rational::Q},
+        parse,
+        printer::{PrintOptions, PrintState},
+        symbol,
+        tensors::matrix::Matrix,
+    };
+
+    const ASCII: PrintOptions = PrintOptions::pretty().with_pretty_ascii(true);
+
+    fn pretty(input: &str, opts: PrintOptions) -> String {
+        parse!(input).unwrap().printer(opts).to_string()
+    }
+
+    #[test]
+    fn fractions_and_powers() {
+        assert_eq!(
+            pretty("x^2/(y+1) - 1/2*sqrt(z)", PrintOptions::pretty()),
+            "   _     2\n ╲╱z    x\n-─── + ─────\n  2    y + 1"
+        );
+
+        assert_eq!(
+            pretty("(x+1)^(-3)*f(x,y^2)", ASCII),
+            " /    2\\\nf\\x, y /\n--------\n       3\n(x + 1)"
+        );
+
+        assert_eq!(
+            pretty("-x^(1/3)+abs(x/y)+(-2)^x", PrintOptions::pretty()),
+            " 3 _       x   │x│\n-╲╱x + (-2)  + │─│\n               │y│"
+        );
+
+        assert_eq!(pretty("(x!)! + y!!", ASCII), "(x!)! + y!!");
+    }
+
+    #[test]
+    fn big_operators() {
+        assert_eq!(
+            pretty(
+                "sum(1/n^2, n, 1, N) <= 𝜋^2",
+                ASCII.with_pretty_big_operators(true)
+            ),
+            "  N\n ___\n \\    1       2\n  \\   -- <= pi\n  /    2\n /    n\n ---\nn = 1"
+        );
+
+        assert_eq!(
+            pretty(
+                "prod(x+k, k, 1, n)",
+                PrintOptions::pretty().with_pretty_big_operators(true)
+            ),
+            "  n\n┬──┬\n│  │  (x + k)\n│  │\nk = 1"
+        );
+
+        assert_eq!(
+            pretty("prod(x+k, k, 1, n)", PrintOptions::pretty()),
+            "prod(x + k, k, 1, n)"
+        );
+    }
+
+    #[test]
+    fn matrices() {
+        assert_eq!(
+            pretty("{{1, x^2}, {x+y, 1/2}}", ASCII),
+            "[        2]\n[  1    x ]\n[         ]\n[       1 ]\n[x + y  - ]\n[       2 ]"
+        );
+
+        let m =
+            Matrix::from_linear(vec![1.into(), 2.into(), 30.into(), 4.into()], 2, 2, Z).unwrap();
+        assert_eq!(
+            m.format_string(&PrintOptions::pretty(), PrintState::new()),
+            "⎡1   2⎤\n⎣30  4⎦"
+        );
+    }
+
+    #[test]
+    fn polynomials() {
+        let r = parse!("(3*x^2+1)/(-x*y-2)")
+            .unwrap()
+            .to_rational_polynomial::<_, _, u8>(&Q, &Z, None);
+        assert_eq!(
+            r.format_string(&PrintOptions::pretty(), PrintState::new()),
+            "        2\n-1 - 3⋅x\n─────────\n 2 + x⋅y"
+        );
+
+        let s = parse!("exp(x)/(1-x)")
+            .unwrap()
+            .series(symbol!("x"), parse!("0").unwrap().as_view(), 3.into(), true)
+            .unwrap();
+        assert_eq!(
+            s.format_string(&PrintOptions::pretty(), PrintState::new()),
+            "          5  2   8  3    ⎛ 4⎞\n1 + 2⋅x + ─⋅x  + ─⋅x  + 𝒪⎝x ⎠\n          2      3"
+        );
+    }
+
+    #[test]
+    fn line_breaking() {
+        let opts = PrintOptions::pretty().with_max_line_length(Some(12));
+        assert_eq!(pretty("x1+x2+x3+x4-x5", opts), "x1 + x2 + x3\n+ x4 - x5");
+    }
+}
@@ -4,7 +4,7 @@ use byteorder::{ReadBytesExt, WriteBytesExt};
 use std::borrow::Cow;
 use std::hash::Hash;
 use std::io::{Read, Write};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock, RwLockWriteGuard};
 use std::thread::LocalKey;
 use std::{
@@ -16,11 +16,13 @@ use std::{
 use ahash::{HashMap, HashMapExt};
 use append_only_vec::AppendOnlyVec;
 use byteorder::LittleEndian;
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use smartstring::alias::String;
 
-use crate::atom::{FunctionAttribute, NamespacedSymbol, NormalizationFunction};
+use crate::atom::{Assumption, FunctionAttribute, NamespacedSymbol, NormalizationFunction};
 use crate::domains::finite_field::Zp64;
+use crate::domains::rational::Rational;
+use crate::parser::CustomOperator;
 use crate::poly::Variable;
 use crate::printer::PrintFunction;
 use crate::wrap_symbol;
@@ -32,7 +34,9 @@ use crate::{
 };
 
 pub(crate) const SYMBOLICA_MAGIC: u32 = 0x37871367;
-pub(crate) const EXPORT_FORMAT_VERSION: u16 = 1;
+/// The version of the export format. Version 2 adds the symbol attributes and assumptions
+/// that are not in version 1. Exports of version 1 can still be imported.
+pub(crate) const EXPORT_FORMAT_VERSION: u16 = 2;
 
 /// An id for a given finite field in a registry.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -54,6 +58,29 @@ pub trait HasStateMap {
     fn get_state_map(&self) -> &StateMap;
 }
 
+impl StateMap {
+    /// Get the symbol in the current state that corresponds to the
+    /// symbol with id `id` in the imported state.
+    pub(crate) fn get_symbol(&self, id: u32) -> Result<Symbol, std::io::Error> {
+        if let Some(s) = self.symbols.get(&id) {
+            return Ok(*s);
+        }
+
+        if ID_TO_STR.len() == 0 {
+            let _ = *STATE; // initialize the state
+        }
+
+        if (id as usize) < ID_TO_STR.len() {
+            Ok(unsafe { State::symbol_from_id(id) })
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unknown symbol id {}", id),
+            ))
+        }
+    }
+}
+
 impl HasStateMap for StateMap {
     fn get_state_map(&self) -> &StateMap {
         self
@@ -73,6 +100,15 @@ pub(crate) struct SymbolData {
     pub(crate) line: usize,
     pub(crate) custom_normalization: Option<NormalizationFunction>,
     pub(crate) custom_print: Option<PrintFunction>,
+    /// Attributes that are not stored in the symbol itself.
+    pub(crate) attributes: Vec<FunctionAttribute>,
+    /// The sorted positions of the arguments in which the function is linear,
+    /// if it is not linear in all of them.
+    pub(crate) linear_arguments: Vec<usize>,
+    /// Assumptions on the value of the symbol.
+    pub(crate) assumptions: Vec<Assumption>,
+    /// The custom operator of the global syntax that the function is written with.
+    pub(crate) operator: OnceCell<CustomOperator>,
 }
 
 static STATE: Lazy<RwLock<State>> = Lazy::new(|| RwLock::new(State::new()));
@@ -80,6 +116,7 @@ static ID_TO_STR: AppendOnlyVec<(Symbol, SymbolData)> = AppendOnlyVec::new();
 static FINITE_FIELDS: AppendOnlyVec<Zp64> = AppendOnlyVec::new();
 static VARIABLE_LISTS: AppendOnlyVec<Arc<Vec<Variable>>> = AppendOnlyVec::new();
 static SYMBOL_OFFSET: AtomicUsize = AtomicUsize::new(0);
+static HAS_GRASSMANN: AtomicBool = AtomicBool::new(false);
 
 thread_local!(
     /// A thread-local workspace, that stores recyclable atoms.
@@ -109,12 +146,86 @@ impl State {
     pub(crate) const E: Symbol = Symbol::raw_var(8, 0);
     pub(crate) const I: Symbol = Symbol::raw_var(9, 0);
     pub(crate) const PI: Symbol = Symbol::raw_var(10, 0);
+    pub(crate) const TAN: Symbol = Symbol::raw_fn(11, 0, false, false, false, false);
+    pub(crate) const ARCSIN: Symbol = Symbol::raw_fn(12, 0, false, false, false, false);
+    pub(crate) const ARCCOS: Symbol = Symbol::raw_fn(13, 0, false, false, false, false);
+    pub(crate) const ARCTAN: Symbol = Symbol::raw_fn(14, 0, false, false, false, false);
+    pub(crate) const SINH: Symbol = Symbol::raw_fn(15, 0, false, false, false, false);
+    pub(crate) const COSH: Symbol = Symbol::raw_fn(16, 0, false, false, false, false);
+    pub(crate) const TANH: Symbol = Symbol::raw_fn(17, 0, false, false, false, false);
+    pub(crate) const ARCSINH: Symbol = Symbol::raw_fn(18, 0, false, false, false, false);
+    pub(crate) const ARCCOSH: Symbol = Symbol::raw_fn(19, 0, false, false, false, false);
+    pub(crate) const ARCTANH: Symbol = Symbol::raw_fn(20, 0, false, false, false, false);
+    pub(crate) const ABS: Symbol = Symbol::raw_fn(21, 0, false, false, false, false);
+    pub(crate) const SIGN: Symbol = Symbol::raw_fn(22, 0, false, false, false, false);
+    pub(crate) const HEAVISIDE: Symbol = Symbol::raw_fn(23, 0, false, false, false, false);
+    pub(crate) const GAMMA: Symbol = Symbol::raw_fn(24, 0, false, false, false, false);
+    pub(crate) const POLYGAMMA: Symbol = Symbol::raw_fn(25, 0, false, false, false, false);
+    pub(crate) const BETA: Symbol = Symbol::raw_fn(26, 0, false, false, false, false);
+    pub(crate) const ERF: Symbol = Symbol::raw_fn(27, 0, false, false, false, false);
+    pub(crate) const ZETA: Symbol = Symbol::raw_fn(28, 0, false, false, false, false);
+    pub(crate) const POLYLOG: Symbol = Symbol::raw_fn(29, 0, false, false, false, false);
+    pub(crate) const BESSEL_J: Symbol = Symbol::raw_fn(30, 0, false, false, false, false);
+    pub(crate) const BESSEL_Y: Symbol = Symbol::raw_fn(31, 0, false, false, false, false);
+    pub(crate) const BESSEL_I: Symbol = Symbol::raw_fn(32, 0, false, false, false, false);
+    pub(crate) const BESSEL_K: Symbol = Symbol::raw_fn(33, 0, false, false, false, false);
+    pub(crate) const MPL: Symbol = Symbol::raw_fn(34, 0, false, false, false, false);
+    pub(crate) const HSUM: Symbol = Symbol::raw_fn(35, 0, false, false, false, false);
+    pub(crate) const LIST: Symbol = Symbol::raw_fn(36, 0, false, false, false, false);
+    pub(crate) const EQUAL: Symbol = Symbol::raw_fn(37, 0, false, false, false, false);
+    pub(crate) const UNEQUAL: Symbol = Symbol::raw_fn(38, 0, false, false, false, false);
+    pub(crate) const LESS: Symbol = Symbol::raw_fn(39, 0, false, false, false, false);
+    pub(crate) const LESS_EQUAL: Symbol = Symbol::raw_fn(40, 0, false, false, false, false);
+    pub(crate) const GREATER: Symbol = Symbol::raw_fn(41, 0, false, false, false, false);
+    pub(crate) const GREATER_EQUAL: Symbol = Symbol::raw_fn(42, 0, false, false, false, false);
+    pub(crate) const FACTORIAL: Symbol = Symbol::raw_fn(43, 0, false, false, false, false);
+    pub(crate) const DOUBLE_FACTORIAL: Symbol = Symbol::raw_fn(44, 0, false, false, false, false);
 
     /// The list of built-in symbols.
     pub const BUILTIN_SYMBOL_NAMES: [&'static str; 11] = [
         "arg", "coeff", "exp", "log", "sin", "cos", "sqrt", "der", "𝑒", "𝑖", "𝜋",
     ];
 
+    /// The list of built-in symbols that are not reserved. They live in the `symbolica`
+    /// namespace and have to be written with it, e.g. `symbolica::gamma(x)`, so that
+    /// users can still define their own `gamma`.
+    pub const NAMESPACED_BUILTIN_SYMBOL_NAMES: [&'static str; 34] = [
+        "tan",
+        "arcsin",
+        "arccos",
+        "arctan",
+        "sinh",
+        "cosh",
+        "tanh",
+        "arcsinh",
+        "arccosh",
+        "arctanh",
+        "abs",
+        "sign",
+        "heaviside",
+        "gamma",
+        "polygamma",
+        "beta",
+        "erf",
+        "zeta",
+        "li",
+        "bessel_j",
+        "bessel_y",
+        "bessel_i",
+        "bessel_k",
+        "mpl",
+        "hsum",
+        "list",
+        "equal",
+        "unequal",
+        "less",
+        "less_equal",
+        "greater",
+        "greater_equal",
+        "factorial",
+        "factorial2",
+    ];
+
     pub fn is_builtin_name<S: AsRef<str>>(str: S) -> bool {
         Self::BUILTIN_SYMBOL_NAMES.contains(&str.as_ref())
     }
@@ -129,6 +240,9 @@ impl State {
         for x in Self::BUILTIN_SYMBOL_NAMES {
             state.get_symbol(wrap_symbol!(x));
         }
+        for x in Self::NAMESPACED_BUILTIN_SYMBOL_NAMES {
+            state.get_symbol(wrap_symbol!(format!("symbolica::{}", x)));
+        }
 
         #[cfg(test)]
         {
@@ -161,6 +275,8 @@ impl State {
             let _ = self.get_symbol_with_attributes(
                 wrap_symbol!(format!("fs{}", i)),
                 &[FunctionAttribute::Symmetric],
+                &[],
+                &[],
                 None,
                 None,
             );
@@ -169,6 +285,8 @@ impl State {
             let _ = self.get_symbol_with_attributes(
                 wrap_symbol!(format!("fc{}", i)),
                 &[FunctionAttribute::Cyclesymmetric],
+                &[],
+                &[],
                 None,
                 None,
             );
@@ -177,6 +295,8 @@ impl State {
             let _ = self.get_symbol_with_attributes(
                 wrap_symbol!(format!("fa{}", i)),
                 &[FunctionAttribute::Antisymmetric],
+                &[],
+                &[],
                 None,
                 None,
             );
@@ -185,6 +305,8 @@ impl State {
             let _ = self.get_symbol_with_attributes(
                 wrap_symbol!(format!("fl{}", i)),
                 &[FunctionAttribute::Linear],
+                &[],
+                &[],
                 None,
                 None,
             );
@@ -193,6 +315,8 @@ impl State {
             let _ = self.get_symbol_with_attributes(
                 wrap_symbol!(format!("fsl{}", i)),
                 &[FunctionAttribute::Symmetric, FunctionAttribute::Linear],
+                &[],
+                &[],
                 None,
                 None,
             );
@@ -219,6 +343,9 @@ impl State {
         for x in Self::BUILTIN_SYMBOL_NAMES {
             state.get_symbol(wrap_symbol!(x));
         }
+        for x in Self::NAMESPACED_BUILTIN_SYMBOL_NAMES {
+            state.get_symbol(wrap_symbol!(format!("symbolica::{}", x)));
+        }
 
         #[cfg(test)]
         {
@@ -250,6 +377,13 @@ impl State {
 
     /// Returns `true` iff this identifier is defined by Symbolica.
     pub(crate) fn is_builtin(id: Symbol) -> bool {
+        id.get_id()
+            < (Self::BUILTIN_SYMBOL_NAMES.len() + Self::NAMESPACED_BUILTIN_SYMBOL_NAMES.len())
+                as u32
+    }
+
+    /// Returns `true` iff this identifier is a built-in symbol that is written without namespace.
+    pub(crate) fn is_reserved(id: Symbol) -> bool {
         id.get_id() < Self::BUILTIN_SYMBOL_NAMES.len() as u32
     }
 
@@ -285,6 +419,10 @@ impl State {
                         line: name.line,
                         custom_normalization: None,
                         custom_print: None,
+                        attributes: vec![],
+                        linear_arguments: vec![],
+                        assumptions: vec![],
+                        operator: OnceCell::new(),
                     },
                 )) - offset;
                 assert_eq!(id, id_ret);
@@ -309,9 +447,18 @@ impl State {
         &mut self,
         name: NamespacedSymbol,
         attributes: &[FunctionAttribute],
+        linear_arguments: &[usize],
+        assumptions: &[Assumption],
         normalization_function: Option<NormalizationFunction>,
         print_function: Option<PrintFunction>,
     ) -> Result<Symbol, String> {
+        let mut extended_attributes: Vec<_> = attributes
+            .iter()
+            .filter(|a| !a.is_packed())
+            .cloned()
+            .collect();
+        extended_attributes.dedup();
+
         match self.str_to_id.entry(name.symbol.into()) {
             Entry::Occupied(o) => {
                 let r = *o.get();
@@ -323,12 +470,26 @@ impl State {
                     attributes.contains(&FunctionAttribute::Antisymmetric),
                     attributes.contains(&FunctionAttribute::Cyclesymmetric),
                     attributes.contains(&FunctionAttribute::Linear),
-                );
-
-                if r == new_id && normalization_function.is_none() {
+                )
+                .with_noncommutative(attributes.contains(&FunctionAttribute::Noncommutative))
+                .with_grassmann(attributes.contains(&FunctionAttribute::Grassmann));
+
+                let data = &ID_TO_STR[r.get_id() as usize].1;
+                let same_attributes = data.attributes.len() == extended_attributes.len()
+                    && extended_attributes
+                        .iter()
+                        .all(|a| data.attributes.contains(a))
+                    && data.linear_arguments == linear_arguments;
+                let same_assumptions = data.assumptions.len() == assumptions.len()
+                    && assumptions.iter().all(|a| data.assumptions.contains(a));
+
+                if r == new_id
+                    && same_attributes
+                    && same_assumptions
+                    && normalization_function.is_none()
+                {
                     Ok(r)
                 } else {
-                    let data = &ID_TO_STR[r.get_id() as usize].1;
                     if data.file.is_empty() {
                         Err(format!("Symbol {} redefined with new attributes.", data.name).into())
                     } else {
@@ -361,7 +522,9 @@ impl State {
                     attributes.contains(&FunctionAttribute::Antisymmetric),
                     attributes.contains(&FunctionAttribute::Cyclesymmetric),
                     attributes.contains(&FunctionAttribute::Linear),
-                );
+                )
+                .with_noncommutative(attributes.contains(&FunctionAttribute::Noncommutative))
+                .with_grassmann(attributes.contains(&FunctionAttribute::Grassmann));
 
                 let id_ret = ID_TO_STR.push((
                     new_symbol,
@@ -372,10 +535,18 @@ impl State {
                         line: name.line,
                         custom_normalization: normalization_function,
                         custom_print: print_function,
+                        attributes: extended_attributes,
+                        linear_arguments: linear_arguments.to_vec(),
+                        assumptions: assumptions.to_vec(),
+                        operator: OnceCell::new(),
                     },
                 )) - offset;
                 assert_eq!(id, id_ret);
 
+                if new_symbol.is_grassmann() {
+                    HAS_GRASSMANN.store(true, Ordering::Relaxed);
+                }
+
                 v.insert(new_symbol);
 
                 Ok(new_symbol)
@@ -418,6 +589,15 @@ impl State {
         &ID_TO_STR[id.get_id() as usize + SYMBOL_OFFSET.load(Ordering::Relaxed)].1
     }
 
+    /// Check if `id` is the id of a symbol in the state.
+    pub(crate) fn has_symbol_id(id: u32) -> bool {
+        if ID_TO_STR.len() == 0 {
+            let _ = *STATE; // initialize the state
+        }
+
+        id as usize + SYMBOL_OFFSET.load(Ordering::Relaxed) < ID_TO_STR.len()
+    }
+
     /// Get the user-specified normalization function for the symbol.
     #[inline]
     pub(crate) fn get_normalization_function(id: Symbol) -> Option<&'static NormalizationFunction> {
@@ -431,10 +611,63 @@ impl State {
             .as_ref()
     }
 
+    /// Get the attributes of the symbol that are not stored in the symbol itself.
+    #[inline]
+    pub(crate) fn get_function_attributes(id: Symbol) -> &'static [FunctionAttribute] {
+        if ID_TO_STR.len() == 0 {
+            let _ = *STATE; // initialize the state
+        }
+
+        &ID_TO_STR[id.get_id() as usize + SYMBOL_OFFSET.load(Ordering::Relaxed)]
+            .1
+            .attributes
+    }
+
+    /// Get the sorted positions of the arguments in which the function is linear,
+    /// if it is not linear in all of them.
+    #[inline]
+    pub(crate) fn get_linear_arguments(id: Symbol) -> &'static [usize] {
+        if ID_TO_STR.len() == 0 {
+            let _ = *STATE; // initialize the state
+        }
+
+        &ID_TO_STR[id.get_id() as usize + SYMBOL_OFFSET.load(Ordering::Relaxed)]
+            .1
+            .linear_arguments
+    }
+
+    /// Get the assumptions on the value of the symbol.
+    pub(crate) fn get_assumptions(id: Symbol) -> &'static [Assumption] {
+        if ID_TO_STR.len() == 0 {
+            let _ = *STATE; // initialize the state
+        }
+
+        &ID_TO_STR[id.get_id() as usize + SYMBOL_OFFSET.load(Ordering::Relaxed)]
+            .1
+            .assumptions
+    }
+
+    /// Get a number that increases whenever a symbol, finite field or variable list
+    /// is added to the state, so that an exported state can be checked for being outdated.
+    pub(crate) fn get_version() -> usize {
+        ID_TO_STR.len() + FINITE_FIELDS.len() + VARIABLE_LISTS.len()
+    }
+
+    /// Returns `true` if any Grassmann-odd symbol has been defined.
+    #[inline]
+    pub(crate) fn has_grassmann_symbols() -> bool {
+        HAS_GRASSMANN.load(Ordering::Relaxed)
+    }
+
     pub(crate) fn get_finite_field(fi: FiniteFieldIndex) -> &'static Zp64 {
         &FINITE_FIELDS[fi.0]
     }
 
+    /// Check if the finite field `fi` exists in the state.
+    pub(crate) fn has_finite_field(fi: FiniteFieldIndex) -> bool {
+        fi.0 < FINITE_FIELDS.len()
+    }
+
     pub(crate) fn get_or_insert_finite_field(f: Zp64) -> FiniteFieldIndex {
         STATE.write().unwrap().get_or_insert_finite_field_impl(f)
     }
@@ -454,6 +687,11 @@ impl State {
         VARIABLE_LISTS[fi.0].clone()
     }
 
+    /// Check if the variable list `fi` exists in the state.
+    pub(crate) fn has_variable_list(fi: VariableListIndex) -> bool {
+        fi.0 < VARIABLE_LISTS.len()
+    }
+
     pub(crate) fn get_or_insert_variable_list(f: Arc<Vec<Variable>>) -> VariableListIndex {
         STATE.write().unwrap().get_or_insert_variable_list_impl(f)
     }
@@ -499,6 +737,46 @@ impl State {
             dest.write_u8(s.is_antisymmetric() as u8)?;
             dest.write_u8(s.is_cyclesymmetric() as u8)?;
             dest.write_u8(s.is_linear() as u8)?;
+            dest.write_u8(s.is_noncommutative() as u8)?;
+            dest.write_u8(s.is_grassmann() as u8)?;
+
+            let attributes = State::get_function_attributes(s);
+            dest.write_u8(attributes.len() as u8)?;
+            for a in attributes {
+                match a {
+                    FunctionAttribute::Idempotent => dest.write_u8(1)?,
+                    FunctionAttribute::Involution => dest.write_u8(2)?,
+                    FunctionAttribute::Associative => dest.write_u8(3)?,
+                    FunctionAttribute::Homogeneous => dest.write_u8(4)?,
+                    _ => unreachable!("Packed attribute stored in the symbol data"),
+                }
+            }
+
+            let linear_arguments = State::get_linear_arguments(s);
+            dest.write_u32::<LittleEndian>(linear_arguments.len() as u32)?;
+            for x in linear_arguments {
+                dest.write_u32::<LittleEndian>(*x as u32)?;
+            }
+
+            let assumptions = State::get_assumptions(s);
+            dest.write_u8(assumptions.len() as u8)?;
+            for a in assumptions {
+                match a {
+                    Assumption::Real => dest.write_u8(0)?,
+                    Assumption::Complex => dest.write_u8(1)?,
+                    Assumption::Positive => dest.write_u8(2)?,
+                    Assumption::Nonnegative => dest.write_u8(3)?,
+                    Assumption::Nonzero => dest.write_u8(4)?,
+                    Assumption::Integer => dest.write_u8(5)?,
+                    Assumption::Even => dest.write_u8(6)?,
+                    Assumption::Odd => dest.write_u8(7)?,
+                    Assumption::Range(min, max) => {
+                        dest.write_u8(8)?;
+                        Atom::new_num(min.clone()).as_view().write(dest.by_ref())?;
+                        Atom::new_num(max.clone()).as_view().write(dest.by_ref())?;
+                    }
+                }
+            }
         }
 
         dest.write_u64::<LittleEndian>(FINITE_FIELDS.len() as u64)?;
@@ -510,25 +788,7 @@ impl State {
         for x in VARIABLE_LISTS.iter() {
             dest.write_u64::<LittleEndian>(x.len() as u64)?;
             for y in x.iter() {
-                match y {
-                    Variable::Symbol(s) => {
-                        dest.write_u8(0)?;
-                        dest.write_u32::<LittleEndian>(s.get_id())?;
-                    }
-                    Variable::Temporary(u) => {
-                        dest.write_u8(1)?;
-                        dest.write_u64::<LittleEndian>(*u as u64)?;
-                    }
-                    Variable::Function(v, t) => {
-                        dest.write_u8(2)?;
-                        dest.write_u32::<LittleEndian>(v.get_id())?;
-                        t.as_view().write(dest.by_ref())?;
-                    }
-                    Variable::Other(t) => {
-                        dest.write_u8(3)?;
-                        t.as_view().write(dest.by_ref())?;
-                    }
-                }
+                State::write_variable(dest, y)?;
             }
         }
 
@@ -539,6 +799,9 @@ impl State {
     /// Upon a conflict, i.e. when a symbol with the same name but different attributes is
     /// encountered, `conflict_fn` is called with the conflicting name as argument which
     /// should yield a new name for the symbol.
+    ///
+    /// States exported by older versions of Symbolica can be imported as well. Attributes
+    /// that were not stored in their format take their default value.
     #[inline(always)]
     pub fn import<R: Read>(
         source: &mut R,
@@ -554,7 +817,7 @@ impl State {
         }
 
         let version = source.read_u16::<LittleEndian>()?;
-        if version != EXPORT_FORMAT_VERSION {
+        if version == 0 || version > EXPORT_FORMAT_VERSION {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 "Invalid export format version",
@@ -570,23 +833,16 @@ impl State {
         let n_symbols = source.read_u64::<LittleEndian>()?;
         let mut attributes = vec![];
         for x in 0..n_symbols {
-            let l = source.read_u32::<LittleEndian>()?;
-            let mut v = vec![0; l as usize];
-            source.read_exact(&mut v)?;
-
-            let mut str: String = std::string::String::from_utf8(v).unwrap().into();
-
-            let l = source.read_u32::<LittleEndian>()?;
-            let mut v = vec![0; l as usize];
-            source.read_exact(&mut v)?;
-
-            let namespace: String = std::string::String::from_utf8(v).unwrap().into();
+            let mut str: String = State::read_str(source)?.into();
+            let namespace: String = State::read_str(source)?.into();
 
             let wildcard_level = source.read_u8()?;
             let is_symmetric = source.read_u8()? != 0;
             let is_antisymmetric = source.read_u8()? != 0;
             let is_cyclesymmetric = source.read_u8()? != 0;
             let is_linear = source.read_u8()? != 0;
+            let is_noncommutative = version >= 2 && source.read_u8()? != 0;
+            let is_grassmann = version >= 2 && source.read_u8()? != 0;
 
             attributes.clear();
             if is_antisymmetric {
@@ -601,6 +857,68 @@ impl State {
             if is_linear {
                 attributes.push(FunctionAttribute::Linear);
             }
+            if is_noncommutative {
+                attributes.push(FunctionAttribute::Noncommutative);
+            }
+            if is_grassmann {
+                attributes.push(FunctionAttribute::Grassmann);
+            }
+
+            let n_attributes = if version >= 2 { source.read_u8()? } else { 0 };
+            for _ in 0..n_attributes {
+                match source.read_u8()? {
+                    1 => attributes.push(FunctionAttribute::Idempotent),
+                    2 => attributes.push(FunctionAttribute::Involution),
+                    3 => attributes.push(FunctionAttribute::Associative),
+                    4 => attributes.push(FunctionAttribute::Homogeneous),
+                    _ => {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            "Unknown function attribute",
+                        ));
+                    }
+                }
+            }
+
+            let n_linear_arguments = if version >= 2 {
+                source.read_u32::<LittleEndian>()?
+            } else {
+                0
+            };
+            let linear_arguments = (0..n_linear_arguments)
+                .map(|_| Ok(source.read_u32::<LittleEndian>()? as usize))
+                .collect::<Result<Vec<_>, std::io::Error>>()?;
+
+            let n_assumptions = if version >= 2 { source.read_u8()? } else { 0 };
+            let mut assumptions = Vec::with_capacity(n_assumptions as usize);
+            for _ in 0..n_assumptions {
+                assumptions.push(match source.read_u8()? {
+                    0 => Assumption::Real,
+                    1 => Assumption::Complex,
+                    2 => Assumption::Positive,
+                    3 => Assumption::Nonnegative,
+                    4 => Assumption::Nonzero,
+                    5 => Assumption::Integer,
+                    6 => Assumption::Even,
+                    7 => Assumption::Odd,
+                    8 => {
+                        let mut read_rational = || {
+                            let mut a = Atom::new();
+                            a.read(&mut *source)?;
+                            Rational::try_from(a.as_view()).map_err(|e| {
+                                std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+                            })
+                        };
+                        Assumption::Range(read_rational()?, read_rational()?)
+                    }
+                    _ => {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            "Unknown assumption",
+                        ));
+                    }
+                });
+            }
 
             loop {
                 match Symbol::new(NamespacedSymbol {
@@ -610,6 +928,8 @@ impl State {
                     line: 0,
                 })
                 .with_attributes(attributes.clone())
+                .with_linear_arguments(&linear_arguments)
+                .with_assumptions(&assumptions)
                 .build()
                 {
                     Ok(id) => {
@@ -647,6 +967,12 @@ impl State {
         let n_finite_fields = source.read_u64::<LittleEndian>()?;
         for x in 0..n_finite_fields {
             let prime = source.read_u64::<LittleEndian>()?;
+            if prime % 2 == 0 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Invalid finite field prime {}", prime),
+                ));
+            }
             let id = State::get_or_insert_finite_field(Zp64::new(prime));
             if x != id.0 as u64 {
                 state_map
@@ -660,47 +986,7 @@ impl State {
             let n_vars = source.read_u64::<LittleEndian>()?;
             let mut variables = vec![];
             for _ in 0..n_vars {
-                match source.read_u8()? {
-                    0 => {
-                        let id = source.read_u32::<LittleEndian>()?;
-                        if let Some(new_id) = state_map.symbols.get(&id) {
-                            variables.push(Variable::Symbol(*new_id));
-                        } else {
-                            variables.push(Variable::Symbol(ID_TO_STR[id as usize].0))
-                        }
-                    }
-                    1 => {
-                        let u = source.read_u64::<LittleEndian>()?;
-                        variables.push(Variable::Temporary(u as usize))
-                    }
-                    2 => {
-                        let id = source.read_u32::<LittleEndian>()?;
-                        let symb = if let Some(new_id) = state_map.symbols.get(&id) {
-                            *new_id
-                        } else {
-                            ID_TO_STR[id as usize].0
-                        };
-
-                        let mut f = Atom::new();
-                        f.read(&mut *source)?;
-
-                        let f_r = f.as_view().rename(&state_map);
-                        variables.push(Variable::Function(symb, Arc::new(f_r)));
-                    }
-                    3 => {
-                        let mut f = Atom::new();
-                        f.read(&mut *source)?;
-
-                        let f_r = f.as_view().rename(&state_map);
-                        variables.push(Variable::Other(Arc::new(f_r)));
-                    }
-                    _ => {
-                        return Err(std::io::Error::new(
-                            std::io::ErrorKind::InvalidData,
-                            "Invalid variable type",
-                        ));
-                    }
-                }
+                variables.push(State::read_variable(source, &state_map)?);
             }
 
             // see if variables are seen before
@@ -713,6 +999,89 @@ impl State {
 
         Ok(state_map)
     }
+
+    /// Read a length-prefixed UTF-8 string from a binary stream. The buffer only
+    /// grows as data arrives, so a corrupt length cannot trigger a huge allocation.
+    pub(crate) fn read_str<R: Read>(source: &mut R) -> Result<std::string::String, std::io::Error> {
+        let l = source.read_u32::<LittleEndian>()?;
+        let mut v = vec![];
+        source.take(l as u64).read_to_end(&mut v)?;
+        if v.len() != l as usize {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "String is truncated",
+            ));
+        }
+        std::string::String::from_utf8(v)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Write a polynomial variable to a binary stream.
+    pub(crate) fn write_variable<W: Write>(
+        dest: &mut W,
+        variable: &Variable,
+    ) -> Result<(), std::io::Error> {
+        match variable {
+            Variable::Symbol(s) => {
+                dest.write_u8(0)?;
+                dest.write_u32::<LittleEndian>(s.get_id())?;
+            }
+            Variable::Temporary(u) => {
+                dest.write_u8(1)?;
+                dest.write_u64::<LittleEndian>(*u as u64)?;
+            }
+            Variable::Function(v, t) => {
+                dest.write_u8(2)?;
+                dest.write_u32::<LittleEndian>(v.get_id())?;
+                t.as_view().write(dest.by_ref())?;
+            }
+            Variable::Other(t) => {
+                dest.write_u8(3)?;
+                t.as_view().write(dest.by_ref())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read a polynomial variable written by [State::write_variable] from a binary stream,
+    /// renaming its symbols using the state map.
+    pub(crate) fn read_variable<R: Read>(
+        source: &mut R,
+        state_map: &StateMap,
+    ) -> Result<Variable, std::io::Error> {
+        match source.read_u8()? {
+            0 => {
+                let id = source.read_u32::<LittleEndian>()?;
+                Ok(Variable::Symbol(state_map.get_symbol(id)?))
+            }
+            1 => {
+                let u = source.read_u64::<LittleEndian>()?;
+                Ok(Variable::Temporary(u as usize))
+            }
+            2 => {
+                let id = source.read_u32::<LittleEndian>()?;
+                let symb = state_map.get_symbol(id)?;
+
+                let mut f = Atom::new();
+                f.read(&mut *source)?;
+
+                let f_r = f.as_view().rename(state_map);
+                Ok(Variable::Function(symb, Arc::new(f_r)))
+            }
+            3 => {
+                let mut f = Atom::new();
+                f.read(&mut *source)?;
+
+                let f_r = f.as_view().rename(state_map);
+                Ok(Variable::Other(Arc::new(f_r)))
+            }
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Invalid variable type",
+            )),
+        }
+    }
 }
 
 /// A workspace that stores recyclable atoms. Upon dropping, the atoms automatically returned to a
@@ -882,14 +1251,22 @@ mod tests {
     use std::io::Cursor;
 
     use crate::{
-        atom::{Atom, AtomView},
-        parse, symbol,
+        atom::{Atom, AtomView, FunctionAttribute, Symbol},
+        parse, symbol, wrap_symbol,
     };
 
-    use super::State;
+    use byteorder::{LittleEndian, WriteBytesExt};
+
+    use super::{EXPORT_FORMAT_VERSION, SYMBOLICA_MAGIC, State};
 
     #[test]
     fn state_export_import() {
+        Symbol::new(wrap_symbol!("state_export_import_f"))
+            .with_attributes(&[FunctionAttribute::Idempotent])
+            .with_linear_arguments(&[1])
+            .build()
+            .unwrap();
+
         let mut export = vec![];
         State::export(&mut export).unwrap();
 
@@ -897,6 +1274,33 @@ mod tests {
         assert!(i.is_empty());
     }
 
+    #[test]
+    fn import_version_1() {
+        // a state in the first export format, which has no extended attributes
+        let (namespace, name) = ("state_version_1", "state_version_1::f");
+        let mut export = vec![];
+        export.write_u32::<LittleEndian>(SYMBOLICA_MAGIC).unwrap();
+        export.write_u16::<LittleEndian>(1).unwrap();
+        export.write_u64::<LittleEndian>(1).unwrap();
+        export.write_u32::<LittleEndian>(name.len() as u32).unwrap();
+        export.extend(name.as_bytes());
+        export
+            .write_u32::<LittleEndian>(namespace.len() as u32)
+            .unwrap();
+        export.extend(namespace.as_bytes());
+        export.extend([0, 1, 0, 0, 0]);
+        export.write_u64::<LittleEndian>(0).unwrap();
+        export.write_u64::<LittleEndian>(0).unwrap();
+
+        let map = State::import(&mut Cursor::new(&export), None).unwrap();
+        let f = map.get_symbol(0).unwrap();
+        assert_eq!(f.get_name(), name);
+        assert!(f.is_symmetric() && !f.is_noncommutative() && !f.is_grassmann());
+
+        export[4] = EXPORT_FORMAT_VERSION as u8 + 1;
+        assert!(State::import(&mut Cursor::new(&export), None).is_err());
+    }
+
     #[test]
     fn custom_normalization() {
         let _real_log = symbol!(
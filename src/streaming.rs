@@ -1,6 +1,11 @@
 //! Methods for streaming large expressions to disk.
 
+pub mod bracket;
+pub mod distributed;
+
 use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
     fs::File,
     io::{BufReader, BufWriter, Read, Write},
     ops::{Add, AddAssign},
@@ -13,11 +18,17 @@ use rayon::{prelude::*, ThreadPool};
 use smartstring::{LazyCompact, SmartString};
 
 use crate::{
-    atom::{Atom, AtomView},
+    atom::{Atom, AtomCore, AtomView},
+    id::BorrowReplacement,
+    poly::Exponent,
     state::{RecycledAtom, State, Workspace},
+    transformer::{Transformer, TransformerError, TransformerState},
     LicenseManager,
 };
 
+/// An optional map of the key or the coefficient of a bracket.
+type BracketMap = Option<Box<dyn Fn(AtomView, &mut Atom)>>;
+
 static TEMP_FILES_COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
 
 /// A stream that can be read from by using `name`.
@@ -412,7 +423,9 @@ impl<W: WriteableNamedStream> TermStreamer<W> {
         self.mem_size = new_size;
     }
 
-    /// Fuse sorted streams into one sorted stream.
+    /// Fuse sorted streams into one sorted stream, using a k-way merge
+    /// of the memory buffer and all sorted files. Like terms are combined
+    /// during the merge.
     pub fn normalize(&mut self) {
         self.sort();
 
@@ -420,84 +433,52 @@ impl<W: WriteableNamedStream> TermStreamer<W> {
             return;
         }
 
-        self.mem_buf.reverse();
-
-        let mut head = vec![self.mem_buf.pop()];
-
-        let n_files = self.file_buf.len();
-
         for b in &mut self.file_buf {
             b.flush().unwrap();
         }
 
-        let mut files: Vec<_> = (0..n_files)
+        let mut files: Vec<_> = (0..self.file_buf.len())
             .map(|i| W::Reader::open(&self.get_filename(i)))
             .collect();
 
-        for ff in &mut files {
-            let mut a = Atom::new();
-            if let Ok(()) = a.read(ff) {
-                head.push(Some(a));
-            } else {
-                head.push(None);
+        let mut mem = std::mem::take(&mut self.mem_buf).into_iter();
+
+        let mut heap = BinaryHeap::with_capacity(files.len() + 1);
+        if let Some(term) = mem.next() {
+            heap.push(MergeEntry { term, source: 0 });
+        }
+        for (i, f) in files.iter_mut().enumerate() {
+            let mut term = Atom::new();
+            if let Ok(()) = term.read(f) {
+                heap.push(MergeEntry {
+                    term,
+                    source: i + 1,
+                });
             }
         }
 
         let mut new_stream = self.next_generation();
 
         let mut last = Atom::new();
-
-        let mut smallest = (0..head.len()).collect::<Vec<_>>();
         let mut helper = Atom::new();
-        loop {
-            // find minimal element
-            smallest.sort_unstable_by(|a, b| {
-                if let Some(aa) = &head[*a] {
-                    if let Some(bb) = &head[*b] {
-                        aa.as_view().cmp_terms(&bb.as_view())
-                    } else {
-                        std::cmp::Ordering::Less
-                    }
-                } else {
-                    std::cmp::Ordering::Greater
-                }
-            });
-
-            let Some(c) = head[smallest[0]].take() else {
-                // all None
-                break;
-            };
-
-            // load the next element
-            if smallest[0] == 0 {
-                head[0] = self.mem_buf.pop();
+        while let Some(MergeEntry { term, source }) = heap.pop() {
+            // load the next element of the source
+            let next = if source == 0 {
+                mem.next()
             } else {
                 let mut a = Atom::new();
-                if let Ok(()) = a.read(&mut files[smallest[0] - 1]) {
-                    head[smallest[0]] = Some(a);
-                }
+                a.read(&mut files[source - 1]).ok().map(|_| a)
+            };
+            if let Some(term) = next {
+                heap.push(MergeEntry { term, source });
             }
 
-            if !last.merge_terms(c.as_view(), &mut helper) {
-                if let AtomView::Num(n) = last.as_view() {
-                    if !n.is_zero() {
-                        new_stream.push_sorted_impl(last.clone());
-                    }
-                } else {
-                    new_stream.push_sorted_impl(last.clone());
-                }
-
-                last.set_from_view(&c.as_view());
+            if !last.merge_terms(term.as_view(), &mut helper) {
+                new_stream.push_nonzero(std::mem::replace(&mut last, term));
             }
         }
 
-        if let AtomView::Num(n) = last.as_view() {
-            if !n.is_zero() {
-                new_stream.push_sorted_impl(last.clone());
-            }
-        } else {
-            new_stream.push_sorted_impl(last.clone());
-        }
+        new_stream.push_nonzero(last);
 
         if !new_stream.file_buf.is_empty() {
             // the memory buffer has the larger elements than the
@@ -508,6 +489,176 @@ impl<W: WriteableNamedStream> TermStreamer<W> {
         *self = new_stream;
     }
 
+    /// Push a sorted term, unless it is zero.
+    fn push_nonzero(&mut self, a: Atom) {
+        if let AtomView::Num(n) = a.as_view() {
+            if n.is_zero() {
+                return;
+            }
+        }
+
+        self.push_sorted_impl(a);
+    }
+
+    /// Collect terms involving the same powers of the indeterminates `xs`, similar to
+    /// bracketing in FORM. The key-coefficient pairs of all terms are sorted by key
+    /// out of core, by spilling sorted runs to disk when the memory limit is exceeded
+    /// and merging the runs afterwards. Only the coefficient of a single key has to fit in memory.
+    ///
+    /// Both the *key* (the quantity collected in) and its coefficient can be mapped using
+    /// `key_map` and `coeff_map` respectively.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io::BufWriter;
+    /// # use std::fs::File;
+    /// use symbolica::{parse, streaming::TermStreamer};
+    ///
+    /// let mut stream = TermStreamer::<BufWriter<File>>::new(Default::default());
+    /// stream.push(parse!("x + x*y + x^2 + z + x*z").unwrap());
+    ///
+    /// let x = parse!("x").unwrap();
+    /// let mut collected = stream.collect_multiple::<u8>(&[x], None, None);
+    ///
+    /// assert_eq!(
+    ///     collected.to_expression(),
+    ///     parse!("z + x*(1+y+z) + x^2").unwrap()
+    /// );
+    /// ```
+    pub fn collect_multiple<E: Exponent>(
+        &mut self,
+        xs: &[impl AtomCore],
+        key_map: BracketMap,
+        coeff_map: BracketMap,
+    ) -> Self {
+        let mut out = self.next_generation();
+
+        self.for_each_bracket::<E>(xs, |key, coeff| {
+            let key = if let Some(key_map) = &key_map {
+                let mut k = Atom::new();
+                key_map(key.as_view(), &mut k);
+                k
+            } else {
+                key
+            };
+
+            let coeff = if let Some(coeff_map) = &coeff_map {
+                let mut c = Atom::new();
+                coeff_map(coeff.as_view(), &mut c);
+                c
+            } else {
+                coeff
+            };
+
+            out.push(key * coeff);
+        });
+
+        out
+    }
+
+    /// Call `f` for every key and non-zero coefficient of the terms collected in
+    /// the indeterminates `xs`, in the order of the keys.
+    fn for_each_bracket<E: Exponent>(
+        &mut self,
+        xs: &[impl AtomCore],
+        mut f: impl FnMut(Atom, Atom),
+    ) {
+        let mut runs = self.next_generation().next_generation();
+
+        let mut buf = vec![];
+        let mut buf_size = 0;
+        for t in self.reader() {
+            for (key, coeff) in t.coefficient_list::<E>(xs) {
+                buf_size += key.as_view().get_byte_size() + coeff.as_view().get_byte_size();
+                buf.push((key, coeff));
+            }
+
+            if buf_size >= runs.config.max_mem_bytes {
+                sort_brackets(&mut buf);
+                runs.file_buf
+                    .push(W::create(&runs.get_filename(runs.file_buf.len())));
+
+                let f = runs.file_buf.last_mut().unwrap();
+                for (key, coeff) in buf.drain(..) {
+                    key.as_view().write(&mut *f).unwrap();
+                    coeff.as_view().write(&mut *f).unwrap();
+                }
+                buf_size = 0;
+            }
+        }
+
+        sort_brackets(&mut buf);
+
+        for b in &mut runs.file_buf {
+            b.flush().unwrap();
+        }
+
+        let mut files: Vec<_> = (0..runs.file_buf.len())
+            .map(|i| W::Reader::open(&runs.get_filename(i)))
+            .collect();
+
+        fn read_pair<R: Read>(r: &mut R) -> Option<(Atom, Atom)> {
+            let mut key = Atom::new();
+            let mut coeff = Atom::new();
+            key.read(&mut *r).ok()?;
+            coeff.read(r).ok()?;
+            Some((key, coeff))
+        }
+
+        let mut mem = buf.into_iter();
+
+        let mut heap = BinaryHeap::with_capacity(files.len() + 1);
+        if let Some((key, coeff)) = mem.next() {
+            heap.push(BracketEntry {
+                key,
+                coeff,
+                source: 0,
+            });
+        }
+        for (i, f) in files.iter_mut().enumerate() {
+            if let Some((key, coeff)) = read_pair(f) {
+                heap.push(BracketEntry {
+                    key,
+                    coeff,
+                    source: i + 1,
+                });
+            }
+        }
+
+        let mut push_bracket = |key: Atom, coeffs: Vec<Atom>| {
+            let coeff = sum_coefficients(coeffs);
+            if !coeff.is_zero() {
+                f(key, coeff);
+            }
+        };
+
+        let mut current: Option<(Atom, Vec<Atom>)> = None;
+        while let Some(BracketEntry { key, coeff, source }) = heap.pop() {
+            let next = if source == 0 {
+                mem.next()
+            } else {
+                read_pair(&mut files[source - 1])
+            };
+            if let Some((key, coeff)) = next {
+                heap.push(BracketEntry { key, coeff, source });
+            }
+
+            match &mut current {
+                Some((k, c)) if *k == key => c.push(coeff),
+                _ => {
+                    if let Some((k, c)) = current.replace((key, vec![coeff])) {
+                        push_bracket(k, c);
+                    }
+                }
+            }
+        }
+
+        if let Some((k, c)) = current {
+            push_bracket(k, c);
+        }
+    }
+
     /// Convert the term stream into an expression. This may exceed the available memory.
     pub fn to_expression(&mut self) -> Atom {
         self.normalize();
@@ -588,6 +739,40 @@ impl<W: WriteableNamedStream> TermStreamer<W> {
         new_out
     }
 
+    /// Apply the `replacements` to every term in the stream, where the replacements are
+    /// tested in the order that they are given. The resulting terms are a stream as well,
+    /// which is returned by this function.
+    pub fn replace_multiple<T: BorrowReplacement + Sync>(&mut self, replacements: &[T]) -> Self {
+        self.map(|x| x.replace_multiple(replacements))
+    }
+
+    /// Apply the chain of transformers `chain` to every term in the stream.
+    /// The resulting terms are a stream as well, which is returned by this function.
+    /// If a transformer fails for any term, the error is returned.
+    pub fn transform(
+        &mut self,
+        chain: &[Transformer],
+        state: &TransformerState,
+    ) -> Result<Self, TransformerError> {
+        let error = Mutex::new(None);
+
+        let out = self.map(|x| {
+            let mut out = Atom::new();
+            if let Err(e) = Workspace::get_local()
+                .with(|ws| Transformer::execute_chain(x.as_view(), chain, ws, state, &mut out))
+            {
+                *error.lock().unwrap() = Some(e);
+                return Atom::new();
+            }
+            out
+        });
+
+        match error.into_inner().unwrap() {
+            Some(e) => Err(e),
+            None => Ok(out),
+        }
+    }
+
     /// Check if two term streams are equal. This will normalize both streams.
     pub fn eq(&mut self, other: &mut Self) -> bool {
         self.normalize();
@@ -616,6 +801,105 @@ impl<W: WriteableNamedStream> TermStreamer<W> {
     }
 }
 
+/// A term from one of the sorted sources of a k-way merge. The ordering is
+/// reversed, so that the smallest term is on top of a [BinaryHeap].
+struct MergeEntry {
+    term: Atom,
+    source: usize,
+}
+
+impl PartialEq for MergeEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MergeEntry {}
+
+impl PartialOrd for MergeEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MergeEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .term
+            .as_view()
+            .cmp_terms(&self.term.as_view())
+            .then(other.source.cmp(&self.source))
+    }
+}
+
+/// A key-coefficient pair from one of the sorted sources of a k-way merge of brackets.
+/// The ordering is reversed, so that the smallest key is on top of a [BinaryHeap].
+struct BracketEntry {
+    key: Atom,
+    coeff: Atom,
+    source: usize,
+}
+
+impl PartialEq for BracketEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for BracketEntry {}
+
+impl PartialOrd for BracketEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BracketEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .key
+            .as_view()
+            .cmp(&self.key.as_view())
+            .then(other.source.cmp(&self.source))
+    }
+}
+
+/// Sort key-coefficient pairs by key and add the coefficients of equal keys.
+fn sort_brackets(buf: &mut Vec<(Atom, Atom)>) {
+    buf.sort_by(|a, b| a.0.as_view().cmp(&b.0.as_view()));
+
+    let mut collected = Vec::with_capacity(buf.len());
+    let mut pairs = buf.drain(..).peekable();
+    while let Some((key, coeff)) = pairs.next() {
+        let mut coeffs = vec![coeff];
+        while let Some((_, c)) = pairs.next_if(|(k, _)| *k == key) {
+            coeffs.push(c);
+        }
+        collected.push((key, sum_coefficients(coeffs)));
+    }
+    drop(pairs);
+
+    *buf = collected;
+}
+
+/// Add the coefficients `coeffs`, normalizing the sum only once.
+fn sum_coefficients(mut coeffs: Vec<Atom>) -> Atom {
+    if coeffs.len() == 1 {
+        return coeffs.pop().unwrap();
+    }
+
+    Workspace::get_local().with(|ws| {
+        let mut r = ws.new_atom();
+        let add = r.to_add();
+        for c in &coeffs {
+            add.extend(c.as_view());
+        }
+        let mut out = Atom::new();
+        r.as_view().normalize(ws, &mut out);
+        out
+    })
+}
+
 impl AtomView<'_> {
     /// Map the function `f` over all terms.
     pub(crate) fn map_terms_single_core(&self, f: impl Fn(AtomView) -> Atom) -> Atom {
@@ -711,10 +995,11 @@ mod test {
     use crate::{
         atom::{Atom, AtomCore, AtomType},
         function,
-        id::WildcardRestriction,
+        id::{Replacement, WildcardRestriction},
         parse,
         streaming::{TermStreamer, TermStreamerConfig},
         symbol,
+        transformer::{Transformer, TransformerState},
     };
 
     #[test]
@@ -838,4 +1123,59 @@ mod test {
         let res = parse!("v1 + v2 + v3 + v4 + 4").unwrap();
         assert_eq!(r, res);
     }
+
+    #[test]
+    fn out_of_core_collect() {
+        let mut streamer =
+            TermStreamer::<CompressorWriter<BufWriter<File>>>::new(TermStreamerConfig {
+                n_cores: 1,
+                path: ".".to_owned(),
+                max_mem_bytes: 100,
+            });
+
+        let input = parse!("(1+x+y)^4*(z+2)^2").unwrap().expand();
+        streamer.push(input.clone());
+        assert!(!streamer.fits_in_memory());
+
+        let x = parse!("x").unwrap();
+        let y = parse!("y").unwrap();
+        let mut collected = streamer.collect_multiple::<u8>(
+            &[x.clone(), y.clone()],
+            None,
+            Some(Box::new(|c, out| *out = c.factor())),
+        );
+
+        let res =
+            input.collect_multiple::<u8>(&[x, y], None, Some(Box::new(|c, out| *out = c.factor())));
+        assert_eq!(collected.to_expression(), res);
+    }
+
+    #[test]
+    fn stream_pipelines() {
+        let mut streamer =
+            TermStreamer::<CompressorWriter<BufWriter<File>>>::new(TermStreamerConfig {
+                n_cores: 1,
+                path: ".".to_owned(),
+                max_mem_bytes: 50,
+            });
+
+        for i in 0..20 {
+            streamer.push(function!(symbol!("f1"), i % 7) * Atom::new_var(symbol!("v1")));
+        }
+
+        let r = Replacement::new(
+            parse!("f1(x_)").unwrap().to_pattern(),
+            parse!("x_+1").unwrap().to_pattern(),
+        );
+        streamer = streamer.replace_multiple(&[r]);
+
+        streamer = streamer
+            .transform(
+                &[Transformer::Expand(None, false)],
+                &TransformerState::default(),
+            )
+            .unwrap();
+
+        assert_eq!(streamer.to_expression(), parse!("77*v1").unwrap());
+    }
 }
@@ -0,0 +1,341 @@
+//! Expressions that are bracketed in a set of indeterminates, similar to
+//! the brackets of FORM.
+//!
+//! A [BracketedExpression] stores the coefficient of every bracket, i.e. every
+//! monomial in the indeterminates, together with an index that maps a bracket to its coefficient.
+//! Coefficients are kept in memory until the memory limit of the [TermStreamerConfig]
+//! is reached, after which they are written to a file. Looking up or transforming
+//! the coefficient of a bracket does not require a scan over the full expression.
+//!
+//! # Examples
+//!
+//! ```
+//! use symbolica::{
+//!     atom::AtomCore,
+//!     parse,
+//!     streaming::{bracket::BracketedExpression, TermStreamerConfig},
+//! };
+//!
+//! let expr = parse!("(1+x+y)^2*(a+b)").unwrap().expand();
+//! let xs = [parse!("x").unwrap(), parse!("y").unwrap()];
+//!
+//! let b = BracketedExpression::new::<u8>(&expr, &xs, TermStreamerConfig::default());
+//! assert_eq!(b.len(), 6);
+//! assert_eq!(
+//!     b.coefficient(parse!("x*y").unwrap()),
+//!     Some(parse!("2*a+2*b").unwrap())
+//! );
+//! assert_eq!(b.coefficient(parse!("x^3").unwrap()), None);
+//! ```
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Seek, SeekFrom, Write},
+};
+
+use ahash::HashMap;
+
+use crate::{
+    atom::{Atom, AtomCore, AtomView},
+    poly::Exponent,
+};
+
+use super::{TermStreamer, TermStreamerConfig, WriteableNamedStream, TEMP_FILES_COUNTER};
+
+/// The location of the coefficient of a bracket.
+#[derive(Clone, Debug)]
+enum BracketContent {
+    Memory(Atom),
+    /// The offset of the coefficient in the file.
+    File(u64),
+}
+
+/// The file that stores the coefficients that do not fit in memory.
+struct BracketFile {
+    filename: String,
+    writer: BufWriter<File>,
+    position: u64,
+}
+
+/// An expression that is bracketed in a set of indeterminates. See the
+/// [module documentation](self) for an example.
+pub struct BracketedExpression {
+    keys: Vec<Atom>,
+    index: HashMap<Atom, usize>,
+    contents: Vec<BracketContent>,
+    mem_size: usize,
+    config: TermStreamerConfig,
+    file: Option<BracketFile>,
+}
+
+impl Drop for BracketedExpression {
+    /// Remove the file with the coefficients.
+    fn drop(&mut self) {
+        if let Some(f) = &self.file {
+            std::fs::remove_file(&f.filename).unwrap();
+        }
+    }
+}
+
+impl BracketedExpression {
+    fn empty(config: TermStreamerConfig) -> Self {
+        Self {
+            keys: vec![],
+            index: HashMap::default(),
+            contents: vec![],
+            mem_size: 0,
+            config,
+            file: None,
+        }
+    }
+
+    /// Bracket the expression `expr` in the indeterminates `xs`. The coefficients
+    /// that exceed the memory limit of `config` are written to a file in the path of `config`.
+    pub fn new<E: Exponent>(
+        expr: impl AtomCore,
+        xs: &[impl AtomCore],
+        config: TermStreamerConfig,
+    ) -> Self {
+        let mut brackets = expr.coefficient_list::<E>(xs);
+        brackets.sort_by(|a, b| a.0.as_view().cmp(&b.0.as_view()));
+
+        let mut b = Self::empty(config);
+        for (key, coeff) in brackets {
+            if !coeff.is_zero() {
+                b.push(key, coeff);
+            }
+        }
+        b.flush();
+        b
+    }
+
+    /// Bracket the terms of the term stream `stream` in the indeterminates `xs`, without
+    /// loading the full stream into memory. The configuration of `stream` is used.
+    pub fn from_stream<E: Exponent>(
+        stream: &mut TermStreamer<impl WriteableNamedStream>,
+        xs: &[impl AtomCore],
+    ) -> Self {
+        let mut b = Self::empty(stream.config.clone());
+        stream.for_each_bracket::<E>(xs, |key, coeff| b.push(key, coeff));
+        b.flush();
+        b
+    }
+
+    /// Get the number of brackets.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns `true` iff there are no brackets.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Get the keys of the brackets, in sorted order.
+    pub fn keys(&self) -> &[Atom] {
+        &self.keys
+    }
+
+    /// Returns true iff no coefficient is stored on disk.
+    pub fn fits_in_memory(&self) -> bool {
+        self.contents
+            .iter()
+            .all(|c| matches!(c, BracketContent::Memory(_)))
+    }
+
+    /// Get the coefficient of the bracket `key`, which is a monomial in the
+    /// bracketed indeterminates. The key of the terms without any of the
+    /// indeterminates is `1`.
+    pub fn coefficient(&self, key: impl AtomCore) -> Option<Atom> {
+        let i = self.index.get(&key.as_atom_view().to_owned())?;
+        Some(self.load(&self.contents[*i]))
+    }
+
+    /// Iterate over the keys and coefficients of the brackets.
+    pub fn iter(&self) -> impl Iterator<Item = (&Atom, Atom)> + '_ {
+        self.keys
+            .iter()
+            .zip(&self.contents)
+            .map(|(k, c)| (k, self.load(c)))
+    }
+
+    /// Map the coefficient of the bracket `key` using `f`. The bracket is removed
+    /// if the new coefficient is zero. Returns `false` if the bracket does not exist.
+    pub fn map_coefficient(&mut self, key: impl AtomCore, f: impl Fn(AtomView) -> Atom) -> bool {
+        let Some(i) = self.index.get(&key.as_atom_view().to_owned()).cloned() else {
+            return false;
+        };
+
+        let new = f(self.load(&self.contents[i]).as_view());
+
+        if let BracketContent::Memory(old) = &self.contents[i] {
+            self.mem_size -= old.as_view().get_byte_size();
+        }
+
+        if new.is_zero() {
+            let key = self.keys.remove(i);
+            self.index.remove(&key);
+            self.contents.remove(i);
+            for (j, k) in self.keys.iter().enumerate().skip(i) {
+                *self.index.get_mut(k).unwrap() = j;
+            }
+        } else {
+            self.contents[i] = self.store(new);
+            self.flush();
+        }
+
+        true
+    }
+
+    /// Map the coefficient of every bracket using `f`, which receives the key and the
+    /// coefficient. Brackets whose new coefficient is zero are removed.
+    pub fn map_coefficients(&mut self, f: impl Fn(AtomView, AtomView) -> Atom) {
+        let mut out = Self::empty(self.config.clone());
+        for (key, coeff) in self.iter() {
+            let new = f(key.as_view(), coeff.as_view());
+            if !new.is_zero() {
+                out.push(key.clone(), new);
+            }
+        }
+        out.flush();
+
+        *self = out;
+    }
+
+    /// Convert the bracketed expression into an expression, where every
+    /// bracket is written as the product of its key and its coefficient.
+    pub fn to_expression(&self) -> Atom {
+        let mut a = Atom::new();
+        for (key, coeff) in self.iter() {
+            a += key * coeff;
+        }
+        a
+    }
+
+    /// Add a bracket with a key that is larger than all present keys.
+    fn push(&mut self, key: Atom, coeff: Atom) {
+        let content = self.store(coeff);
+        self.index.insert(key.clone(), self.keys.len());
+        self.keys.push(key);
+        self.contents.push(content);
+    }
+
+    /// Store a coefficient in memory, or in the file if the memory limit is exceeded.
+    fn store(&mut self, coeff: Atom) -> BracketContent {
+        let size = coeff.as_view().get_byte_size();
+        if self.mem_size + size <= self.config.max_mem_bytes {
+            self.mem_size += size;
+            return BracketContent::Memory(coeff);
+        }
+
+        let f = self.file.get_or_insert_with(|| {
+            let filename = format!(
+                "{}/{}_{:x}_brackets.tmp",
+                self.config.path,
+                std::process::id(),
+                TEMP_FILES_COUNTER.fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+            );
+            BracketFile {
+                writer: BufWriter::new(File::create(&filename).unwrap()),
+                filename,
+                position: 0,
+            }
+        });
+
+        let offset = f.position;
+        coeff.as_view().write(&mut f.writer).unwrap();
+        f.position += 9 + size as u64; // the tag, the length and the data
+        BracketContent::File(offset)
+    }
+
+    /// Flush the coefficients that are written to the file.
+    fn flush(&mut self) {
+        if let Some(f) = &mut self.file {
+            f.writer.flush().unwrap();
+        }
+    }
+
+    /// Load a coefficient.
+    fn load(&self, content: &BracketContent) -> Atom {
+        match content {
+            BracketContent::Memory(a) => a.clone(),
+            BracketContent::File(offset) => {
+                let f = self.file.as_ref().unwrap();
+                let mut r = BufReader::new(File::open(&f.filename).unwrap());
+                r.seek(SeekFrom::Start(*offset)).unwrap();
+
+                let mut a = Atom::new();
+                a.read(&mut r).unwrap();
+                a
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs::File, io::BufWriter};
+
+    use crate::{
+        atom::AtomCore,
+        parse,
+        streaming::{TermStreamer, TermStreamerConfig},
+    };
+
+    use super::BracketedExpression;
+
+    #[test]
+    fn bracket() {
+        let expr = parse!("(1+x+y)^4*(a+b+f(x))^2").unwrap().expand();
+        let xs = [parse!("x").unwrap(), parse!("y").unwrap()];
+
+        let config = TermStreamerConfig {
+            n_cores: 1,
+            path: ".".to_owned(),
+            max_mem_bytes: 200,
+        };
+
+        let mem = BracketedExpression::new::<u8>(&expr, &xs, TermStreamerConfig::default());
+        assert!(mem.fits_in_memory());
+        assert_eq!(mem.len(), 15);
+
+        let mut stream = TermStreamer::<BufWriter<File>>::new(config.clone());
+        stream.push(expr.clone());
+        let mut disk = BracketedExpression::from_stream::<u8>(&mut stream, &xs);
+        assert!(!disk.fits_in_memory());
+
+        assert_eq!(mem.keys(), disk.keys());
+        for (k, c) in mem.iter() {
+            assert_eq!(disk.coefficient(k).unwrap(), c);
+        }
+        assert_eq!(disk.to_expression().expand(), expr);
+
+        let key = parse!("x^2*y^2").unwrap();
+        assert!(disk.map_coefficient(&key, |c| c.factor()));
+        assert_eq!(
+            disk.coefficient(&key).unwrap(),
+            parse!("6*(a+b+f(x))^2").unwrap()
+        );
+
+        assert!(disk.map_coefficient(&key, |_| parse!("0").unwrap()));
+        assert_eq!(disk.len(), 14);
+        assert_eq!(disk.coefficient(&key), None);
+        assert_eq!(
+            disk.coefficient(parse!("y^4").unwrap()),
+            mem.coefficient(parse!("y^4").unwrap())
+        );
+
+        disk.map_coefficients(|k, c| {
+            if k == parse!("1").unwrap().as_view() {
+                c.to_owned()
+            } else {
+                parse!("0").unwrap()
+            }
+        });
+        assert_eq!(disk.len(), 1);
+        assert_eq!(
+            disk.to_expression(),
+            parse!("(a+b+f(x))^2").unwrap().expand()
+        );
+    }
+}
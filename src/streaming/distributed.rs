@@ -0,0 +1,651 @@
+//! Distributed term streaming over sockets.
+//!
+//! A [DistributedTermStreamer] coordinates several workers, typically separate processes,
+//! that each run a [TermStreamWorker]. Terms are partitioned over the workers by a hash
+//! of the term without its coefficient, so that like terms end up at the same worker.
+//! The workers apply maps and normalize their terms locally and the results are merged
+//! at the coordinator.
+//!
+//! Terms are sent in batches in the binary format of [Atom::export](crate::atom::AtomCore::export).
+//! The state is sent once per connection, and again only when new symbols have been defined,
+//! so that the coordinator and the workers do not need to share their symbols.
+//! Any connection that implements [Read] and [Write], such as a
+//! [UnixStream](std::os::unix::net::UnixStream) or a [TcpStream](std::net::TcpStream), can be used.
+//! Since functions cannot be sent over a connection, maps are registered by name at the workers.
+//!
+//! # Examples
+//!
+//! Every worker process listens on a socket and executes the commands of the coordinator:
+//! ```no_run
+//! use std::{fs::File, io::BufWriter, os::unix::net::UnixListener};
+//!
+//! use symbolica::{
+//!     atom::AtomCore,
+//!     streaming::{distributed::TermStreamWorker, TermStreamerConfig},
+//! };
+//!
+//! let listener = UnixListener::bind("/tmp/symbolica_worker_0").unwrap();
+//! let mut worker = TermStreamWorker::<BufWriter<File>>::new(TermStreamerConfig::default());
+//! worker.register_map("expand", |x| x.expand());
+//!
+//! let (connection, _) = listener.accept().unwrap();
+//! worker.serve(connection).unwrap();
+//! ```
+//!
+//! The coordinator process connects to the workers:
+//! ```no_run
+//! use std::{fs::File, io::BufWriter, os::unix::net::UnixStream};
+//!
+//! use symbolica::{
+//!     parse,
+//!     streaming::{distributed::DistributedTermStreamer, TermStreamer, TermStreamerConfig},
+//! };
+//!
+//! let workers = (0..4)
+//!     .map(|i| UnixStream::connect(format!("/tmp/symbolica_worker_{}", i)).unwrap())
+//!     .collect();
+//!
+//! let mut d = DistributedTermStreamer::new(workers, 1 << 20);
+//! d.push(parse!("(x+1)*y + (y+1)^2*z").unwrap()).unwrap();
+//! d.map("expand").unwrap();
+//!
+//! let mut out = TermStreamer::<BufWriter<File>>::new(TermStreamerConfig::default());
+//! d.merge_into(&mut out).unwrap();
+//! println!("{}", out.to_expression());
+//!
+//! d.shutdown().unwrap();
+//! ```
+
+use std::{
+    hash::{Hash, Hasher},
+    io::{Cursor, Read, Write},
+};
+
+use ahash::HashMap;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::{
+    atom::{Atom, AtomView},
+    state::{State, StateMap},
+};
+
+use super::{TermStreamer, TermStreamerConfig, WriteableNamedStream};
+
+const PUSH: u8 = 0;
+const MAP: u8 = 1;
+const NORMALIZE: u8 = 2;
+const FETCH: u8 = 3;
+const NUM_TERMS: u8 = 4;
+const CLEAR: u8 = 5;
+const SHUTDOWN: u8 = 6;
+const STATE: u8 = 7;
+
+const STATUS_OK: u8 = 0;
+const STATUS_ERROR: u8 = 1;
+const STATUS_DATA: u8 = 2;
+const STATUS_STATE: u8 = 3;
+
+/// The maximal size of a message, which protects against malformed length prefixes.
+const MAX_MESSAGE_BYTES: u64 = 1 << 32;
+/// The size at which the terms that are fetched from a worker are sent in a new message.
+const FETCH_BATCH_BYTES: usize = 1 << 20;
+
+/// A map that is applied to every term at a worker.
+type TermMap = Box<dyn Fn(Atom) -> Atom + Send + Sync>;
+
+/// Write a message that is prefixed by its length.
+fn send_message<S: Write>(connection: &mut S, message: &[u8]) -> Result<(), std::io::Error> {
+    if message.len() as u64 > MAX_MESSAGE_BYTES {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Message of {} bytes is too large", message.len()),
+        ));
+    }
+
+    connection.write_u64::<LittleEndian>(message.len() as u64)?;
+    connection.write_all(message)?;
+    connection.flush()
+}
+
+/// Read a message that is prefixed by its length.
+fn receive_message<S: Read>(connection: &mut S) -> Result<Vec<u8>, std::io::Error> {
+    let len = connection.read_u64::<LittleEndian>()?;
+    if len > MAX_MESSAGE_BYTES {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Message of {} bytes is too large", len),
+        ));
+    }
+
+    // grow the buffer as data arrives, instead of trusting the length
+    let mut message = vec![];
+    connection.take(len).read_to_end(&mut message)?;
+    if message.len() as u64 != len {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(message)
+}
+
+/// Create a message with header `header` that contains the state, if the state has changed
+/// since `sent_version`, which is the version of the state that was last sent.
+fn state_message(
+    header: u8,
+    sent_version: &mut Option<usize>,
+) -> Result<Option<Vec<u8>>, std::io::Error> {
+    let version = State::get_version();
+    if *sent_version == Some(version) {
+        return Ok(None);
+    }
+
+    let mut message = vec![header];
+    State::export(&mut message)?;
+    *sent_version = Some(version);
+    Ok(Some(message))
+}
+
+/// Write a batch of terms in the export format, without the state.
+fn write_batch<'a>(
+    dest: &mut Vec<u8>,
+    terms: impl ExactSizeIterator<Item = AtomView<'a>>,
+) -> Result<(), std::io::Error> {
+    dest.write_u64::<LittleEndian>(terms.len() as u64)?;
+    for t in terms {
+        t.write(&mut *dest)?;
+    }
+    Ok(())
+}
+
+/// Read a batch of terms in the export format and push them to `stream`,
+/// using the state map of the last state that was received.
+fn read_batch<W: WriteableNamedStream>(
+    mut source: &[u8],
+    state_map: Option<&StateMap>,
+    stream: &mut TermStreamer<W>,
+) -> Result<(), std::io::Error> {
+    let Some(state_map) = state_map else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Terms were received before the state",
+        ));
+    };
+
+    let n_terms = source.read_u64::<LittleEndian>()?;
+
+    let mut tmp = Atom::new();
+    for _ in 0..n_terms {
+        tmp.read(&mut source)?;
+        stream.push(tmp.as_view().rename(state_map));
+    }
+
+    Ok(())
+}
+
+/// Convert an error reply into an error.
+fn check_reply(reply: &[u8]) -> Result<&[u8], std::io::Error> {
+    match reply.first() {
+        Some(&STATUS_OK) | Some(&STATUS_DATA) | Some(&STATUS_STATE) => Ok(&reply[1..]),
+        Some(&STATUS_ERROR) => Err(std::io::Error::other(
+            String::from_utf8_lossy(&reply[1..]).into_owned(),
+        )),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Invalid reply from worker",
+        )),
+    }
+}
+
+/// A worker that holds a part of the terms of a [DistributedTermStreamer]
+/// in a [TermStreamer] and executes the commands of the coordinator.
+pub struct TermStreamWorker<W: WriteableNamedStream> {
+    stream: TermStreamer<W>,
+    maps: HashMap<String, TermMap>,
+    /// The map from the state of the coordinator to the local state.
+    state_map: Option<StateMap>,
+    /// The version of the local state that was last sent to the coordinator.
+    sent_state: Option<usize>,
+}
+
+impl<W: WriteableNamedStream> TermStreamWorker<W> {
+    /// Create a new worker that stores its terms in a term streamer with configuration `config`.
+    pub fn new(config: TermStreamerConfig) -> Self {
+        Self {
+            stream: TermStreamer::new(config),
+            maps: HashMap::default(),
+            state_map: None,
+            sent_state: None,
+        }
+    }
+
+    /// Register a map with the name `name`, that can be applied to every term
+    /// using [DistributedTermStreamer::map].
+    pub fn register_map(&mut self, name: &str, f: impl Fn(Atom) -> Atom + Send + Sync + 'static) {
+        self.maps.insert(name.to_owned(), Box::new(f));
+    }
+
+    /// Execute the commands of the coordinator that are received over `connection`,
+    /// until the coordinator shuts down the worker or closes the connection.
+    pub fn serve<S: Read + Write>(&mut self, mut connection: S) -> Result<(), std::io::Error> {
+        // the state is exchanged once per connection
+        self.state_map = None;
+        self.sent_state = None;
+
+        loop {
+            let message = match receive_message(&mut connection) {
+                Ok(m) => m,
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e),
+            };
+
+            if !self.handle(&message, &mut connection)? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Execute the command in `message` and write the reply to `connection`.
+    /// Returns `false` if the worker is shut down.
+    fn handle<S: Write>(
+        &mut self,
+        message: &[u8],
+        connection: &mut S,
+    ) -> Result<bool, std::io::Error> {
+        let Some((&command, payload)) = message.split_first() else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Empty command",
+            ));
+        };
+
+        let mut reply = vec![STATUS_OK];
+        match command {
+            STATE => self.state_map = Some(State::import(&mut &*payload, None)?),
+            PUSH => read_batch(payload, self.state_map.as_ref(), &mut self.stream)?,
+            MAP => {
+                let name = String::from_utf8_lossy(payload);
+                if let Some(f) = self.maps.get(name.as_ref()) {
+                    self.stream = self.stream.map(f);
+                } else {
+                    reply[0] = STATUS_ERROR;
+                    reply.extend(format!("Unknown map {}", name).as_bytes());
+                }
+            }
+            NORMALIZE => self.stream.normalize(),
+            FETCH => {
+                self.stream.normalize();
+
+                if let Some(m) = state_message(STATUS_STATE, &mut self.sent_state)? {
+                    send_message(connection, &m)?;
+                }
+
+                let mut batch = vec![];
+                let mut size = 0;
+                let mut data = vec![STATUS_DATA];
+                for t in self.stream.reader() {
+                    size += t.as_view().get_byte_size();
+                    batch.push(t);
+
+                    if batch.len() >= 1024 || size >= FETCH_BATCH_BYTES {
+                        write_batch(&mut data, batch.iter().map(|x| x.as_view()))?;
+                        send_message(connection, &data)?;
+                        batch.clear();
+                        size = 0;
+                        data.truncate(1);
+                    }
+                }
+
+                if !batch.is_empty() {
+                    write_batch(&mut data, batch.iter().map(|x| x.as_view()))?;
+                    send_message(connection, &data)?;
+                }
+            }
+            NUM_TERMS => reply.write_u64::<LittleEndian>(self.stream.get_num_terms() as u64)?,
+            CLEAR => self.stream.clear(),
+            SHUTDOWN => {
+                send_message(connection, &reply)?;
+                return Ok(false);
+            }
+            _ => {
+                reply[0] = STATUS_ERROR;
+                reply.extend(format!("Unknown command {}", command).as_bytes());
+            }
+        }
+
+        send_message(connection, &reply)?;
+        Ok(true)
+    }
+}
+
+/// A coordinator that distributes the terms of an expression over workers,
+/// that are connected over `S`. See the [module documentation](self) for an example.
+pub struct DistributedTermStreamer<S: Read + Write> {
+    workers: Vec<S>,
+    buffers: Vec<(Vec<Atom>, usize)>,
+    max_batch_bytes: usize,
+    /// The version of the local state that was last sent to each worker.
+    sent_states: Vec<Option<usize>>,
+    /// The map from the state of each worker to the local state.
+    state_maps: Vec<Option<StateMap>>,
+}
+
+impl<S: Read + Write> DistributedTermStreamer<S> {
+    /// Create a new coordinator for the connected `workers`. Terms are sent to
+    /// a worker in batches of about `max_batch_bytes` bytes.
+    pub fn new(workers: Vec<S>, max_batch_bytes: usize) -> Self {
+        Self {
+            buffers: workers.iter().map(|_| (vec![], 0)).collect(),
+            sent_states: workers.iter().map(|_| None).collect(),
+            state_maps: workers.iter().map(|_| None).collect(),
+            workers,
+            max_batch_bytes,
+        }
+    }
+
+    /// Get the number of workers.
+    pub fn get_num_workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Get the worker of a term, which is determined by a hash of
+    /// the term without its coefficient.
+    fn get_worker(&self, term: AtomView) -> usize {
+        let mut hasher = ahash::AHasher::default();
+        if let AtomView::Mul(m) = term {
+            for f in m.iter() {
+                if !matches!(f, AtomView::Num(_)) {
+                    f.hash(&mut hasher);
+                }
+            }
+        } else if !matches!(term, AtomView::Num(_)) {
+            term.hash(&mut hasher);
+        }
+
+        (hasher.finish() % self.workers.len() as u64) as usize
+    }
+
+    /// Add terms to the workers.
+    pub fn push(&mut self, a: Atom) -> Result<(), std::io::Error> {
+        if let AtomView::Add(aa) = a.as_view() {
+            for arg in aa.iter() {
+                self.push_term(arg.to_owned())?;
+            }
+            Ok(())
+        } else {
+            self.push_term(a)
+        }
+    }
+
+    fn push_term(&mut self, a: Atom) -> Result<(), std::io::Error> {
+        let w = self.get_worker(a.as_view());
+        self.buffers[w].1 += a.as_view().get_byte_size();
+        self.buffers[w].0.push(a);
+
+        if self.buffers[w].1 >= self.max_batch_bytes {
+            self.send_buffer(w)?;
+        }
+
+        Ok(())
+    }
+
+    /// Send the buffered terms of worker `w`.
+    fn send_buffer(&mut self, w: usize) -> Result<(), std::io::Error> {
+        let (terms, size) = &mut self.buffers[w];
+        if terms.is_empty() {
+            return Ok(());
+        }
+
+        let mut message = Vec::with_capacity(*size + 1);
+        message.push(PUSH);
+        write_batch(&mut message, terms.iter().map(|x| x.as_view()))?;
+        terms.clear();
+        *size = 0;
+
+        if let Some(m) = state_message(STATE, &mut self.sent_states[w])? {
+            send_message(&mut self.workers[w], &m)?;
+            check_reply(&receive_message(&mut self.workers[w])?)?;
+        }
+
+        send_message(&mut self.workers[w], &message)?;
+        check_reply(&receive_message(&mut self.workers[w])?)?;
+        Ok(())
+    }
+
+    /// Send all buffered terms and send `message` to all workers. The workers
+    /// execute the command in parallel. The replies of the workers are returned.
+    fn broadcast(&mut self, message: &[u8]) -> Result<Vec<Vec<u8>>, std::io::Error> {
+        for w in 0..self.workers.len() {
+            self.send_buffer(w)?;
+        }
+
+        for w in &mut self.workers {
+            send_message(w, message)?;
+        }
+
+        // read all replies before reporting an error, so that
+        // the connections remain synchronized
+        let mut replies = Vec::with_capacity(self.workers.len());
+        for w in &mut self.workers {
+            replies.push(receive_message(w)?);
+        }
+
+        for r in &replies {
+            check_reply(r)?;
+        }
+
+        Ok(replies)
+    }
+
+    /// Map every term at the workers using the map that is registered
+    /// with the name `name` at every worker.
+    pub fn map(&mut self, name: &str) -> Result<(), std::io::Error> {
+        let mut message = vec![MAP];
+        message.extend(name.as_bytes());
+        self.broadcast(&message)?;
+        Ok(())
+    }
+
+    /// Sort and fuse the terms at every worker. After mapping, like terms may
+    /// be at different workers, which are fused by [Self::merge_into].
+    pub fn normalize(&mut self) -> Result<(), std::io::Error> {
+        self.broadcast(&[NORMALIZE])?;
+        Ok(())
+    }
+
+    /// Get the total number of terms at the workers.
+    pub fn get_num_terms(&mut self) -> Result<usize, std::io::Error> {
+        let mut n = 0;
+        for r in self.broadcast(&[NUM_TERMS])? {
+            n += Cursor::new(&r[1..]).read_u64::<LittleEndian>()? as usize;
+        }
+        Ok(n)
+    }
+
+    /// Fetch the normalized terms of all workers and merge them into `out`.
+    /// The terms remain at the workers.
+    pub fn merge_into<W: WriteableNamedStream>(
+        &mut self,
+        out: &mut TermStreamer<W>,
+    ) -> Result<(), std::io::Error> {
+        for w in 0..self.workers.len() {
+            self.send_buffer(w)?;
+        }
+
+        for w in &mut self.workers {
+            send_message(w, &[FETCH])?;
+        }
+
+        for (w, state_map) in self.workers.iter_mut().zip(&mut self.state_maps) {
+            loop {
+                let reply = receive_message(w)?;
+                let data = check_reply(&reply)?;
+                match reply[0] {
+                    STATUS_OK => break,
+                    STATUS_STATE => *state_map = Some(State::import(&mut &*data, None)?),
+                    _ => read_batch(data, state_map.as_ref(), out)?,
+                }
+            }
+        }
+
+        out.normalize();
+        Ok(())
+    }
+
+    /// Remove all terms from the workers.
+    pub fn clear(&mut self) -> Result<(), std::io::Error> {
+        for b in &mut self.buffers {
+            b.0.clear();
+            b.1 = 0;
+        }
+
+        self.broadcast(&[CLEAR])?;
+        Ok(())
+    }
+
+    /// Shut down all workers.
+    pub fn shutdown(mut self) -> Result<(), std::io::Error> {
+        self.broadcast(&[SHUTDOWN])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::VecDeque,
+        fs::File,
+        io::{BufWriter, Read, Write},
+    };
+
+    use crate::{
+        LicenseManager,
+        atom::AtomCore,
+        parse,
+        streaming::{TermStreamer, TermStreamerConfig},
+    };
+
+    use super::{DistributedTermStreamer, TermStreamWorker};
+
+    /// A connection to a worker that handles every message as soon as it is written.
+    struct Loopback {
+        worker: TermStreamWorker<BufWriter<File>>,
+        input: Vec<u8>,
+        output: VecDeque<u8>,
+    }
+
+    impl Write for Loopback {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.input.extend_from_slice(buf);
+
+            while self.input.len() >= 8 {
+                let len = u64::from_le_bytes(self.input[..8].try_into().unwrap()) as usize;
+                if self.input.len() < 8 + len {
+                    break;
+                }
+
+                let message: Vec<_> = self.input.drain(..8 + len).skip(8).collect();
+                let mut reply = vec![];
+                self.worker.handle(&message, &mut reply)?;
+                self.output.extend(reply);
+            }
+
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Read for Loopback {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.output.read(buf)
+        }
+    }
+
+    #[test]
+    fn distributed_stream() {
+        let workers = (0..3)
+            .map(|_| {
+                let mut worker = TermStreamWorker::new(TermStreamerConfig {
+                    n_cores: 1,
+                    path: ".".to_owned(),
+                    max_mem_bytes: 100,
+                });
+                worker.register_map("expand", |x| x.expand());
+                Loopback {
+                    worker,
+                    input: vec![],
+                    output: VecDeque::new(),
+                }
+            })
+            .collect();
+
+        let mut d = DistributedTermStreamer::new(workers, 64);
+        let input = parse!("(1+x+y)^3*(f(z)+2)").unwrap();
+        d.push(input.expand()).unwrap();
+        d.push(parse!("(x+y)^2").unwrap()).unwrap();
+        d.map("expand").unwrap();
+        d.normalize().unwrap();
+        assert!(d.map("unknown").is_err());
+        assert!(
+            d.workers
+                .iter()
+                .all(|w| w.worker.stream.get_num_terms() > 0)
+        );
+
+        let mut out = TermStreamer::<BufWriter<File>>::new(TermStreamerConfig::default());
+        d.merge_into(&mut out).unwrap();
+
+        let res = (input + parse!("(x+y)^2").unwrap()).expand();
+        assert_eq!(out.to_expression(), res);
+
+        d.clear().unwrap();
+        assert_eq!(d.get_num_terms().unwrap(), 0);
+        d.shutdown().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn distributed_stream_threads() {
+        use std::os::unix::net::UnixStream;
+
+        // an unlicensed instance may only be used from a single thread
+        if !LicenseManager::is_licensed() {
+            return;
+        }
+
+        let mut connections = vec![];
+        let mut threads = vec![];
+        for _ in 0..4 {
+            let (coordinator, worker) = UnixStream::pair().unwrap();
+            connections.push(coordinator);
+            threads.push(std::thread::spawn(move || {
+                let mut w = TermStreamWorker::<BufWriter<File>>::new(TermStreamerConfig {
+                    n_cores: 1,
+                    path: ".".to_owned(),
+                    max_mem_bytes: 100,
+                });
+                w.register_map("expand", |x| x.expand());
+                w.serve(worker)
+            }));
+        }
+
+        let mut d = DistributedTermStreamer::new(connections, 64);
+        let input = parse!("(1+x+y)^3*(f(z)+2)").unwrap().expand();
+        d.push(input.clone()).unwrap();
+
+        // the symbols defined after the first batches must reach the workers
+        let new = parse!("(dist_thread_x+dist_thread_y)^2").unwrap();
+        d.push(new.clone()).unwrap();
+        d.map("expand").unwrap();
+        assert!(d.map("unknown").is_err());
+
+        let mut out = TermStreamer::<BufWriter<File>>::new(TermStreamerConfig::default());
+        d.merge_into(&mut out).unwrap();
+        assert_eq!(out.to_expression(), (input + new).expand());
+
+        d.shutdown().unwrap();
+        for t in threads {
+            t.join().unwrap().unwrap();
+        }
+    }
+}
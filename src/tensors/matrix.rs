@@ -33,7 +33,7 @@ use crate::{
         rational::{Q, Rational},
     },
     poly::Variable,
-    printer::{PrintOptions, PrintState},
+    printer::{PrintOptions, PrintState, pretty},
 };
 
 /// An n-dimensional vector.
@@ -1012,6 +1012,16 @@ impl<F: Ring> SelfRing for Matrix<F> {
 
             f.write_str("\\end{pmatrix}")?;
             Ok(false)
+        } else if opts.mode.is_pretty() {
+            let rows = self
+                .row_iter()
+                .map(|r| {
+                    r.iter()
+                        .map(|c| pretty::ring_element(&self.field, c, opts))
+                        .collect()
+                })
+                .collect();
+            pretty::write(f, &pretty::matrix(rows, opts))
         } else if opts.pretty_matrix {
             f.write_char('[')?;
             for (ri, r) in self.row_iter().enumerate() {
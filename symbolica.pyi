@@ -55,6 +55,13 @@ def S(name: str,
       is_antisymmetric: Optional[bool] = None,
       is_cyclesymmetric: Optional[bool] = None,
       is_linear: Optional[bool] = None,
+      is_noncommutative: Optional[bool] = None,
+      is_grassmann: Optional[bool] = None,
+      is_idempotent: Optional[bool] = None,
+      is_involution: Optional[bool] = None,
+      is_associative: Optional[bool] = None,
+      is_homogeneous: Optional[bool] = None,
+      linear_args: Optional[Sequence[int]] = None,
       custom_normalization: Optional[Transformer] = None,
       custom_print: Optional[Callable[..., Optional[str]]] = None) -> Expression:
     """
@@ -120,6 +127,20 @@ def S(name: str,
         Set to true if the symbol is cyclesymmetric.
     is_linear : Optional[bool]
         Set to true if the symbol is linear.
+    is_noncommutative : Optional[bool]
+        Set to true if the symbol is noncommutative: its relative order in products is preserved.
+    is_grassmann : Optional[bool]
+        Set to true if the symbol is Grassmann-odd: it anticommutes with other Grassmann-odd symbols.
+    is_idempotent : Optional[bool]
+        Set to true if the function is idempotent: `f(f(x)) = f(x)`.
+    is_involution : Optional[bool]
+        Set to true if the function is an involution: `f(f(x)) = x`.
+    is_associative : Optional[bool]
+        Set to true if the function is associative: `f(a, f(b, c)) = f(a, b, c)`.
+    is_homogeneous : Optional[bool]
+        Set to true if numerical factors are pulled out of every argument: `f(2*x, 3*y) = 6*f(x, y)`.
+    linear_args : Optional[Sequence[int]]
+        The positions of the arguments in which the function is linear.
     custom_normalization : Optional[Transformer]
         A transformer that is called after every normalization. Note that the symbol
         name cannot be used in the transformer as this will lead to a definition of the
@@ -136,7 +157,14 @@ def S(*names: str,
       is_symmetric: Optional[bool] = None,
       is_antisymmetric: Optional[bool] = None,
       is_cyclesymmetric: Optional[bool] = None,
-      is_linear: Optional[bool] = None) -> Sequence[Expression]:
+      is_linear: Optional[bool] = None,
+      is_noncommutative: Optional[bool] = None,
+      is_grassmann: Optional[bool] = None,
+      is_idempotent: Optional[bool] = None,
+      is_involution: Optional[bool] = None,
+      is_associative: Optional[bool] = None,
+      is_homogeneous: Optional[bool] = None,
+      linear_args: Optional[Sequence[int]] = None) -> Sequence[Expression]:
     """
     Create new symbols from `names`. Symbols can have attributes,
     such as symmetries. If no attributes
@@ -166,6 +194,20 @@ def S(*names: str,
         Set to true if the symbol is cyclesymmetric.
     is_linear : Optional[bool]
         Set to true if the symbol is multilinear.
+    is_noncommutative : Optional[bool]
+        Set to true if the symbol is noncommutative: its relative order in products is preserved.
+    is_grassmann : Optional[bool]
+        Set to true if the symbol is Grassmann-odd: it anticommutes with other Grassmann-odd symbols.
+    is_idempotent : Optional[bool]
+        Set to true if the function is idempotent: `f(f(x)) = f(x)`.
+    is_involution : Optional[bool]
+        Set to true if the function is an involution: `f(f(x)) = x`.
+    is_associative : Optional[bool]
+        Set to true if the function is associative: `f(a, f(b, c)) = f(a, b, c)`.
+    is_homogeneous : Optional[bool]
+        Set to true if numerical factors are pulled out of every argument: `f(2*x, 3*y) = 6*f(x, y)`.
+    linear_args : Optional[Sequence[int]]
+        The positions of the arguments in which the function is linear.
     """
 
 
@@ -267,6 +309,20 @@ class PrintMode(Enum):
     """Print using Mathematica notation."""
     Sympy = 4
     """Print using Sympy notation."""
+    Pretty = 5
+    """Print using a two-dimensional layout for terminals."""
+    C = 6
+    """Print as a C/C++ expression."""
+    Fortran = 7
+    """Print as a Fortran 90 expression."""
+    NumPy = 8
+    """Print as a vectorized NumPy expression."""
+    MathML = 9
+    """Print using Presentation MathML."""
+    ContentMathML = 10
+    """Print using Content MathML."""
+    Form = 11
+    """Print using FORM notation."""
 
 
 class Expression:
@@ -315,6 +371,13 @@ class Expression:
                is_antisymmetric: Optional[bool] = None,
                is_cyclesymmetric: Optional[bool] = None,
                is_linear: Optional[bool] = None,
+               is_noncommutative: Optional[bool] = None,
+               is_grassmann: Optional[bool] = None,
+               is_idempotent: Optional[bool] = None,
+               is_involution: Optional[bool] = None,
+               is_associative: Optional[bool] = None,
+               is_homogeneous: Optional[bool] = None,
+               linear_args: Optional[Sequence[int]] = None,
                custom_normalization: Optional[Transformer] = None,
                custom_print: Optional[Callable[[Expression], Optional[str]]] = None) -> Expression:
         """
@@ -380,6 +443,20 @@ class Expression:
             Set to true if the symbol is cyclesymmetric.
         is_linear : Optional[bool]
             Set to true if the symbol is linear.
+        is_noncommutative : Optional[bool]
+            Set to true if the symbol is noncommutative: its relative order in products is preserved.
+        is_grassmann : Optional[bool]
+            Set to true if the symbol is Grassmann-odd: it anticommutes with other Grassmann-odd symbols.
+        is_idempotent : Optional[bool]
+            Set to true if the function is idempotent: `f(f(x)) = f(x)`.
+        is_involution : Optional[bool]
+            Set to true if the function is an involution: `f(f(x)) = x`.
+        is_associative : Optional[bool]
+            Set to true if the function is associative: `f(a, f(b, c)) = f(a, b, c)`.
+        is_homogeneous : Optional[bool]
+            Set to true if numerical factors are pulled out of every argument: `f(2*x, 3*y) = 6*f(x, y)`.
+        linear_args : Optional[Sequence[int]]
+            The positions of the arguments in which the function is linear.
         custom_normalization : Optional[Transformer]
             A transformer that is called after every normalization. Note that the symbol
             name cannot be used in the transformer as this will lead to a definition of the
@@ -397,7 +474,14 @@ class Expression:
                is_symmetric: Optional[bool] = None,
                is_antisymmetric: Optional[bool] = None,
                is_cyclesymmetric: Optional[bool] = None,
-               is_linear: Optional[bool] = None) -> Sequence[Expression]:
+               is_linear: Optional[bool] = None,
+               is_noncommutative: Optional[bool] = None,
+               is_grassmann: Optional[bool] = None,
+               is_idempotent: Optional[bool] = None,
+               is_involution: Optional[bool] = None,
+               is_associative: Optional[bool] = None,
+               is_homogeneous: Optional[bool] = None,
+               linear_args: Optional[Sequence[int]] = None) -> Sequence[Expression]:
         """
         Create new symbols from `names`. Symbols can have attributes,
         such as symmetries. If no attributes
@@ -427,6 +511,20 @@ class Expression:
             Set to true if the symbol is cyclesymmetric.
         is_linear : Optional[bool]
             Set to true if the symbol is multilinear.
+        is_noncommutative : Optional[bool]
+            Set to true if the symbol is noncommutative: its relative order in products is preserved.
+        is_grassmann : Optional[bool]
+            Set to true if the symbol is Grassmann-odd: it anticommutes with other Grassmann-odd symbols.
+        is_idempotent : Optional[bool]
+            Set to true if the function is idempotent: `f(f(x)) = f(x)`.
+        is_involution : Optional[bool]
+            Set to true if the function is an involution: `f(f(x)) = x`.
+        is_associative : Optional[bool]
+            Set to true if the function is associative: `f(a, f(b, c)) = f(a, b, c)`.
+        is_homogeneous : Optional[bool]
+            Set to true if numerical factors are pulled out of every argument: `f(2*x, 3*y) = 6*f(x, y)`.
+        linear_args : Optional[Sequence[int]]
+            The positions of the arguments in which the function is linear.
         """
 
     @overload
@@ -586,9 +684,12 @@ class Expression:
         show_namespaces: bool = False,
         max_terms: Optional[int] = 100,
         custom_print_mode: Optional[int] = None,
+        max_line_length: Optional[int] = None,
     ) -> str:
         """
         Convert the expression into a human-readable string, with tunable settings.
+        If `max_line_length` is set, long lines are broken at the operators with the
+        lowest nesting depth.
 
         Examples
         --------
@@ -3948,6 +4049,7 @@ class Evaluator:
         - `('pow', ('out', 0), ('param', 0), -1)` which means `out[0] = param[0]^-1`.
         - `('powf', ('out', 0), ('param', 0), ('param', 1))` which means `out[0] = param[0]^param[1]`.
         - `('fun', ('temp', 1), cos, ('param', 0))` which means `temp[1] = cos(param[0])`.
+        - `('funmulti', ('temp', 1), polygamma, [('param', 0), ('param', 1)])` which means `temp[1] = polygamma(param[0], param[1])`.
 
         Examples
         --------
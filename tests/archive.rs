@@ -0,0 +1,110 @@
+use std::io::Cursor;
+
+use smartstring::SmartString;
+use symbolica::{
+    archive::{Archive, Compression},
+    atom::AtomCore,
+    domains::rational::Q,
+    evaluate::{FunctionMap, OptimizationSettings},
+    parse,
+    state::State,
+    symbol,
+};
+
+#[test]
+fn archive() {
+    symbol!("x", "y");
+    symbol!("f"; Symmetric).unwrap();
+
+    let mut archive = Archive::new();
+    archive.insert_expression("e", parse!("f(x, y)*x^2").unwrap());
+    archive.insert_expression("c", parse!("1/3*x + 2^100/3^70 + 1.5*y").unwrap());
+    archive.insert_polynomial(
+        "p",
+        &parse!("x^2/2 + f(y)*x + 3")
+            .unwrap()
+            .to_polynomial::<_, u16>(&Q, None),
+    );
+
+    let params = vec![parse!("x").unwrap(), parse!("y").unwrap()];
+    let evaluator = parse!("x^2 + sin(y)*f(x, y)")
+        .unwrap()
+        .replace(parse!("f(x, y)").unwrap())
+        .with(parse!("x*y").unwrap())
+        .evaluator(
+            &FunctionMap::new(),
+            &params,
+            OptimizationSettings::default(),
+        )
+        .unwrap();
+    archive.insert_evaluator("ev", evaluator);
+
+    let mut export = vec![];
+    archive.export(&mut export, Compression::Brotli(6)).unwrap();
+
+    // load the archive in a state with different symbol ids and a conflict
+    unsafe { State::reset() };
+
+    symbol!("y");
+    symbol!("x");
+    symbol!("f");
+
+    let archive = Archive::import(
+        Cursor::new(&export),
+        Some(Box::new(|old_name| SmartString::from(old_name) + "1")),
+    )
+    .unwrap();
+
+    assert_eq!(
+        archive.expression("e"),
+        Some(&parse!("x^2*f1(y, x)").unwrap())
+    );
+
+    let p = archive.polynomial::<_, u16>("p", &Q).unwrap();
+    assert_eq!(p.to_expression(), parse!("x^2/2 + f1(y)*x + 3").unwrap());
+
+    let mut ev = archive
+        .evaluator("ev")
+        .unwrap()
+        .clone()
+        .map_coeff(&|x| x.to_f64());
+    let r = ev.evaluate_single(&[2., 0.5]);
+    assert!((r - (4. + 0.5f64.sin())).abs() < 1e-14);
+
+    let mut uncompressed = vec![];
+    archive
+        .export(&mut uncompressed, Compression::None)
+        .unwrap();
+    let archive = Archive::import(Cursor::new(&uncompressed), None).unwrap();
+    assert_eq!(
+        archive.expression("e"),
+        Some(&parse!("x^2*f1(y, x)").unwrap())
+    );
+
+    assert!(Archive::import(Cursor::new(&export[1..]), None).is_err());
+
+    // truncated archives are rejected without panicking
+    for i in 0..uncompressed.len() {
+        assert!(Archive::import(Cursor::new(&uncompressed[..i]), None).is_err());
+    }
+
+    // archives with a corrupted byte are rejected or loaded without panicking
+    for i in 0..uncompressed.len() {
+        for flip in [0x01, 0x80, 0xff] {
+            let mut corrupt = uncompressed.clone();
+            corrupt[i] ^= flip;
+            if let Ok(archive) = Archive::import(Cursor::new(&corrupt), None) {
+                for (_, e) in archive.expressions() {
+                    let _ = e.expand();
+                }
+                for (_, e) in archive.evaluators() {
+                    let _ = e
+                        .clone()
+                        .map_coeff(&|x| x.to_f64())
+                        .evaluate_single(&[2., 0.5]);
+                }
+                let _ = archive.polynomial::<_, u16>("p", &Q);
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::io::Cursor;
+
+use symbolica::{
+    atom::{Assumption, Symbol},
+    parse,
+    state::State,
+    symbol, wrap_symbol,
+};
+
+#[test]
+fn assumptions() {
+    Symbol::new(wrap_symbol!("x"))
+        .with_assumptions(&[
+            Assumption::Real,
+            Assumption::Range((-2).into(), (1, 2).into()),
+        ])
+        .build()
+        .unwrap();
+
+    let mut state_export = vec![];
+    State::export(&mut state_export).unwrap();
+
+    unsafe { State::reset() };
+
+    State::import(&mut Cursor::new(&state_export), None).unwrap();
+    assert_eq!(
+        symbol!("x").get_assumptions(),
+        &[
+            Assumption::Real,
+            Assumption::Range((-2).into(), (1, 2).into())
+        ]
+    );
+    assert_eq!(
+        parse!("sqrt(x^2)").unwrap(),
+        parse!("symbolica::abs(x)").unwrap()
+    );
+}
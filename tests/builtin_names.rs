@@ -0,0 +1,16 @@
+use symbolica::{atom::Atom, parse, symbol};
+
+#[test]
+fn unreserved_builtin_names() {
+    let gamma = symbol!("gamma"; Noncommutative).unwrap();
+    let beta = symbol!("beta"; Symmetric).unwrap();
+    assert_ne!(gamma, Atom::GAMMA);
+    assert_ne!(beta, Atom::BETA);
+
+    assert_ne!(parse!("gamma(5)").unwrap(), Atom::new_num(24));
+    assert_eq!(parse!("beta(x, y)").unwrap(), parse!("beta(y, x)").unwrap());
+
+    let a = parse!("symbolica::gamma(5) + symbolica::zeta(x)").unwrap();
+    assert_eq!(format!("{:#}", a), "symbolica::zeta(builtin_names::x)+24");
+    assert_eq!(parse!(&format!("{:#}", a)).unwrap(), a);
+}